@@ -0,0 +1,935 @@
+//! Support for reading Hive ACID transactional tables.
+//!
+//! Hive stores each row of a transactional table as an event wrapped in a struct with the fields
+//! `operation`, `originalTransaction`, `bucket`, `rowId`, `currentTransaction`, and `row`. A table
+//! directory contains at most one current `base_N` directory along with any number of
+//! `delta_M_N` and `delete_delta_M_N` directories. This module merges these into the logical rows
+//! of the table. Note that we don't currently filter events by a list of valid transactions, so
+//! aborted or open transactions will be treated as committed.
+
+use crate::{
+    bitmap::Bitmap,
+    column::Column,
+    meta::{Footer, TypeKind},
+    parser::{self, OrcFile, StripeInfo},
+    value::Value,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The type ID of the nested `row` struct in an ACID file.
+pub(crate) const ROW_TYPE_ID: usize = 6;
+
+const ACID_FIELD_NAMES: [&str; 6] = [
+    "operation",
+    "originalTransaction",
+    "bucket",
+    "rowId",
+    "currentTransaction",
+    "row",
+];
+const ACID_FIELD_KINDS: [TypeKind; 5] = [
+    TypeKind::Int,
+    TypeKind::Long,
+    TypeKind::Int,
+    TypeKind::Long,
+    TypeKind::Long,
+];
+const EVENT_COLUMNS: [usize; 5] = [0, 1, 2, 3, 4];
+// Column indices skip the root struct, so these are one less than the type IDs.
+const ROW_COLUMN: usize = ROW_TYPE_ID - 1;
+
+const OPERATION_INSERT: u64 = 0;
+const OPERATION_UPDATE: u64 = 1;
+const OPERATION_DELETE: u64 = 2;
+
+const BASE_PREFIX: &str = "base_";
+const DELTA_PREFIX: &str = "delta_";
+const DELETE_DELTA_PREFIX: &str = "delete_delta_";
+const BUCKET_PREFIX: &str = "bucket_";
+const FLUSH_LENGTH_SUFFIX: &str = "_flush_length";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    #[error("Parser error")]
+    Parser(#[from] parser::Error),
+    #[error("Not a Hive ACID file")]
+    NotAcid(PathBuf),
+    #[error("Invalid ACID event")]
+    InvalidEvent { path: PathBuf, row_index: usize },
+}
+
+/// Indicates whether the file schema is the Hive ACID event wrapper struct.
+pub(crate) fn is_acid_schema(footer: &Footer) -> bool {
+    let types = footer.get_types();
+
+    types.len() > ROW_TYPE_ID
+        && types[0].get_kind() == TypeKind::Struct
+        && types[0].get_field_names() == ACID_FIELD_NAMES
+        && types[0].get_subtypes() == [1, 2, 3, 4, 5, ROW_TYPE_ID as u32]
+        && ACID_FIELD_KINDS
+            .iter()
+            .zip(&types[1..ROW_TYPE_ID])
+            .all(|(kind, column_type)| column_type.get_kind() == *kind)
+        && types[ROW_TYPE_ID].get_kind() == TypeKind::Struct
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct RowId {
+    original_transaction: u64,
+    bucket: u64,
+    row_id: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Event {
+    operation: u64,
+    id: RowId,
+    current_transaction: u64,
+}
+
+impl Event {
+    fn from_values(values: &[Value<'_>]) -> Option<Event> {
+        let value = |index: usize| values.get(index)?.to_u64_checked();
+
+        Some(Event {
+            operation: value(0)?,
+            id: RowId {
+                original_transaction: value(1)?,
+                bucket: value(2)?,
+                row_id: value(3)?,
+            },
+            current_transaction: value(4)?,
+        })
+    }
+}
+
+/// The delete and update events that may hide earlier versions of rows.
+#[derive(Debug, Default)]
+struct Tombstones {
+    deletes: HashMap<RowId, u64>,
+    updates: HashMap<RowId, u64>,
+}
+
+impl Tombstones {
+    fn add(&mut self, event: Event) {
+        let target = match event.operation {
+            OPERATION_DELETE => &mut self.deletes,
+            OPERATION_UPDATE => &mut self.updates,
+            _ => return,
+        };
+
+        let transaction = target.entry(event.id).or_insert(event.current_transaction);
+        *transaction = (*transaction).max(event.current_transaction);
+    }
+
+    fn is_visible(&self, event: &Event) -> bool {
+        (event.operation == OPERATION_INSERT || event.operation == OPERATION_UPDATE)
+            && self
+                .deletes
+                .get(&event.id)
+                .is_none_or(|transaction| *transaction < event.current_transaction)
+            && self
+                .updates
+                .get(&event.id)
+                .is_none_or(|transaction| *transaction <= event.current_transaction)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Directory {
+    Base {
+        write_id: u64,
+    },
+    Delta {
+        min_write_id: u64,
+        max_write_id: u64,
+        statement_id: Option<u64>,
+    },
+    DeleteDelta {
+        min_write_id: u64,
+        max_write_id: u64,
+        statement_id: Option<u64>,
+    },
+}
+
+impl Directory {
+    fn parse(name: &str) -> Option<Directory> {
+        if let Some(rest) = name.strip_prefix(BASE_PREFIX) {
+            // Newer versions of Hive may add a visibility suffix (e.g. `base_0000005_v0000123`).
+            let write_id = rest.split('_').next()?.parse().ok()?;
+
+            Some(Directory::Base { write_id })
+        } else if let Some(rest) = name.strip_prefix(DELETE_DELTA_PREFIX) {
+            let (min_write_id, max_write_id, statement_id) = Self::parse_delta_range(rest)?;
+
+            Some(Directory::DeleteDelta {
+                min_write_id,
+                max_write_id,
+                statement_id,
+            })
+        } else if let Some(rest) = name.strip_prefix(DELTA_PREFIX) {
+            let (min_write_id, max_write_id, statement_id) = Self::parse_delta_range(rest)?;
+
+            Some(Directory::Delta {
+                min_write_id,
+                max_write_id,
+                statement_id,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn parse_delta_range(input: &str) -> Option<(u64, u64, Option<u64>)> {
+        let mut parts = input.split('_');
+        let min_write_id = parts.next()?.parse().ok()?;
+        let max_write_id = parts.next()?.parse().ok()?;
+        let statement_id = match parts.next() {
+            Some(part) if !part.starts_with('v') => Some(part.parse().ok()?),
+            _ => None,
+        };
+
+        Some((min_write_id, max_write_id, statement_id))
+    }
+
+    fn delta_range(&self) -> Option<(u64, u64)> {
+        match self {
+            Directory::Base { .. } => None,
+            Directory::Delta {
+                min_write_id,
+                max_write_id,
+                ..
+            }
+            | Directory::DeleteDelta {
+                min_write_id,
+                max_write_id,
+                ..
+            } => Some((*min_write_id, *max_write_id)),
+        }
+    }
+}
+
+/// Select the deltas that aren't covered by the base or by another (compacted) delta.
+fn select_deltas(mut deltas: Vec<(Directory, PathBuf)>, base_write_id: u64) -> Vec<PathBuf> {
+    deltas.sort_by_key(|(directory, _)| {
+        let (min_write_id, max_write_id) = directory.delta_range().unwrap_or_default();
+        (min_write_id, std::cmp::Reverse(max_write_id), *directory)
+    });
+
+    let mut selected = Vec::with_capacity(deltas.len());
+    let mut covered_write_id = base_write_id;
+    let mut last_range = None;
+
+    for (directory, path) in deltas {
+        if let Some(range @ (_, max_write_id)) = directory.delta_range() {
+            // Deltas for multiple statements in a single transaction share the same range.
+            if max_write_id > covered_write_id || last_range == Some(range) {
+                covered_write_id = covered_write_id.max(max_write_id);
+                last_range = Some(range);
+                selected.push(path);
+            }
+        }
+    }
+
+    selected
+}
+
+/// A Hive ACID table directory.
+#[derive(Debug)]
+pub struct AcidTable {
+    base: Option<PathBuf>,
+    deltas: Vec<PathBuf>,
+    delete_deltas: Vec<PathBuf>,
+}
+
+impl AcidTable {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<AcidTable, Error> {
+        let mut base: Option<(u64, PathBuf)> = None;
+        let mut deltas = vec![];
+        let mut delete_deltas = vec![];
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name();
+
+            match name.to_str().and_then(Directory::parse) {
+                Some(Directory::Base { write_id })
+                    if base.as_ref().is_none_or(|(current, _)| write_id > *current) =>
+                {
+                    base = Some((write_id, entry.path()));
+                }
+                Some(directory @ Directory::Delta { .. }) => {
+                    deltas.push((directory, entry.path()));
+                }
+                Some(directory @ Directory::DeleteDelta { .. }) => {
+                    delete_deltas.push((directory, entry.path()));
+                }
+                _ => {}
+            }
+        }
+
+        let base_write_id = base.as_ref().map_or(0, |(write_id, _)| *write_id);
+
+        Ok(AcidTable {
+            base: base.map(|(_, path)| path),
+            deltas: select_deltas(deltas, base_write_id),
+            delete_deltas: select_deltas(delete_deltas, base_write_id),
+        })
+    }
+
+    pub fn get_base(&self) -> Option<&Path> {
+        self.base.as_deref()
+    }
+
+    pub fn get_deltas(&self) -> &[PathBuf] {
+        &self.deltas
+    }
+
+    pub fn get_delete_deltas(&self) -> &[PathBuf] {
+        &self.delete_deltas
+    }
+
+    /// The field names of the nested `row` struct (empty if the table has no data files).
+    pub fn get_field_names(&self) -> Result<Vec<String>, Error> {
+        match self.event_file_paths()?.first() {
+            Some(path) => {
+                let orc_file = Self::open_event_file(path)?;
+
+                Ok(orc_file.get_footer().get_types()[ROW_TYPE_ID]
+                    .get_field_names()
+                    .to_vec())
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Map over the merged logical rows of the table, using indices into the `row` field names.
+    pub fn map_rows<T, E: From<Error>, F>(
+        &self,
+        columns: &[usize],
+        f: F,
+    ) -> Result<AcidRows<F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        let delta_file_paths = Self::bucket_file_paths(&self.deltas)?;
+        let mut tombstones = Tombstones::default();
+
+        for path in Self::bucket_file_paths(&self.delete_deltas)?
+            .iter()
+            .chain(&delta_file_paths)
+        {
+            let mut orc_file = Self::open_event_file(path)?;
+            let mut row_index = 0;
+
+            for event in orc_file.map_rows(&EVENT_COLUMNS, |values| {
+                let event = Event::from_values(values).ok_or_else(|| Error::InvalidEvent {
+                    path: path.clone(),
+                    row_index,
+                });
+                row_index += 1;
+                event
+            })? {
+                tombstones.add(event?);
+            }
+        }
+
+        Ok(AcidRows {
+            paths: self.event_file_paths()?.into_iter(),
+            current: None,
+            columns: columns.to_vec(),
+            tombstones,
+            f,
+        })
+    }
+
+    fn event_file_paths(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = match &self.base {
+            Some(base) => Self::bucket_file_paths(std::slice::from_ref(base))?,
+            None => vec![],
+        };
+        paths.extend(Self::bucket_file_paths(&self.deltas)?);
+
+        Ok(paths)
+    }
+
+    fn bucket_file_paths(directories: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = vec![];
+
+        for directory in directories {
+            let mut directory_paths = vec![];
+
+            for entry in std::fs::read_dir(directory)? {
+                let entry = entry?;
+                let name = entry.file_name();
+
+                if let Some(name) = name.to_str() {
+                    if name.starts_with(BUCKET_PREFIX) && !name.ends_with(FLUSH_LENGTH_SUFFIX) {
+                        directory_paths.push(entry.path());
+                    }
+                }
+            }
+
+            directory_paths.sort();
+            paths.extend(directory_paths);
+        }
+
+        Ok(paths)
+    }
+
+    fn open_event_file(path: &Path) -> Result<OrcFile, Error> {
+        let orc_file = OrcFile::open(path)?;
+
+        if orc_file.is_acid() {
+            Ok(orc_file)
+        } else {
+            Err(Error::NotAcid(path.to_path_buf()))
+        }
+    }
+}
+
+struct EventFileState {
+    path: PathBuf,
+    file: OrcFile,
+    stripe_info: Vec<StripeInfo>,
+    current_stripe: usize,
+    current_row: usize,
+    current_row_field_row: usize,
+    file_row_index: usize,
+    event_data: Vec<Column>,
+    row_present: Option<Bitmap>,
+    row_data: Vec<Column>,
+}
+
+impl EventFileState {
+    fn open(path: PathBuf) -> Result<EventFileState, Error> {
+        let mut file = AcidTable::open_event_file(&path)?;
+        let stripe_info = file.get_stripe_info()?;
+
+        Ok(EventFileState {
+            path,
+            file,
+            stripe_info,
+            current_stripe: 0,
+            current_row: 0,
+            current_row_field_row: 0,
+            file_row_index: 0,
+            event_data: vec![],
+            row_present: None,
+            row_data: vec![],
+        })
+    }
+
+    fn load_stripe(&mut self, columns: &[usize]) -> Result<(), Error> {
+        let stripe_info = &self.stripe_info[self.current_stripe];
+
+        self.event_data.clear();
+        for i in EVENT_COLUMNS {
+            self.event_data.push(self.file.read_column(stripe_info, i)?);
+        }
+
+        self.row_present = self
+            .file
+            .read_struct_null_runs(stripe_info, ROW_COLUMN)?
+            .map(|null_runs| {
+                let mut present = Bitmap::with_capacity(stripe_info.get_row_count());
+
+                for (i, null_run) in null_runs.iter().enumerate() {
+                    present.grow(*null_run as usize, false);
+
+                    // The last null run follows the final present value.
+                    if i + 1 < null_runs.len() {
+                        present.push(true);
+                    }
+                }
+
+                present
+            });
+
+        self.row_data.clear();
+        for i in columns {
+            // Row fields may be nested, so their column indices aren't always consecutive.
+            let column_index = self.file.get_footer().get_types()[ROW_TYPE_ID]
+                .get_subtypes()
+                .get(*i)
+                .map(|type_id| *type_id as usize - 1)
+                .ok_or(parser::Error::InvalidColumnIndex(*i))?;
+
+            self.row_data
+                .push(self.file.read_column(stripe_info, column_index)?);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct AcidRows<F> {
+    paths: std::vec::IntoIter<PathBuf>,
+    current: Option<EventFileState>,
+    columns: Vec<usize>,
+    tombstones: Tombstones,
+    f: F,
+}
+
+impl<F> AcidRows<F> {
+    fn fail<T, E: From<Error>>(&mut self, error: Error) -> Option<Result<T, E>> {
+        // Unrecoverable error.
+        self.paths = Vec::new().into_iter();
+        self.current = None;
+        Some(Err(E::from(error)))
+    }
+}
+
+impl<T, E, F> Iterator for AcidRows<F>
+where
+    E: From<Error>,
+    F: FnMut(&[Value<'_>]) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let state = match &mut self.current {
+                Some(state) => state,
+                None => {
+                    let path = self.paths.next()?;
+
+                    match EventFileState::open(path) {
+                        Ok(state) => self.current.insert(state),
+                        Err(error) => return self.fail(error),
+                    }
+                }
+            };
+
+            if state.current_stripe >= state.stripe_info.len() {
+                self.current = None;
+                continue;
+            }
+
+            if state.current_row >= state.stripe_info[state.current_stripe].get_row_count() {
+                state.current_stripe += 1;
+                state.current_row = 0;
+                state.current_row_field_row = 0;
+                continue;
+            }
+
+            if state.current_row == 0 {
+                if let Err(error) = state.load_stripe(&self.columns) {
+                    return self.fail(error);
+                }
+            }
+
+            let row_index = state.current_row;
+            let file_row_index = state.file_row_index;
+            let event = match state
+                .event_data
+                .iter()
+                .map(|column| column.get(row_index))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|values| Event::from_values(&values))
+            {
+                Some(event) => event,
+                None => {
+                    let error = Error::InvalidEvent {
+                        path: state.path.clone(),
+                        row_index: file_row_index,
+                    };
+                    return self.fail(error);
+                }
+            };
+
+            // Fields of the nested row struct only have values where the row is present.
+            let is_present = state
+                .row_present
+                .as_ref()
+                .is_none_or(|present| present.get(row_index).unwrap_or(false));
+            let row_field_row = state.current_row_field_row;
+
+            state.current_row += 1;
+            state.file_row_index += 1;
+            if is_present {
+                state.current_row_field_row += 1;
+            }
+
+            if !is_present || !self.tombstones.is_visible(&event) {
+                continue;
+            }
+
+            let values = state
+                .row_data
+                .iter()
+                .map(|column| column.get(row_field_row))
+                .collect::<Option<Vec<_>>>();
+
+            match values {
+                Some(values) => return Some((self.f)(&values)),
+                None => {
+                    let error = Error::InvalidEvent {
+                        path: state.path.clone(),
+                        row_index: file_row_index,
+                    };
+                    return self.fail(error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::orc_proto;
+    use integer_encoding::VarIntWriter;
+
+    // The operation, original transaction, row ID, current transaction, and `(id, value)` row of
+    // an event (all in bucket 0).
+    type TestEvent = (u64, u64, u64, u64, Option<(i64, i64)>);
+
+    // A version 1 literal run of integers.
+    fn integer_run<I: IntoIterator<Item = i64>>(values: I) -> Vec<u8> {
+        let values = values.into_iter().collect::<Vec<_>>();
+        let mut bytes = vec![(values.len() as u8).wrapping_neg()];
+
+        for value in values {
+            bytes.write_varint(value).unwrap();
+        }
+
+        bytes
+    }
+
+    fn column_type(
+        kind: orc_proto::type_::Kind,
+        field_names: &[&str],
+        subtypes: &[u32],
+    ) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(kind);
+        column_type.fieldNames = field_names.iter().map(|name| name.to_string()).collect();
+        column_type.subtypes = subtypes.to_vec();
+        column_type
+    }
+
+    /// Write an uncompressed event file with a nested `row` struct with two long fields (`id` and
+    /// `value`), or if `nested` is set, a struct field `point` containing `value` and then `id`.
+    fn write_event_file<P: AsRef<Path>>(path: P, events: &[TestEvent], nested: bool) {
+        use orc_proto::{column_encoding, stream::Kind as StreamKind, type_::Kind};
+
+        // The row struct type and its descendants, and the column ids of the two long fields.
+        let (row_types, id_column, value_column) = if nested {
+            (
+                vec![
+                    column_type(Kind::STRUCT, &["point", "id"], &[7, 9]),
+                    column_type(Kind::STRUCT, &["value"], &[8]),
+                    column_type(Kind::LONG, &[], &[]),
+                    column_type(Kind::LONG, &[], &[]),
+                ],
+                9,
+                8,
+            )
+        } else {
+            (
+                vec![
+                    column_type(Kind::STRUCT, &["id", "value"], &[7, 8]),
+                    column_type(Kind::LONG, &[], &[]),
+                    column_type(Kind::LONG, &[], &[]),
+                ],
+                7,
+                8,
+            )
+        };
+
+        let rows = events
+            .iter()
+            .filter_map(|(_, _, _, _, row)| *row)
+            .collect::<Vec<_>>();
+
+        // A byte run of the present bits of the row struct.
+        let mut present = vec![0; events.len().div_ceil(8)];
+        for (index, (_, _, _, _, row)) in events.iter().enumerate() {
+            if row.is_some() {
+                present[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        present.insert(0, (present.len() as u8).wrapping_neg());
+
+        let mut streams = vec![
+            (
+                1,
+                StreamKind::DATA,
+                integer_run(events.iter().map(|event| event.0 as i64)),
+            ),
+            (
+                2,
+                StreamKind::DATA,
+                integer_run(events.iter().map(|event| event.1 as i64)),
+            ),
+            (3, StreamKind::DATA, integer_run(events.iter().map(|_| 0))),
+            (
+                4,
+                StreamKind::DATA,
+                integer_run(events.iter().map(|event| event.2 as i64)),
+            ),
+            (
+                5,
+                StreamKind::DATA,
+                integer_run(events.iter().map(|event| event.3 as i64)),
+            ),
+            (6, StreamKind::PRESENT, present),
+            (
+                id_column,
+                StreamKind::DATA,
+                integer_run(rows.iter().map(|row| row.0)),
+            ),
+            (
+                value_column,
+                StreamKind::DATA,
+                integer_run(rows.iter().map(|row| row.1)),
+            ),
+        ];
+
+        // Streams are stored in column order.
+        streams.sort_by_key(|(column_id, _, _)| *column_id);
+
+        let mut data = vec![];
+        let mut stripe_footer = orc_proto::StripeFooter::new();
+
+        for (column_id, kind, bytes) in streams {
+            let mut stream = orc_proto::Stream::new();
+            stream.set_kind(kind);
+            stream.set_column(column_id);
+            stream.set_length(bytes.len() as u64);
+            stripe_footer.streams.push(stream);
+            data.extend(bytes);
+        }
+
+        let mut types = vec![
+            column_type(Kind::STRUCT, &ACID_FIELD_NAMES, &[1, 2, 3, 4, 5, 6]),
+            column_type(Kind::INT, &[], &[]),
+            column_type(Kind::LONG, &[], &[]),
+            column_type(Kind::INT, &[], &[]),
+            column_type(Kind::LONG, &[], &[]),
+            column_type(Kind::LONG, &[], &[]),
+        ];
+        types.extend(row_types);
+
+        for _ in &types {
+            let mut encoding = orc_proto::ColumnEncoding::new();
+            encoding.set_kind(column_encoding::Kind::DIRECT);
+            stripe_footer.columns.push(encoding);
+        }
+
+        crate::parser::tests::write_stripe_file(
+            path,
+            events.len() as u64,
+            &data,
+            &stripe_footer,
+            types,
+        );
+    }
+
+    fn event(operation: u64, row_id: u64, current_transaction: u64) -> Event {
+        Event {
+            operation,
+            id: RowId {
+                original_transaction: 1,
+                bucket: 536870912,
+                row_id,
+            },
+            current_transaction,
+        }
+    }
+
+    #[test]
+    fn parse_directory_names() {
+        assert_eq!(
+            Directory::parse("base_0000005"),
+            Some(Directory::Base { write_id: 5 })
+        );
+        assert_eq!(
+            Directory::parse("base_0000005_v0000123"),
+            Some(Directory::Base { write_id: 5 })
+        );
+        assert_eq!(
+            Directory::parse("delta_0000002_0000003_0000"),
+            Some(Directory::Delta {
+                min_write_id: 2,
+                max_write_id: 3,
+                statement_id: Some(0)
+            })
+        );
+        assert_eq!(
+            Directory::parse("delete_delta_0000004_0000004"),
+            Some(Directory::DeleteDelta {
+                min_write_id: 4,
+                max_write_id: 4,
+                statement_id: None
+            })
+        );
+        assert_eq!(Directory::parse("_orc_acid_version"), None);
+        assert_eq!(Directory::parse("delta_abc_0000001"), None);
+    }
+
+    #[test]
+    fn select_uncovered_deltas() {
+        let deltas = [
+            "delta_0000001_0000001_0000",
+            "delta_0000006_0000006_0000",
+            "delta_0000006_0000006_0001",
+            "delta_0000006_0000008",
+            "delta_0000007_0000007_0000",
+            "delta_0000009_0000009_0000",
+        ]
+        .iter()
+        .map(|name| (Directory::parse(name).unwrap(), PathBuf::from(name)))
+        .collect();
+
+        assert_eq!(
+            select_deltas(deltas, 5),
+            vec![
+                PathBuf::from("delta_0000006_0000008"),
+                PathBuf::from("delta_0000009_0000009_0000")
+            ]
+        );
+    }
+
+    #[test]
+    fn tombstones_hide_rows() {
+        let mut tombstones = Tombstones::default();
+        tombstones.add(event(OPERATION_DELETE, 0, 3));
+        tombstones.add(event(OPERATION_UPDATE, 1, 4));
+
+        assert!(!tombstones.is_visible(&event(OPERATION_INSERT, 0, 1)));
+        assert!(!tombstones.is_visible(&event(OPERATION_INSERT, 1, 1)));
+        assert!(tombstones.is_visible(&event(OPERATION_UPDATE, 1, 4)));
+        assert!(tombstones.is_visible(&event(OPERATION_INSERT, 2, 1)));
+        assert!(!tombstones.is_visible(&event(OPERATION_DELETE, 2, 1)));
+    }
+
+    #[test]
+    fn merge_table_rows() {
+        let path = std::env::temp_dir().join(format!("orcrs-acid-test-{}", std::process::id()));
+        let write = |directory: &str, events: &[TestEvent]| {
+            let directory = path.join(directory);
+            std::fs::create_dir_all(&directory).unwrap();
+            write_event_file(directory.join("bucket_00000"), events, false);
+        };
+
+        write(
+            "base_0000001",
+            &[
+                (OPERATION_INSERT, 1, 0, 1, Some((1, 10))),
+                (OPERATION_INSERT, 1, 1, 1, Some((2, 20))),
+                (OPERATION_INSERT, 1, 2, 1, Some((3, 30))),
+            ],
+        );
+        // Covered by the base.
+        write(
+            "delta_0000001_0000001_0000",
+            &[(OPERATION_INSERT, 1, 0, 1, Some((9, 90)))],
+        );
+        write(
+            "delta_0000002_0000002_0000",
+            &[
+                (OPERATION_INSERT, 2, 0, 2, Some((4, 40))),
+                (OPERATION_INSERT, 2, 1, 2, Some((5, 50))),
+            ],
+        );
+        write(
+            "delete_delta_0000003_0000003_0000",
+            &[
+                (OPERATION_DELETE, 1, 1, 3, None),
+                (OPERATION_DELETE, 2, 1, 3, None),
+            ],
+        );
+        write(
+            "delta_0000004_0000004_0000",
+            &[
+                (OPERATION_UPDATE, 1, 2, 4, Some((3, 31))),
+                (OPERATION_INSERT, 4, 0, 4, Some((6, 60))),
+            ],
+        );
+        std::fs::write(
+            path.join("delta_0000004_0000004_0000")
+                .join("bucket_00000_flush_length"),
+            [0; 8],
+        )
+        .unwrap();
+
+        let table = AcidTable::open(&path).unwrap();
+
+        assert_eq!(table.get_base(), Some(path.join("base_0000001").as_path()));
+        assert_eq!(
+            table.get_deltas(),
+            [
+                path.join("delta_0000002_0000002_0000"),
+                path.join("delta_0000004_0000004_0000")
+            ]
+        );
+        assert_eq!(
+            table.get_delete_deltas(),
+            [path.join("delete_delta_0000003_0000003_0000")]
+        );
+        assert_eq!(table.get_field_names().unwrap(), vec!["id", "value"]);
+
+        let rows = table
+            .map_rows(&[1, 0], |values| {
+                Ok::<_, Error>(
+                    values
+                        .iter()
+                        .map(|value| value.as_i64().unwrap())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+
+        std::fs::remove_dir_all(&path).unwrap();
+
+        // Base rows come first, then each delta in order, without deleted or updated rows.
+        assert_eq!(
+            rows.unwrap(),
+            vec![vec![10, 1], vec![40, 4], vec![31, 3], vec![60, 6]]
+        );
+    }
+
+    #[test]
+    fn nested_row_field() {
+        let path =
+            std::env::temp_dir().join(format!("orcrs-acid-test-{}-nested", std::process::id()));
+        let directory = path.join("base_0000001");
+        std::fs::create_dir_all(&directory).unwrap();
+        write_event_file(
+            directory.join("bucket_00000"),
+            &[
+                (OPERATION_INSERT, 1, 0, 1, Some((1, 10))),
+                (OPERATION_INSERT, 1, 1, 1, Some((2, 20))),
+            ],
+            true,
+        );
+
+        let table = AcidTable::open(&path).unwrap();
+        let field_names = table.get_field_names();
+        let rows = table
+            .map_rows(&[1], |values| Ok::<_, Error>(values[0].as_i64()))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        let invalid = table.map_rows(&[2], |_| Ok::<_, Error>(())).unwrap().next();
+
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(field_names.unwrap(), vec!["point", "id"]);
+        assert_eq!(rows.unwrap(), vec![Some(1), Some(2)]);
+        assert!(matches!(
+            invalid,
+            Some(Err(Error::Parser(parser::Error::InvalidColumnIndex(2))))
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+//! Summaries computed by scanning column values.
+//!
+//! ORC statistics only describe the range of each column (see
+//! [`crate::parser::OrcFile::get_file_statistics`]), not how its values are distributed or how
+//! many distinct values it has. The
+//! summaries here are updated one decoded column at a time, so a file can be described in a single
+//! pass over its stripes (see also [`crate::stats`] for statistics collected during a scan).
+
+use crate::column::Column;
+use crate::value::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+
+const DEFAULT_PRECISION: u8 = 14;
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+
+/// Counts of integer values in buckets of equal width.
+///
+/// Booleans are counted as 0 and 1, and strings are ignored.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    min: i64,
+    max: i64,
+    bucket_width: u128,
+    counts: Vec<u64>,
+    null_count: u64,
+}
+
+impl Histogram {
+    /// Buckets covering `min` to `max` (inclusive), with fewer than `bucket_count` buckets if the
+    /// range contains fewer values.
+    pub fn new(min: i64, max: i64, bucket_count: usize) -> Self {
+        let (min, max) = if min <= max { (min, max) } else { (max, min) };
+        let span = (max as i128 - min as i128 + 1) as u128;
+        let bucket_width = span.div_ceil(bucket_count.max(1) as u128);
+
+        Self {
+            min,
+            max,
+            bucket_width,
+            counts: vec![0; span.div_ceil(bucket_width) as usize],
+            null_count: 0,
+        }
+    }
+
+    /// Count a value (values outside the range are counted in the first or last bucket).
+    pub fn add(&mut self, value: i64) {
+        let value = value.clamp(self.min, self.max);
+        let index = ((value as i128 - self.min as i128) as u128 / self.bucket_width) as usize;
+
+        self.counts[index] += 1;
+    }
+
+    pub fn add_column(&mut self, column: &Column) {
+        for value in values(column) {
+            match value {
+                Value::Null => self.null_count += 1,
+                other => {
+                    if let Some(value) = other.to_i64_lossy() {
+                        self.add(value);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_null_count(&self) -> u64 {
+        self.null_count
+    }
+
+    /// The range of each bucket with the number of values in it.
+    pub fn get_buckets(&self) -> Vec<(RangeInclusive<i64>, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let start = self.min as i128 + index as i128 * self.bucket_width as i128;
+                let end = (start + self.bucket_width as i128 - 1).min(self.max as i128);
+
+                (start as i64..=end as i64, *count)
+            })
+            .collect()
+    }
+}
+
+/// Exact counts of string values, for finding the most frequent ones.
+///
+/// Every distinct value is kept, so memory use grows with the number of distinct values.
+#[derive(Clone, Debug, Default)]
+pub struct FrequentValues {
+    counts: HashMap<String, u64>,
+    null_count: u64,
+}
+
+impl FrequentValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the values of a string column (other columns are ignored).
+    ///
+    /// For dictionary columns, rows are counted by dictionary entry.
+    pub fn add_column(&mut self, column: &Column) {
+        match column {
+            Column::Utf8Dictionary {
+                data,
+                dictionary,
+                indices,
+            } => {
+                let mut entry_counts = vec![0; indices.len()];
+
+                for index in data {
+                    match usize::try_from(*index)
+                        .ok()
+                        .and_then(|index| entry_counts.get_mut(index))
+                    {
+                        Some(count) => *count += 1,
+                        None => self.null_count += 1,
+                    }
+                }
+
+                for ((start, end), count) in indices.iter().zip(entry_counts) {
+                    if count > 0 {
+                        self.add(&dictionary[*start..*end], count);
+                    }
+                }
+            }
+            Column::Utf8Direct { data, indices } => {
+                for range in indices {
+                    match range {
+                        Some((start, end)) => self.add(&data[*start..*end], 1),
+                        None => self.null_count += 1,
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add(&mut self, value: &str, count: u64) {
+        match self.counts.get_mut(value) {
+            Some(current) => *current += count,
+            None => {
+                self.counts.insert(value.to_string(), count);
+            }
+        }
+    }
+
+    pub fn get_null_count(&self) -> u64 {
+        self.null_count
+    }
+
+    /// The `k` most frequent values, with ties ordered by value.
+    pub fn get_top(&self, k: usize) -> Vec<(&str, u64)> {
+        let mut counts = self
+            .counts
+            .iter()
+            .map(|(value, count)| (value.as_str(), *count))
+            .collect::<Vec<_>>();
+
+        counts.sort_by(|(value_a, count_a), (value_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| value_a.cmp(value_b))
+        });
+        counts.truncate(k);
+        counts
+    }
+}
+
+/// An approximate count of distinct non-null values using HyperLogLog.
+///
+/// With the default precision this uses 16 KiB and has a standard error of about 0.8%.
+#[derive(Clone, Debug)]
+pub struct DistinctCount {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl Default for DistinctCount {
+    fn default() -> Self {
+        Self::with_precision(DEFAULT_PRECISION)
+    }
+}
+
+impl DistinctCount {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `2^precision` registers (the precision is clamped to between 4 and 18), for a standard
+    /// error of about `1.04 / sqrt(2^precision)`.
+    pub fn with_precision(precision: u8) -> Self {
+        let precision = precision.clamp(MIN_PRECISION, MAX_PRECISION);
+
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Count a value (nulls are ignored).
+    pub fn add(&mut self, value: &Value) {
+        if *value != Value::Null {
+            // The default hasher uses fixed keys, so estimates are reproducible.
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            let hash = hasher.finish();
+
+            let index = (hash >> (64 - self.precision)) as usize;
+            let rank =
+                ((hash << self.precision).leading_zeros() as u8 + 1).min(65 - self.precision);
+
+            self.registers[index] = self.registers[index].max(rank);
+        }
+    }
+
+    /// Count the values of a column.
+    ///
+    /// For dictionary columns, each dictionary entry that's used is only counted once.
+    pub fn add_column(&mut self, column: &Column) {
+        match column {
+            Column::Utf8Dictionary {
+                data,
+                dictionary,
+                indices,
+            } => {
+                let mut used = vec![false; indices.len()];
+
+                for index in data {
+                    if let Some(used) = usize::try_from(*index)
+                        .ok()
+                        .and_then(|index| used.get_mut(index))
+                    {
+                        *used = true;
+                    }
+                }
+
+                for ((start, end), used) in indices.iter().zip(used) {
+                    if used {
+                        self.add(&Value::Utf8(&dictionary[*start..*end]));
+                    }
+                }
+            }
+            other => {
+                for value in values(other) {
+                    self.add(&value);
+                }
+            }
+        }
+    }
+
+    /// Combine with a count for other values (which must have the same precision).
+    pub fn merge(&mut self, other: &DistinctCount) {
+        if self.precision == other.precision {
+            for (register, other_register) in self.registers.iter_mut().zip(&other.registers) {
+                *register = (*register).max(*other_register);
+            }
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        let register_count = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / register_count);
+        let sum = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-(*register as i32)))
+            .sum::<f64>();
+        let raw_estimate = alpha * register_count * register_count / sum;
+        let zero_count = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+
+        // Linear counting is more accurate for small cardinalities.
+        if raw_estimate <= 2.5 * register_count && zero_count > 0 {
+            (register_count * (register_count / zero_count as f64).ln()).round() as u64
+        } else {
+            raw_estimate.round() as u64
+        }
+    }
+}
+
+fn values(column: &Column) -> impl Iterator<Item = Value<'_>> {
+    (0..).map_while(|row| column.get(row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::Utf8Policy;
+
+    #[test]
+    fn histogram() {
+        let mut histogram = Histogram::new(-5, 14, 4);
+        histogram.add_column(&Column::make_i64_column(
+            vec![-5, 0, 4, 5, 14, 20],
+            &[1, 0, 0, 0, 0, 0, 2],
+        ));
+
+        assert_eq!(histogram.get_null_count(), 3);
+        assert_eq!(
+            histogram.get_buckets(),
+            vec![(-5..=-1, 1), (0..=4, 2), (5..=9, 1), (10..=14, 2)]
+        );
+
+        // A small range has fewer buckets.
+        let histogram = Histogram::new(0, 2, 10);
+        assert_eq!(
+            histogram.get_buckets(),
+            vec![(0..=0, 0), (1..=1, 0), (2..=2, 0)]
+        );
+
+        let mut histogram = Histogram::new(i64::MIN, i64::MAX, 2);
+        histogram.add(i64::MIN);
+        histogram.add(-1);
+        histogram.add(i64::MAX);
+        assert_eq!(
+            histogram.get_buckets(),
+            vec![(i64::MIN..=-1, 2), (0..=i64::MAX, 1)]
+        );
+    }
+
+    #[test]
+    fn frequent_values() {
+        let dictionary_column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "fooba".as_bytes().to_vec(),
+            vec![3, 2],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        let direct_column = Column::make_utf8_direct_column(
+            None,
+            "foofooqux".as_bytes().to_vec(),
+            vec![3, 3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        let mut frequent_values = FrequentValues::new();
+        frequent_values.add_column(&dictionary_column);
+        frequent_values.add_column(&direct_column);
+        frequent_values.add_column(&Column::make_i64_column(vec![1], &[]));
+
+        assert_eq!(frequent_values.get_null_count(), 1);
+        assert_eq!(frequent_values.get_top(2), vec![("foo", 3), ("ba", 2)]);
+        assert_eq!(frequent_values.get_top(10).len(), 3);
+    }
+
+    #[test]
+    fn distinct_count() {
+        let mut distinct_count = DistinctCount::new();
+        assert_eq!(distinct_count.estimate(), 0);
+
+        let dictionary_column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "foobaqux".as_bytes().to_vec(),
+            vec![3, 2, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        distinct_count.add_column(&dictionary_column);
+        // The unused dictionary entry isn't counted.
+        assert_eq!(distinct_count.estimate(), 2);
+
+        let mut small = DistinctCount::new();
+        let mut large = DistinctCount::new();
+
+        for value in 0..100_000u64 {
+            large.add(&Value::U64(value));
+
+            if value < 1_000 {
+                small.add(&Value::U64(value));
+                small.add(&Value::U64(value));
+            }
+        }
+
+        let small_estimate = small.estimate();
+        let large_estimate = large.estimate();
+
+        assert!((990..=1_010).contains(&small_estimate));
+        assert!((97_000..=103_000).contains(&large_estimate));
+
+        small.merge(&large);
+        assert_eq!(small.estimate(), large_estimate);
+    }
+}
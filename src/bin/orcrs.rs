@@ -1,6 +1,25 @@
 use clap::{ArgAction, Parser};
-use orcrs::{parser::OrcFile, value::Value};
+use orcrs::{
+    analyze::{DistinctCount, FrequentValues, Histogram},
+    cast::OutOfRangePolicy,
+    dedupe::RowSelector,
+    hash::RowOrder,
+    meta::{ColumnStatistics, StreamKind, TypeKind, ValueRange},
+    parser::{OrcFile, StripeInfo},
+    schema::{
+        format::{format_schema, SchemaFormat},
+        Incompatibility, Schema,
+    },
+    sort::sort_rows,
+    value::{to_hex, OwnedValue, Value},
+};
+use rusqlite::types::{ToSqlOutput, ValueRef};
 use simplelog::LevelFilter;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -12,6 +31,40 @@ pub enum Error {
     Csv(#[from] csv::Error),
     #[error("Missing value")]
     MissingValue { stripe: u64, row: u64, column: u64 },
+    #[error("Unknown column")]
+    UnknownColumn(String),
+    #[error("Non-numeric column")]
+    NonNumericColumn(String),
+    #[error("Expression error")]
+    Expr(#[from] orcrs::expr::Error),
+    #[error("Missing option")]
+    MissingOption(&'static str),
+    #[error("SQLite error")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("MessagePack writing error")]
+    MessagePack(#[from] rmp_serde::encode::Error),
+    #[error("Regular expression error")]
+    Regex(#[from] regex::Error),
+    #[error("Schema error")]
+    Schema(#[from] orcrs::infer::Error),
+    #[error("Invalid timestamp format")]
+    InvalidTimestampFormat(String),
+    #[error("Invalid timestamp")]
+    InvalidTimestamp(String),
+    #[error("Incompatible schemas")]
+    IncompatibleSchemas(usize),
+    #[error("Cast error")]
+    Cast(#[from] orcrs::cast::Error),
+    #[error("Schema format error")]
+    SchemaFormat(#[from] orcrs::schema::format::Error),
+    #[error("Invalid REPL command")]
+    InvalidReplCommand(String),
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+    #[error("Comparison error")]
+    Compare(#[from] orcrs::compare::Error),
+    #[error("Spreadsheet writing error")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
 }
 
 fn main() -> Result<(), Error> {
@@ -20,72 +73,922 @@ fn main() -> Result<(), Error> {
 
     match opts.command {
         Command::Export {
-            format: _,
+            format,
             columns,
+            columns_matching,
+            exclude_columns,
+            fields,
+            select,
             header,
             null: null_string_value,
+            output,
+            table,
+            timestamp_columns,
+            timestamp_format,
+            timestamp_unit,
+            row_numbers,
+            empty_as_null,
+            trim_char,
+            skip_unsupported,
+            sheet_rows,
+            watch,
+            poll_interval,
+            path,
+        } => {
+            let options = ExportOptions {
+                column_indices: columns.and_then(|value| parse_column_indices(&value)),
+                columns_matching: columns_matching
+                    .map(|pattern| regex::Regex::new(&pattern))
+                    .transpose()?,
+                exclude_columns,
+                fields,
+                select,
+                timestamp_format: TimestampFormat::new(
+                    timestamp_columns,
+                    timestamp_format,
+                    &timestamp_unit,
+                )?,
+                row_numbers,
+            };
+            let mut writer = RecordWriter::new(
+                &format,
+                output.as_deref(),
+                table.as_deref(),
+                &null_string_value,
+                sheet_rows,
+            )?;
+
+            if watch {
+                let mut processed = HashSet::new();
+                let mut header = header;
+
+                loop {
+                    let mut paths = std::fs::read_dir(&path)?
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    paths.sort();
+
+                    for orc_path in paths {
+                        if orc_path
+                            .extension()
+                            .is_none_or(|extension| extension != "orc")
+                            || processed.contains(&orc_path)
+                        {
+                            continue;
+                        }
+
+                        // A file that's still being written won't have a valid footer yet.
+                        match OrcFile::open(&orc_path) {
+                            Ok(mut orc_file) => {
+                                log::info!("Exporting {}", orc_path.display());
+                                orc_file.set_empty_strings_as_nulls(empty_as_null);
+                                orc_file.set_trim_char_padding(trim_char);
+                                orc_file.set_skip_unsupported_columns(skip_unsupported);
+                                export_rows(&mut writer, &mut orc_file, &options, header)?;
+                                writer.flush()?;
+                                processed.insert(orc_path);
+                                header = false;
+                            }
+                            Err(error) => {
+                                log::debug!("Skipping {} for now: {:?}", orc_path.display(), error);
+                            }
+                        }
+                    }
+
+                    std::thread::sleep(Duration::from_secs(poll_interval));
+                }
+            } else {
+                let mut orc_file = OrcFile::open(&path)?;
+                orc_file.set_empty_strings_as_nulls(empty_as_null);
+                orc_file.set_trim_char_padding(trim_char);
+                orc_file.set_skip_unsupported_columns(skip_unsupported);
+                export_rows(&mut writer, &mut orc_file, &options, header)?;
+                writer.flush()?;
+            }
+        }
+        Command::Agg {
+            group_by,
+            count,
+            sum,
+            null: null_string_value,
             path,
         } => {
             let mut writer = csv::Writer::from_writer(std::io::stdout());
             let mut orc_file = OrcFile::open(&path)?;
-            let field_names = orc_file.get_field_names();
+            let group_by_indices =
+                field_columns(&orc_file, &lookup_columns(&orc_file, &group_by)?)?;
+            let sum_indices = field_columns(&orc_file, &lookup_columns(&orc_file, &sum)?)?;
 
-            let column_indices = match columns.and_then(|value| parse_column_indices(&value)) {
-                Some(ref value) => value.clone(),
-                None => (0..field_names.len()).collect(),
-            };
+            let mut groups: HashMap<Vec<OwnedValue>, (u64, Vec<i128>)> = HashMap::new();
 
-            if header {
-                if let Some(field_names) = column_indices
+            for (stripe_index, stripe) in orc_file.get_stripe_info()?.iter().enumerate() {
+                let group_by_columns = group_by_indices
                     .iter()
-                    .map(|i| field_names.get(*i))
-                    .collect::<Option<Vec<_>>>()
-                {
-                    writer.write_record(field_names)?;
-                } else {
-                    log::warn!("A header was requested but field names could not be found.")
+                    .map(|i| orc_file.read_column(stripe, *i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let sum_columns = sum_indices
+                    .iter()
+                    .map(|i| orc_file.read_column(stripe, *i))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for row in 0..stripe.get_row_count() {
+                    let missing_value = |column: usize| Error::MissingValue {
+                        stripe: stripe_index as u64,
+                        row: row as u64,
+                        column: column as u64,
+                    };
+
+                    let key = group_by_columns
+                        .iter()
+                        .zip(&group_by_indices)
+                        .map(|(column, i)| {
+                            column
+                                .get(row)
+                                .map(|value| value.to_owned_value())
+                                .ok_or_else(|| missing_value(*i))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    let (group_count, group_sums) = groups
+                        .entry(key)
+                        .or_insert_with(|| (0, vec![0; sum_columns.len()]));
+
+                    *group_count += 1;
+
+                    for ((group_sum, column), (i, name)) in group_sums
+                        .iter_mut()
+                        .zip(&sum_columns)
+                        .zip(sum_indices.iter().zip(&sum))
+                    {
+                        match column.get(row).ok_or_else(|| missing_value(*i))? {
+                            Value::U64(value) => *group_sum += value as i128,
+                            Value::I64(value) => *group_sum += value as i128,
+                            Value::Null => {}
+                            _ => return Err(Error::NonNumericColumn(name.to_string())),
+                        }
+                    }
                 }
             }
 
-            for record in orc_file.map_rows(&column_indices, |values| {
-                values
+            let mut header = group_by.clone();
+            if count {
+                header.push("count".to_string());
+            }
+            header.extend(sum.iter().map(|name| format!("sum_{}", name)));
+            writer.write_record(header)?;
+
+            let mut groups = groups.into_iter().collect::<Vec<_>>();
+            groups.sort();
+
+            for (key, (group_count, group_sums)) in groups {
+                let mut record = key
                     .iter()
-                    .map(|value| match value {
-                        Value::Null => Ok(null_string_value.clone()),
-                        Value::Bool(value) => Ok(value.to_string()),
-                        Value::U64(value) => Ok(value.to_string()),
-                        Value::Utf8(value) => Ok(escape(value)),
-                    })
-                    .collect::<Result<Vec<_>, Error>>()
-            })? {
-                let record = record?;
+                    .map(|value| format_value(&value.as_value(), &null_string_value))
+                    .collect::<Vec<_>>();
+                if count {
+                    record.push(group_count.to_string());
+                }
+                record.extend(group_sums.iter().map(|value| value.to_string()));
                 writer.write_record(record)?;
             }
 
             writer.flush()?;
         }
-        Command::Info { path } => {
+        Command::Dedupe {
+            key,
+            keep,
+            header,
+            null: null_string_value,
+            path,
+        } => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
             let mut orc_file = OrcFile::open(&path)?;
-            let footer = orc_file.get_footer();
-            println!("Footer: {:?}\n================", footer);
+            let key_indices = lookup_columns(&orc_file, &key)?;
+            let column_indices = orc_file.get_field_column_indices();
 
-            for (i, (stripe_footer, stripe_info)) in orc_file
-                .get_stripe_footers()?
-                .iter()
-                .zip(orc_file.get_stripe_info()?)
+            let row_key = |values: &[Value]| {
+                key_indices
+                    .iter()
+                    .map(|i| values[*i].to_owned_value())
+                    .collect::<Vec<_>>()
+            };
+
+            // For keep-last we need a first pass to find the last row for each key.
+            let mut selector = if keep == "last" {
+                RowSelector::keep_last(
+                    orc_file.map_rows(&column_indices, |values| Ok::<_, Error>(row_key(values)))?,
+                )?
+            } else {
+                RowSelector::keep_first()
+            };
+
+            if header {
+                writer.write_record(orc_file.get_field_names())?;
+            }
+
+            for (row, record) in orc_file
+                .map_rows(&column_indices, |values| {
+                    let key = row_key(values);
+                    let record = values
+                        .iter()
+                        .map(|value| format_value(value, &null_string_value))
+                        .collect::<Vec<_>>();
+
+                    Ok::<_, Error>((key, record))
+                })?
                 .enumerate()
             {
-                println!("Stripe {} footer: {:?}\n----------------", i, stripe_footer);
-                println!("Stripe {} info: {:?}\n================", i, stripe_info);
+                let (key, record) = record?;
+
+                if selector.is_kept(row, key) {
+                    writer.write_record(record)?;
+                }
+            }
+
+            writer.flush()?;
+        }
+        Command::Sort {
+            by,
+            reverse,
+            max_rows_in_memory,
+            header,
+            null: null_string_value,
+            path,
+        } => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            let mut orc_file = OrcFile::open(&path)?;
+            let key_indices = lookup_columns(&orc_file, &by)?;
+            let column_indices = orc_file.get_field_column_indices();
+
+            if header {
+                writer.write_record(orc_file.get_field_names())?;
+            }
+
+            let rows = orc_file.map_rows(&column_indices, |values| {
+                let key = key_indices
+                    .iter()
+                    .map(|i| values[*i].to_owned_value())
+                    .collect::<Vec<_>>();
+                let record = values
+                    .iter()
+                    .map(|value| format_value(value, &null_string_value))
+                    .collect::<Vec<_>>();
+
+                Ok::<_, Error>((key, record))
+            })?;
+
+            sort_rows(rows, reverse, max_rows_in_memory, |record| {
+                Ok(writer.write_record(record)?)
+            })?;
+
+            writer.flush()?;
+        }
+        Command::Split {
+            rows,
+            stripes,
+            block_size,
+            padding_tolerance,
+            output,
+            path,
+        } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let stripe_infos = orc_file.get_footer().get_stripes();
+
+            let groups = match (rows, stripes) {
+                (Some(rows), _) => orcrs::split::plan_by_rows(stripe_infos, rows),
+                (None, Some(stripes)) => orcrs::split::plan_by_stripes(stripe_infos.len(), stripes),
+                (None, None) => orcrs::split::plan_by_stripes(stripe_infos.len(), 1),
+            };
+
+            for (i, group) in groups.into_iter().enumerate() {
+                let output_path = output.replace("{}", &i.to_string());
+                log::info!("Writing stripes {:?} to {}", group, output_path);
+
+                let writer = BufWriter::new(File::create(&output_path)?);
+
+                match block_size {
+                    Some(block_size) => {
+                        let padding = orcrs::split::BlockPadding::new(block_size)
+                            .with_tolerance(padding_tolerance);
+                        orcrs::split::write_padded_stripes(&mut orc_file, group, writer, &padding)?;
+                    }
+                    None => orcrs::split::write_stripes(&mut orc_file, group, writer)?,
+                }
+            }
+        }
+        Command::Repair { output, path } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let writer = BufWriter::new(File::create(&output)?);
+            let lost_stripes = orcrs::repair::repair(&mut orc_file, writer)?;
+
+            for lost_stripe in &lost_stripes {
+                let rows = lost_stripe.get_rows();
+                println!(
+                    "Lost rows {}..{} (stripe {}): {:?}",
+                    rows.start,
+                    rows.end,
+                    lost_stripe.get_stripe_index(),
+                    lost_stripe.get_error()
+                );
+            }
+
+            log::info!(
+                "Copied {} of {} stripes to {}",
+                orc_file.get_footer().get_stripes().len() - lost_stripes.len(),
+                orc_file.get_footer().get_stripes().len(),
+                output
+            );
+        }
+        Command::Grep {
+            column,
+            regex,
+            columns,
+            header,
+            null: null_string_value,
+            path,
+        } => {
+            let regex = regex::Regex::new(&regex)?;
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            let mut orc_file = OrcFile::open(&path)?;
+            let search_index = field_columns(&orc_file, &lookup_columns(&orc_file, &[column])?)?[0];
+            let column_indices = columns
+                .and_then(|value| parse_column_indices(&value))
+                .unwrap_or_else(|| (0..orc_file.get_field_names().len()).collect());
+
+            if header {
+                let field_names = orc_file.get_field_names();
+                writer.write_record(column_indices.iter().map(|i| &field_names[*i]))?;
+            }
+
+            for stripe in orc_file.get_stripe_info()? {
+                let matches = orc_file
+                    .read_column(&stripe, search_index)?
+                    .match_strings(|value| regex.is_match(value));
+
+                // Only read the other columns for stripes with matches.
+                if matches.count_ones() == 0 {
+                    continue;
+                }
+
+                let output_columns = field_columns(&orc_file, &column_indices)?
+                    .iter()
+                    .map(|i| orc_file.read_column(&stripe, *i))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for row in 0..stripe.get_row_count() {
+                    if matches[row] {
+                        let record = output_columns
+                            .iter()
+                            .zip(&column_indices)
+                            .map(|(output_column, column)| {
+                                output_column
+                                    .get(row)
+                                    .map(|value| format_value(&value, &null_string_value))
+                                    .ok_or(Error::MissingValue {
+                                        stripe: stripe.get_index() as u64,
+                                        row: row as u64,
+                                        column: *column as u64,
+                                    })
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        writer.write_record(record)?;
+                    }
+                }
+            }
+        }
+        Command::Cast {
+            output,
+            set,
+            on_error,
+            path,
+        } => {
+            let casts = orcrs::infer::parse_schema(&set)?;
+            let policy = match on_error.as_str() {
+                "drop-stripe" => OutOfRangePolicy::DropStripe,
+                "null" => OutOfRangePolicy::Null,
+                _ => OutOfRangePolicy::Fail,
+            };
+
+            let mut orc_file = OrcFile::open(&path)?;
+            let writer = BufWriter::new(File::create(&output)?);
+            let report = orcrs::cast::cast(&mut orc_file, &casts, policy, writer)?;
+
+            for stripe_index in report.get_dropped_stripes() {
+                log::warn!("Dropped stripe {} with out-of-range values", stripe_index);
+            }
+
+            for value in report.get_null_values() {
+                log::warn!(
+                    "Set out-of-range value {} of {} to null (stripe {}, row {})",
+                    value.value,
+                    value.field_name,
+                    value.stripe_index,
+                    value.row_index
+                );
+            }
+        }
+        Command::Bench {
+            columns,
+            iterations,
+            path,
+        } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let stripes = orc_file.get_stripe_info()?;
+            let column_indices = if columns.is_empty() {
+                (0..orc_file.get_field_names().len()).collect()
+            } else {
+                lookup_columns(&orc_file, &columns)?
+            };
+            let row_count = orc_file.get_footer().get_number_of_rows();
+
+            let read_indices = field_columns(&orc_file, &column_indices)?;
+            let mut column_totals = vec![ColumnBenchmark::default(); column_indices.len()];
+
+            for iteration in 0..iterations {
+                let mut iteration_total = ColumnBenchmark::default();
+
+                for stripe in &stripes {
+                    for (i, column_index) in read_indices.iter().enumerate() {
+                        let result = bench_column(&mut orc_file, stripe, *column_index)?;
+                        column_totals[i] += &result;
+                        iteration_total += &result;
+                    }
+                }
+
+                println!(
+                    "Iteration {}: {} rows in {:.3}s ({:.0} rows/s, {:.2} MB/s)",
+                    iteration + 1,
+                    row_count,
+                    iteration_total.duration.as_secs_f64(),
+                    row_count as f64 / iteration_total.duration.as_secs_f64(),
+                    iteration_total.megabytes_per_second()
+                );
+            }
+
+            if iterations > 0 {
+                let field_names = orc_file.get_field_names();
+
+                for (column_index, total) in column_indices.iter().zip(column_totals) {
+                    println!(
+                        "Column {} ({}): {:.3}ms per iteration, {:.2} MB/s, {} bytes read, {} bytes decompressed",
+                        column_index,
+                        field_names[*column_index],
+                        total.duration.as_secs_f64() * 1000.0 / iterations as f64,
+                        total.megabytes_per_second(),
+                        total.bytes_read / iterations as u64,
+                        total.bytes_decompressed / iterations as u64
+                    );
+                }
+            }
+        }
+        Command::Describe { path } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let stripe_infos = orc_file.get_stripe_info()?;
+            let row_count = orc_file.get_footer().get_number_of_rows();
+
+            let mut rows = vec![[
+                "name", "type", "encoding", "nulls", "distinct", "min", "max",
+            ]
+            .map(String::from)
+            .to_vec()];
+
+            for (field_index, field_name) in orc_file.get_field_names().iter().enumerate() {
+                let column_id = orc_file
+                    .get_column_id(field_index)
+                    .ok_or(orcrs::parser::Error::InvalidMetadata)?
+                    as usize;
+                let kind = orc_file
+                    .get_footer()
+                    .get_types()
+                    .get(column_id)
+                    .ok_or(orcrs::parser::Error::InvalidMetadata)?
+                    .get_kind();
+                let statistics = orc_file.get_file_statistics().get(column_id);
+
+                let mut encodings = vec![];
+                // Dictionaries are per-stripe, so the largest is a lower bound on distinct values.
+                let mut dictionary_size = None;
+
+                for stripe_info in &stripe_infos {
+                    if let Some(encoding) = stripe_info.column_encodings().get(column_id) {
+                        let encoding_name = format!("{:?}", encoding.get_kind());
+                        if !encodings.contains(&encoding_name) {
+                            encodings.push(encoding_name);
+                        }
+
+                        if let Some(size) = encoding.get_dictionary_size() {
+                            dictionary_size = dictionary_size.max(Some(size));
+                        }
+                    }
+                }
+
+                let distinct = match dictionary_size {
+                    Some(size) if stripe_infos.len() > 1 => format!(">={}", size),
+                    Some(size) => size.to_string(),
+                    None => String::new(),
+                };
+
+                let nulls = statistics
+                    .and_then(|statistics| statistics.get_number_of_values())
+                    .map(|value_count| row_count.saturating_sub(value_count).to_string())
+                    .unwrap_or_default();
+
+                let (min, max) = statistics
+                    .and_then(|statistics| statistics.get_range())
+                    .map(format_range)
+                    .unwrap_or_default();
+
+                rows.push(vec![
+                    field_name.to_string(),
+                    format!("{:?}", kind),
+                    encodings.join("/"),
+                    nulls,
+                    distinct,
+                    truncate(&escape(&min), DESCRIBE_VALUE_WIDTH),
+                    truncate(&escape(&max), DESCRIBE_VALUE_WIDTH),
+                ]);
+            }
+
+            print_table(&rows);
+        }
+        Command::Stats {
+            column,
+            columns_matching,
+            distinct,
+            histogram,
+            buckets,
+            top,
+            path,
+        } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let column_indices = match columns_matching {
+                Some(pattern) => matching_columns(&orc_file, &regex::Regex::new(&pattern)?)?,
+                None if column.is_empty() => (0..orc_file.get_field_names().len()).collect(),
+                None => lookup_columns(&orc_file, &column)?,
+            };
+
+            let mut summaries = Vec::with_capacity(column_indices.len());
+
+            for column_index in &column_indices {
+                let kind = orc_file
+                    .get_column_id(*column_index)
+                    .and_then(|column_id| orc_file.get_footer().get_types().get(column_id as usize))
+                    .ok_or(orcrs::parser::Error::InvalidMetadata)?
+                    .get_kind();
+
+                let summary = if !histogram {
+                    ColumnSummary::Count
+                } else if kind == TypeKind::String {
+                    ColumnSummary::FrequentValues(FrequentValues::new())
+                } else {
+                    match integer_bounds(&mut orc_file, *column_index, kind)? {
+                        Some((min, max)) => {
+                            ColumnSummary::Histogram(Histogram::new(min, max, buckets))
+                        }
+                        None => ColumnSummary::Count,
+                    }
+                };
+
+                summaries.push(ColumnStats {
+                    kind,
+                    null_count: 0,
+                    distinct_count: distinct.then(DistinctCount::new),
+                    summary,
+                });
+            }
+
+            for stripe in orc_file.get_stripe_info()? {
+                for (column_index, stats) in field_columns(&orc_file, &column_indices)?
+                    .iter()
+                    .zip(summaries.iter_mut())
+                {
+                    let column = orc_file.read_column(&stripe, *column_index)?;
+
+                    stats.null_count += (0..stripe.get_row_count())
+                        .filter(|row| column.get(*row) == Some(Value::Null))
+                        .count() as u64;
+
+                    if let Some(distinct_count) = stats.distinct_count.as_mut() {
+                        distinct_count.add_column(&column);
+                    }
+
+                    match &mut stats.summary {
+                        ColumnSummary::Count => {}
+                        ColumnSummary::Histogram(histogram) => histogram.add_column(&column),
+                        ColumnSummary::FrequentValues(frequent_values) => {
+                            frequent_values.add_column(&column)
+                        }
+                    }
+                }
+            }
+
+            let field_names = orc_file.get_field_names();
+            let row_count = orc_file.get_footer().get_number_of_rows();
+            let mut header = ["name", "type", "values", "nulls"]
+                .map(String::from)
+                .to_vec();
+            if distinct {
+                header.push("distinct (approx.)".to_string());
+            }
+            let mut rows = vec![header];
+
+            for (column_index, stats) in column_indices.iter().zip(&summaries) {
+                let mut row = vec![
+                    field_names[*column_index].to_string(),
+                    format!("{:?}", stats.kind),
+                    (row_count - stats.null_count).to_string(),
+                    stats.null_count.to_string(),
+                ];
+                if let Some(distinct_count) = &stats.distinct_count {
+                    row.push(distinct_count.estimate().to_string());
+                }
+                rows.push(row);
+            }
+
+            print_table(&rows);
+
+            for (column_index, stats) in column_indices.iter().zip(&summaries) {
+                let rows = match &stats.summary {
+                    ColumnSummary::Count => continue,
+                    ColumnSummary::Histogram(histogram) => {
+                        std::iter::once(["min", "max", "count"].map(String::from).to_vec())
+                            .chain(histogram.get_buckets().into_iter().map(|(range, count)| {
+                                vec![
+                                    range.start().to_string(),
+                                    range.end().to_string(),
+                                    count.to_string(),
+                                ]
+                            }))
+                            .collect::<Vec<_>>()
+                    }
+                    ColumnSummary::FrequentValues(frequent_values) => {
+                        std::iter::once(["value", "count"].map(String::from).to_vec())
+                            .chain(frequent_values.get_top(top).into_iter().map(
+                                |(value, count)| {
+                                    vec![
+                                        truncate(&escape(value), DESCRIBE_VALUE_WIDTH),
+                                        count.to_string(),
+                                    ]
+                                },
+                            ))
+                            .collect::<Vec<_>>()
+                    }
+                };
+
+                println!();
+                println!("{}", field_names[*column_index]);
+                print_table(&rows);
+            }
+        }
+        Command::Schema { format, name, path } => {
+            let orc_file = OrcFile::open(&path)?;
+            let format = match format.as_str() {
+                "avro" => SchemaFormat::Avro,
+                "arrow" => SchemaFormat::Arrow,
+                "jsonschema" => SchemaFormat::JsonSchema,
+                "hive-ddl" => SchemaFormat::HiveDdl,
+                _ => SchemaFormat::Orc,
+            };
+
+            println!("{}", format_schema(orc_file.get_footer(), format, &name)?);
+        }
+        Command::SchemaDiff { paths } => {
+            let mut orc_paths = vec![];
+
+            for path in paths {
+                let path = PathBuf::from(path);
+
+                if path.is_dir() {
+                    let mut dir_paths = std::fs::read_dir(&path)?
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    dir_paths.retain(|path| {
+                        path.extension().is_some_and(|extension| extension == "orc")
+                    });
+                    dir_paths.sort();
+                    orc_paths.extend(dir_paths);
+                } else {
+                    orc_paths.push(path);
+                }
+            }
+
+            let mut reference = None;
+            let mut breaking_count = 0;
+
+            for orc_path in orc_paths {
+                let orc_file = OrcFile::open(&orc_path)?;
+                let schema = Schema::from_footer(orc_file.get_footer())
+                    .ok_or(orcrs::parser::Error::InvalidMetadata)?;
+
+                match &reference {
+                    None => {
+                        println!("{} (reference)", orc_path.display());
+                        reference = Some(schema);
+                    }
+                    Some(reference) => {
+                        let incompatibilities = reference.check_compatible(&schema);
+
+                        if !incompatibilities.is_empty() {
+                            println!("{}", orc_path.display());
+
+                            for incompatibility in incompatibilities {
+                                if incompatibility.is_breaking() {
+                                    breaking_count += 1;
+                                }
+
+                                println!("  {}", format_incompatibility(&incompatibility));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if breaking_count > 0 {
+                return Err(Error::IncompatibleSchemas(breaking_count));
+            }
+        }
+        Command::Codegen { struct_name, path } => {
+            let orc_file = OrcFile::open(&path)?;
+            print!("{}", orcrs::codegen::row_struct(&orc_file, &struct_name));
+        }
+        Command::Hash { unordered, path } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            let row_order = if unordered {
+                RowOrder::Insensitive
+            } else {
+                RowOrder::Sensitive
+            };
+            let hash = orcrs::hash::content_hash(&mut orc_file, row_order)?;
+
+            println!(
+                "{}",
+                hash.iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            );
+        }
+        Command::Repl { path } => {
+            let mut orc_file = OrcFile::open(&path)?;
+            run_repl(&mut orc_file)?;
+        }
+        Command::Info { raw, path } => {
+            let mut orc_file = OrcFile::open(&path)?;
+
+            if raw {
+                let footer = orc_file.get_footer();
+                println!("Footer: {:?}\n================", footer);
+
+                for (i, (stripe_footer, stripe_info)) in orc_file
+                    .get_stripe_footers()?
+                    .iter()
+                    .zip(orc_file.get_stripe_info()?)
+                    .enumerate()
+                {
+                    println!("Stripe {} footer: {:?}\n----------------", i, stripe_footer);
+                    println!("Stripe {} info: {:?}\n================", i, stripe_info);
+                }
+            } else {
+                let layouts = orc_file.column_layout()?;
+                let footer = orc_file.get_footer();
+                let column_count = footer.get_types().len();
+
+                println!(
+                    "{} rows in {} stripes, {:?} compression\n",
+                    footer.get_number_of_rows(),
+                    footer.get_stripes().len(),
+                    orc_file.get_postscript().get_compression()
+                );
+
+                // Stored bytes by column id and stream group.
+                let mut sizes = vec![[0; INFO_STREAM_GROUPS.len()]; column_count];
+
+                for layout in &layouts {
+                    for (kind, len) in layout.get_streams() {
+                        if let Some(column_sizes) = sizes.get_mut(layout.get_column_id() as usize) {
+                            column_sizes[info_stream_group(*kind)] += len;
+                        }
+                    }
+                }
+
+                let total = sizes.iter().flatten().sum::<u64>();
+                let field_names = (0..orc_file.get_field_names().len())
+                    .filter_map(|field_index| {
+                        orc_file.get_column_id(field_index).map(|column_id| {
+                            (column_id, orc_file.get_field_names()[field_index].as_str())
+                        })
+                    })
+                    .collect::<HashMap<_, _>>();
+
+                let mut rows = vec![["id", "name", "type"]
+                    .into_iter()
+                    .chain(INFO_STREAM_GROUPS)
+                    .chain(["total", "%", "ratio"])
+                    .map(String::from)
+                    .collect::<Vec<_>>()];
+
+                for (column_id, column_sizes) in sizes.iter().enumerate().skip(1) {
+                    let kind = footer.get_types()[column_id].get_kind();
+                    let column_total = column_sizes.iter().sum::<u64>();
+                    let ratio = orc_file
+                        .get_file_statistics()
+                        .get(column_id)
+                        .and_then(|statistics| raw_data_size(kind, statistics))
+                        .filter(|_| column_total > 0)
+                        .map(|raw_size| format!("{:.2}", raw_size as f64 / column_total as f64))
+                        .unwrap_or_default();
+
+                    let mut row = vec![
+                        column_id.to_string(),
+                        field_names
+                            .get(&(column_id as u32))
+                            .unwrap_or(&"")
+                            .to_string(),
+                        format!("{:?}", kind),
+                    ];
+                    row.extend(column_sizes.iter().map(|size| size.to_string()));
+                    row.push(column_total.to_string());
+                    row.push(if total > 0 {
+                        format!("{:.1}", column_total as f64 * 100.0 / total as f64)
+                    } else {
+                        String::new()
+                    });
+                    row.push(ratio);
+
+                    rows.push(row);
+                }
+
+                print_table(&rows);
             }
         }
-        Command::Validate { path } => match OrcFile::open(&path) {
-            Ok(_) => {}
-            Err(error) => {
+        Command::Validate {
+            strict,
+            compare,
+            key,
+            path,
+        } => {
+            let result = OrcFile::open(&path).and_then(|mut orc_file| {
+                if strict {
+                    orc_file.set_strict(true);
+
+                    for stripe in orc_file.get_stripe_info()? {
+                        for column_id in 0..stripe.get_column_count() {
+                            match orc_file.read_column(&stripe, column_id) {
+                                // Nested structs (as in ACID files) have no values to check.
+                                Err(orcrs::parser::Error::UnsupportedType(TypeKind::Struct)) => {}
+                                other => {
+                                    other?;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+
+            if let Err(error) = result {
                 log::error!("Error in {}: {:?}", path, error);
                 std::process::exit(1);
             }
-        },
+
+            if let Some(reference_path) = compare {
+                let mut orc_file = OrcFile::open(&path)?;
+                let key = key.iter().map(String::as_str).collect::<Vec<_>>();
+                let comparison = orcrs::compare::compare_with_reference(
+                    &mut orc_file,
+                    &key,
+                    read_reference(&reference_path)?,
+                )?;
+
+                for mismatch in comparison.get_mismatches() {
+                    println!(
+                        "Mismatch for {} in {}: expected {}, found {}",
+                        mismatch.get_key(),
+                        mismatch.get_field_name(),
+                        mismatch.get_expected(),
+                        format_value(&mismatch.get_actual().as_value(), "null")
+                    );
+                }
+                for key in comparison.get_missing_from_file() {
+                    println!("Missing from {}: {}", path, key);
+                }
+                for key in comparison.get_missing_from_reference() {
+                    println!("Missing from {}: {}", reference_path, key);
+                }
+
+                if !comparison.is_match() {
+                    log::error!(
+                        "{} rows matched, {} field mismatches, {} records missing, {} rows missing from reference",
+                        comparison.get_matched_count(),
+                        comparison.get_mismatches().len(),
+                        comparison.get_missing_from_file().len(),
+                        comparison.get_missing_from_reference().len()
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
     }
 
     Ok(())
@@ -96,47 +999,1683 @@ fn main() -> Result<(), Error> {
 struct Opts {
     /// Level of verbosity
     #[clap(short, long, global = true, action = ArgAction::Count)]
-    verbose: i32,
+    verbose: u8,
     #[clap(subcommand)]
     command: Command,
 }
 
+// The command is only parsed once, so the size of the export options doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Parser)]
 enum Command {
     /// Export the contents of the ORC file
     Export {
         /// Export format
-        #[clap(short, long, default_value = "csv", value_parser(["csv"]))]
+        #[clap(short, long, default_value = "csv", value_parser(["csv", "msgpack", "ndjson", "pg-copy", "sqlite", "xlsx"]))]
         format: String,
-        /// Column indices (comma-separated list of numbers)
+        /// Top-level field indices (comma-separated list of numbers)
         #[clap(short, long)]
         columns: Option<String>,
-        /// Include header
+        /// Regular expression for the names of the columns to export
+        #[clap(long, conflicts_with = "columns")]
+        columns_matching: Option<String>,
+        /// Columns not to export (comma-separated list of names)
+        #[clap(long, value_delimiter = ',', conflicts_with = "columns")]
+        exclude_columns: Vec<String>,
+        /// Fields to export by name or dotted path (e.g. "id,user.address.city"), reading only
+        /// their leaf columns
+        #[clap(long, value_delimiter = ',', conflicts_with_all = ["columns", "columns_matching", "exclude_columns"])]
+        fields: Vec<String>,
+        /// Columns and expressions to export (e.g. "id, lower(screen_name) AS sn")
+        #[clap(short, long, conflicts_with_all = ["columns", "columns_matching", "exclude_columns", "fields"])]
+        select: Option<String>,
+        /// Include header (xlsx worksheets always have one)
         #[clap(long)]
         header: bool,
         /// String to use for null values
         #[clap(long, default_value = "")]
         null: String,
-        /// ORC file
+        /// Output path (required for SQLite and xlsx, otherwise defaults to standard output)
+        #[clap(short, long, required_if_eq_any([("format", "sqlite"), ("format", "xlsx")]))]
+        output: Option<String>,
+        /// Table to create or append to in the SQLite database
+        #[clap(long, required_if_eq("format", "sqlite"))]
+        table: Option<String>,
+        /// Integer columns to export as timestamps (comma-separated list of names)
+        #[clap(long, value_delimiter = ',')]
+        timestamp_columns: Vec<String>,
+        /// Format for timestamp columns (using strftime syntax)
+        #[clap(long, default_value = "%Y-%m-%dT%H:%M:%SZ")]
+        timestamp_format: String,
+        /// Unit of the values in timestamp columns
+        #[clap(long, default_value = "s", value_parser(["s", "ms", "us"]))]
+        timestamp_unit: String,
+        /// Include each row's position in the file as a leading "row_number" column
+        #[clap(long)]
+        row_numbers: bool,
+        /// Export empty strings as nulls
+        #[clap(long)]
+        empty_as_null: bool,
+        /// Strip the trailing space padding from CHAR values
+        #[clap(long)]
+        trim_char: bool,
+        /// Export columns of unsupported types as nulls instead of failing
+        #[clap(long)]
+        skip_unsupported: bool,
+        /// Maximum number of rows per xlsx worksheet (further rows are written to new worksheets)
+        #[clap(long)]
+        sheet_rows: Option<u32>,
+        /// Watch a directory and export ORC files as they are added
+        #[clap(long)]
+        watch: bool,
+        /// Number of seconds to wait between checks for new files in watch mode
+        #[clap(long, default_value = "1")]
+        poll_interval: u64,
+        /// ORC file (or directory in watch mode)
         path: String,
     },
-    /// Dump raw info about the ORC file
-    Info {
+    /// Aggregate rows grouped by the values of some columns
+    Agg {
+        /// Columns to group by (comma-separated list of names)
+        #[clap(long, value_delimiter = ',')]
+        group_by: Vec<String>,
+        /// Include the number of rows in each group
+        #[clap(long)]
+        count: bool,
+        /// Integer columns to sum (comma-separated list of names)
+        #[clap(long, value_delimiter = ',')]
+        sum: Vec<String>,
+        /// String to use for null values
+        #[clap(long, default_value = "")]
+        null: String,
         /// ORC file
         path: String,
     },
-    /// Validate the ORC file footer
-    Validate {
+    /// Export the rows of the ORC file with duplicate keys removed
+    Dedupe {
+        /// Key columns (comma-separated list of names)
+        #[clap(long, value_delimiter = ',', required = true)]
+        key: Vec<String>,
+        /// Which row to keep for each key
+        #[clap(long, default_value = "first", value_parser(["first", "last"]))]
+        keep: String,
+        /// Include header
+        #[clap(long)]
+        header: bool,
+        /// String to use for null values
+        #[clap(long, default_value = "")]
+        null: String,
         /// ORC file
         path: String,
     },
+    /// Export the rows of the ORC file sorted by some columns
+    Sort {
+        /// Columns to sort by (comma-separated list of names)
+        #[clap(long, value_delimiter = ',', required = true)]
+        by: Vec<String>,
+        /// Sort in descending order
+        #[clap(long)]
+        reverse: bool,
+        /// Maximum number of rows to sort in memory before spilling to temporary files
+        #[clap(
+            long,
+            default_value = "1000000",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        max_rows_in_memory: usize,
+        /// Include header
+        #[clap(long)]
+        header: bool,
+        /// String to use for null values
+        #[clap(long, default_value = "")]
+        null: String,
+        /// ORC file
+        path: String,
+    },
+    /// Split the ORC file into smaller files by copying whole stripes
+    Split {
+        /// Maximum number of rows per file (a single larger stripe will still be copied whole)
+        #[clap(long, conflicts_with = "stripes")]
+        rows: Option<u64>,
+        /// Maximum number of stripes per file (defaults to one)
+        #[clap(long)]
+        stripes: Option<usize>,
+        /// Pad the output so that stripes don't cross boundaries of blocks of this size (in bytes)
+        #[clap(long)]
+        block_size: Option<u64>,
+        /// Maximum padding as a fraction of the block size
+        #[clap(long, default_value = "0.05", requires = "block_size")]
+        padding_tolerance: f64,
+        /// Output path template ("{}" is replaced by the part number)
+        #[clap(short, long, default_value = "part-{}.orc")]
+        output: String,
+        /// ORC file
+        path: String,
+    },
+    /// Copy the stripes that can be decoded into a new file, reporting the rows that were lost
+    Repair {
+        /// Output path
+        #[clap(short, long)]
+        output: String,
+        /// ORC file
+        path: String,
+    },
+    /// Export the rows where a string column matches a regular expression
+    Grep {
+        /// String column to search
+        #[clap(long)]
+        column: String,
+        /// Regular expression
+        #[clap(long)]
+        regex: String,
+        /// Top-level field indices to export (comma-separated list of numbers)
+        #[clap(short, long)]
+        columns: Option<String>,
+        /// Include header
+        #[clap(long)]
+        header: bool,
+        /// String to use for null values
+        #[clap(long, default_value = "")]
+        null: String,
+        /// ORC file
+        path: String,
+    },
+    /// Copy the ORC file with some columns changed to compatible types
+    Cast {
+        /// Output path
+        #[clap(short, long)]
+        output: String,
+        /// New column types (e.g. "followers_count:bigint,id:int")
+        #[clap(long)]
+        set: String,
+        /// What to do with values that don't fit in the new type (failing, dropping their
+        /// stripes, or setting them to null)
+        #[clap(long, default_value = "fail", value_parser(["fail", "drop-stripe", "null"]))]
+        on_error: String,
+        /// ORC file
+        path: String,
+    },
+    /// Measure decode throughput by reading columns repeatedly
+    Bench {
+        /// Columns to read (comma-separated list of names; defaults to all columns)
+        #[clap(long, value_delimiter = ',')]
+        columns: Vec<String>,
+        /// Number of times to read the columns
+        #[clap(long, default_value = "5")]
+        iterations: usize,
+        /// ORC file
+        path: String,
+    },
+    /// Summarize each column using the file metadata
+    Describe {
+        /// ORC file
+        path: String,
+    },
+    /// Summarize columns by scanning their values
+    Stats {
+        /// Columns (comma-separated list of names; defaults to all columns)
+        #[clap(long, value_delimiter = ',')]
+        column: Vec<String>,
+        /// Regular expression for the names of the columns to summarize
+        #[clap(long, conflicts_with = "column")]
+        columns_matching: Option<String>,
+        /// Estimate the number of distinct values in each column
+        #[clap(long)]
+        distinct: bool,
+        /// Show a histogram for integer columns and the most frequent values for string columns
+        #[clap(long)]
+        histogram: bool,
+        /// Number of histogram buckets
+        #[clap(long, default_value = "10")]
+        buckets: usize,
+        /// Number of frequent values to show for string columns
+        #[clap(long, default_value = "10")]
+        top: usize,
+        /// ORC file
+        path: String,
+    },
+    /// Print the schema of the ORC file
+    Schema {
+        /// Schema format
+        #[clap(short, long, default_value = "orc", value_parser(["orc", "avro", "arrow", "jsonschema", "hive-ddl"]))]
+        format: String,
+        /// Table name (for Hive), record name (for Avro), or title (for JSON Schema)
+        #[clap(long, default_value = "row")]
+        name: String,
+        /// ORC file
+        path: String,
+    },
+    /// Compare the schemas of ORC files with the schema of the first file
+    SchemaDiff {
+        /// ORC files or directories
+        #[clap(required = true)]
+        paths: Vec<String>,
+    },
+    /// Generate a Rust struct for deserializing the rows of the ORC file
+    Codegen {
+        /// Name of the generated struct
+        #[clap(long, default_value = "Row")]
+        struct_name: String,
+        /// ORC file
+        path: String,
+    },
+    /// Compute a SHA-256 hash of the decoded rows (independent of compression and layout)
+    Hash {
+        /// Ignore the order of the rows
+        #[clap(long)]
+        unordered: bool,
+        /// ORC file
+        path: String,
+    },
+    /// Explore the ORC file interactively (type "help" for a list of commands)
+    Repl {
+        /// ORC file
+        path: String,
+    },
+    /// Show the stored size of each column by stream kind
+    Info {
+        /// Dump the raw file and stripe metadata instead
+        #[clap(long)]
+        raw: bool,
+        /// ORC file
+        path: String,
+    },
+    /// Validate the ORC file footer
+    Validate {
+        /// Also decode all data and check it against the file metadata
+        #[clap(long)]
+        strict: bool,
+        /// Compare the rows with a reference NDJSON or CSV file (CSV if the extension is "csv")
+        #[clap(long, requires = "key")]
+        compare: Option<String>,
+        /// Key columns for joining rows with the reference records (comma-separated list of names)
+        #[clap(long, value_delimiter = ',', requires = "compare")]
+        key: Vec<String>,
+        /// ORC file
+        path: String,
+    },
+}
+
+fn escape(input: &str) -> String {
+    input.replace('\n', "\\n")
+}
+
+/// The export options that apply to each exported file.
+struct ExportOptions {
+    column_indices: Option<Vec<usize>>,
+    columns_matching: Option<regex::Regex>,
+    exclude_columns: Vec<String>,
+    fields: Vec<String>,
+    select: Option<String>,
+    timestamp_format: TimestampFormat,
+    row_numbers: bool,
+}
+
+// The name of the leading column added by `--row-numbers`.
+const ROW_NUMBER_FIELD_NAME: &str = "row_number";
+
+fn export_rows(
+    writer: &mut RecordWriter,
+    orc_file: &mut OrcFile,
+    options: &ExportOptions,
+    header: bool,
+) -> Result<(), Error> {
+    let timestamp_format = &options.timestamp_format;
+
+    if let Some(select) = &options.select {
+        let select = orcrs::expr::Select::parse(select, |name| orc_file.get_field_index(name))?;
+        let mut field_names = select
+            .get_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let mut kinds = vec![None; field_names.len()];
+
+        if options.row_numbers {
+            field_names.insert(0, ROW_NUMBER_FIELD_NAME.to_string());
+            kinds.insert(0, Some(TypeKind::Long));
+        }
+
+        let timestamp_positions = timestamp_format.get_positions(&field_names)?;
+        for position in &timestamp_positions {
+            kinds[*position] = Some(TypeKind::String);
+        }
+
+        writer.write_header(&field_names, &kinds, header)?;
+
+        let rows =
+            orc_file.map_rows(&field_columns(orc_file, select.get_columns())?, |values| {
+                let (row_number, values) = match values.split_first() {
+                    Some((row_number, values)) if options.row_numbers => {
+                        (Some(*row_number), values)
+                    }
+                    _ => (None, values),
+                };
+                let values = select.eval(values)?;
+
+                timestamp_format.write_values(
+                    writer,
+                    &timestamp_positions,
+                    &row_number
+                        .into_iter()
+                        .chain(values.iter().map(OwnedValue::as_value))
+                        .collect::<Vec<_>>(),
+                )
+            })?;
+
+        for result in if options.row_numbers {
+            rows.with_row_numbers()
+        } else {
+            rows
+        } {
+            result?;
+        }
+
+        return Ok(());
+    }
+
+    if !options.fields.is_empty() {
+        let (column_indices, field_names) = lookup_leaf_columns(orc_file, &options.fields)?;
+
+        return export_columns(
+            writer,
+            orc_file,
+            options,
+            header,
+            &column_indices,
+            Some(field_names),
+        );
+    }
+
+    let field_names = orc_file.get_field_names();
+
+    let mut column_indices = match (&options.column_indices, &options.columns_matching) {
+        (Some(value), _) => value.to_vec(),
+        (None, Some(regex)) => matching_columns(orc_file, regex)?,
+        (None, None) => (0..field_names.len()).collect(),
+    };
+
+    if !options.exclude_columns.is_empty() {
+        // Check for unknown names, since a typo would otherwise export the excluded column.
+        lookup_columns(orc_file, &options.exclude_columns)?;
+
+        let remaining = Schema::from_footer(orc_file.get_footer())
+            .ok_or(orcrs::parser::Error::InvalidMetadata)?
+            .get_field_indices_except(&options.exclude_columns);
+        column_indices.retain(|index| remaining.contains(index));
+    }
+
+    let field_names = column_indices
+        .iter()
+        .map(|i| field_names.get(*i).cloned())
+        .collect::<Option<Vec<_>>>();
+
+    export_columns(
+        writer,
+        orc_file,
+        options,
+        header,
+        &column_indices,
+        field_names,
+    )
+}
+
+fn export_columns(
+    writer: &mut RecordWriter,
+    orc_file: &mut OrcFile,
+    options: &ExportOptions,
+    header: bool,
+    column_indices: &[usize],
+    field_names: Option<Vec<String>>,
+) -> Result<(), Error> {
+    let timestamp_format = &options.timestamp_format;
+    let mut timestamp_positions = vec![];
+
+    if let Some(mut field_names) = field_names {
+        let mut kinds = column_indices
+            .iter()
+            .map(|i| {
+                orc_file
+                    .get_column_id(*i)
+                    .and_then(|column_id| orc_file.get_footer().get_types().get(column_id as usize))
+                    .map(|column_type| column_type.get_kind())
+            })
+            .collect::<Vec<_>>();
+
+        if options.row_numbers {
+            field_names.insert(0, ROW_NUMBER_FIELD_NAME.to_string());
+            kinds.insert(0, Some(TypeKind::Long));
+        }
+
+        timestamp_positions = timestamp_format.get_positions(&field_names)?;
+        for position in &timestamp_positions {
+            kinds[*position] = Some(TypeKind::String);
+        }
+
+        writer.write_header(&field_names, &kinds, header)?;
+    } else if header {
+        log::warn!("A header was requested but field names could not be found.")
+    }
+
+    let rows = orc_file.map_rows(&field_columns(orc_file, column_indices)?, |values| {
+        timestamp_format.write_values(writer, &timestamp_positions, values)
+    })?;
+
+    for result in if options.row_numbers {
+        rows.with_row_numbers()
+    } else {
+        rows
+    } {
+        result?;
+    }
+
+    Ok(())
+}
+
+/// Renders epoch-based integer columns as formatted timestamps.
+struct TimestampFormat {
+    column_names: Vec<String>,
+    format: String,
+    unit: String,
+}
+
+impl TimestampFormat {
+    fn new(column_names: Vec<String>, format: String, unit: &str) -> Result<Self, Error> {
+        if chrono::format::StrftimeItems::new(&format)
+            .any(|item| item == chrono::format::Item::Error)
+        {
+            return Err(Error::InvalidTimestampFormat(format));
+        }
+
+        Ok(Self {
+            column_names,
+            format,
+            unit: unit.to_string(),
+        })
+    }
+
+    /// The positions of the timestamp columns in the exported fields.
+    fn get_positions(&self, field_names: &[String]) -> Result<Vec<usize>, Error> {
+        self.column_names
+            .iter()
+            .map(|column_name| {
+                field_names
+                    .iter()
+                    .position(|field_name| field_name == column_name)
+                    .ok_or_else(|| Error::UnknownColumn(column_name.to_string()))
+            })
+            .collect()
+    }
+
+    fn format(&self, value: &Value) -> Result<Option<String>, Error> {
+        Ok(self
+            .date_time(value)?
+            .map(|date_time| date_time.format(&self.format).to_string()))
+    }
+
+    fn date_time(&self, value: &Value) -> Result<Option<chrono::DateTime<chrono::Utc>>, Error> {
+        let timestamp = match value {
+            Value::Null => return Ok(None),
+            Value::I64(value) => *value,
+            other => return Err(Error::InvalidTimestamp(format!("{:?}", other))),
+        };
+
+        let date_time = match self.unit.as_str() {
+            "ms" => chrono::DateTime::from_timestamp_millis(timestamp),
+            "us" => chrono::DateTime::from_timestamp_micros(timestamp),
+            _ => chrono::DateTime::from_timestamp(timestamp, 0),
+        }
+        .ok_or_else(|| Error::InvalidTimestamp(timestamp.to_string()))?;
+
+        Ok(Some(date_time))
+    }
+
+    /// Write a row with the values at the given positions formatted.
+    fn write_values(
+        &self,
+        writer: &mut RecordWriter,
+        positions: &[usize],
+        values: &[Value],
+    ) -> Result<(), Error> {
+        if positions.is_empty() {
+            return writer.write_values(values);
+        }
+
+        // Spreadsheets have a date type, so the timestamps aren't formatted as strings.
+        if let RecordWriter::Xlsx(xlsx_writer) = writer {
+            let date_times = positions
+                .iter()
+                .map(|position| {
+                    self.date_time(&values[*position])
+                        .map(|date_time| (*position, date_time))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            return xlsx_writer.write_row(values, &date_times);
+        }
+
+        let formatted = positions
+            .iter()
+            .map(|position| self.format(&values[*position]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut values = values.to_vec();
+        for (position, formatted) in positions.iter().zip(&formatted) {
+            values[*position] = formatted.as_deref().map_or(Value::Null, Value::Utf8);
+        }
+
+        writer.write_values(&values)
+    }
+}
+
+// The number of rows inserted in each SQLite transaction.
+const SQLITE_TRANSACTION_ROWS: usize = 10_000;
+
+/// Writes exported rows in one of the supported formats.
+enum RecordWriter {
+    /// CSV, NDJSON, and MessagePack.
+    Serde(SerdeWriter),
+    /// PostgreSQL's `COPY` text format (which has no header).
+    PgCopy {
+        writer: BufWriter<Box<dyn Write>>,
+    },
+    /// Rows are inserted into a table that's created from the first header.
+    Sqlite {
+        connection: rusqlite::Connection,
+        table: String,
+        insert: String,
+        pending_rows: usize,
+    },
+    Xlsx(Box<XlsxWriter>),
+}
+
+impl RecordWriter {
+    fn new(
+        format: &str,
+        output: Option<&str>,
+        table: Option<&str>,
+        null_string_value: &str,
+        sheet_rows: Option<u32>,
+    ) -> Result<Self, Error> {
+        if format == "xlsx" {
+            return Ok(Self::Xlsx(Box::new(XlsxWriter::new(
+                output.ok_or(Error::MissingOption("output"))?,
+                sheet_rows,
+            ))));
+        }
+
+        if format == "sqlite" {
+            return Ok(Self::Sqlite {
+                connection: rusqlite::Connection::open(
+                    output.ok_or(Error::MissingOption("output"))?,
+                )?,
+                table: table.ok_or(Error::MissingOption("table"))?.to_string(),
+                insert: String::new(),
+                pending_rows: 0,
+            });
+        }
+
+        let writer: Box<dyn Write> = match output {
+            Some(output) => Box::new(File::create(output)?),
+            None => Box::new(std::io::stdout()),
+        };
+
+        Ok(match format {
+            "msgpack" => Self::Serde(SerdeWriter::new(
+                SerdeOutput::MessagePack(BufWriter::new(writer)),
+                SerdeConfig::msgpack(),
+            )),
+            "ndjson" => Self::Serde(SerdeWriter::new(
+                SerdeOutput::Ndjson(BufWriter::new(writer)),
+                SerdeConfig::ndjson(),
+            )),
+            "pg-copy" => Self::PgCopy {
+                writer: BufWriter::new(writer),
+            },
+            _ => Self::Serde(SerdeWriter::new(
+                SerdeOutput::csv(writer),
+                SerdeConfig::csv(null_string_value),
+            )),
+        })
+    }
+
+    /// Set the field names for the following rows (which are only written for CSV if requested).
+    fn write_header(
+        &mut self,
+        names: &[String],
+        kinds: &[Option<TypeKind>],
+        header: bool,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Serde(serde_writer) => serde_writer.write_header(names, header)?,
+            Self::PgCopy { .. } => {}
+            Self::Xlsx(xlsx_writer) => {
+                xlsx_writer.field_names = names.to_vec();
+            }
+            Self::Sqlite {
+                connection,
+                table,
+                insert,
+                ..
+            } => {
+                let columns = names
+                    .iter()
+                    .zip(kinds)
+                    .map(|(name, kind)| {
+                        format!("{} {}", quote_identifier(name), sqlite_type(*kind))
+                            .trim_end()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+
+                connection.execute(
+                    &format!(
+                        "CREATE TABLE IF NOT EXISTS {} ({})",
+                        quote_identifier(table),
+                        columns.join(", ")
+                    ),
+                    [],
+                )?;
+
+                *insert = format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    quote_identifier(table),
+                    names
+                        .iter()
+                        .map(|name| quote_identifier(name))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    vec!["?"; names.len()].join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_values(&mut self, values: &[Value]) -> Result<(), Error> {
+        match self {
+            Self::Serde(serde_writer) => serde_writer.write_values(values)?,
+            Self::PgCopy { writer } => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        writer.write_all(b"\t")?;
+                    }
+
+                    match value {
+                        Value::Null => writer.write_all(b"\\N")?,
+                        Value::Bool(value) => writer.write_all(if *value { b"t" } else { b"f" })?,
+                        Value::U64(value) => write!(writer, "{}", value)?,
+                        Value::I64(value) => write!(writer, "{}", value)?,
+                        Value::Decimal { .. } => writer
+                            .write_all(value.to_decimal_string().unwrap_or_default().as_bytes())?,
+                        Value::Timestamp(value) => write!(writer, "{}", value)?,
+                        Value::Utf8(value) => writer.write_all(escape_pg_copy(value).as_bytes())?,
+                        // The hex format for bytea (with the backslash escaped for COPY).
+                        Value::Bytes(value) => write!(writer, "\\\\x{}", to_hex(value))?,
+                        // Lists are written as JSON (for json or jsonb columns).
+                        Value::List(value) => {
+                            writer.write_all(escape_pg_copy(&value.to_json_string()).as_bytes())?
+                        }
+                    }
+                }
+
+                writer.write_all(b"\n")?;
+            }
+            Self::Sqlite {
+                connection,
+                insert,
+                pending_rows,
+                ..
+            } => {
+                if *pending_rows == 0 {
+                    connection.execute_batch("BEGIN")?;
+                }
+
+                connection
+                    .prepare_cached(insert)?
+                    .execute(rusqlite::params_from_iter(values.iter().map(sqlite_value)))?;
+                *pending_rows += 1;
+
+                if *pending_rows == SQLITE_TRANSACTION_ROWS {
+                    connection.execute_batch("COMMIT")?;
+                    *pending_rows = 0;
+                }
+            }
+            Self::Xlsx(xlsx_writer) => xlsx_writer.write_row(values, &[])?,
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            Self::Serde(serde_writer) => serde_writer.flush()?,
+            Self::PgCopy { writer } => writer.flush()?,
+            Self::Xlsx(xlsx_writer) => xlsx_writer.save()?,
+            Self::Sqlite {
+                connection,
+                pending_rows,
+                ..
+            } => {
+                if *pending_rows > 0 {
+                    connection.execute_batch("COMMIT")?;
+                    *pending_rows = 0;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How rows are represented for a Serde-based format.
+#[derive(Clone, Debug)]
+struct SerdeConfig {
+    /// Write each row as a map from field names to values (instead of a sequence of values).
+    named_fields: bool,
+    /// Write nulls as this string (instead of the serializer's null).
+    null_string_value: Option<String>,
+    /// Write newlines in strings as `\n`.
+    escape_newlines: bool,
+    /// Write bytes values as hexadecimal strings (instead of the serializer's bytes).
+    hex_bytes: bool,
+    /// Write lists as JSON strings (for formats that don't support nested values).
+    json_lists: bool,
+}
+
+impl SerdeConfig {
+    fn csv(null_string_value: &str) -> Self {
+        Self {
+            named_fields: false,
+            null_string_value: Some(null_string_value.to_string()),
+            escape_newlines: true,
+            hex_bytes: true,
+            json_lists: true,
+        }
+    }
+
+    fn ndjson() -> Self {
+        Self {
+            named_fields: true,
+            null_string_value: None,
+            escape_newlines: false,
+            hex_bytes: true,
+            json_lists: false,
+        }
+    }
+
+    fn msgpack() -> Self {
+        Self {
+            named_fields: true,
+            null_string_value: None,
+            escape_newlines: false,
+            hex_bytes: false,
+            json_lists: false,
+        }
+    }
+}
+
+/// A row as it's serialized for export.
+struct SerdeRow<'a, 'v> {
+    config: &'a SerdeConfig,
+    field_names: &'a [String],
+    values: &'a [Value<'v>],
+}
+
+impl serde::Serialize for SerdeRow<'_, '_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        let values = self.values.iter().map(|value| SerdeValue {
+            config: self.config,
+            value,
+        });
+
+        if self.config.named_fields {
+            let mut map = serializer.serialize_map(Some(self.values.len()))?;
+            for (field_name, value) in self.field_names.iter().zip(values) {
+                map.serialize_entry(field_name, &value)?;
+            }
+            map.end()
+        } else {
+            let mut seq = serializer.serialize_seq(Some(self.values.len()))?;
+            for value in values {
+                seq.serialize_element(&value)?;
+            }
+            seq.end()
+        }
+    }
+}
+
+struct SerdeValue<'a, 'v> {
+    config: &'a SerdeConfig,
+    value: &'a Value<'v>,
+}
+
+impl serde::Serialize for SerdeValue<'_, '_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.value, &self.config.null_string_value) {
+            (Value::Null, Some(null_string_value)) => serializer.serialize_str(null_string_value),
+            (Value::Utf8(value), _) if self.config.escape_newlines => {
+                serializer.serialize_str(&escape(value))
+            }
+            (Value::Bytes(value), _) if self.config.hex_bytes => {
+                serializer.serialize_str(&to_hex(value))
+            }
+            (Value::List(value), _) if self.config.json_lists => {
+                serializer.serialize_str(&value.to_json_string())
+            }
+            (Value::List(value), _) => {
+                let values = value.iter().collect::<Vec<_>>();
+
+                serializer.collect_seq(values.iter().map(|value| SerdeValue {
+                    config: self.config,
+                    value,
+                }))
+            }
+            (value, _) => serde::Serialize::serialize(value, serializer),
+        }
+    }
+}
+
+/// The serializer for a Serde-based format.
+enum SerdeOutput {
+    Csv(Box<csv::Writer<Box<dyn Write>>>),
+    /// Newline-delimited JSON.
+    Ndjson(BufWriter<Box<dyn Write>>),
+    MessagePack(BufWriter<Box<dyn Write>>),
+}
+
+impl SerdeOutput {
+    fn csv(writer: Box<dyn Write>) -> Self {
+        // Headers are written separately, since rows are serialized as sequences.
+        Self::Csv(Box::new(
+            csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(writer),
+        ))
+    }
+
+    fn write<T: serde::Serialize>(&mut self, row: &T) -> Result<(), Error> {
+        match self {
+            Self::Csv(writer) => writer.serialize(row)?,
+            Self::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writer.write_all(b"\n")?;
+            }
+            Self::MessagePack(writer) => rmp_serde::encode::write(writer, row)?,
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            Self::Csv(writer) => writer.flush()?,
+            Self::Ndjson(writer) => writer.flush()?,
+            Self::MessagePack(writer) => writer.flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes rows in any of the Serde-based formats.
+///
+/// Adding a format only requires a `SerdeOutput` case and a `SerdeConfig` constructor.
+struct SerdeWriter {
+    output: SerdeOutput,
+    config: SerdeConfig,
+    field_names: Vec<String>,
+}
+
+impl SerdeWriter {
+    fn new(output: SerdeOutput, config: SerdeConfig) -> Self {
+        Self {
+            output,
+            config,
+            field_names: vec![],
+        }
+    }
+
+    /// Set the field names for the following rows (which are only written for CSV if requested).
+    fn write_header(&mut self, names: &[String], header: bool) -> Result<(), Error> {
+        self.field_names = names.to_vec();
+
+        if let (SerdeOutput::Csv(writer), true) = (&mut self.output, header) {
+            writer.write_record(names)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_values(&mut self, values: &[Value]) -> Result<(), Error> {
+        self.output.write(&SerdeRow {
+            config: &self.config,
+            field_names: &self.field_names,
+            values,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.output.flush()
+    }
+}
+
+// Excel's limit (including the header row).
+const XLSX_MAX_ROWS: u32 = 1_048_576;
+// Integers with larger magnitudes can't be represented exactly by Excel's numbers, so they're
+// written as strings instead (this includes most IDs).
+const XLSX_MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+/// Writes rows to worksheets, starting a new worksheet when the current one is full.
+///
+/// Each worksheet has a header row. The workbook is kept in memory and written when it's flushed.
+struct XlsxWriter {
+    workbook: rust_xlsxwriter::Workbook,
+    path: String,
+    field_names: Vec<String>,
+    max_rows: u32,
+    sheet_count: usize,
+    // The index of the next row in the current worksheet.
+    next_row: u32,
+    date_time_format: rust_xlsxwriter::Format,
+}
+
+impl XlsxWriter {
+    fn new(path: &str, sheet_rows: Option<u32>) -> Self {
+        Self {
+            workbook: rust_xlsxwriter::Workbook::new(),
+            path: path.to_string(),
+            field_names: vec![],
+            max_rows: sheet_rows
+                .map_or(XLSX_MAX_ROWS, |sheet_rows| sheet_rows.saturating_add(1))
+                .clamp(2, XLSX_MAX_ROWS),
+            sheet_count: 0,
+            next_row: 0,
+            date_time_format: rust_xlsxwriter::Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+        }
+    }
+
+    /// Write a row, using the given date-times in place of the values at their positions.
+    fn write_row(
+        &mut self,
+        values: &[Value],
+        date_times: &[(usize, Option<chrono::DateTime<chrono::Utc>>)],
+    ) -> Result<(), Error> {
+        if self.sheet_count == 0 || self.next_row == self.max_rows {
+            self.add_worksheet()?;
+        }
+
+        let worksheet = self.workbook.worksheet_from_index(self.sheet_count - 1)?;
+        let row = self.next_row;
+
+        for (column, value) in values.iter().enumerate() {
+            let column = column as u16;
+
+            if let Some((_, date_time)) = date_times
+                .iter()
+                .find(|(position, _)| *position == column as usize)
+            {
+                if let Some(date_time) = date_time {
+                    worksheet.write_datetime_with_format(
+                        row,
+                        column,
+                        date_time.naive_utc(),
+                        &self.date_time_format,
+                    )?;
+                }
+                continue;
+            }
+
+            match value {
+                Value::Null => {}
+                Value::Bool(value) => {
+                    worksheet.write_boolean(row, column, *value)?;
+                }
+                Value::U64(value) => {
+                    if *value <= XLSX_MAX_EXACT_INTEGER {
+                        worksheet.write_number(row, column, *value as f64)?;
+                    } else {
+                        worksheet.write_string(row, column, value.to_string())?;
+                    }
+                }
+                Value::I64(value) => {
+                    let value = *value;
+
+                    if value.unsigned_abs() <= XLSX_MAX_EXACT_INTEGER {
+                        worksheet.write_number(row, column, value as f64)?;
+                    } else {
+                        worksheet.write_string(row, column, value.to_string())?;
+                    }
+                }
+                // Written as strings to avoid losing precision.
+                Value::Decimal { .. } => {
+                    worksheet.write_string(
+                        row,
+                        column,
+                        value.to_decimal_string().unwrap_or_default(),
+                    )?;
+                }
+                Value::Timestamp(value) => match value.to_naive_date_time() {
+                    Some(date_time) => {
+                        worksheet.write_datetime_with_format(
+                            row,
+                            column,
+                            date_time,
+                            &self.date_time_format,
+                        )?;
+                    }
+                    None => {
+                        worksheet.write_string(row, column, value.to_string())?;
+                    }
+                },
+                Value::Utf8(value) => {
+                    worksheet.write_string(row, column, *value)?;
+                }
+                Value::List(value) => {
+                    worksheet.write_string(row, column, value.to_json_string())?;
+                }
+                Value::Bytes(value) => {
+                    worksheet.write_string(row, column, to_hex(value))?;
+                }
+            }
+        }
+
+        self.next_row += 1;
+
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<(), Error> {
+        // An empty workbook still needs a worksheet (with the header).
+        if self.sheet_count == 0 {
+            self.add_worksheet()?;
+        }
+
+        Ok(self.workbook.save(&self.path)?)
+    }
+
+    // Start a new worksheet with a header row.
+    fn add_worksheet(&mut self) -> Result<(), Error> {
+        let worksheet = self.workbook.add_worksheet();
+        for (column, field_name) in self.field_names.iter().enumerate() {
+            worksheet.write_string(0, column as u16, field_name)?;
+        }
+
+        self.sheet_count += 1;
+        self.next_row = 1;
+
+        Ok(())
+    }
+}
+
+// Backslashes and the characters used as delimiters need to be escaped in `COPY` text.
+fn escape_pg_copy(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Columns computed by expressions have no declared type.
+fn sqlite_type(kind: Option<TypeKind>) -> &'static str {
+    match kind {
+        Some(
+            TypeKind::Boolean | TypeKind::Byte | TypeKind::Short | TypeKind::Int | TypeKind::Long,
+        ) => "INTEGER",
+        Some(TypeKind::Float | TypeKind::Double) => "REAL",
+        Some(TypeKind::Binary) => "BLOB",
+        Some(_) => "TEXT",
+        None => "",
+    }
+}
+
+fn sqlite_value<'a>(value: &'a Value) -> ToSqlOutput<'a> {
+    ToSqlOutput::Borrowed(match value {
+        // Lists are stored as JSON text.
+        Value::List(value) => return ToSqlOutput::Owned(value.to_json_string().into()),
+        // Decimals are stored as text to avoid losing precision.
+        Value::Decimal { .. } => {
+            return ToSqlOutput::Owned(value.to_decimal_string().unwrap_or_default().into())
+        }
+        // Timestamps are stored as ISO 8601 text.
+        Value::Timestamp(value) => return ToSqlOutput::Owned(value.to_string().into()),
+        Value::Bool(value) => ValueRef::Integer(*value as i64),
+        // SQLite integers are signed, so unsigned values above the maximum wrap.
+        Value::U64(value) => ValueRef::Integer(*value as i64),
+        Value::I64(value) => ValueRef::Integer(*value),
+        Value::Utf8(value) => ValueRef::Text(value.as_bytes()),
+        Value::Bytes(value) => ValueRef::Blob(value),
+        Value::Null => ValueRef::Null,
+    })
+}
+
+fn format_value(value: &Value, null_string_value: &str) -> String {
+    match value {
+        Value::Null => null_string_value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::Decimal { .. } => value.to_decimal_string().unwrap_or_default(),
+        Value::Timestamp(value) => value.to_string(),
+        Value::Utf8(value) => escape(value),
+        Value::Bytes(value) => to_hex(value),
+        Value::List(value) => value.to_json_string(),
+    }
+}
+
+/// Timings and sizes for reading one or more column chunks.
+#[derive(Clone, Copy, Default)]
+struct ColumnBenchmark {
+    duration: Duration,
+    bytes_read: u64,
+    bytes_decompressed: u64,
+}
+
+impl ColumnBenchmark {
+    // Throughput in terms of the decompressed stream data.
+    fn megabytes_per_second(&self) -> f64 {
+        self.bytes_decompressed as f64 / 1_000_000.0 / self.duration.as_secs_f64()
+    }
+}
+
+impl std::ops::AddAssign<&ColumnBenchmark> for ColumnBenchmark {
+    fn add_assign(&mut self, other: &ColumnBenchmark) {
+        self.duration += other.duration;
+        self.bytes_read += other.bytes_read;
+        self.bytes_decompressed += other.bytes_decompressed;
+    }
+}
+
+fn bench_column(
+    orc_file: &mut OrcFile,
+    stripe: &StripeInfo,
+    column_index: usize,
+) -> Result<ColumnBenchmark, Error> {
+    let before = *orc_file.get_metrics();
+    let start = Instant::now();
+
+    match orc_file.read_column(stripe, column_index) {
+        // Nested structs have no values to decode.
+        Err(orcrs::parser::Error::UnsupportedType(TypeKind::Struct)) => {}
+        other => {
+            other?;
+        }
+    }
+
+    let duration = start.elapsed();
+    let after = orc_file.get_metrics();
+
+    Ok(ColumnBenchmark {
+        duration,
+        bytes_read: after.get_bytes_read() - before.get_bytes_read(),
+        bytes_decompressed: after.get_bytes_decompressed() - before.get_bytes_decompressed(),
+    })
+}
+
+const DESCRIBE_VALUE_WIDTH: usize = 32;
+
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() > width {
+        let mut truncated = value.chars().take(width - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_range(range: &ValueRange) -> (String, String) {
+    match range {
+        ValueRange::Integer(min, max) | ValueRange::Timestamp(min, max) => {
+            (min.to_string(), max.to_string())
+        }
+        ValueRange::Double(min, max) => (min.to_string(), max.to_string()),
+        ValueRange::String(min, max) | ValueRange::Decimal(min, max) => {
+            (min.to_string(), max.to_string())
+        }
+        ValueRange::Date(min, max) => (min.to_string(), max.to_string()),
+    }
+}
+
+/// Print rows with each column padded to its widest value.
+// The groups of streams whose sizes are shown by `info`.
+const INFO_STREAM_GROUPS: [&str; 5] = ["present", "data", "length", "dictionary", "other"];
+
+fn info_stream_group(kind: StreamKind) -> usize {
+    match kind {
+        StreamKind::Present => 0,
+        // Secondary streams hold value data (such as the nanoseconds of timestamps).
+        StreamKind::Data | StreamKind::Secondary => 1,
+        StreamKind::Length => 2,
+        StreamKind::DictionaryData | StreamKind::DictionaryCount => 3,
+        _ => 4,
+    }
+}
+
+// The size of a column's values without encoding or compression, if the statistics allow
+// estimating it (variable-length values are counted without their lengths).
+fn raw_data_size(kind: TypeKind, statistics: &ColumnStatistics) -> Option<u64> {
+    let width = match kind {
+        TypeKind::String | TypeKind::Binary | TypeKind::Varchar | TypeKind::Char => {
+            return statistics.get_total_length();
+        }
+        TypeKind::Boolean | TypeKind::Byte => 1,
+        TypeKind::Short => 2,
+        TypeKind::Int | TypeKind::Float | TypeKind::Date => 4,
+        TypeKind::Long | TypeKind::Double => 8,
+        TypeKind::Timestamp | TypeKind::TimestampInstant => 12,
+        _ => return None,
+    };
+
+    statistics
+        .get_number_of_values()
+        .map(|value_count| value_count * width)
+}
+
+fn print_table(rows: &[Vec<String>]) {
+    let mut widths = vec![];
+
+    for row in rows {
+        widths.resize(widths.len().max(row.len()), 0);
+
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        println!("{}", line.trim_end());
+    }
+}
+
+const REPL_HELP: &str = "Commands:
+  schema                            print the schema
+  head [n]                          print the first n rows (default 10)
+  select <exprs> [where <expr>]     print the values of expressions (see export --select)
+  stats <column>                    summarize a column
+  help                              print this message
+  quit                              exit";
+const REPL_DEFAULT_ROW_COUNT: usize = 10;
+const REPL_MAX_ROW_COUNT: usize = 100;
+
+/// Read commands from standard input until it's closed or the user quits.
+///
+/// Errors are printed without ending the session, so the file's tail is only parsed once.
+fn run_repl(orc_file: &mut OrcFile) -> Result<(), Error> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let mut line = String::new();
+
+    loop {
+        write!(stdout, "orcrs> ")?;
+        stdout.flush()?;
+
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            println!();
+            return Ok(());
+        }
+
+        match run_repl_command(orc_file, line.trim()) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(error) => eprintln!("Error: {:?}", error),
+        }
+    }
+}
+
+/// Run a single REPL command, returning false if the session should end.
+fn run_repl_command(orc_file: &mut OrcFile, input: &str) -> Result<bool, Error> {
+    let (command, args) = input
+        .split_once(char::is_whitespace)
+        .map(|(command, args)| (command, args.trim()))
+        .unwrap_or((input, ""));
+
+    match command.to_lowercase().as_str() {
+        "" => {}
+        "quit" | "exit" => return Ok(false),
+        "help" => println!("{}", REPL_HELP),
+        "schema" => println!(
+            "{}",
+            format_schema(orc_file.get_footer(), SchemaFormat::Orc, "row")?
+        ),
+        "head" => {
+            let row_count = if args.is_empty() {
+                REPL_DEFAULT_ROW_COUNT
+            } else {
+                args.parse()
+                    .map_err(|_| Error::InvalidReplCommand(input.to_string()))?
+            };
+            let column_indices = orc_file.get_field_column_indices();
+            let mut rows = vec![orc_file.get_field_names().to_vec()];
+
+            for result in orc_file
+                .map_rows(&column_indices, |values| {
+                    Ok::<_, Error>(repl_row(values.iter().copied()))
+                })?
+                .take(row_count)
+            {
+                rows.push(result?);
+            }
+
+            print_table(&rows);
+        }
+        "select" => {
+            // The condition is parsed as an extra item at the end of the select list, so that the
+            // columns for both are read together.
+            let (select, condition) = match split_keyword(args, "where") {
+                Some((select, condition)) => (select, Some(condition)),
+                None => (args, None),
+            };
+            let select = orcrs::expr::Select::parse(
+                &match condition {
+                    Some(condition) => format!("{}, {}", select, condition),
+                    None => select.to_string(),
+                },
+                |name| orc_file.get_field_index(name),
+            )?;
+            let mut names = select.get_names();
+            if condition.is_some() {
+                names.pop();
+            }
+
+            let mut rows = vec![names.iter().map(|name| name.to_string()).collect()];
+            let mut match_count = 0;
+
+            for result in
+                orc_file.map_rows(&field_columns(orc_file, select.get_columns())?, |values| {
+                    let mut values = select.eval(values)?;
+                    let selected = match condition {
+                        Some(_) => values.pop() == Some(OwnedValue::Bool(true)),
+                        None => true,
+                    };
+
+                    Ok::<_, Error>(selected.then_some(values))
+                })?
+            {
+                if let Some(values) = result? {
+                    match_count += 1;
+
+                    if match_count <= REPL_MAX_ROW_COUNT {
+                        rows.push(repl_row(values.iter().map(OwnedValue::as_value)));
+                    }
+                }
+            }
+
+            print_table(&rows);
+            if match_count > REPL_MAX_ROW_COUNT {
+                println!("({} more rows)", match_count - REPL_MAX_ROW_COUNT);
+            }
+        }
+        "stats" => {
+            let column_index = orc_file
+                .get_field_index(args)
+                .ok_or_else(|| Error::UnknownColumn(args.to_string()))?;
+            let kind = orc_file
+                .get_column_id(column_index)
+                .and_then(|column_id| orc_file.get_footer().get_types().get(column_id as usize))
+                .ok_or(orcrs::parser::Error::InvalidMetadata)?
+                .get_kind();
+            let mut null_count = 0;
+            let mut distinct_count = DistinctCount::new();
+
+            for stripe in orc_file.get_stripe_info()? {
+                let column =
+                    orc_file.read_column(&stripe, field_columns(orc_file, &[column_index])?[0])?;
+
+                null_count += (0..stripe.get_row_count())
+                    .filter(|row| column.get(*row) == Some(Value::Null))
+                    .count() as u64;
+                distinct_count.add_column(&column);
+            }
+
+            let row_count = orc_file.get_footer().get_number_of_rows();
+
+            print_table(&[
+                vec!["type".to_string(), format!("{:?}", kind)],
+                vec!["values".to_string(), (row_count - null_count).to_string()],
+                vec!["nulls".to_string(), null_count.to_string()],
+                vec![
+                    "distinct (approx.)".to_string(),
+                    distinct_count.estimate().to_string(),
+                ],
+            ]);
+        }
+        _ => return Err(Error::InvalidReplCommand(input.to_string())),
+    }
+
+    Ok(true)
+}
+
+fn repl_row<'a, I: Iterator<Item = Value<'a>>>(values: I) -> Vec<String> {
+    values
+        .map(|value| truncate(&format_value(&value, "null"), DESCRIBE_VALUE_WIDTH))
+        .collect()
+}
+
+/// Split the input at the first occurrence of a keyword that isn't inside quotes.
+fn split_keyword<'a>(input: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    let mut quote = None;
+    let mut previous_is_word = false;
+
+    for (offset, c) in input.char_indices() {
+        match quote {
+            Some(quote_char) if c == quote_char => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None => {
+                let rest = &input[offset..];
+
+                if !previous_is_word
+                    && rest
+                        .get(..keyword.len())
+                        .is_some_and(|word| word.eq_ignore_ascii_case(keyword))
+                    && !rest[keyword.len()..]
+                        .chars()
+                        .next()
+                        .is_some_and(|next| next.is_alphanumeric() || next == '_')
+                {
+                    return Some((input[..offset].trim(), rest[keyword.len()..].trim()));
+                }
+            }
+        }
+
+        previous_is_word = c.is_alphanumeric() || c == '_';
+    }
+
+    None
+}
+
+/// The statistics computed by the stats command for a column.
+struct ColumnStats {
+    kind: TypeKind,
+    null_count: u64,
+    distinct_count: Option<DistinctCount>,
+    summary: ColumnSummary,
+}
+
+enum ColumnSummary {
+    Count,
+    Histogram(Histogram),
+    FrequentValues(FrequentValues),
+}
+
+/// The range of an integer or boolean column, using a scan if the file statistics don't have it.
+fn integer_bounds(
+    orc_file: &mut OrcFile,
+    column_index: usize,
+    kind: TypeKind,
+) -> Result<Option<(i64, i64)>, Error> {
+    match kind {
+        TypeKind::Boolean => return Ok(Some((0, 1))),
+        TypeKind::Byte | TypeKind::Short | TypeKind::Int | TypeKind::Long => {}
+        _ => return Ok(None),
+    }
+
+    let statistics_range = orc_file
+        .get_column_id(column_index)
+        .and_then(|column_id| orc_file.get_file_statistics().get(column_id as usize))
+        .and_then(|statistics| statistics.get_range());
+
+    if let Some(ValueRange::Integer(min, max)) = statistics_range {
+        return Ok(Some((*min, *max)));
+    }
+
+    let mut bounds: Option<(i64, i64)> = None;
+    let column_index = field_columns(orc_file, &[column_index])?[0];
+
+    for stripe in orc_file.get_stripe_info()? {
+        let column = orc_file.read_column(&stripe, column_index)?;
+
+        for row in 0..stripe.get_row_count() {
+            if let Some(value) = column.get(row).and_then(|value| value.to_i64_lossy()) {
+                bounds = Some(match bounds {
+                    Some((min, max)) => (min.min(value), max.max(value)),
+                    None => (value, value),
+                });
+            }
+        }
+    }
+
+    Ok(bounds)
+}
+
+fn format_incompatibility(incompatibility: &Incompatibility) -> String {
+    match incompatibility {
+        Incompatibility::AddedField { name, kind } => format!("added {} ({:?})", name, kind),
+        Incompatibility::RemovedField { name, kind } => format!("removed {} ({:?})", name, kind),
+        Incompatibility::TypeWidening { name, from, to } => {
+            format!("widened {} ({:?} to {:?})", name, from, to)
+        }
+        Incompatibility::TypeConflict { name, from, to } => {
+            format!("conflicting types for {} ({:?} and {:?})", name, from, to)
+        }
+        Incompatibility::RenameCandidate { from, to, kind } => {
+            format!("possibly renamed {} to {} ({:?})", from, to, kind)
+        }
+    }
+}
+
+/// Read reference records from an NDJSON file, or from a CSV file with a header (where empty
+/// fields are nulls).
+fn read_reference(path: &str) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, Error> {
+    if path.ends_with(".csv") {
+        let mut reader = csv::Reader::from_path(path)?;
+        let headers = reader.headers()?.clone();
+
+        reader
+            .records()
+            .map(|record| {
+                Ok(headers
+                    .iter()
+                    .zip(record?.iter())
+                    .map(|(name, value)| {
+                        let value = if value.is_empty() {
+                            serde_json::Value::Null
+                        } else {
+                            serde_json::Value::from(value)
+                        };
+
+                        (name.to_string(), value)
+                    })
+                    .collect())
+            })
+            .collect()
+    } else {
+        BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+}
+
+fn matching_columns(orc_file: &OrcFile, regex: &regex::Regex) -> Result<Vec<usize>, Error> {
+    Ok(Schema::from_footer(orc_file.get_footer())
+        .ok_or(orcrs::parser::Error::InvalidMetadata)?
+        .get_matching_field_indices(|name| regex.is_match(name)))
+}
+
+/// The column indices to read for the given top-level fields (which differ from the field indices
+/// when a nested field comes before another field).
+fn field_columns(orc_file: &OrcFile, field_indices: &[usize]) -> Result<Vec<usize>, Error> {
+    let column_indices = orc_file.get_field_column_indices();
+
+    field_indices
+        .iter()
+        .map(|field_index| {
+            column_indices
+                .get(*field_index)
+                .copied()
+                .ok_or_else(|| orcrs::parser::Error::InvalidColumnIndex(*field_index).into())
+        })
+        .collect()
+}
+
+fn lookup_columns(orc_file: &OrcFile, field_names: &[String]) -> Result<Vec<usize>, Error> {
+    field_names
+        .iter()
+        .map(|field_name| {
+            orc_file
+                .get_field_index(field_name)
+                .ok_or_else(|| Error::UnknownColumn(field_name.to_string()))
+        })
+        .collect()
+}
+
+/// The column indices and dotted paths of the leaf columns under the given fields (which may be
+/// dotted paths to nested fields).
+fn lookup_leaf_columns(
+    orc_file: &OrcFile,
+    paths: &[String],
+) -> Result<(Vec<usize>, Vec<String>), Error> {
+    let footer = orc_file.get_footer();
+    let mut column_indices = vec![];
+    let mut field_names = vec![];
+
+    for path in paths {
+        let column_id = orc_file
+            .get_column_id_by_path(path)
+            .filter(|column_id| *column_id > 0)
+            .ok_or_else(|| Error::UnknownColumn(path.to_string()))?;
+
+        for leaf_column_id in footer.get_leaf_column_ids(column_id) {
+            column_indices.push(leaf_column_id as usize - 1);
+            field_names.push(
+                footer
+                    .get_column_path(leaf_column_id)
+                    .unwrap_or_else(|| path.to_string()),
+            );
+        }
+    }
+
+    Ok((column_indices, field_names))
 }
 
-fn escape(input: &str) -> String {
-    input.replace('\n', "\\n")
-}
-
-fn select_log_level_filter(verbosity: i32) -> LevelFilter {
+fn select_log_level_filter(verbosity: u8) -> LevelFilter {
     match verbosity {
         0 => LevelFilter::Off,
         1 => LevelFilter::Error,
@@ -147,7 +2686,7 @@ fn select_log_level_filter(verbosity: i32) -> LevelFilter {
     }
 }
 
-fn init_logging(verbosity: i32) -> Result<(), log::SetLoggerError> {
+fn init_logging(verbosity: u8) -> Result<(), log::SetLoggerError> {
     simplelog::TermLogger::init(
         select_log_level_filter(verbosity),
         simplelog::Config::default(),
@@ -0,0 +1,132 @@
+//! A growable bitmap backed by 64-bit words.
+//!
+//! Bits are stored least-significant first within each word, which is the same layout as Arrow
+//! validity buffers (on little-endian platforms).
+
+const WORD_BITS: usize = 64;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(capacity.div_ceil(WORD_BITS)),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.len {
+            Some(self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0)
+        } else {
+            None
+        }
+    }
+
+    pub fn push(&mut self, value: bool) {
+        let bit = self.len % WORD_BITS;
+
+        if bit == 0 {
+            self.words.push(value as u64);
+        } else if value {
+            *self.words.last_mut().unwrap() |= 1 << bit;
+        }
+
+        self.len += 1;
+    }
+
+    /// Append `count` copies of `value`.
+    pub fn grow(&mut self, count: usize, value: bool) {
+        let new_len = self.len + count;
+
+        if value {
+            // Fill the rest of the current word.
+            let bit = self.len % WORD_BITS;
+            if bit != 0 {
+                *self.words.last_mut().unwrap() |= u64::MAX << bit;
+            }
+        }
+
+        let fill = if value { u64::MAX } else { 0 };
+        self.words.resize(new_len.div_ceil(WORD_BITS), fill);
+        self.len = new_len;
+
+        // Unused bits in the last word are always zero.
+        let bit = new_len % WORD_BITS;
+        if bit != 0 {
+            *self.words.last_mut().unwrap() &= (1 << bit) - 1;
+        }
+    }
+
+    /// The number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The underlying words.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+}
+
+impl std::ops::Index<usize> for Bitmap {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        match self.get(index) {
+            Some(true) => &true,
+            Some(false) => &false,
+            None => panic!(
+                "Index out of bounds: the len is {} but the index is {}",
+                self.len, index
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_grow() {
+        let mut bitmap = Bitmap::new();
+        let mut expected = vec![];
+
+        for (count, value) in [(3, true), (70, false), (1, true), (130, true), (5, false)] {
+            if count == 1 {
+                bitmap.push(value);
+            } else {
+                bitmap.grow(count, value);
+            }
+            expected.extend(std::iter::repeat_n(value, count));
+        }
+
+        assert_eq!(bitmap.len(), expected.len());
+        assert_eq!(
+            (0..bitmap.len()).map(|i| bitmap[i]).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(bitmap.get(expected.len()), None);
+        assert_eq!(bitmap.count_ones(), 134);
+    }
+}
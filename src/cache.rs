@@ -0,0 +1,157 @@
+//! Keeping decoded columns in memory across reads.
+//!
+//! A cache is attached to a file with [`crate::parser::OrcFile::set_column_cache`], after which
+//! every column that's read successfully is stored by stripe and column index, and reading the same
+//! column again (for example in the second pass of a two-pass algorithm) returns a copy of the
+//! stored column instead of reading and decoding it. Copying a decoded column is much cheaper than
+//! decompressing and decoding it, but it isn't free.
+
+use crate::column::Column;
+use std::collections::HashMap;
+
+/// A bounded cache of decoded columns, evicting the least recently used columns first.
+#[derive(Clone, Default)]
+pub struct ColumnCache {
+    max_size: usize,
+    size: usize,
+    // The column, its size, and when it was last used, by stripe index and column index.
+    entries: HashMap<(usize, usize), (Column, usize, u64)>,
+    clock: u64,
+    hit_count: u64,
+    miss_count: u64,
+}
+
+impl ColumnCache {
+    /// A cache holding at most `max_size` bytes of decoded data (as estimated by
+    /// [`Column::get_data_size`]).
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            ..Self::default()
+        }
+    }
+
+    pub fn get_max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// The estimated size of the cached columns in bytes.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get_hit_count(&self) -> u64 {
+        self.hit_count
+    }
+
+    pub fn get_miss_count(&self) -> u64 {
+        self.miss_count
+    }
+
+    pub fn contains(&self, stripe_index: usize, column_index: usize) -> bool {
+        self.entries.contains_key(&(stripe_index, column_index))
+    }
+
+    /// Remove all columns (the hit and miss counts are kept).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+
+    pub(crate) fn get(&mut self, stripe_index: usize, column_index: usize) -> Option<Column> {
+        self.clock += 1;
+
+        match self.entries.get_mut(&(stripe_index, column_index)) {
+            Some((column, _, last_used)) => {
+                *last_used = self.clock;
+                self.hit_count += 1;
+                Some(column.clone())
+            }
+            None => {
+                self.miss_count += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a copy of the column, evicting other columns if necessary (a column that's larger
+    /// than the cache isn't stored).
+    pub(crate) fn insert(&mut self, stripe_index: usize, column_index: usize, column: &Column) {
+        let column_size = column.get_data_size();
+
+        if column_size > self.max_size {
+            return;
+        }
+
+        if let Some((_, previous_size, _)) = self.entries.remove(&(stripe_index, column_index)) {
+            self.size -= previous_size;
+        }
+
+        while self.size + column_size > self.max_size {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, _, last_used))| *last_used)
+                .map(|(key, _)| *key);
+
+            match least_recently_used.and_then(|key| self.entries.remove(&key)) {
+                Some((_, size, _)) => self.size -= size,
+                None => break,
+            }
+        }
+
+        self.clock += 1;
+        self.size += column_size;
+        self.entries.insert(
+            (stripe_index, column_index),
+            (column.clone(), column_size, self.clock),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u64_column(len: usize) -> Column {
+        Column::make_i64_column(vec![0; len], &[])
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = ColumnCache::new(100);
+
+        cache.insert(0, 0, &u64_column(5));
+        cache.insert(0, 1, &u64_column(5));
+        assert_eq!(cache.get_size(), 80);
+
+        // Using the first column makes the second the least recently used.
+        assert!(cache.get(0, 0).is_some());
+        cache.insert(1, 0, &u64_column(3));
+
+        assert!(cache.contains(0, 0));
+        assert!(!cache.contains(0, 1));
+        assert!(cache.contains(1, 0));
+        assert_eq!(cache.get_size(), 64);
+
+        // A column that can never fit isn't stored.
+        cache.insert(1, 1, &u64_column(20));
+        assert!(!cache.contains(1, 1));
+        assert!(cache.get(1, 1).is_none());
+
+        assert_eq!(cache.get_hit_count(), 1);
+        assert_eq!(cache.get_miss_count(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get_size(), 0);
+    }
+}
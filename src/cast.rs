@@ -0,0 +1,459 @@
+//! Changing the declared types of columns without re-encoding their data.
+//!
+//! ORC stores short, int, and bigint columns with the same integer encoding, so a file can be
+//! migrated between these types by copying its stripes (see
+//! [`crate::split::write_selected_stripes`]) and rewriting the schema in the footer. Narrowing
+//! casts check every value, and a stripe with values that don't fit in the new type either fails
+//! the cast, is dropped, or has those values set to null (see [`OutOfRangePolicy`]).
+//!
+//! Setting values to null re-encodes the affected columns of the stripe (and the new file's row
+//! indexes are disabled, since their positions would no longer be valid). Rows can't be skipped
+//! individually, since that would require re-encoding every column.
+
+use crate::meta::TypeKind;
+use crate::parser::OrcFile;
+use crate::proto::orc_proto::{self, column_encoding, stream, type_::Kind};
+use crate::split::ColumnStreams;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Parser error")]
+    Parser(#[from] crate::parser::Error),
+    #[error("Unknown field")]
+    UnknownField(String),
+    #[error("Unsupported cast")]
+    UnsupportedCast {
+        field_name: String,
+        from: TypeKind,
+        to: TypeKind,
+    },
+    #[error("Value out of range")]
+    OutOfRange {
+        field_name: String,
+        stripe_index: usize,
+        row_index: usize,
+        value: i64,
+    },
+}
+
+/// What to do with a stripe that has values that don't fit in the new type.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutOfRangePolicy {
+    /// Fail with an error.
+    #[default]
+    Fail,
+    /// Leave the stripe out of the new file.
+    DropStripe,
+    /// Keep the other rows of the stripe, with the values that don't fit set to null.
+    Null,
+}
+
+/// A value that doesn't fit in the new type of its field.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutOfRangeValue {
+    pub field_name: String,
+    pub stripe_index: usize,
+    pub row_index: usize,
+    pub value: i64,
+}
+
+impl From<OutOfRangeValue> for Error {
+    fn from(value: OutOfRangeValue) -> Self {
+        Self::OutOfRange {
+            field_name: value.field_name,
+            stripe_index: value.stripe_index,
+            row_index: value.row_index,
+            value: value.value,
+        }
+    }
+}
+
+/// The stripes and values that were affected by the out-of-range policy.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CastReport {
+    dropped_stripes: Vec<usize>,
+    null_values: Vec<OutOfRangeValue>,
+}
+
+impl CastReport {
+    pub fn get_dropped_stripes(&self) -> &[usize] {
+        &self.dropped_stripes
+    }
+
+    /// The values that were set to null (in stripe, field, and row order).
+    pub fn get_null_values(&self) -> &[OutOfRangeValue] {
+        &self.null_values
+    }
+}
+
+/// Copy a file into `output` with the given fields changed to new types.
+pub fn cast<W: Write>(
+    orc_file: &mut OrcFile,
+    casts: &[(String, TypeKind)],
+    policy: OutOfRangePolicy,
+    output: W,
+) -> Result<CastReport, Error> {
+    // The footer's column ids are one greater than the file's if there's a synthetic root.
+    let column_id_offset = orc_file.get_footer().has_synthetic_root() as u32;
+    let mut planned = Vec::with_capacity(casts.len());
+
+    for (field_name, to) in casts {
+        let field_index = orc_file
+            .get_field_index(field_name)
+            .ok_or_else(|| Error::UnknownField(field_name.to_string()))?;
+        let column_id = orc_file
+            .get_column_id(field_index)
+            .ok_or(crate::parser::Error::InvalidMetadata)?;
+        let from = orc_file
+            .get_footer()
+            .get_types()
+            .get(column_id as usize)
+            .ok_or(crate::parser::Error::InvalidMetadata)?
+            .get_kind();
+
+        let (to_kind, check_range) = match (integer_kind(from), integer_kind(*to)) {
+            // Only narrowing casts need their values checked.
+            (Some((_, (from_min, from_max))), Some((to_kind, (to_min, to_max)))) => (
+                to_kind,
+                (from_min < to_min || from_max > to_max).then_some((to_min, to_max)),
+            ),
+            _ => {
+                return Err(Error::UnsupportedCast {
+                    field_name: field_name.to_string(),
+                    from,
+                    to: *to,
+                })
+            }
+        };
+
+        planned.push((field_name, column_id, to_kind, check_range));
+    }
+
+    let mut stripes = vec![];
+    let mut report = CastReport::default();
+    // The rows to set to null for each column, for the stripes that will be re-encoded.
+    let mut null_rows: HashMap<usize, Vec<(u32, Vec<usize>)>> = HashMap::new();
+
+    for (stripe_index, stripe) in orc_file.get_stripe_info()?.iter().enumerate() {
+        let mut out_of_range = vec![];
+
+        for (field_name, column_id, _, check_range) in &planned {
+            if let Some((min, max)) = check_range {
+                let column = orc_file.read_column_by_id(stripe, *column_id)?;
+
+                let values = (0..stripe.get_row_count())
+                    .filter_map(|row_index| match column.get(row_index) {
+                        Some(Value::I64(value)) if !(*min..=*max).contains(&value) => {
+                            Some(OutOfRangeValue {
+                                field_name: field_name.to_string(),
+                                stripe_index,
+                                row_index,
+                                value,
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                if !values.is_empty() {
+                    out_of_range.push((*column_id, values));
+
+                    // Only the first value is needed unless the values are set to null.
+                    if policy != OutOfRangePolicy::Null {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if out_of_range.is_empty() {
+            stripes.push(stripe_index);
+        } else {
+            match policy {
+                OutOfRangePolicy::Fail => {
+                    return Err(out_of_range.swap_remove(0).1.swap_remove(0).into())
+                }
+                OutOfRangePolicy::DropStripe => report.dropped_stripes.push(stripe_index),
+                OutOfRangePolicy::Null => {
+                    stripes.push(stripe_index);
+                    null_rows.insert(
+                        stripe_index,
+                        out_of_range
+                            .iter()
+                            .map(|(column_id, values)| {
+                                (
+                                    *column_id,
+                                    values.iter().map(|value| value.row_index).collect(),
+                                )
+                            })
+                            .collect(),
+                    );
+                    report
+                        .null_values
+                        .extend(out_of_range.into_iter().flat_map(|(_, values)| values));
+                }
+            }
+        }
+    }
+
+    crate::split::write_selected_stripes_with_replacements(
+        orc_file,
+        &stripes,
+        output,
+        None,
+        |orc_file, stripe_index, stripe| match null_rows.get(&stripe_index) {
+            Some(rows) => {
+                let stripe_info = orc_file.read_stripe_info(stripe_index)?;
+                let mut columns = Vec::with_capacity(rows.len());
+
+                for (column_id, rows) in rows {
+                    let column = orc_file.read_column_by_id(&stripe_info, *column_id)?;
+                    let values = (0..stripe_info.get_row_count())
+                        .map(|row_index| match column.get(row_index) {
+                            Some(Value::I64(value)) if rows.binary_search(&row_index).is_err() => {
+                                Some(value)
+                            }
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+
+                    columns.push(integer_column_streams(
+                        *column_id - column_id_offset,
+                        &values,
+                    ));
+                }
+
+                crate::split::replace_column_streams(orc_file, stripe, columns).map(Some)
+            }
+            None => Ok(None),
+        },
+        |footer| {
+            for (_, column_id, to_kind, _) in &planned {
+                if let Some(column_type) =
+                    footer.types.get_mut((*column_id - column_id_offset) as usize)
+                {
+                    column_type.set_kind(*to_kind);
+                }
+            }
+
+            if !null_rows.is_empty() {
+                footer.set_rowIndexStride(0);
+            }
+        },
+    )?;
+
+    Ok(report)
+}
+
+// Directly encoded streams and statistics for an integer column.
+fn integer_column_streams(column_id: u32, values: &[Option<i64>]) -> ColumnStreams {
+    let present = values.iter().map(Option::is_some).collect::<Vec<_>>();
+    let data = values.iter().flatten().copied().collect::<Vec<_>>();
+
+    let mut encoding = orc_proto::ColumnEncoding::new();
+    encoding.set_kind(column_encoding::Kind::DIRECT);
+
+    let mut integer_statistics = orc_proto::IntegerStatistics::new();
+    if let (Some(min), Some(max)) = (data.iter().min(), data.iter().max()) {
+        integer_statistics.set_minimum(*min);
+        integer_statistics.set_maximum(*max);
+    }
+    // Like other writers, leave the sum out if it overflows.
+    if let Some(sum) = data
+        .iter()
+        .try_fold(0i64, |sum, value| sum.checked_add(*value))
+    {
+        integer_statistics.set_sum(sum);
+    }
+
+    let mut statistics = orc_proto::ColumnStatistics::new();
+    statistics.set_numberOfValues(data.len() as u64);
+    statistics.set_hasNull(data.len() < values.len());
+    statistics.intStatistics = protobuf::MessageField::some(integer_statistics);
+
+    let data = data.iter().map(|value| *value as u64).collect::<Vec<_>>();
+
+    ColumnStreams {
+        column_id,
+        encoding,
+        streams: vec![
+            (
+                stream::Kind::PRESENT,
+                crate::rle::byte::encode_bools(&present),
+            ),
+            (
+                stream::Kind::DATA,
+                crate::rle::intv1::encode_literals(&data, true),
+            ),
+        ],
+        statistics,
+    }
+}
+
+// The protobuf kind and the range of values for the integer types that share an encoding.
+fn integer_kind(kind: TypeKind) -> Option<(Kind, (i64, i64))> {
+    match kind {
+        TypeKind::Short => Some((Kind::SHORT, (i16::MIN as i64, i16::MAX as i64))),
+        TypeKind::Int => Some((Kind::INT, (i32::MIN as i64, i32::MAX as i64))),
+        TypeKind::Long => Some((Kind::LONG, (i64::MIN, i64::MAX))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::OwnedValue;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("orcrs-cast-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn widen_and_narrow() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let output_path = temp_path("widen.orc");
+
+        let report = cast(
+            &mut orc_file,
+            &[("followers_count".to_string(), TypeKind::Long)],
+            OutOfRangePolicy::Fail,
+            std::fs::File::create(&output_path).unwrap(),
+        )
+        .unwrap();
+
+        let mut widened = OrcFile::open(&output_path).unwrap();
+        std::fs::remove_file(&output_path).unwrap();
+
+        assert_eq!(report, CastReport::default());
+        assert_eq!(
+            widened.get_footer().get_types()[11].get_kind(),
+            TypeKind::Long
+        );
+
+        let stripe = &orc_file.get_stripe_info().unwrap()[0];
+        let copy_stripe = &widened.get_stripe_info().unwrap()[0];
+        let original = orc_file.read_column(stripe, 10).unwrap();
+        let copy = widened.read_column(copy_stripe, 10).unwrap();
+
+        assert!((0..stripe.get_row_count()).all(|row| original.get(row) == copy.get(row)));
+
+        // Ids don't fit in an int.
+        let result = cast(
+            &mut orc_file,
+            &[("id".to_string(), TypeKind::Int)],
+            OutOfRangePolicy::Fail,
+            std::io::sink(),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::OutOfRange {
+                stripe_index: 0,
+                ..
+            })
+        ));
+
+        let report = cast(
+            &mut orc_file,
+            &[("id".to_string(), TypeKind::Int)],
+            OutOfRangePolicy::DropStripe,
+            std::io::sink(),
+        )
+        .unwrap();
+        assert_eq!(report.get_dropped_stripes(), &[0]);
+        assert!(report.get_null_values().is_empty());
+
+        let result = cast(
+            &mut orc_file,
+            &[("verified".to_string(), TypeKind::String)],
+            OutOfRangePolicy::Fail,
+            std::io::sink(),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedCast {
+                from: TypeKind::Boolean,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn out_of_range_values_to_null() {
+        for path in [
+            "examples/ts-1k-zlib-2020-09-20.orc",
+            "examples/ts-10k-zstd-2020-09-20.orc",
+        ] {
+            let mut orc_file = OrcFile::open(path).unwrap();
+            let output_path = temp_path("null.orc");
+
+            let report = cast(
+                &mut orc_file,
+                &[("followers_count".to_string(), TypeKind::Short)],
+                OutOfRangePolicy::Null,
+                std::fs::File::create(&output_path).unwrap(),
+            )
+            .unwrap();
+
+            let mut narrowed = OrcFile::open(&output_path).unwrap();
+            std::fs::remove_file(&output_path).unwrap();
+            // Check the values against the new stripe statistics.
+            narrowed.set_strict(true);
+
+            assert!(report.get_dropped_stripes().is_empty());
+            assert!(!report.get_null_values().is_empty());
+            assert_eq!(
+                narrowed.get_footer().get_types()[11].get_kind(),
+                TypeKind::Short
+            );
+            assert_eq!(narrowed.get_footer().get_row_index_stride(), Some(0));
+
+            let columns = orc_file.get_field_column_indices();
+            let read_rows = |orc_file: &mut OrcFile| {
+                orc_file
+                    .map_rows(&columns, |values| {
+                        Ok::<_, crate::parser::Error>(
+                            values
+                                .iter()
+                                .map(|value| value.to_owned_value())
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .unwrap()
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            };
+
+            let original_rows = read_rows(&mut orc_file);
+            let narrowed_rows = read_rows(&mut narrowed);
+            let mut null_values = report.get_null_values().iter().peekable();
+            let mut stripe_start = 0;
+
+            assert_eq!(narrowed_rows.len(), original_rows.len());
+
+            // Only the out-of-range values are missing.
+            for (stripe_index, stripe) in orc_file.get_stripe_info().unwrap().iter().enumerate() {
+                for row_index in 0..stripe.get_row_count() {
+                    let mut expected = original_rows[stripe_start + row_index].clone();
+
+                    if let Some(value) = null_values.next_if(|value| {
+                        value.stripe_index == stripe_index && value.row_index == row_index
+                    }) {
+                        assert_eq!(expected[10], OwnedValue::I64(value.value));
+                        expected[10] = OwnedValue::Null;
+                    }
+
+                    assert_eq!(narrowed_rows[stripe_start + row_index], expected);
+                }
+
+                stripe_start += stripe.get_row_count();
+            }
+
+            assert!(null_values.next().is_none());
+        }
+    }
+}
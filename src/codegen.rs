@@ -0,0 +1,193 @@
+//! Generating Rust row types from ORC schemas.
+//!
+//! The generated struct has a field for each top-level column that can be deserialized (see
+//! [`crate::parser::OrcFile::deserialize`]), with field names converted to snake case (and renamed
+//! with serde attributes when they differ). A field is optional unless the file statistics show
+//! that the column has no nulls. Lists are vectors, with optional elements unless the statistics
+//! show that the element column has no nulls.
+
+use crate::meta::TypeKind;
+use crate::parser::OrcFile;
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "union",
+    "unsafe", "use", "where", "while",
+];
+
+/// The source of a struct (with `use` statement) for the rows of the file.
+pub fn row_struct(orc_file: &OrcFile, struct_name: &str) -> String {
+    let types = orc_file.get_footer().get_types();
+    let statistics = orc_file.get_file_statistics();
+    let mut lines = vec![
+        "use serde::{Deserialize, Serialize};".to_string(),
+        String::new(),
+        "#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]".to_string(),
+        format!("pub struct {} {{", struct_name),
+    ];
+
+    for (field_index, field_name) in orc_file.get_field_names().iter().enumerate() {
+        let column_id = orc_file.get_column_id(field_index).unwrap_or_default() as usize;
+        let kind = types
+            .get(column_id)
+            .map(|column_type| column_type.get_kind());
+
+        let field_type = match kind {
+            Some(TypeKind::List) => types
+                .get(column_id)
+                .and_then(|column_type| column_type.get_subtypes().first())
+                .and_then(|element_id| {
+                    let element_type = types
+                        .get(*element_id as usize)
+                        .and_then(|column_type| rust_type(column_type.get_kind()))?;
+                    let nullable = statistics
+                        .get(*element_id as usize)
+                        .and_then(|statistics| statistics.has_null())
+                        .unwrap_or(true);
+
+                    Some(if nullable {
+                        format!("Vec<Option<{}>>", element_type)
+                    } else {
+                        format!("Vec<{}>", element_type)
+                    })
+                }),
+            kind => kind.and_then(rust_type).map(str::to_string),
+        };
+
+        match field_type {
+            Some(rust_type) => {
+                let identifier = to_identifier(field_name);
+                // Without statistics we can't rule out nulls.
+                let nullable = statistics
+                    .get(column_id)
+                    .and_then(|statistics| statistics.has_null())
+                    .unwrap_or(true);
+
+                if identifier.trim_start_matches("r#") != field_name {
+                    lines.push(format!("    #[serde(rename = {:?})]", field_name));
+                }
+
+                lines.push(if nullable {
+                    format!("    pub {}: Option<{}>,", identifier, rust_type)
+                } else {
+                    format!("    pub {}: {},", identifier, rust_type)
+                });
+            }
+            None => {
+                lines.push(format!(
+                    "    // Unsupported column: {} ({:?})",
+                    field_name,
+                    kind.unwrap_or(TypeKind::Struct)
+                ));
+            }
+        }
+    }
+
+    lines.push("}".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn rust_type(kind: TypeKind) -> Option<&'static str> {
+    match kind {
+        TypeKind::Boolean => Some("bool"),
+        TypeKind::Byte | TypeKind::Short | TypeKind::Int => Some("u32"),
+        TypeKind::Long => Some("u64"),
+        // Timestamps deserialize as signed milliseconds since the Unix epoch.
+        TypeKind::Timestamp | TypeKind::TimestampInstant => Some("i64"),
+        TypeKind::String | TypeKind::Varchar | TypeKind::Char => Some("String"),
+        _ => None,
+    }
+}
+
+/// Convert a field name to a snake case Rust identifier.
+fn to_identifier(name: &str) -> String {
+    let mut identifier = String::with_capacity(name.len());
+    let mut previous: Option<char> = None;
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase()
+                && previous.is_some_and(|previous| {
+                    previous.is_ascii_lowercase() || previous.is_ascii_digit()
+                })
+            {
+                identifier.push('_');
+            }
+
+            identifier.push(c.to_ascii_lowercase());
+        } else if !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+
+        previous = Some(c);
+    }
+
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    if KEYWORDS.contains(&identifier.as_str()) {
+        // These keywords can't be raw identifiers.
+        if ["crate", "self", "super"].contains(&identifier.as_str()) {
+            identifier.push('_');
+        } else {
+            identifier.insert_str(0, "r#");
+        }
+    }
+
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(to_identifier("screen_name"), "screen_name");
+        assert_eq!(to_identifier("screenName"), "screen_name");
+        assert_eq!(to_identifier("URL"), "url");
+        assert_eq!(to_identifier("user.id"), "user_id");
+        assert_eq!(to_identifier("2nd value"), "_2nd_value");
+        assert_eq!(to_identifier("type"), "r#type");
+        assert_eq!(to_identifier("self"), "self_");
+        assert_eq!(to_identifier(""), "_");
+    }
+
+    #[test]
+    fn rust_types() {
+        assert_eq!(rust_type(TypeKind::Long), Some("u64"));
+        assert_eq!(rust_type(TypeKind::Timestamp), Some("i64"));
+        assert_eq!(rust_type(TypeKind::TimestampInstant), Some("i64"));
+        assert_eq!(rust_type(TypeKind::Double), None);
+    }
+
+    #[test]
+    fn ts_1k_row_struct() {
+        let orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let source = row_struct(&orc_file, "UserRow");
+
+        assert_eq!(
+            source,
+            "use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UserRow {
+    pub id: u64,
+    pub status_id: u64,
+    pub timestamp: u64,
+    pub screen_name: String,
+    pub name: String,
+    pub url: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub profile_image_url: Option<String>,
+    pub verified: Option<bool>,
+    pub followers_count: Option<u32>,
+}
+"
+        );
+    }
+}
@@ -1,27 +1,60 @@
-use crate::value::Value;
-use bit_vec::BitVec;
+use crate::bitmap::Bitmap;
+use crate::time::Timestamp;
+use crate::value::{FromValue, ListValue, Value};
 use std::io::{Error, Write};
 
 const PRESENT_VALUE_CAPACITY: usize = 512;
 
+/// A string that isn't valid UTF-8 (or that extends past the end of the data).
+#[derive(thiserror::Error, Debug, Clone, Copy, Eq, PartialEq)]
+#[error("Invalid string")]
+pub struct InvalidString {
+    /// The index of the dictionary entry or row.
+    pub index: usize,
+}
+
+#[derive(Clone)]
 pub enum Column {
     Utf8Direct {
-        data: Vec<u8>,
-        indices: Vec<(i64, u64)>,
+        // Validated once when the column is constructed.
+        data: String,
+        // The start and end of each value, or `None` for nulls.
+        indices: Vec<Option<(usize, usize)>>,
     },
     Utf8Dictionary {
         data: Vec<i64>,
-        dictionary: Vec<u8>,
-        indices: Vec<(u64, u64)>,
+        // Validated once when the column is constructed.
+        dictionary: String,
+        // The start and end of each dictionary entry.
+        indices: Vec<(usize, usize)>,
+    },
+    Binary {
+        data: Vec<u8>,
+        // The start and end of each value, or `None` for nulls.
+        indices: Vec<Option<(usize, usize)>>,
     },
     Bool {
         row_count: usize,
-        values: BitVec,
-        nulls: Option<BitVec>,
+        values: Bitmap,
+        nulls: Option<Bitmap>,
     },
-    U64 {
-        values: Vec<u64>,
-        nulls: Option<BitVec>,
+    I64 {
+        values: Vec<i64>,
+        nulls: Option<Bitmap>,
+    },
+    Decimal {
+        // The unscaled value and scale of each value.
+        values: Vec<(i128, u8)>,
+        nulls: Option<Bitmap>,
+    },
+    Timestamp {
+        values: Vec<Timestamp>,
+        nulls: Option<Bitmap>,
+    },
+    List {
+        // The start and end of each list in the child column, or `None` for nulls.
+        indices: Vec<Option<(usize, usize)>>,
+        values: Box<Column>,
     },
 }
 
@@ -45,7 +78,7 @@ impl Column {
                     None
                 }
             }
-            Column::U64 { values, nulls } => {
+            Column::I64 { values, nulls } => {
                 if row < values.len() {
                     if let Some(nulls) = nulls {
                         if nulls[row] {
@@ -53,79 +86,272 @@ impl Column {
                         }
                     };
 
-                    Some(Value::U64(values[row]))
+                    Some(Value::I64(values[row]))
                 } else {
                     None
                 }
             }
+            Column::Decimal { values, nulls } => {
+                let (value, scale) = *values.get(row)?;
+
+                if nulls.as_ref().is_some_and(|nulls| nulls[row]) {
+                    Some(Value::Null)
+                } else {
+                    Some(Value::Decimal { value, scale })
+                }
+            }
+            Column::Timestamp { values, nulls } => {
+                let value = *values.get(row)?;
+
+                if nulls.as_ref().is_some_and(|nulls| nulls[row]) {
+                    Some(Value::Null)
+                } else {
+                    Some(Value::Timestamp(value))
+                }
+            }
             Column::Utf8Dictionary {
                 data,
                 dictionary,
                 indices,
             } => {
-                if data[row] == -1 {
+                let index = *data.get(row)?;
+
+                if index == -1 {
                     Some(Value::Null)
                 } else {
-                    let (start, len) = indices[data[row] as usize];
-                    Some(Value::Utf8(
-                        // TODO: Don't hard crash here.
-                        std::str::from_utf8(&dictionary[start as usize..(start + len) as usize])
-                            .unwrap(),
-                    ))
+                    let (start, end) = *indices.get(index as usize)?;
+                    Some(Value::Utf8(&dictionary[start..end]))
                 }
             }
-            Column::Utf8Direct { data, indices } => {
-                let (start, len) = indices[row];
+            Column::Utf8Direct { data, indices } => match indices.get(row)? {
+                Some((start, end)) => Some(Value::Utf8(&data[*start..*end])),
+                None => Some(Value::Null),
+            },
+            Column::Binary { data, indices } => match indices.get(row)? {
+                Some((start, end)) => Some(Value::Bytes(&data[*start..*end])),
+                None => Some(Value::Null),
+            },
+            Column::List { indices, values } => match indices.get(row)? {
+                Some((start, end)) => Some(Value::List(ListValue::new(values, *start, *end))),
+                None => Some(Value::Null),
+            },
+        }
+    }
 
-                if start == -1 {
-                    Some(Value::Null)
-                } else {
-                    Some(Value::Utf8(
-                        // TODO: Don't hard crash here.
-                        std::str::from_utf8(&data[start as usize..(start as usize + len as usize)])
-                            .unwrap(),
-                    ))
-                }
+    /// The approximate number of bytes of decoded data held by the column.
+    pub fn get_data_size(&self) -> usize {
+        fn bitmap_size(bitmap: &Bitmap) -> usize {
+            bitmap.len().div_ceil(8)
+        }
+
+        match self {
+            Column::Utf8Direct { data, indices } => {
+                data.len() + std::mem::size_of_val(indices.as_slice())
+            }
+            Column::Binary { data, indices } => {
+                data.len() + std::mem::size_of_val(indices.as_slice())
+            }
+            Column::Utf8Dictionary {
+                data,
+                dictionary,
+                indices,
+            } => {
+                std::mem::size_of_val(data.as_slice())
+                    + dictionary.len()
+                    + std::mem::size_of_val(indices.as_slice())
+            }
+            Column::Bool { values, nulls, .. } => {
+                bitmap_size(values) + nulls.as_ref().map(bitmap_size).unwrap_or_default()
+            }
+            Column::I64 { values, nulls } => {
+                std::mem::size_of_val(values.as_slice())
+                    + nulls.as_ref().map(bitmap_size).unwrap_or_default()
+            }
+            Column::Decimal { values, nulls } => {
+                std::mem::size_of_val(values.as_slice())
+                    + nulls.as_ref().map(bitmap_size).unwrap_or_default()
+            }
+            Column::Timestamp { values, nulls } => {
+                std::mem::size_of_val(values.as_slice())
+                    + nulls.as_ref().map(bitmap_size).unwrap_or_default()
+            }
+            Column::List { indices, values } => {
+                std::mem::size_of_val(indices.as_slice()) + values.get_data_size()
             }
         }
     }
 
-    pub(crate) fn make_u64_column(values: Vec<u64>, null_runs: &[u64]) -> Column {
-        if null_runs.is_empty() {
-            Column::U64 {
-                values,
-                nulls: None,
+    /// Mark the rows with string values that satisfy the predicate (other rows are unmarked).
+    ///
+    /// For dictionary columns, the predicate is called once per dictionary entry.
+    pub fn match_strings<F: FnMut(&str) -> bool>(&self, mut predicate: F) -> Bitmap {
+        match self {
+            Column::Utf8Dictionary {
+                data,
+                dictionary,
+                indices,
+            } => {
+                let matching_entries = indices
+                    .iter()
+                    .map(|(start, end)| predicate(&dictionary[*start..*end]))
+                    .collect::<Vec<_>>();
+                let mut matches = Bitmap::with_capacity(data.len());
+
+                for index in data {
+                    matches.push(
+                        usize::try_from(*index)
+                            .ok()
+                            .and_then(|index| matching_entries.get(index))
+                            .copied()
+                            .unwrap_or(false),
+                    );
+                }
+
+                matches
             }
-        } else {
-            let new_len = values.len() + null_runs.iter().sum::<u64>() as usize;
-            let mut new_values = Vec::with_capacity(new_len);
-            let mut nulls = BitVec::with_capacity(new_len);
+            Column::Utf8Direct { data, indices } => {
+                let mut matches = Bitmap::with_capacity(indices.len());
 
-            for (current_present_index, null_run) in null_runs.iter().enumerate() {
-                for _ in 0..*null_run {
-                    new_values.push(0);
-                    nulls.push(true);
+                for range in indices {
+                    matches.push(range.is_some_and(|(start, end)| predicate(&data[start..end])));
                 }
 
-                if let Some(value) = values.get(current_present_index) {
-                    new_values.push(*value);
-                    nulls.push(false);
+                matches
+            }
+            Column::Binary { indices, .. } | Column::List { indices, .. } => {
+                let mut matches = Bitmap::with_capacity(indices.len());
+                matches.grow(indices.len(), false);
+                matches
+            }
+            Column::Bool { row_count, .. } => {
+                let mut matches = Bitmap::with_capacity(*row_count);
+                matches.grow(*row_count, false);
+                matches
+            }
+            Column::I64 { values, .. } => {
+                let mut matches = Bitmap::with_capacity(values.len());
+                matches.grow(values.len(), false);
+                matches
+            }
+            Column::Decimal { values, .. } => {
+                let mut matches = Bitmap::with_capacity(values.len());
+                matches.grow(values.len(), false);
+                matches
+            }
+            Column::Timestamp { values, .. } => {
+                let mut matches = Bitmap::with_capacity(values.len());
+                matches.grow(values.len(), false);
+                matches
+            }
+        }
+    }
+
+    /// Convert the column into plain values (with `None` for nulls), or return `None` if any value
+    /// has a different type.
+    pub fn to_vec_opt<T: FromValue>(&self) -> Option<Vec<Option<T>>> {
+        (0..)
+            .map_while(|row| self.get(row))
+            .map(|value| match value {
+                Value::Null => Some(None),
+                other => T::from_value(&other).map(Some),
+            })
+            .collect()
+    }
+
+    /// Replace empty string values with nulls (other columns are unchanged, except that this applies
+    /// to the elements of lists).
+    pub fn replace_empty_strings_with_nulls(&mut self) {
+        match self {
+            Column::Utf8Direct { indices, .. } => {
+                for index in indices {
+                    if matches!(index, Some((start, end)) if start == end) {
+                        *index = None;
+                    }
                 }
             }
+            Column::Utf8Dictionary { data, indices, .. } => {
+                for value in data {
+                    if indices
+                        .get(*value as usize)
+                        .is_some_and(|(start, end)| start == end)
+                    {
+                        *value = -1;
+                    }
+                }
+            }
+            Column::List { values, .. } => values.replace_empty_strings_with_nulls(),
+            Column::Binary { .. }
+            | Column::Bool { .. }
+            | Column::I64 { .. }
+            | Column::Decimal { .. }
+            | Column::Timestamp { .. } => {}
+        }
+    }
 
-            Column::U64 {
-                values: new_values,
-                nulls: Some(nulls),
+    /// Remove trailing spaces from string values (other columns are unchanged, except that this
+    /// applies to the elements of lists).
+    pub fn trim_trailing_spaces(&mut self) {
+        fn trimmed_end(data: &str, start: usize, end: usize) -> usize {
+            start + data[start..end].trim_end_matches(' ').len()
+        }
+
+        match self {
+            Column::Utf8Direct { data, indices } => {
+                for (start, end) in indices.iter_mut().flatten() {
+                    *end = trimmed_end(data, *start, *end);
+                }
             }
+            Column::Utf8Dictionary {
+                dictionary,
+                indices,
+                ..
+            } => {
+                for (start, end) in indices {
+                    *end = trimmed_end(dictionary, *start, *end);
+                }
+            }
+            Column::List { values, .. } => values.trim_trailing_spaces(),
+            Column::Binary { .. }
+            | Column::Bool { .. }
+            | Column::I64 { .. }
+            | Column::Decimal { .. }
+            | Column::Timestamp { .. } => {}
         }
     }
 
+    pub(crate) fn make_i64_column(values: Vec<i64>, null_runs: &[u64]) -> Column {
+        let (values, nulls) = spread_nulls(values, null_runs);
+
+        Column::I64 { values, nulls }
+    }
+
+    /// Build a column of the given number of nulls.
+    pub(crate) fn make_null_column(row_count: usize) -> Column {
+        Self::make_i64_column(vec![], &[row_count as u64])
+    }
+
+    /// Build a decimal column from unscaled values and their scales.
+    pub(crate) fn make_decimal_column(values: Vec<(i128, u8)>, null_runs: &[u64]) -> Column {
+        let (values, nulls) = spread_nulls(values, null_runs);
+
+        Column::Decimal { values, nulls }
+    }
+
+    /// Build a timestamp column from decoded values.
+    pub(crate) fn make_timestamp_column(values: Vec<Timestamp>, null_runs: &[u64]) -> Column {
+        let (values, nulls) = spread_nulls(values, null_runs);
+
+        Column::Timestamp { values, nulls }
+    }
+
+    /// Build a dictionary column, checking every dictionary entry according to the policy.
     pub(crate) fn make_utf8_dictionary_column(
         null_runs: Option<Vec<u64>>,
         data: Vec<u64>,
         dictionary_bytes: Vec<u8>,
         lengths: Vec<u64>,
-    ) -> Column {
+        utf8_policy: Utf8Policy,
+    ) -> Result<Column, InvalidString> {
         let new_data = if let Some(null_runs) = null_runs {
             let new_len = data.len() + null_runs.iter().sum::<u64>() as usize;
             let mut new_data: Vec<i64> = Vec::with_capacity(new_len);
@@ -145,69 +371,188 @@ impl Column {
             data.iter().map(|v| *v as i64).collect()
         };
 
-        let mut indices = Vec::with_capacity(lengths.len());
-        let mut total_inc = 0;
+        let mut start = 0usize;
+        let ranges = lengths
+            .iter()
+            .map(|length| {
+                let range = (start, start.saturating_add(*length as usize));
+                start = range.1;
+                Some(range)
+            })
+            .collect();
 
-        for length in lengths {
-            indices.push((total_inc, length));
-            total_inc += length;
-        }
+        let (dictionary, indices) = index_strings(dictionary_bytes, ranges, utf8_policy)?;
+        let indices = indices.into_iter().flatten().collect();
 
-        Column::Utf8Dictionary {
+        Ok(Column::Utf8Dictionary {
             data: new_data,
-            dictionary: dictionary_bytes,
+            dictionary,
             indices,
-        }
+        })
     }
 
+    /// Build a direct column, checking every value according to the policy.
     pub(crate) fn make_utf8_direct_column(
         null_runs: Option<Vec<u64>>,
         data_bytes: Vec<u8>,
         lengths: Vec<u64>,
-    ) -> Column {
-        let new_lengths = if let Some(null_runs) = null_runs {
-            let new_len = lengths.len() + null_runs.iter().sum::<u64>() as usize;
-            let mut new_lengths: Vec<i64> = Vec::with_capacity(new_len);
+        utf8_policy: Utf8Policy,
+    ) -> Result<Column, InvalidString> {
+        let ranges = direct_ranges(null_runs, &lengths);
+        let (data, indices) = index_strings(data_bytes, ranges, utf8_policy)?;
 
-            for (current_present_index, null_run) in null_runs.iter().enumerate() {
-                for _ in 0..*null_run {
-                    new_lengths.push(-1);
-                }
+        Ok(Column::Utf8Direct { data, indices })
+    }
 
-                if let Some(value) = lengths.get(current_present_index) {
-                    new_lengths.push(*value as i64);
-                }
-            }
+    /// Build a binary column (the lengths must add up to the length of the data).
+    pub(crate) fn make_binary_column(
+        null_runs: Option<Vec<u64>>,
+        data: Vec<u8>,
+        lengths: Vec<u64>,
+    ) -> Column {
+        let indices = direct_ranges(null_runs, &lengths);
 
-            new_lengths
-        } else {
-            lengths.iter().map(|v| *v as i64).collect()
-        };
+        Column::Binary { data, indices }
+    }
 
-        let mut indices = Vec::with_capacity(new_lengths.len());
-        let mut total_inc = 0;
+    /// Build a list column (the lengths must add up to the number of rows in the child column).
+    pub(crate) fn make_list_column(
+        null_runs: Option<Vec<u64>>,
+        lengths: Vec<u64>,
+        values: Column,
+    ) -> Column {
+        let indices = direct_ranges(null_runs, &lengths);
 
-        for length in new_lengths {
-            if length == -1 {
-                indices.push((-1, 0));
-            } else {
-                indices.push((total_inc, length as u64));
-                total_inc += length;
+        Column::List {
+            indices,
+            values: Box::new(values),
+        }
+    }
+}
+
+/// Insert default values for the null runs, with a bitmap marking them (if there are any).
+fn spread_nulls<T: Copy + Default>(values: Vec<T>, null_runs: &[u64]) -> (Vec<T>, Option<Bitmap>) {
+    if null_runs.is_empty() {
+        (values, None)
+    } else {
+        let new_len = values.len() + null_runs.iter().sum::<u64>() as usize;
+        let mut new_values = Vec::with_capacity(new_len);
+        let mut nulls = Bitmap::with_capacity(new_len);
+
+        for (current_present_index, null_run) in null_runs.iter().enumerate() {
+            new_values.resize(new_values.len() + *null_run as usize, T::default());
+            nulls.grow(*null_run as usize, true);
+
+            if let Some(value) = values.get(current_present_index) {
+                new_values.push(*value);
+                nulls.push(false);
             }
         }
 
-        Column::Utf8Direct {
-            data: data_bytes,
-            indices,
+        (new_values, Some(nulls))
+    }
+}
+
+/// The range of each value of a direct column (bytes, or rows of the child column for lists), or
+/// `None` for nulls.
+fn direct_ranges(null_runs: Option<Vec<u64>>, lengths: &[u64]) -> Vec<Option<(usize, usize)>> {
+    let null_count = null_runs
+        .as_ref()
+        .map(|null_runs| null_runs.iter().sum::<u64>() as usize)
+        .unwrap_or(0);
+    let mut ranges = Vec::with_capacity(lengths.len() + null_count);
+    let mut start = 0usize;
+
+    for (current_present_index, length) in lengths.iter().enumerate() {
+        if let Some(null_runs) = &null_runs {
+            ranges.resize(
+                ranges.len() + *null_runs.get(current_present_index).unwrap_or(&0) as usize,
+                None,
+            );
         }
+
+        // Corrupt lengths may overflow, but the resulting ranges are still checked against the data.
+        ranges.push(Some((start, start.saturating_add(*length as usize))));
+        start = start.saturating_add(*length as usize);
+    }
+
+    // The last null run follows the final value.
+    if let Some(null_run) = null_runs
+        .as_ref()
+        .and_then(|null_runs| null_runs.get(lengths.len()))
+    {
+        ranges.resize(ranges.len() + *null_run as usize, None);
     }
+
+    ranges
+}
+
+/// How to handle string values that aren't valid UTF-8.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Utf8Policy {
+    /// Fail with an error.
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD REPLACEMENT CHARACTER.
+    Lossy,
+}
+
+/// String data with the byte range of each value (or `None` for nulls).
+type IndexedStrings = (String, Vec<Option<(usize, usize)>>);
+
+/// Check that each range (if present) is valid UTF-8, and convert the data into a string.
+///
+/// With the lossy policy, if any value is invalid the data is rebuilt with replacement characters
+/// (in this case ranges past the end of the data are truncated), and the returned ranges refer to
+/// the rebuilt data.
+fn index_strings(
+    bytes: Vec<u8>,
+    ranges: Vec<Option<(usize, usize)>>,
+    utf8_policy: Utf8Policy,
+) -> Result<IndexedStrings, InvalidString> {
+    let invalid_index = ranges.iter().position(|range| {
+        range.is_some_and(|(start, end)| {
+            bytes
+                .get(start..end)
+                .is_none_or(|value| std::str::from_utf8(value).is_err())
+        })
+    });
+
+    match (invalid_index, utf8_policy) {
+        (None, _) => match String::from_utf8(bytes) {
+            Ok(data) => Ok((data, ranges)),
+            // There are invalid bytes that aren't part of any value, which we can drop.
+            Err(error) => Ok(index_strings_lossy(error.as_bytes(), &ranges)),
+        },
+        (Some(index), Utf8Policy::Strict) => Err(InvalidString { index }),
+        (Some(_), Utf8Policy::Lossy) => Ok(index_strings_lossy(&bytes, &ranges)),
+    }
+}
+
+fn index_strings_lossy(bytes: &[u8], ranges: &[Option<(usize, usize)>]) -> IndexedStrings {
+    let mut data = String::with_capacity(bytes.len());
+
+    let ranges = ranges
+        .iter()
+        .map(|range| {
+            range.map(|(start, end)| {
+                let start = start.min(bytes.len());
+                let end = end.clamp(start, bytes.len());
+                let new_start = data.len();
+                data.push_str(&String::from_utf8_lossy(&bytes[start..end]));
+                (new_start, data.len())
+            })
+        })
+        .collect();
+
+    (data, ranges)
 }
 
 pub struct BoolWriter {
     row_count: usize,
     present_info: PresentInfo,
-    values: BitVec,
-    nulls: Option<BitVec>,
+    values: Bitmap,
+    nulls: Option<Bitmap>,
     current_index: usize,
     current_present_index: usize,
 }
@@ -216,19 +561,24 @@ impl BoolWriter {
     pub fn new(row_count: usize, present_info: PresentInfo) -> BoolWriter {
         let nulls = match present_info {
             PresentInfo::All => None,
-            PresentInfo::NullRuns(_) => Some(BitVec::with_capacity(row_count)),
+            PresentInfo::NullRuns(_) => Some(Bitmap::with_capacity(row_count)),
         };
 
         BoolWriter {
             row_count,
             present_info,
-            values: BitVec::with_capacity(row_count),
+            values: Bitmap::with_capacity(row_count),
             nulls,
             current_index: 0,
             current_present_index: 0,
         }
     }
 
+    /// The number of values (not including nulls) written so far.
+    pub fn get_value_count(&self) -> usize {
+        self.current_present_index
+    }
+
     pub fn finish(mut self) -> Column {
         if let Some(ref mut nulls) = self.nulls {
             let null_run = self.present_info.null_run(self.current_present_index);
@@ -314,8 +664,14 @@ impl PresentInfoWriter {
         }
     }
 
+    /// The number of bits written so far (which may include padding).
+    pub fn get_bit_count(&self) -> u64 {
+        self.current_total + self.current_null_run_len
+    }
+
     pub fn into_inner(mut self) -> Vec<u64> {
-        self.null_runs.push(self.row_count - self.current_total);
+        self.null_runs
+            .push(self.row_count.saturating_sub(self.current_total));
         self.null_runs
     }
 }
@@ -340,3 +696,223 @@ impl Write for PresentInfoWriter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_dictionary_column() {
+        let column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "fooéa".as_bytes().to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        assert_eq!(
+            (0..5).map(|row| column.get(row)).collect::<Vec<_>>(),
+            vec![
+                Some(Value::Null),
+                Some(Value::Utf8("éa")),
+                Some(Value::Utf8("foo")),
+                Some(Value::Utf8("éa")),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_empty_strings_with_nulls() {
+        let mut dictionary_column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "foo".as_bytes().to_vec(),
+            vec![3, 0],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        let mut direct_column = Column::make_utf8_direct_column(
+            Some(vec![0, 1, 0]),
+            "foo".as_bytes().to_vec(),
+            vec![0, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        dictionary_column.replace_empty_strings_with_nulls();
+        direct_column.replace_empty_strings_with_nulls();
+
+        assert_eq!(
+            (0..4)
+                .map(|row| dictionary_column.get(row))
+                .collect::<Vec<_>>(),
+            vec![
+                Some(Value::Null),
+                Some(Value::Null),
+                Some(Value::Utf8("foo")),
+                Some(Value::Null)
+            ]
+        );
+        assert_eq!(
+            (0..3).map(|row| direct_column.get(row)).collect::<Vec<_>>(),
+            vec![
+                Some(Value::Null),
+                Some(Value::Null),
+                Some(Value::Utf8("foo"))
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8_dictionary_column_invalid() {
+        let bytes = "fooé".as_bytes().to_vec();
+
+        // The first entry ends in the middle of a character.
+        assert_eq!(
+            Column::make_utf8_dictionary_column(
+                None,
+                vec![0],
+                bytes.clone(),
+                vec![4, 1],
+                Utf8Policy::Strict
+            )
+            .err(),
+            Some(InvalidString { index: 0 })
+        );
+        // The second entry extends past the end of the dictionary.
+        assert_eq!(
+            Column::make_utf8_dictionary_column(
+                None,
+                vec![0],
+                bytes,
+                vec![3, 3],
+                Utf8Policy::Strict
+            )
+            .err(),
+            Some(InvalidString { index: 1 })
+        );
+    }
+
+    #[test]
+    fn match_strings() {
+        let column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "fooéa".as_bytes().to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        let mut calls = 0;
+        let matches = column.match_strings(|value| {
+            calls += 1;
+            value.starts_with('é')
+        });
+
+        assert_eq!(calls, 2);
+        assert_eq!(
+            (0..4).map(|row| matches[row]).collect::<Vec<_>>(),
+            vec![false, true, false, true]
+        );
+
+        let column = Column::make_utf8_direct_column(
+            Some(vec![1, 0, 0]),
+            "fooéa".as_bytes().to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        let matches = column.match_strings(|value| value == "foo");
+
+        assert_eq!(
+            (0..3).map(|row| matches[row]).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+    }
+
+    #[test]
+    fn utf8_direct_column() {
+        let column = Column::make_utf8_direct_column(
+            Some(vec![1, 0, 2]),
+            "fooéa".as_bytes().to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        assert_eq!(
+            (0..6).map(|row| column.get(row)).collect::<Vec<_>>(),
+            vec![
+                Some(Value::Null),
+                Some(Value::Utf8("foo")),
+                Some(Value::Utf8("éa")),
+                Some(Value::Null),
+                Some(Value::Null),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn to_vec_opt() {
+        let column = Column::make_i64_column(vec![1, -2], &[0, 1]);
+
+        assert_eq!(
+            column.to_vec_opt::<i64>(),
+            Some(vec![Some(1), None, Some(-2)])
+        );
+        // Negative values aren't converted to unsigned integers.
+        assert_eq!(column.to_vec_opt::<u64>(), None);
+        assert_eq!(
+            Column::make_i64_column(vec![1], &[]).to_vec_opt::<u64>(),
+            Some(vec![Some(1)])
+        );
+        assert_eq!(column.to_vec_opt::<String>(), None);
+
+        let column = Column::make_utf8_direct_column(
+            Some(vec![0, 1]),
+            b"foobar".to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        assert_eq!(
+            column.to_vec_opt::<String>(),
+            Some(vec![Some("foo".to_string()), None, Some("bar".to_string())])
+        );
+        assert_eq!(column.to_vec_opt::<bool>(), None);
+    }
+
+    #[test]
+    fn utf8_direct_column_invalid() {
+        let bytes = b"ab\xffcd".to_vec();
+
+        assert_eq!(
+            Column::make_utf8_direct_column(
+                Some(vec![1, 0]),
+                bytes.clone(),
+                vec![1, 4],
+                Utf8Policy::Strict
+            )
+            .err(),
+            Some(InvalidString { index: 2 })
+        );
+
+        let column =
+            Column::make_utf8_direct_column(Some(vec![1, 0]), bytes, vec![1, 4], Utf8Policy::Lossy)
+                .unwrap();
+
+        assert_eq!(
+            (0..3).map(|row| column.get(row)).collect::<Vec<_>>(),
+            vec![
+                Some(Value::Null),
+                Some(Value::Utf8("a")),
+                Some(Value::Utf8("b\u{FFFD}cd")),
+            ]
+        );
+    }
+}
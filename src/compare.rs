@@ -0,0 +1,329 @@
+//! Comparing the rows of a file with a reference dataset.
+//!
+//! Reference records are JSON objects (as in NDJSON), joined with the file's rows on one or more key
+//! fields. Values are compared leniently, so that references with less type information (such as CSV
+//! files, where every value is a string) can still be used: a JSON string matches a boolean or
+//! integer with the same string representation (or a byte string with the same hexadecimal
+//! representation), a JSON array matches a list whose elements match, and a missing field matches
+//! a null.
+
+use crate::parser::{self, OrcFile};
+use crate::value::{to_hex, OwnedValue, Value};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Parser error")]
+    Parser(#[from] parser::Error),
+    #[error("Unknown key field")]
+    UnknownKeyField(String),
+    #[error("No key fields")]
+    NoKeyFields,
+    #[error("Missing key")]
+    MissingKey { record_index: usize },
+}
+
+/// A field whose value in the file doesn't match the reference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldMismatch {
+    key: String,
+    field_name: String,
+    expected: JsonValue,
+    actual: OwnedValue,
+}
+
+impl FieldMismatch {
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get_field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    /// The value in the reference record.
+    pub fn get_expected(&self) -> &JsonValue {
+        &self.expected
+    }
+
+    /// The value in the file.
+    pub fn get_actual(&self) -> &OwnedValue {
+        &self.actual
+    }
+}
+
+/// The result of comparing a file with a reference dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comparison {
+    matched_count: u64,
+    mismatches: Vec<FieldMismatch>,
+    missing_from_file: Vec<String>,
+    missing_from_reference: Vec<String>,
+}
+
+impl Comparison {
+    /// The number of rows that match their reference records in every field.
+    pub fn get_matched_count(&self) -> u64 {
+        self.matched_count
+    }
+
+    pub fn get_mismatches(&self) -> &[FieldMismatch] {
+        &self.mismatches
+    }
+
+    /// The keys of reference records with no row in the file.
+    pub fn get_missing_from_file(&self) -> &[String] {
+        &self.missing_from_file
+    }
+
+    /// The keys of rows with no record in the reference (including rows with repeated keys that
+    /// have more rows than records).
+    pub fn get_missing_from_reference(&self) -> &[String] {
+        &self.missing_from_reference
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+            && self.missing_from_file.is_empty()
+            && self.missing_from_reference.is_empty()
+    }
+}
+
+/// Join the rows of the file with the reference records on the key fields and compare every
+/// field.
+///
+/// Keys are the key field values joined with commas. Keys don't have to be unique: a row is
+/// matched with a record with the same key and the same values if there is one, and otherwise with
+/// the first remaining record with the same key. The reference records are held in memory.
+pub fn compare_with_reference<I>(
+    orc_file: &mut OrcFile,
+    key_field_names: &[&str],
+    reference: I,
+) -> Result<Comparison, Error>
+where
+    I: IntoIterator<Item = Map<String, JsonValue>>,
+{
+    if key_field_names.is_empty() {
+        return Err(Error::NoKeyFields);
+    }
+
+    let key_indices = key_field_names
+        .iter()
+        .map(|field_name| {
+            orc_file
+                .get_field_index(field_name)
+                .ok_or_else(|| Error::UnknownKeyField(field_name.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut records: HashMap<String, Vec<Map<String, JsonValue>>> = HashMap::new();
+    // Keep the reference order for reporting missing records.
+    let mut keys = vec![];
+
+    for (record_index, record) in reference.into_iter().enumerate() {
+        let key = key_field_names
+            .iter()
+            .map(|field_name| record.get(*field_name).and_then(json_key))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::MissingKey { record_index })?
+            .join(",");
+
+        keys.push(key.clone());
+        records.entry(key).or_default().push(record);
+    }
+
+    let field_names = orc_file.get_field_names().to_vec();
+    let columns = orc_file.get_field_column_indices();
+    let mut comparison = Comparison::default();
+
+    for result in orc_file.map_rows(&columns, |values| {
+        Ok::<_, parser::Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+    })? {
+        let values = result?;
+        let key = key_indices
+            .iter()
+            .map(|index| value_key(&values[*index]))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        match records.get_mut(&key).filter(|group| !group.is_empty()) {
+            Some(group) => {
+                let position = group
+                    .iter()
+                    .position(|record| mismatched_fields(&field_names, record, &values).is_empty())
+                    .unwrap_or(0);
+                let record = group.remove(position);
+                let mismatched = mismatched_fields(&field_names, &record, &values);
+
+                if mismatched.is_empty() {
+                    comparison.matched_count += 1;
+                }
+
+                for index in mismatched {
+                    comparison.mismatches.push(FieldMismatch {
+                        key: key.clone(),
+                        field_name: field_names[index].clone(),
+                        expected: record
+                            .get(&field_names[index])
+                            .cloned()
+                            .unwrap_or(JsonValue::Null),
+                        actual: values[index].clone(),
+                    });
+                }
+            }
+            None => comparison.missing_from_reference.push(key),
+        }
+    }
+
+    // Each remaining record is reported once, in reference order.
+    for key in keys {
+        if let Some(group) = records.get_mut(&key) {
+            if !group.is_empty() {
+                group.remove(0);
+                comparison.missing_from_file.push(key);
+            }
+        }
+    }
+
+    Ok(comparison)
+}
+
+// The indices of the fields whose values don't match the record.
+fn mismatched_fields(
+    field_names: &[String],
+    record: &Map<String, JsonValue>,
+    values: &[OwnedValue],
+) -> Vec<usize> {
+    field_names
+        .iter()
+        .zip(values)
+        .enumerate()
+        .filter(|(_, (field_name, value))| {
+            !matches(record.get(*field_name).unwrap_or(&JsonValue::Null), value)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn matches(expected: &JsonValue, actual: &OwnedValue) -> bool {
+    match (expected, actual) {
+        (JsonValue::Null, OwnedValue::Null) => true,
+        (JsonValue::Bool(expected), OwnedValue::Bool(actual)) => expected == actual,
+        (JsonValue::Number(expected), OwnedValue::U64(actual)) => {
+            expected.as_u64() == Some(*actual)
+        }
+        (JsonValue::Number(expected), OwnedValue::I64(actual)) => {
+            expected.as_i64() == Some(*actual)
+        }
+        (JsonValue::String(expected), OwnedValue::Utf8(actual)) => expected == actual,
+        (JsonValue::String(expected), OwnedValue::Bytes(actual)) => *expected == to_hex(actual),
+        (JsonValue::Array(expected), OwnedValue::List(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| matches(expected, actual))
+        }
+        (JsonValue::String(expected), OwnedValue::Bool(actual)) => *expected == actual.to_string(),
+        (JsonValue::String(expected), OwnedValue::U64(actual)) => *expected == actual.to_string(),
+        (JsonValue::String(expected), OwnedValue::I64(actual)) => *expected == actual.to_string(),
+        (JsonValue::String(expected), OwnedValue::Decimal { value, scale }) => {
+            *expected == crate::decimal::format_scaled(*value, *scale)
+        }
+        (JsonValue::String(expected), OwnedValue::Timestamp(actual)) => {
+            *expected == actual.to_string()
+        }
+        _ => false,
+    }
+}
+
+fn json_key(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(value) => Some(value.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn value_key(value: &OwnedValue) -> String {
+    match value {
+        OwnedValue::Null => "null".to_string(),
+        OwnedValue::Bool(value) => value.to_string(),
+        OwnedValue::U64(value) => value.to_string(),
+        OwnedValue::I64(value) => value.to_string(),
+        OwnedValue::Decimal { .. } => value.as_value().to_decimal_string().unwrap_or_default(),
+        OwnedValue::Timestamp(value) => value.to_string(),
+        OwnedValue::Utf8(value) => value.clone(),
+        OwnedValue::Bytes(value) => to_hex(value),
+        OwnedValue::List(_) => value
+            .as_value()
+            .as_list()
+            .map(|list| list.to_json_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+
+    fn load_reference() -> Vec<Map<String, JsonValue>> {
+        std::io::BufReader::new(std::fs::File::open("examples/ts-1k-2020-09-20.ndjson").unwrap())
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn compare_ts_1k() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let mut reference = load_reference();
+
+        let comparison =
+            compare_with_reference(&mut orc_file, &["id", "status_id"], reference.clone()).unwrap();
+        assert!(comparison.is_match());
+        assert_eq!(
+            comparison.get_matched_count(),
+            orc_file.get_footer().get_number_of_rows()
+        );
+
+        // Change one value, drop one record, and use strings for the integers in another.
+        let key =
+            |record: &Map<String, JsonValue>| format!("{},{}", record["id"], record["status_id"]);
+        let changed_key = key(&reference[0]);
+        let missing_key = key(&reference[1]);
+        reference[0].insert("screen_name".to_string(), JsonValue::from("foo"));
+        reference.remove(1);
+        for field_name in ["id", "status_id", "timestamp"] {
+            let value = reference[2][field_name].to_string();
+            reference[2].insert(field_name.to_string(), JsonValue::String(value));
+        }
+
+        let comparison =
+            compare_with_reference(&mut orc_file, &["id", "status_id"], reference).unwrap();
+        assert_eq!(comparison.get_mismatches().len(), 1);
+        assert_eq!(comparison.get_mismatches()[0].get_key(), changed_key);
+        assert_eq!(
+            comparison.get_mismatches()[0].get_field_name(),
+            "screen_name"
+        );
+        assert_eq!(comparison.get_missing_from_reference(), [missing_key]);
+        assert!(comparison.get_missing_from_file().is_empty());
+    }
+
+    #[test]
+    fn unknown_key() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+
+        assert!(matches!(
+            compare_with_reference(&mut orc_file, &["missing"], vec![]),
+            Err(Error::UnknownKeyField(_))
+        ));
+        assert!(matches!(
+            compare_with_reference(&mut orc_file, &["id"], vec![Map::new()]),
+            Err(Error::MissingKey { record_index: 0 })
+        ));
+    }
+}
@@ -1,13 +1,43 @@
-use crate::proto::orc_proto::CompressionKind;
-use flate2::read::DeflateDecoder;
-use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom, Take};
-use zstd::stream::read::Decoder as ZstdDecoder;
+//! Decompression of ORC streams.
+//!
+//! When a file is compressed, each stream is stored as a sequence of chunks, each of which starts
+//! with a three-byte little-endian header. The lowest bit of the header indicates whether the
+//! chunk is stored in its original (uncompressed) form, and the remaining 23 bits give the length
+//! of the chunk, not including the header. Uncompressed files don't use chunk headers.
+
+use crate::meta::CompressionKind;
+#[cfg(feature = "zlib")]
+use flate2::{Decompress, FlushDecompress, Status};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::sync::Arc;
+#[cfg(feature = "zstd")]
+use zstd::stream::raw::{Decoder as ZstdContext, InBuffer, Operation, OutBuffer};
 
 // The compression header will always be three bytes.
 const COMPRESSION_HEADER_LEN: usize = 3;
-// No compression is typically only used for small messages.
-const NONE_COMPRESSION_BUFFER_CAPACITY: usize = 512;
+// The size of the Brotli decoder's input buffer.
+#[cfg(feature = "brotli")]
+const BROTLI_BUFFER_LEN: usize = 4096;
+// The minimum amount of space we make available in the output buffer before each decoding step.
+#[cfg(any(feature = "lz4", feature = "zlib", feature = "zstd"))]
+const MIN_OUTPUT_RESERVE: usize = 32 * 1024;
+
+/// The compression kinds that this build can read (depending on the `brotli`, `lz4`, `lzo`,
+/// `zlib`, and `zstd` features).
+pub const SUPPORTED_COMPRESSION_KINDS: &[CompressionKind] = &[
+    #[cfg(feature = "zstd")]
+    CompressionKind::Zstd,
+    #[cfg(feature = "zlib")]
+    CompressionKind::Zlib,
+    #[cfg(feature = "lz4")]
+    CompressionKind::Lz4,
+    #[cfg(feature = "lzo")]
+    CompressionKind::Lzo,
+    #[cfg(feature = "brotli")]
+    CompressionKind::Brotli,
+    CompressionKind::None,
+];
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -19,48 +49,360 @@ pub enum Error {
     ExpectedLenMismatch(u64, u64),
     #[error("Invalid state")]
     InvalidState,
+    /// A chunk header at the given offset in the stream declares more bytes than remain.
+    #[error("Invalid chunk length")]
+    InvalidChunkLength {
+        offset: u64,
+        chunk_len: u64,
+        remaining: u64,
+    },
+    /// The chunk at the given offset in the stream decompresses to more than the maximum length.
+    #[error("Chunk too large")]
+    ChunkTooLarge { offset: u64, max_len: usize },
+    /// The chunk at the given offset in the stream couldn't be read or decompressed.
+    #[error("Invalid chunk")]
+    InvalidChunk {
+        offset: u64,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    pub fn category(&self) -> crate::parser::ErrorCategory {
+        use crate::parser::ErrorCategory;
+
+        match self {
+            Error::Io(error) => crate::parser::io_error_category(error),
+            Error::UnsupportedCompression(_) => ErrorCategory::Unsupported,
+            Error::ChunkTooLarge { .. } => ErrorCategory::Limits,
+            Error::InvalidState => ErrorCategory::Usage,
+            Error::ExpectedLenMismatch(_, _)
+            | Error::InvalidChunkLength { .. }
+            | Error::InvalidChunk { .. } => ErrorCategory::Corruption,
+        }
+    }
+}
+
+// Errors from reading a decompressor have to be I/O errors, but the original can be recovered with
+// `std::io::Error::into_inner`.
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) => error,
+            other => std::io::Error::new(ErrorKind::InvalidData, other),
+        }
+    }
 }
 
-pub struct Decompressor {
-    decoder: Option<Decoder>,
+/// A decompressor for single chunks, which can be registered for a compression kind in a
+/// [`CodecRegistry`].
+pub trait BlockCodec: Send + Sync {
+    /// Decompress a chunk, appending the result to `output`.
+    ///
+    /// If a maximum length is given, the codec can stop as soon as the output is longer (the chunk
+    /// is reported as too large either way).
+    fn decompress(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        max_len: Option<usize>,
+    ) -> Result<(), std::io::Error>;
+}
+
+/// Codecs to use for compression kinds that this build doesn't support (or in place of the
+/// built-in ones).
+#[derive(Clone, Default)]
+pub struct CodecRegistry {
+    codecs: HashMap<CompressionKind, Arc<dyn BlockCodec>>,
+}
+
+impl CodecRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a codec, replacing any codec previously registered for the same kind.
+    ///
+    /// Uncompressed streams have no chunks, so a codec registered for `CompressionKind::None` is
+    /// never used.
+    pub fn register<C: BlockCodec + 'static>(&mut self, compression: CompressionKind, codec: C) {
+        self.codecs.insert(compression, Arc::new(codec));
+    }
+
+    pub fn get(&self, compression: CompressionKind) -> Option<&dyn BlockCodec> {
+        self.codecs.get(&compression).map(|codec| codec.as_ref())
+    }
+
+    /// Whether chunks of this kind can be decompressed, either by a registered codec or by this
+    /// build.
+    pub fn is_supported(&self, compression: CompressionKind) -> bool {
+        self.codecs.contains_key(&compression) || SUPPORTED_COMPRESSION_KINDS.contains(&compression)
+    }
+}
+
+/// Decompression state and buffers that can be reused across chunks and streams.
+///
+/// Compressed chunks are decompressed in full into the output buffer, so after the first few
+/// chunks of a file no further allocation is needed.
+#[derive(Default)]
+pub struct DecompressionContext {
+    #[cfg(feature = "zstd")]
+    zstd: Option<ZstdContext<'static>>,
+    #[cfg(feature = "zlib")]
+    zlib: Option<Decompress>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    max_output_len: Option<usize>,
+    codecs: CodecRegistry,
+}
+
+impl DecompressionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail with `ChunkTooLarge` if a chunk decompresses to more than this many bytes.
+    pub fn set_max_output_len(&mut self, max_output_len: Option<usize>) {
+        self.max_output_len = max_output_len;
+    }
+
+    /// Use the registered codecs in preference to the built-in ones.
+    pub fn set_codecs(&mut self, codecs: CodecRegistry) {
+        self.codecs = codecs;
+    }
+
+    pub fn get_codecs(&self) -> &CodecRegistry {
+        &self.codecs
+    }
+
+    fn decompress(&mut self, compression: CompressionKind) -> Result<(), std::io::Error> {
+        self.output.clear();
+
+        if let Some(codec) = self.codecs.get(compression) {
+            return codec.decompress(&self.input, &mut self.output, self.max_output_len);
+        }
+
+        match compression {
+            #[cfg(feature = "zstd")]
+            CompressionKind::Zstd => self.decompress_zstd(),
+            #[cfg(feature = "zlib")]
+            CompressionKind::Zlib => self.decompress_zlib(),
+            #[cfg(feature = "lz4")]
+            CompressionKind::Lz4 => self.decompress_lz4(),
+            #[cfg(feature = "lzo")]
+            CompressionKind::Lzo => {
+                crate::lzo::decompress(&self.input, &mut self.output, self.max_output_len)
+                    .map_err(|error| std::io::Error::new(ErrorKind::InvalidData, error))
+            }
+            #[cfg(feature = "brotli")]
+            CompressionKind::Brotli => self.decompress_brotli(),
+            other => Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported compression: {:?}", other),
+            )),
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    fn decompress_zstd(&mut self) -> Result<(), std::io::Error> {
+        let context = match &mut self.zstd {
+            Some(context) => context,
+            None => self.zstd.insert(ZstdContext::new()?),
+        };
+        context.reinit()?;
+
+        if self.input.is_empty() {
+            return Ok(());
+        }
+
+        let mut input = InBuffer::around(&self.input);
+
+        loop {
+            if is_over_limit(&self.output, self.max_output_len) {
+                return Ok(());
+            }
+
+            reserve_output(&mut self.output);
+            let pos = self.output.len();
+            let mut output = OutBuffer::around_pos(&mut self.output, pos);
+            let hint = context.run(&mut input, &mut output)?;
+            let output_full = output.pos() == self.output.capacity();
+
+            if input.pos() == self.input.len() {
+                if hint == 0 {
+                    return Ok(());
+                } else if !output_full {
+                    return Err(std::io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "incomplete frame",
+                    ));
+                }
+            } else if hint == 0 {
+                // A chunk may contain more than one frame.
+                context.reinit()?;
+            }
+        }
+    }
+
+    #[cfg(feature = "zlib")]
+    fn decompress_zlib(&mut self) -> Result<(), std::io::Error> {
+        let context = match &mut self.zlib {
+            Some(context) => {
+                context.reset(false);
+                context
+            }
+            None => self.zlib.insert(Decompress::new(false)),
+        };
+
+        loop {
+            if is_over_limit(&self.output, self.max_output_len) {
+                return Ok(());
+            }
+
+            reserve_output(&mut self.output);
+            let consumed = context.total_in() as usize;
+            let status = context
+                .decompress_vec(
+                    &self.input[consumed..],
+                    &mut self.output,
+                    FlushDecompress::None,
+                )
+                .map_err(|error| std::io::Error::new(ErrorKind::InvalidData, error))?;
+
+            let output_full = self.output.len() == self.output.capacity();
+
+            if status == Status::StreamEnd
+                || (context.total_in() as usize == self.input.len() && !output_full)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    #[cfg(feature = "brotli")]
+    fn decompress_brotli(&mut self) -> Result<(), std::io::Error> {
+        let mut decoder =
+            brotli_decompressor::Decompressor::new(self.input.as_slice(), BROTLI_BUFFER_LEN);
+
+        // Reading stops once the output is over the limit.
+        match self.max_output_len {
+            Some(max_output_len) => decoder
+                .take(max_output_len as u64 + 1)
+                .read_to_end(&mut self.output)?,
+            None => decoder.read_to_end(&mut self.output)?,
+        };
+
+        Ok(())
+    }
+
+    // Chunks are single LZ4 blocks (without the frame format), which don't record their
+    // decompressed length, so the output buffer is grown until the block fits.
+    #[cfg(feature = "lz4")]
+    fn decompress_lz4(&mut self) -> Result<(), std::io::Error> {
+        let mut output_len = MIN_OUTPUT_RESERVE.max(self.input.len() * 4);
+
+        loop {
+            self.output.resize(output_len, 0);
+
+            match lz4_flex::block::decompress_into(&self.input, &mut self.output) {
+                Ok(len) => {
+                    self.output.truncate(len);
+                    return Ok(());
+                }
+                Err(lz4_flex::block::DecompressError::OutputTooSmall { expected, .. }) => {
+                    if is_over_limit(&self.output, self.max_output_len) {
+                        return Ok(());
+                    }
+
+                    output_len = expected.max(output_len * 2);
+                }
+                Err(error) => return Err(std::io::Error::new(ErrorKind::InvalidData, error)),
+            }
+        }
+    }
+}
+
+// Decompression stops early once the output is over the limit (which `next_chunk` checks).
+#[cfg(any(feature = "lz4", feature = "zlib", feature = "zstd"))]
+fn is_over_limit(output: &[u8], max_output_len: Option<usize>) -> bool {
+    max_output_len.is_some_and(|max_output_len| output.len() > max_output_len)
+}
+
+#[cfg(any(feature = "zlib", feature = "zstd"))]
+fn reserve_output(output: &mut Vec<u8>) {
+    if output.capacity() - output.len() < MIN_OUTPUT_RESERVE {
+        output.reserve(output.len().max(MIN_OUTPUT_RESERVE));
+    }
+}
+
+/// A reader for a single stream that decompresses its chunks in sequence.
+///
+/// The underlying reader is positioned at the start of the stream when the decompressor is opened,
+/// and is returned by `into_inner` so that it can be reused for the next stream. Similarly the
+/// decompression context can be passed in with `open_with_context` and recovered with
+/// `into_parts`.
+pub struct Decompressor<R: Read> {
+    file: R,
+    chunk: Chunk,
+    context: DecompressionContext,
     compression: CompressionKind,
+    len: u64,
+    // The number of bytes in the stream after the current chunk.
     remaining: u64,
+    decompressed_len: u64,
 }
 
-impl Decompressor {
+enum Chunk {
+    /// A compressed chunk that has been decompressed into the context's output buffer.
+    Buffered { position: usize },
+    /// A chunk stored in its original form (or an uncompressed stream), with the bytes left to read.
+    Original { remaining: u64 },
+}
+
+impl<R: Read + Seek> Decompressor<R> {
+    /// Open a decompressor for the stream of `len` bytes (including chunk headers) at `pos`.
     pub fn open(
-        mut file: File,
+        file: R,
         compression: CompressionKind,
         pos: SeekFrom,
         len: u64,
-    ) -> Result<Decompressor, Error> {
-        file.seek(pos)?;
-
-        let (chunk_compression, chunk_len, remaining) = if compression == CompressionKind::NONE {
-            (compression, len, 0)
-        } else {
-            let (is_original, chunk_len) = Self::read_header(&mut file)?;
+    ) -> Result<Decompressor<R>, Error> {
+        Self::open_with_context(file, DecompressionContext::new(), compression, pos, len)
+    }
 
-            let chunk_compression = if is_original {
-                CompressionKind::NONE
-            } else {
-                compression
-            };
+    /// Open a decompressor that reuses the given decompression context.
+    pub fn open_with_context(
+        mut file: R,
+        context: DecompressionContext,
+        compression: CompressionKind,
+        pos: SeekFrom,
+        len: u64,
+    ) -> Result<Decompressor<R>, Error> {
+        file.seek(pos)?;
 
-            (chunk_compression, chunk_len, len - (chunk_len + 3))
+        let mut decompressor = Decompressor {
+            file,
+            chunk: Chunk::Original { remaining: 0 },
+            context,
+            compression,
+            len,
+            remaining: len,
+            decompressed_len: 0,
         };
 
-        let file = file.take(chunk_len);
-        let decoder = Self::open_decoder(file, chunk_compression)?;
+        if compression == CompressionKind::None {
+            // Uncompressed streams don't have chunk headers.
+            decompressor.chunk = Chunk::Original { remaining: len };
+            decompressor.remaining = 0;
+        } else {
+            decompressor.next_chunk()?;
+        }
 
-        Ok(Decompressor {
-            decoder: Some(decoder),
-            compression,
-            remaining,
-        })
+        Ok(decompressor)
     }
 
-    fn read_header(file: &mut File) -> Result<(bool, u64), std::io::Error> {
+    fn read_header(file: &mut R) -> Result<(bool, u64), std::io::Error> {
         let mut header_buffer = [0; COMPRESSION_HEADER_LEN];
 
         file.read_exact(&mut header_buffer)?;
@@ -73,77 +415,376 @@ impl Decompressor {
         Ok((is_original, header_value))
     }
 
-    fn open_decoder(
-        file: Take<File>,
-        compression: CompressionKind,
-    ) -> Result<Decoder, std::io::Error> {
-        match compression {
-            CompressionKind::ZSTD => Ok(Decoder::Zstd(ZstdDecoder::new(file)?)),
-            CompressionKind::ZLIB => Ok(Decoder::Zlib(DeflateDecoder::new(file))),
-            CompressionKind::NONE => Ok(Decoder::None(BufReader::with_capacity(
-                NONE_COMPRESSION_BUFFER_CAPACITY,
-                file,
-            ))),
-            other => {
-                panic!(
-                    "We should have already checked that this compression type ({:?}) was supported",
-                    other
-                )
+    /// Read the next chunk header and decompress the chunk if necessary.
+    fn next_chunk(&mut self) -> Result<(), Error> {
+        let offset = self.len - self.remaining;
+        let (is_original, chunk_len) = Self::read_header(&mut self.file)
+            .map_err(|source| Error::InvalidChunk { offset, source })?;
+
+        let remaining = self
+            .remaining
+            .checked_sub(chunk_len + COMPRESSION_HEADER_LEN as u64)
+            .ok_or(Error::InvalidChunkLength {
+                offset,
+                chunk_len,
+                remaining: self.remaining,
+            })?;
+
+        self.remaining = remaining;
+
+        if is_original {
+            self.chunk = Chunk::Original {
+                remaining: chunk_len,
+            };
+        } else {
+            // If decompression fails, the chunk is left empty.
+            self.context.input.clear();
+            self.context.output.clear();
+            self.chunk = Chunk::Buffered { position: 0 };
+
+            (&mut self.file)
+                .take(chunk_len)
+                .read_to_end(&mut self.context.input)
+                .and_then(|_| self.context.decompress(self.compression))
+                .map_err(|source| {
+                    self.context.output.clear();
+                    Error::InvalidChunk { offset, source }
+                })?;
+
+            if let Some(max_len) = self.context.max_output_len {
+                if self.context.output.len() > max_len {
+                    self.context.output.clear();
+                    return Err(Error::ChunkTooLarge { offset, max_len });
+                }
             }
         }
+
+        Ok(())
     }
 
-    pub fn into_inner(mut self) -> File {
-        self.decoder.take().unwrap().into_inner()
+    /// The number of decompressed bytes that have been read so far.
+    pub fn get_decompressed_len(&self) -> u64 {
+        self.decompressed_len
+    }
+
+    pub fn into_inner(self) -> R {
+        self.file
+    }
+
+    /// Return the underlying reader and the decompression context, so that both can be reused.
+    pub fn into_parts(self) -> (R, DecompressionContext) {
+        (self.file, self.context)
     }
 }
 
-impl Read for Decompressor {
+impl<R: Read + Seek> Read for Decompressor<R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
-        let mut decoder = self.decoder.as_mut().expect("Invalid state");
+        loop {
+            let bytes_read = match &mut self.chunk {
+                Chunk::Buffered { position } => {
+                    let available = &self.context.output[*position..];
+                    let len = available.len().min(buf.len());
+                    buf[..len].copy_from_slice(&available[..len]);
+                    *position += len;
+                    len
+                }
+                Chunk::Original { remaining } => {
+                    let len = (*remaining).min(buf.len() as u64) as usize;
+                    let len = self.file.read(&mut buf[..len])?;
+                    *remaining -= len as u64;
+                    len
+                }
+            };
 
-        let bytes_read = match &mut decoder {
-            Decoder::Zstd(decoder) => decoder.read(buf),
-            Decoder::Zlib(decoder) => decoder.read(buf),
-            Decoder::None(reader) => reader.read(buf),
-        }?;
+            if bytes_read == 0 && self.remaining != 0 && !buf.is_empty() {
+                self.next_chunk()?;
+            } else {
+                self.decompressed_len += bytes_read as u64;
+                return Ok(bytes_read);
+            }
+        }
+    }
+}
 
-        if bytes_read == 0 && self.remaining != 0 {
-            let mut file = self.decoder.take().expect("Invalid state").into_inner();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
 
-            let (is_original, chunk_len) = Self::read_header(&mut file)?;
+    #[test]
+    fn decompress_original_chunks() {
+        // Two chunks stored in their original form, preceded by some unrelated bytes.
+        let mut bytes = vec![0xff, 0xff];
+        bytes.extend([0x07, 0x00, 0x00, b'a', b'b', b'c']);
+        bytes.extend([0x05, 0x00, 0x00, b'd', b'e']);
 
-            let chunk_compression = if is_original {
-                CompressionKind::NONE
-            } else {
-                self.compression
-            };
-            let file = file.take(chunk_len);
-            let decoder = Self::open_decoder(file, chunk_compression)?;
-            self.decoder = Some(decoder);
-            self.remaining -= chunk_len + 3;
+        let mut decompressor = Decompressor::open(
+            Cursor::new(bytes),
+            CompressionKind::Zlib,
+            SeekFrom::Start(2),
+            11,
+        )
+        .unwrap();
 
-            self.read(buf)
-        } else {
-            Ok(bytes_read)
+        let mut result = vec![];
+        decompressor.read_to_end(&mut result).unwrap();
+
+        assert_eq!(result, b"abcde");
+        assert_eq!(decompressor.into_inner().position(), 13);
+    }
+
+    #[test]
+    fn invalid_chunk_length() {
+        // The second chunk claims to be longer than the rest of the stream.
+        let mut bytes = vec![0x07, 0x00, 0x00, b'a', b'b', b'c'];
+        bytes.extend([0x09, 0x00, 0x00, b'd', b'e']);
+
+        assert!(matches!(
+            Decompressor::open(
+                Cursor::new(&bytes),
+                CompressionKind::Zlib,
+                SeekFrom::Start(6),
+                5
+            ),
+            Err(Error::InvalidChunkLength {
+                offset: 0,
+                chunk_len: 4,
+                remaining: 5
+            })
+        ));
+
+        let mut decompressor = Decompressor::open(
+            Cursor::new(&bytes),
+            CompressionKind::Zlib,
+            SeekFrom::Start(0),
+            11,
+        )
+        .unwrap();
+
+        let mut result = vec![];
+        let error = decompressor.read_to_end(&mut result).unwrap_err();
+
+        assert!(matches!(
+            error.into_inner().unwrap().downcast::<Error>().as_deref(),
+            Ok(Error::InvalidChunkLength {
+                offset: 6,
+                chunk_len: 4,
+                remaining: 5
+            })
+        ));
+        assert_eq!(result, b"abc");
+    }
+
+    #[test]
+    fn invalid_compressed_chunk() {
+        // A compressed chunk that isn't valid for the codec.
+        let bytes = vec![0x06, 0x00, 0x00, 0xff, 0xff, 0xff];
+
+        for compression in [
+            CompressionKind::Zlib,
+            CompressionKind::Zstd,
+            CompressionKind::Lz4,
+            CompressionKind::Lzo,
+            CompressionKind::Brotli,
+        ] {
+            assert!(matches!(
+                Decompressor::open(
+                    Cursor::new(&bytes),
+                    compression,
+                    SeekFrom::Start(0),
+                    bytes.len() as u64
+                ),
+                Err(Error::InvalidChunk { offset: 0, .. })
+            ));
         }
     }
-}
 
-enum Decoder {
-    Zstd(ZstdDecoder<'static, BufReader<Take<File>>>),
-    Zlib(DeflateDecoder<Take<File>>),
-    None(BufReader<Take<File>>),
-}
+    // A toy codec that stores chunks reversed.
+    struct ReversedCodec;
 
-impl Decoder {
-    fn into_inner(self) -> File {
-        let take = match self {
-            Decoder::Zstd(decoder) => decoder.finish().into_inner(),
-            Decoder::Zlib(decoder) => decoder.into_inner(),
-            Decoder::None(reader) => reader.into_inner(),
-        };
+    impl BlockCodec for ReversedCodec {
+        fn decompress(
+            &self,
+            input: &[u8],
+            output: &mut Vec<u8>,
+            _max_len: Option<usize>,
+        ) -> Result<(), std::io::Error> {
+            output.extend(input.iter().rev());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registered_codecs() {
+        // Two compressed chunks, "cba" and "fed".
+        let bytes = vec![
+            0x06, 0x00, 0x00, b'c', b'b', b'a', 0x06, 0x00, 0x00, b'f', b'e', b'd',
+        ];
+
+        let mut codecs = CodecRegistry::new();
+        assert!(!codecs.is_supported(CompressionKind::Snappy));
+        codecs.register(CompressionKind::Snappy, ReversedCodec);
+        // Registered codecs take precedence over the built-in ones.
+        codecs.register(CompressionKind::Zlib, ReversedCodec);
+        assert!(codecs.is_supported(CompressionKind::Snappy));
+        assert!(codecs.is_supported(CompressionKind::None));
+
+        for compression in [CompressionKind::Snappy, CompressionKind::Zlib] {
+            let mut context = DecompressionContext::new();
+            context.set_codecs(codecs.clone());
+
+            let mut decompressor = Decompressor::open_with_context(
+                Cursor::new(&bytes),
+                context,
+                compression,
+                SeekFrom::Start(0),
+                bytes.len() as u64,
+            )
+            .unwrap();
+
+            let mut result = vec![];
+            decompressor.read_to_end(&mut result).unwrap();
+            assert_eq!(result, b"abcdef");
+        }
+
+        let mut context = DecompressionContext::new();
+        context.set_codecs(codecs);
+        context.set_max_output_len(Some(2));
+
+        assert!(matches!(
+            Decompressor::open_with_context(
+                Cursor::new(&bytes),
+                context,
+                CompressionKind::Snappy,
+                SeekFrom::Start(0),
+                bytes.len() as u64
+            ),
+            Err(Error::ChunkTooLarge {
+                offset: 0,
+                max_len: 2
+            })
+        ));
+    }
+
+    #[cfg(any(
+        feature = "brotli",
+        feature = "lz4",
+        feature = "zlib",
+        feature = "zstd"
+    ))]
+    fn chunk(compressed: &[u8]) -> Vec<u8> {
+        let header = (compressed.len() as u32) << 1;
+        let mut bytes = header.to_le_bytes()[..3].to_vec();
+        bytes.extend(compressed);
+        bytes
+    }
+
+    #[cfg(any(
+        feature = "brotli",
+        feature = "lz4",
+        feature = "zlib",
+        feature = "zstd"
+    ))]
+    fn decompress_chunks(
+        compression: CompressionKind,
+        compress: fn(&[u8]) -> Vec<u8>,
+    ) -> DecompressionContext {
+        let first = (0..100_000u32)
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<_>>();
+        let second = b"abcde".repeat(1000);
+
+        let mut bytes = chunk(&compress(&first[..200_000]));
+        bytes.extend(chunk(&compress(&first[200_000..])));
+        let first_len = bytes.len() as u64;
+        bytes.extend(chunk(&compress(&second)));
+
+        let mut context = DecompressionContext::new();
+
+        for (pos, len, expected) in [
+            (0, first_len, &first),
+            (first_len, bytes.len() as u64 - first_len, &second),
+        ] {
+            let mut decompressor = Decompressor::open_with_context(
+                Cursor::new(&bytes),
+                context,
+                compression,
+                SeekFrom::Start(pos),
+                len,
+            )
+            .unwrap();
+
+            let mut result = vec![];
+            decompressor.read_to_end(&mut result).unwrap();
+            assert_eq!(&result, expected);
+
+            let (file, returned_context) = decompressor.into_parts();
+            assert_eq!(file.position(), pos + len);
+            context = returned_context;
+        }
+
+        context
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn decompress_zstd_chunks_with_context() {
+        let context = decompress_chunks(CompressionKind::Zstd, |bytes| {
+            zstd::bulk::compress(bytes, 0).unwrap()
+        });
+        assert!(context.zstd.is_some());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn chunk_too_large() {
+        // A small chunk that expands to a megabyte.
+        let bytes = chunk(&zstd::bulk::compress(&[0; 1 << 20], 0).unwrap());
+        let mut context = DecompressionContext::new();
+        context.set_max_output_len(Some(1 << 16));
+
+        assert!(matches!(
+            Decompressor::open_with_context(
+                Cursor::new(&bytes),
+                context,
+                CompressionKind::Zstd,
+                SeekFrom::Start(0),
+                bytes.len() as u64
+            ),
+            Err(Error::ChunkTooLarge {
+                offset: 0,
+                max_len: 65536
+            })
+        ));
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn decompress_brotli_chunks_with_context() {
+        decompress_chunks(CompressionKind::Brotli, |bytes| {
+            let mut output = vec![];
+            let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 5, 22);
+            std::io::Write::write_all(&mut encoder, bytes).unwrap();
+            drop(encoder);
+            output
+        });
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn decompress_lz4_chunks_with_context() {
+        decompress_chunks(CompressionKind::Lz4, lz4_flex::block::compress);
+    }
 
-        take.into_inner()
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn decompress_zlib_chunks_with_context() {
+        let context = decompress_chunks(CompressionKind::Zlib, |bytes| {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+            std::io::Write::write_all(&mut encoder, bytes).unwrap();
+            encoder.finish().unwrap()
+        });
+        assert!(context.zlib.is_some());
     }
 }
@@ -1,16 +1,190 @@
-use crate::value::Value;
+use crate::parser::ErrorCategory;
+use crate::value::{ListValue, OwnedValue, Value};
 use serde::de::{Deserialize, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub(crate) fn get_field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
     serde_aux::serde_introspection::serde_introspect::<T>()
 }
 
+/// Options for resolving struct field names to ORC field names.
+///
+/// An exact match is always preferred; otherwise the first ORC field whose normalized name equals
+/// the normalized struct field name is used.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FieldNameMatching {
+    case_insensitive: bool,
+    ignore_underscores: bool,
+    prefixes: Vec<String>,
+}
+
+impl FieldNameMatching {
+    /// Only match field names exactly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore ASCII case when comparing names.
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Ignore case and underscores when comparing names, so that `screenName`, `ScreenName`, and
+    /// `screen_name` all match.
+    pub fn ignore_case_style(mut self) -> Self {
+        self.case_insensitive = true;
+        self.ignore_underscores = true;
+        self
+    }
+
+    /// Strip a prefix (such as `t.` or `tbl_`) from ORC field names before comparing them.
+    ///
+    /// Only the first matching prefix is stripped.
+    pub fn strip_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// The index of the ORC field that the struct field name resolves to, if any.
+    pub fn resolve(&self, field_name: &str, orc_field_names: &[String]) -> Option<usize> {
+        orc_field_names
+            .iter()
+            .position(|orc_field_name| orc_field_name == field_name)
+            .or_else(|| {
+                let field_name = self.normalize(field_name);
+
+                orc_field_names.iter().position(|orc_field_name| {
+                    let orc_field_name = self
+                        .prefixes
+                        .iter()
+                        .find_map(|prefix| orc_field_name.strip_prefix(prefix.as_str()))
+                        .unwrap_or(orc_field_name);
+
+                    self.normalize(orc_field_name) == field_name
+                })
+            })
+    }
+
+    fn normalize(&self, name: &str) -> String {
+        let name = if self.ignore_underscores {
+            name.replace('_', "")
+        } else {
+            name.to_string()
+        };
+
+        if self.case_insensitive {
+            name.to_ascii_lowercase()
+        } else {
+            name
+        }
+    }
+}
+
+/// A function that converts a field's value before it's deserialized, or fails with a message.
+pub type Converter = Arc<dyn Fn(Value<'_>) -> Result<OwnedValue, String> + Send + Sync>;
+
+/// Per-field functions that run between decoding and deserialization.
+///
+/// This allows domain-specific decoding (such as turning epoch milliseconds into a date string, or
+/// extracting the timestamp from a Twitter ID) to be configured once instead of applied to every
+/// deserialized row afterwards.
+#[derive(Clone, Default)]
+pub struct ValueConverters {
+    converters: HashMap<String, Converter>,
+}
+
+impl ValueConverters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Convert the values of a struct field (identified by its name in the struct, not the ORC
+    /// field name it resolves to).
+    ///
+    /// Nulls are passed to the converter like other values.
+    pub fn with_converter<S, F>(mut self, field_name: S, converter: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(Value<'_>) -> Result<OwnedValue, String> + Send + Sync + 'static,
+    {
+        self.converters
+            .insert(field_name.into(), Arc::new(converter));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.converters.is_empty()
+    }
+
+    /// The converter for each of the given fields.
+    pub(crate) fn select(&self, field_names: &[&str]) -> Vec<Option<Converter>> {
+        field_names
+            .iter()
+            .map(|field_name| self.converters.get(*field_name).cloned())
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for ValueConverters {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_set().entries(self.converters.keys()).finish()
+    }
+}
+
+/// Apply the converters to a row, returning the new value for each field that has a converter.
+pub(crate) fn convert_row(
+    converters: &[Option<Converter>],
+    row: &[Value<'_>],
+) -> Result<Vec<Option<OwnedValue>>, Error> {
+    converters
+        .iter()
+        .zip(row)
+        .enumerate()
+        .map(|(field, (converter, value))| {
+            converter
+                .as_ref()
+                .map(|converter| {
+                    converter(*value).map_err(|message| Error {
+                        field: Some(field),
+                        kind: ErrorKind::Conversion(message),
+                    })
+                })
+                .transpose()
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Error {
     field: Option<usize>,
     kind: ErrorKind,
 }
 
+impl Error {
+    /// The index of the field being deserialized when the error occurred (if known).
+    pub fn get_field(&self) -> Option<usize> {
+        self.field
+    }
+
+    pub fn get_kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match &self.kind {
+            ErrorKind::Parser(error) => error.category(),
+            ErrorKind::Unsupported(_) => ErrorCategory::Unsupported,
+            ErrorKind::InvalidFieldNames(_)
+            | ErrorKind::SerdeMessage(_)
+            | ErrorKind::InvalidColumn
+            | ErrorKind::InvalidValue => ErrorCategory::Usage,
+            ErrorKind::Conversion(_) => ErrorCategory::Corruption,
+        }
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
         Self { field: None, kind }
@@ -59,6 +233,8 @@ pub enum ErrorKind {
     InvalidColumn,
     #[error("Invalid value")]
     InvalidValue,
+    #[error("Conversion error")]
+    Conversion(String),
     #[error("Parser error")]
     Parser(crate::parser::Error),
 }
@@ -82,6 +258,17 @@ impl<'a> RowDe<'a> {
             kind,
         }
     }
+
+    // The current value as an integer, if it's in the range of the target type.
+    fn get_integer<T: TryFrom<i64> + TryFrom<u64>>(&self) -> Option<T> {
+        match self.row.get(self.current_field)? {
+            Value::I64(value) => T::try_from(*value).ok(),
+            Value::U64(value) => T::try_from(*value).ok(),
+            // Timestamps are deserialized as milliseconds since the Unix epoch.
+            Value::Timestamp(value) => T::try_from(value.to_millis()?).ok(),
+            _ => None,
+        }
+    }
 }
 
 impl<'a, 'de: 'a> SeqAccess<'de> for &mut RowDe<'a> {
@@ -120,20 +307,44 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
         }
     }
 
-    fn deserialize_i8<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("i8".to_string())))
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.get_integer() {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_i8(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
-    fn deserialize_i16<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("i16".to_string())))
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.get_integer() {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_i16(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
-    fn deserialize_i32<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("i32".to_string())))
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.get_integer() {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_i32(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
-    fn deserialize_i64<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("i64".to_string())))
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.get_integer() {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_i64(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
     fn deserialize_u8<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
@@ -145,12 +356,7 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
     }
 
     fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        match self
-            .row
-            .get(self.current_field)
-            .and_then(|value| value.as_u64())
-            .and_then(|value| u32::try_from(value).ok())
-        {
+        match self.get_integer() {
             Some(value) => {
                 self.current_field += 1;
                 visitor.visit_u32(value)
@@ -160,11 +366,7 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
     }
 
     fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        match self
-            .row
-            .get(self.current_field)
-            .and_then(|value| value.as_u64())
-        {
+        match self.get_integer() {
             Some(value) => {
                 self.current_field += 1;
                 visitor.visit_u64(value)
@@ -185,16 +387,27 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
         Err(self.error(ErrorKind::Unsupported("char".to_string())))
     }
 
-    fn deserialize_str<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("str".to_string())))
-    }
-
-    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self
             .row
             .get(self.current_field)
-            .and_then(|value| value.as_string())
+            .and_then(|value| value.as_str())
         {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_str(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.row.get(self.current_field).and_then(|value| {
+            value
+                .as_string()
+                .or_else(|| value.to_decimal_string())
+                .or_else(|| value.as_timestamp().map(|value| value.to_string()))
+        }) {
             Some(value) => {
                 self.current_field += 1;
                 visitor.visit_string(value)
@@ -203,12 +416,32 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
         }
     }
 
-    fn deserialize_bytes<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("bytes".to_string())))
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self
+            .row
+            .get(self.current_field)
+            .and_then(|value| value.as_bytes())
+        {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_bytes(value)
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
-    fn deserialize_byte_buf<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("byte_buf".to_string())))
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self
+            .row
+            .get(self.current_field)
+            .and_then(|value| value.as_bytes())
+        {
+            Some(value) => {
+                self.current_field += 1;
+                visitor.visit_byte_buf(value.to_vec())
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
     fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
@@ -242,8 +475,28 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
         Err(self.error(ErrorKind::Unsupported("newtype_struct".to_string())))
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(self.error(ErrorKind::Unsupported("seq".to_string())))
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self
+            .row
+            .get(self.current_field)
+            .and_then(|value| value.as_list())
+        {
+            Some(list) => {
+                let field = self.current_field;
+                self.current_field += 1;
+                visitor
+                    .visit_seq(ListDe {
+                        list,
+                        index: 0,
+                        field,
+                    })
+                    .map_err(|mut error| {
+                        error.field.get_or_insert(field);
+                        error
+                    })
+            }
+            None => Err(self.error(ErrorKind::InvalidValue)),
+        }
     }
 
     fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, _: V) -> Result<V::Value, Self::Error> {
@@ -292,3 +545,140 @@ impl<'a, 'de: 'a> Deserializer<'de> for &mut RowDe<'a> {
         Err(self.error(ErrorKind::Unsupported("ignored_any".to_string())))
     }
 }
+
+// The elements of a list value.
+struct ListDe<'a> {
+    list: ListValue<'a>,
+    index: usize,
+    field: usize,
+}
+
+impl<'de> SeqAccess<'de> for ListDe<'_> {
+    type Error = Error;
+
+    fn next_element_seed<U: DeserializeSeed<'de>>(
+        &mut self,
+        seed: U,
+    ) -> Result<Option<U::Value>, Self::Error> {
+        match self.list.get(self.index) {
+            Some(value) => {
+                self.index += 1;
+                seed.deserialize(ElementDe {
+                    value,
+                    field: self.field,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.list.len() - self.index)
+    }
+}
+
+// A single list element, which is deserialized according to its type (so integers are
+// range-checked by the target type's visitor).
+struct ElementDe<'a> {
+    value: Value<'a>,
+    field: usize,
+}
+
+impl<'de> Deserializer<'de> for ElementDe<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::U64(value) => visitor.visit_u64(value),
+            Value::I64(value) => visitor.visit_i64(value),
+            Value::Decimal { value, scale } => {
+                visitor.visit_string(crate::decimal::format_scaled(value, scale))
+            }
+            Value::Timestamp(value) => match value.to_millis() {
+                Some(millis) => visitor.visit_i64(millis),
+                None => visitor.visit_string(value.to_string()),
+            },
+            Value::Utf8(value) => visitor.visit_str(value),
+            Value::Bytes(value) => visitor.visit_bytes(value),
+            Value::List(list) => visitor.visit_seq(ListDe {
+                list,
+                index: 0,
+                field: self.field,
+            }),
+            Value::Null => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_categories() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Row {
+            #[allow(dead_code)]
+            missing: u64,
+        }
+
+        let mut orc_file =
+            crate::parser::OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let error = orc_file.deserialize::<Row>().next().unwrap().unwrap_err();
+
+        assert!(matches!(error.get_kind(), ErrorKind::InvalidFieldNames(_)));
+        assert_eq!(error.category(), ErrorCategory::Usage);
+
+        let error = Error::from(crate::parser::Error::InvalidMetadata);
+        assert_eq!(error.category(), ErrorCategory::Corruption);
+    }
+
+    #[test]
+    fn resolve_field_names() {
+        let orc_field_names = ["t.Screen_Name", "tbl_followers_count", "id", "ID"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+
+        let exact = FieldNameMatching::new();
+        let flexible = FieldNameMatching::new()
+            .ignore_case_style()
+            .strip_prefix("t.")
+            .strip_prefix("tbl_");
+
+        assert_eq!(exact.resolve("ID", &orc_field_names), Some(3));
+        assert_eq!(exact.resolve("screenName", &orc_field_names), None);
+        assert_eq!(flexible.resolve("ID", &orc_field_names), Some(3));
+        assert_eq!(flexible.resolve("screenName", &orc_field_names), Some(0));
+        assert_eq!(
+            flexible.resolve("followersCount", &orc_field_names),
+            Some(1)
+        );
+        assert_eq!(
+            FieldNameMatching::new()
+                .case_insensitive()
+                .resolve("Id", &orc_field_names),
+            Some(2)
+        );
+        assert_eq!(
+            FieldNameMatching::new()
+                .case_insensitive()
+                .resolve("followers_count", &orc_field_names),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Decoding decimal values.
+//!
+//! Decimals can have up to 38 digits, so they're decoded to unscaled 128-bit integers with a scale
+//! (the scale of the column type, if it has one, or otherwise the scale each value was written
+//! with). See [`crate::value::Value::Decimal`].
+
+use std::cmp::Ordering;
+
+// The maximum precision (and scale) of an ORC decimal.
+const MAX_SCALE: u32 = 38;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The varint starting at the given byte offset is truncated or too large.
+    #[error("Invalid decimal varint")]
+    InvalidVarint { offset: usize },
+    #[error("Invalid decimal scale")]
+    InvalidScale(i64),
+    #[error("Decimal overflow")]
+    Overflow,
+}
+
+/// Decode a stream of zigzag-encoded base 128 varints of any length up to 128 bits.
+pub fn decode_varints(bytes: &[u8]) -> Result<Vec<i128>, Error> {
+    let mut values = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let mut value = 0u128;
+        let mut shift = 0;
+        let start = offset;
+
+        loop {
+            let byte = *bytes
+                .get(offset)
+                .ok_or(Error::InvalidVarint { offset: start })?;
+            offset += 1;
+
+            let bits = (byte & 0x7f) as u128;
+            if shift >= 128 || (shift > 0 && bits >> (128 - shift) != 0) {
+                return Err(Error::InvalidVarint { offset: start });
+            }
+
+            value |= bits << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        values.push((value >> 1) as i128 ^ -((value & 1) as i128));
+    }
+
+    Ok(values)
+}
+
+/// Rescale an unscaled value with the given scale to the target scale if there is one (rounding
+/// half away from zero), returning the new unscaled value and scale.
+pub fn rescale(unscaled: i128, scale: i64, target_scale: Option<u32>) -> Result<(i128, u8), Error> {
+    let scale = u32::try_from(scale)
+        .ok()
+        .filter(|scale| *scale <= MAX_SCALE)
+        .ok_or(Error::InvalidScale(scale))?;
+    let target_scale = target_scale.unwrap_or(scale).min(MAX_SCALE);
+
+    let value = if target_scale >= scale {
+        unscaled
+            .checked_mul(10i128.pow(target_scale - scale))
+            .ok_or(Error::Overflow)?
+    } else {
+        let divisor = 10i128.pow(scale - target_scale);
+        let quotient = unscaled / divisor;
+        let remainder = unscaled % divisor;
+
+        if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+            quotient + unscaled.signum()
+        } else {
+            quotient
+        }
+    };
+
+    Ok((value, target_scale as u8))
+}
+
+/// Format an unscaled value with the given scale, rescaling it to the target scale if there is one
+/// (rounding half away from zero).
+pub fn format(unscaled: i128, scale: i64, target_scale: Option<u32>) -> Result<String, Error> {
+    let (value, scale) = rescale(unscaled, scale, target_scale)?;
+
+    Ok(format_scaled(value, scale))
+}
+
+/// Format an unscaled value with the given scale (which isn't checked against the maximum ORC
+/// scale).
+pub fn format_scaled(unscaled: i128, scale: u8) -> String {
+    let digits = format!(
+        "{:0width$}",
+        unscaled.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (integer_part, fraction_part) = digits.split_at(digits.len() - scale as usize);
+    let sign = if unscaled < 0 { "-" } else { "" };
+
+    if fraction_part.is_empty() {
+        format!("{}{}", sign, integer_part)
+    } else {
+        format!("{}{}.{}", sign, integer_part, fraction_part)
+    }
+}
+
+/// Compare two unscaled values with their scales numerically.
+pub fn compare(left: i128, left_scale: u8, right: i128, right_scale: u8) -> Ordering {
+    // If rescaling to the larger scale overflows, the rescaled value has the larger magnitude.
+    let rescaled_cmp = |value: i128, from: u8, to: u8, other: i128| match 10i128
+        .checked_pow((to - from) as u32)
+        .and_then(|factor| value.checked_mul(factor))
+    {
+        Some(value) => value.cmp(&other),
+        None => value.signum().cmp(&0),
+    };
+
+    match left_scale.cmp(&right_scale) {
+        Ordering::Equal => left.cmp(&right),
+        Ordering::Less => rescaled_cmp(left, left_scale, right_scale, right),
+        Ordering::Greater => rescaled_cmp(right, right_scale, left_scale, left).reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_zigzag_varints() {
+        // 0, -1, 1, 64, and 2^70.
+        let mut bytes = vec![0x00, 0x01, 0x02, 0x80, 0x01];
+        bytes.extend([0x80; 10]);
+        bytes.push(0x02);
+
+        assert_eq!(decode_varints(&bytes), Ok(vec![0, -1, 1, 64, 1 << 70]));
+        assert_eq!(
+            decode_varints(&[0x00, 0x80]),
+            Err(Error::InvalidVarint { offset: 1 })
+        );
+        assert_eq!(
+            decode_varints(&[0xff; 20]),
+            Err(Error::InvalidVarint { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn format_values() {
+        assert_eq!(format(12345, 2, None), Ok("123.45".to_string()));
+        assert_eq!(format(-5, 3, None), Ok("-0.005".to_string()));
+        assert_eq!(format(7, 0, None), Ok("7".to_string()));
+        assert_eq!(format(15, 1, Some(3)), Ok("1.500".to_string()));
+        assert_eq!(format(1235, 3, Some(2)), Ok("1.24".to_string()));
+        assert_eq!(format(-1235, 3, Some(2)), Ok("-1.24".to_string()));
+        assert_eq!(format(-1234, 3, Some(2)), Ok("-1.23".to_string()));
+        assert_eq!(format(1, -1, None), Err(Error::InvalidScale(-1)));
+        assert_eq!(format(i128::MAX, 0, Some(2)), Err(Error::Overflow));
+        assert_eq!(rescale(1235, 3, Some(2)), Ok((124, 2)));
+        assert_eq!(format_scaled(-5, 3), "-0.005");
+    }
+
+    #[test]
+    fn compare_values() {
+        assert_eq!(compare(150, 2, 15, 1), Ordering::Equal);
+        assert_eq!(compare(151, 2, 15, 1), Ordering::Greater);
+        assert_eq!(compare(-151, 2, -15, 1), Ordering::Less);
+        assert_eq!(compare(1, 0, i128::MAX, 38), Ordering::Less);
+        assert_eq!(compare(i128::MAX, 0, 1, 38), Ordering::Greater);
+        assert_eq!(compare(i128::MIN, 0, 1, 38), Ordering::Less);
+    }
+}
@@ -0,0 +1,109 @@
+//! Selecting one row for each distinct key.
+//!
+//! Keeping the first row for each key only needs a single pass over the rows, but keeping the last
+//! requires a first pass over the keys to find the last row for each.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Decides whether each row is the one to keep for its key.
+#[derive(Debug)]
+pub enum RowSelector<K> {
+    First { seen: HashSet<K> },
+    Last { last_rows: HashMap<K, usize> },
+}
+
+impl<K: Eq + Hash> RowSelector<K> {
+    pub fn keep_first() -> Self {
+        Self::First {
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Find the last row for each key from the keys of all rows (in row order).
+    pub fn keep_last<E, I: IntoIterator<Item = Result<K, E>>>(keys: I) -> Result<Self, E> {
+        let mut last_rows = HashMap::new();
+
+        for (row, key) in keys.into_iter().enumerate() {
+            last_rows.insert(key?, row);
+        }
+
+        Ok(Self::Last { last_rows })
+    }
+
+    /// Indicates whether the row should be kept (rows must be checked in order, starting at 0).
+    pub fn is_kept(&mut self, row: usize, key: K) -> bool {
+        match self {
+            Self::First { seen } => seen.insert(key),
+            Self::Last { last_rows } => last_rows.get(&key) == Some(&row),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Error, OrcFile};
+
+    const TS_1K_ZLIB_PATH: &str = "examples/ts-1k-zlib-2020-09-20.orc";
+    const KEYS: [&str; 6] = ["a", "b", "a", "c", "b", "a"];
+
+    fn kept_rows<K: Eq + Hash>(mut selector: RowSelector<K>, keys: Vec<K>) -> Vec<usize> {
+        keys.into_iter()
+            .enumerate()
+            .filter_map(|(row, key)| selector.is_kept(row, key).then_some(row))
+            .collect()
+    }
+
+    #[test]
+    fn keep_first() {
+        assert_eq!(
+            kept_rows(RowSelector::keep_first(), KEYS.to_vec()),
+            vec![0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn keep_last() {
+        let selector = RowSelector::keep_last(KEYS.map(Ok::<_, ()>)).unwrap();
+
+        assert_eq!(kept_rows(selector, KEYS.to_vec()), vec![3, 4, 5]);
+        assert_eq!(
+            RowSelector::keep_last([Ok("a"), Err("failed"), Ok("b")]).unwrap_err(),
+            "failed"
+        );
+    }
+
+    #[test]
+    fn ts_1k_verified() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let verified_index = orc_file.get_field_index("verified").unwrap();
+        let verified_column = orc_file.get_field_column_indices()[verified_index];
+        let keys = orc_file
+            .map_rows(&[verified_column], |values| {
+                Ok::<_, Error>(values[0].to_owned_value())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let distinct_count = keys.iter().collect::<HashSet<_>>().len();
+
+        let first_rows = kept_rows(RowSelector::keep_first(), keys.clone());
+        let last_rows = kept_rows(
+            RowSelector::keep_last(keys.iter().cloned().map(Ok::<_, ()>)).unwrap(),
+            keys.clone(),
+        );
+
+        assert!(distinct_count > 1);
+        assert_eq!(first_rows.len(), distinct_count);
+        assert_eq!(last_rows.len(), distinct_count);
+
+        for row in first_rows {
+            assert!(!keys[..row].contains(&keys[row]));
+        }
+
+        for row in last_rows {
+            assert!(!keys[row + 1..].contains(&keys[row]));
+        }
+    }
+}
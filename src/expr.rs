@@ -0,0 +1,732 @@
+//! A small expression language for computing derived columns.
+//!
+//! A select list is a comma-separated list of expressions, each optionally followed by `AS name`:
+//!
+//! ```text
+//! id, lower(screen_name) AS sn, followers_count / 1000
+//! ```
+//!
+//! Expressions can refer to columns by name (using double quotes for names that aren't simple
+//! identifiers), and can include integer and string (single-quoted) literals, `true`, `false`,
+//! `null`, the arithmetic operators `+`, `-`, `*`, `/`, and `%`, the functions `lower`, `upper`,
+//! `trim`, `length`, `substr`, `concat`, `coalesce`, and `abs`, and casts of the form
+//! `cast(expr AS type)`, where the type is `string`, `int`, or `bool`.
+//!
+//! Integers are treated as signed (see [`crate::rle`]) and arithmetic wraps on overflow. Division
+//! by zero and any operation on a null value result in null.
+//! Byte strings (from binary columns) are converted to strings as hexadecimal, and lists are
+//! converted to strings as JSON arrays. Both are cast to null as integers or booleans.
+
+use crate::value::{to_hex, OwnedValue, Value};
+use std::ops::Range;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    #[error("Unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("Unexpected token")]
+    UnexpectedToken { offset: usize, token: String },
+    #[error("Unknown column")]
+    UnknownColumn(String),
+    #[error("Unknown function")]
+    UnknownFunction(String),
+    #[error("Unknown type")]
+    UnknownType(String),
+    #[error("Invalid argument count")]
+    InvalidArgumentCount(String),
+    #[error("Type mismatch")]
+    TypeMismatch(String),
+}
+
+/// A parsed select list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    items: Vec<(String, Expr)>,
+    columns: Vec<usize>,
+}
+
+impl Select {
+    /// Parse a select list, using `lookup` to find the index of a column by name.
+    pub fn parse<L: Fn(&str) -> Option<usize>>(input: &str, lookup: L) -> Result<Self, Error> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            columns: vec![],
+            lookup: &lookup,
+        };
+
+        let mut items = vec![];
+
+        loop {
+            let start = parser.offset(input.len());
+            let expr = parser.parse_expr()?;
+            let end = parser.previous_end();
+
+            let name = if parser.next_keyword("as") {
+                match parser.next() {
+                    Some((Token::Ident(name) | Token::QuotedIdent(name), _)) => name,
+                    Some((token, span)) => return Err(unexpected(&token, span)),
+                    None => return Err(Error::UnexpectedEnd),
+                }
+            } else {
+                match &expr {
+                    Expr::Column(_) => input[start..end].trim_matches('"').to_string(),
+                    _ => input[start..end].to_string(),
+                }
+            };
+
+            items.push((name, expr));
+
+            match parser.next() {
+                Some((Token::Comma, _)) => {}
+                Some((token, span)) => return Err(unexpected(&token, span)),
+                None => break,
+            }
+        }
+
+        Ok(Self {
+            items,
+            columns: parser.columns,
+        })
+    }
+
+    /// The indices of the columns that the expressions refer to.
+    ///
+    /// The values passed to `eval` must be the values of these columns, in this order.
+    pub fn get_columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    pub fn get_names(&self) -> Vec<&str> {
+        self.items.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn eval(&self, values: &[Value<'_>]) -> Result<Vec<OwnedValue>, Error> {
+        self.items
+            .iter()
+            .map(|(_, expr)| expr.eval(values))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Function {
+    Lower,
+    Upper,
+    Trim,
+    Length,
+    Substr,
+    Concat,
+    Coalesce,
+    Abs,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lower" => Some(Self::Lower),
+            "upper" => Some(Self::Upper),
+            "trim" => Some(Self::Trim),
+            "length" => Some(Self::Length),
+            "substr" => Some(Self::Substr),
+            "concat" => Some(Self::Concat),
+            "coalesce" => Some(Self::Coalesce),
+            "abs" => Some(Self::Abs),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> Range<usize> {
+        match self {
+            Self::Substr => 2..4,
+            Self::Concat | Self::Coalesce => 1..usize::MAX,
+            _ => 1..2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CastType {
+    String,
+    Int,
+    Bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Literal(OwnedValue),
+    Column(usize),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    Cast(Box<Expr>, CastType),
+}
+
+impl Expr {
+    fn eval(&self, values: &[Value<'_>]) -> Result<OwnedValue, Error> {
+        match self {
+            Self::Literal(value) => Ok(value.clone()),
+            Self::Column(position) => Ok(values
+                .get(*position)
+                .map(Value::to_owned_value)
+                .unwrap_or(OwnedValue::Null)),
+            Self::Negate(expr) => match expr.eval(values)? {
+                OwnedValue::Null => Ok(OwnedValue::Null),
+                value => as_int(&value)
+                    .map(|value| int(value.wrapping_neg()))
+                    .ok_or_else(|| Error::TypeMismatch("-".to_string())),
+            },
+            Self::Binary(op, left, right) => match (left.eval(values)?, right.eval(values)?) {
+                (left, right) if as_int(&left).is_some() && as_int(&right).is_some() => {
+                    let (left, right) = (
+                        as_int(&left).unwrap_or_default(),
+                        as_int(&right).unwrap_or_default(),
+                    );
+                    let result = match op {
+                        BinaryOp::Add => Some(left.wrapping_add(right)),
+                        BinaryOp::Subtract => Some(left.wrapping_sub(right)),
+                        BinaryOp::Multiply => Some(left.wrapping_mul(right)),
+                        BinaryOp::Divide => left.checked_div(right),
+                        BinaryOp::Remainder => left.checked_rem(right),
+                    };
+
+                    Ok(result.map(int).unwrap_or(OwnedValue::Null))
+                }
+                (OwnedValue::Null, _) | (_, OwnedValue::Null) => Ok(OwnedValue::Null),
+                _ => Err(Error::TypeMismatch(format!("{:?}", op))),
+            },
+            Self::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(values))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                eval_function(*function, args)
+            }
+            Self::Cast(expr, cast_type) => Ok(cast(expr.eval(values)?, *cast_type)),
+        }
+    }
+}
+
+fn int(value: i64) -> OwnedValue {
+    OwnedValue::I64(value)
+}
+
+// Unsigned values (such as row numbers) are used as signed integers.
+fn as_int(value: &OwnedValue) -> Option<i64> {
+    match value {
+        OwnedValue::I64(value) => Some(*value),
+        OwnedValue::U64(value) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+fn to_display_string(value: &OwnedValue) -> Option<String> {
+    match value {
+        OwnedValue::Bool(value) => Some(value.to_string()),
+        OwnedValue::U64(value) => Some(value.to_string()),
+        OwnedValue::I64(value) => Some(value.to_string()),
+        OwnedValue::Decimal { .. } => value.as_value().to_decimal_string(),
+        OwnedValue::Timestamp(value) => Some(value.to_string()),
+        OwnedValue::Utf8(value) => Some(value.clone()),
+        OwnedValue::Bytes(value) => Some(to_hex(value)),
+        OwnedValue::List(_) => value.as_value().as_list().map(|list| list.to_json_string()),
+        OwnedValue::Null => None,
+    }
+}
+
+fn cast(value: OwnedValue, cast_type: CastType) -> OwnedValue {
+    match (cast_type, value) {
+        (_, OwnedValue::Null) => OwnedValue::Null,
+        (CastType::String, value) => to_display_string(&value)
+            .map(OwnedValue::Utf8)
+            .unwrap_or(OwnedValue::Null),
+        (CastType::Int, OwnedValue::Bool(value)) => int(value as i64),
+        (CastType::Int, OwnedValue::U64(value)) => int(value as i64),
+        (CastType::Int, OwnedValue::I64(value)) => int(value),
+        (CastType::Int, OwnedValue::Utf8(value)) => value
+            .trim()
+            .parse::<i64>()
+            .map(int)
+            .unwrap_or(OwnedValue::Null),
+        (CastType::Bool, OwnedValue::Bool(value)) => OwnedValue::Bool(value),
+        (CastType::Bool, OwnedValue::U64(value)) => OwnedValue::Bool(value != 0),
+        (CastType::Bool, OwnedValue::I64(value)) => OwnedValue::Bool(value != 0),
+        // Decimals are truncated towards zero.
+        (CastType::Int, OwnedValue::Decimal { value, scale }) => 10i128
+            .checked_pow(scale as u32)
+            .and_then(|divisor| i64::try_from(value / divisor).ok())
+            .map(int)
+            .unwrap_or(OwnedValue::Null),
+        (CastType::Bool, OwnedValue::Decimal { value, .. }) => OwnedValue::Bool(value != 0),
+        // Timestamps are cast to milliseconds since the Unix epoch.
+        (CastType::Int, OwnedValue::Timestamp(value)) => {
+            value.to_millis().map(int).unwrap_or(OwnedValue::Null)
+        }
+        (CastType::Bool, OwnedValue::Timestamp(_))
+        | (_, OwnedValue::Bytes(_) | OwnedValue::List(_)) => OwnedValue::Null,
+        (CastType::Bool, OwnedValue::Utf8(value)) => {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" => OwnedValue::Bool(true),
+                "false" => OwnedValue::Bool(false),
+                _ => OwnedValue::Null,
+            }
+        }
+    }
+}
+
+fn eval_function(function: Function, mut args: Vec<OwnedValue>) -> Result<OwnedValue, Error> {
+    let type_mismatch = || Error::TypeMismatch(format!("{:?}", function));
+
+    match function {
+        Function::Concat => Ok(OwnedValue::Utf8(
+            args.iter().filter_map(to_display_string).collect(),
+        )),
+        Function::Coalesce => Ok(args
+            .into_iter()
+            .find(|value| !value.is_null())
+            .unwrap_or(OwnedValue::Null)),
+        _ if args.iter().any(OwnedValue::is_null) => Ok(OwnedValue::Null),
+        Function::Abs => as_int(&args[0])
+            .map(|value| int(value.wrapping_abs()))
+            .ok_or_else(type_mismatch),
+        Function::Substr => {
+            let len = match args.get(2) {
+                Some(len) => Some(as_int(len).ok_or_else(type_mismatch)?.max(0) as usize),
+                None => None,
+            };
+
+            match (&args[0], as_int(&args[1])) {
+                (OwnedValue::Utf8(value), Some(start)) => {
+                    // Positions start at one, as in SQL.
+                    let start = (start.max(1) - 1) as usize;
+                    let chars = value.chars().skip(start);
+
+                    Ok(OwnedValue::Utf8(match len {
+                        Some(len) => chars.take(len).collect(),
+                        None => chars.collect(),
+                    }))
+                }
+                _ => Err(type_mismatch()),
+            }
+        }
+        _ => match args.swap_remove(0) {
+            OwnedValue::Utf8(value) => Ok(match function {
+                Function::Lower => OwnedValue::Utf8(value.to_lowercase()),
+                Function::Upper => OwnedValue::Utf8(value.to_uppercase()),
+                Function::Trim => OwnedValue::Utf8(value.trim().to_string()),
+                _ => int(value.chars().count() as i64),
+            }),
+            _ => Err(type_mismatch()),
+        },
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Ident(String),
+    QuotedIdent(String),
+    Int(i64),
+    Str(String),
+    Op(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn unexpected(token: &Token, span: Range<usize>) -> Error {
+    Error::UnexpectedToken {
+        offset: span.start,
+        token: format!("{:?}", token),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, Range<usize>)>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '+' | '-' | '*' | '/' | '%' => Token::Op(c),
+            '\'' | '"' => {
+                let mut value = String::new();
+                let mut closed = false;
+
+                while let Some((_, next)) = chars.next() {
+                    if next == c {
+                        // A doubled quote is an escaped quote.
+                        if chars.peek().map(|(_, next)| *next) == Some(c) {
+                            chars.next();
+                        } else {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    value.push(next);
+                }
+
+                if !closed {
+                    return Err(Error::UnexpectedEnd);
+                }
+
+                if c == '\'' {
+                    Token::Str(value)
+                } else {
+                    Token::QuotedIdent(value)
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = start + 1;
+                while let Some((i, _)) = chars.next_if(|(_, next)| next.is_ascii_digit()) {
+                    end = i + 1;
+                }
+
+                Token::Int(
+                    input[start..end]
+                        .parse()
+                        .map_err(|_| Error::UnexpectedToken {
+                            offset: start,
+                            token: input[start..end].to_string(),
+                        })?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, next)) =
+                    chars.next_if(|(_, next)| next.is_alphanumeric() || *next == '_')
+                {
+                    end = i + next.len_utf8();
+                }
+
+                Token::Ident(input[start..end].to_string())
+            }
+            other => {
+                return Err(Error::UnexpectedToken {
+                    offset: start,
+                    token: other.to_string(),
+                })
+            }
+        };
+
+        let end = chars.peek().map(|(i, _)| *i).unwrap_or(input.len());
+        tokens.push((token, start..end));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, Range<usize>)>,
+    position: usize,
+    columns: Vec<usize>,
+    lookup: &'a dyn Fn(&str) -> Option<usize>,
+}
+
+impl Parser<'_> {
+    fn offset(&self, default: usize) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(_, span)| span.start)
+            .unwrap_or(default)
+    }
+
+    fn previous_end(&self) -> usize {
+        self.position
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|(_, span)| span.end)
+            .unwrap_or(0)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(Token, Range<usize>)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(value)) if value.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some((token, _)) if token == expected => Ok(()),
+            Some((token, span)) => Err(unexpected(&token, span)),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn column(&mut self, name: &str) -> Option<Expr> {
+        let index = (self.lookup)(name)?;
+
+        let position = match self.columns.iter().position(|column| *column == index) {
+            Some(position) => position,
+            None => {
+                self.columns.push(index);
+                self.columns.len() - 1
+            }
+        };
+
+        Some(Expr::Column(position))
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_term()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('+')) => BinaryOp::Add,
+                Some(Token::Op('-')) => BinaryOp::Subtract,
+                _ => return Ok(expr),
+            };
+            self.position += 1;
+
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.parse_term()?));
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_unary()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('*')) => BinaryOp::Multiply,
+                Some(Token::Op('/')) => BinaryOp::Divide,
+                Some(Token::Op('%')) => BinaryOp::Remainder,
+                _ => return Ok(expr),
+            };
+            self.position += 1;
+
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        if self.peek() == Some(&Token::Op('-')) {
+            self.position += 1;
+            Ok(Expr::Negate(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, Error> {
+        match self.next().ok_or(Error::UnexpectedEnd)? {
+            (Token::Int(value), _) => Ok(Expr::Literal(int(value))),
+            (Token::Str(value), _) => Ok(Expr::Literal(OwnedValue::Utf8(value))),
+            (Token::LeftParen, _) => {
+                let expr = self.parse_expr()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            (Token::QuotedIdent(name), _) => self.column(&name).ok_or(Error::UnknownColumn(name)),
+            (Token::Ident(name), _) if self.peek() == Some(&Token::LeftParen) => {
+                self.position += 1;
+                self.parse_call(name)
+            }
+            (Token::Ident(name), _) => {
+                if let Some(expr) = self.column(&name) {
+                    return Ok(expr);
+                }
+
+                match name.to_ascii_lowercase().as_str() {
+                    "true" => Ok(Expr::Literal(OwnedValue::Bool(true))),
+                    "false" => Ok(Expr::Literal(OwnedValue::Bool(false))),
+                    "null" => Ok(Expr::Literal(OwnedValue::Null)),
+                    _ => Err(Error::UnknownColumn(name)),
+                }
+            }
+            (token, span) => Err(unexpected(&token, span)),
+        }
+    }
+
+    fn parse_call(&mut self, name: String) -> Result<Expr, Error> {
+        if name.eq_ignore_ascii_case("cast") {
+            let expr = self.parse_expr()?;
+
+            if !self.next_keyword("as") {
+                return match self.next() {
+                    Some((token, span)) => Err(unexpected(&token, span)),
+                    None => Err(Error::UnexpectedEnd),
+                };
+            }
+
+            let cast_type = match self.next() {
+                Some((Token::Ident(type_name), _)) => {
+                    match type_name.to_ascii_lowercase().as_str() {
+                        "string" | "varchar" | "text" => CastType::String,
+                        "int" | "integer" | "bigint" | "long" => CastType::Int,
+                        "bool" | "boolean" => CastType::Bool,
+                        _ => return Err(Error::UnknownType(type_name)),
+                    }
+                }
+                Some((token, span)) => return Err(unexpected(&token, span)),
+                None => return Err(Error::UnexpectedEnd),
+            };
+
+            self.expect(Token::RightParen)?;
+
+            return Ok(Expr::Cast(Box::new(expr), cast_type));
+        }
+
+        let function =
+            Function::from_name(&name).ok_or_else(|| Error::UnknownFunction(name.clone()))?;
+        let mut args = vec![];
+
+        if self.peek() == Some(&Token::RightParen) {
+            self.position += 1;
+        } else {
+            loop {
+                args.push(self.parse_expr()?);
+
+                match self.next() {
+                    Some((Token::Comma, _)) => {}
+                    Some((Token::RightParen, _)) => break,
+                    Some((token, span)) => return Err(unexpected(&token, span)),
+                    None => return Err(Error::UnexpectedEnd),
+                }
+            }
+        }
+
+        if function.arity().contains(&args.len()) {
+            Ok(Expr::Call(function, args))
+        } else {
+            Err(Error::InvalidArgumentCount(name))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELD_NAMES: [&str; 4] = ["id", "screen_name", "followers_count", "display name"];
+
+    fn parse(input: &str) -> Result<Select, Error> {
+        Select::parse(input, |name| {
+            FIELD_NAMES
+                .iter()
+                .position(|field_name| *field_name == name)
+        })
+    }
+
+    #[test]
+    fn select_names_and_columns() {
+        let select = parse(
+            r#"id, lower(screen_name) AS sn, followers_count / 1000, "display name", followers_count"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            select.get_names(),
+            vec![
+                "id",
+                "sn",
+                "followers_count / 1000",
+                "display name",
+                "followers_count"
+            ]
+        );
+        assert_eq!(select.get_columns(), &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn eval_expressions() {
+        let select = parse(
+            "lower(screen_name), followers_count / 1000, -(followers_count - 5000) * 2 % 7, \
+             cast(id AS string), concat(screen_name, '-', id), substr(screen_name, 2, 3), \
+             length(screen_name), coalesce(null, id), followers_count / 0, cast('12' AS int)",
+        )
+        .unwrap();
+
+        let values = [
+            Value::Utf8("TravisBrown"),
+            Value::I64(12345),
+            Value::I64(123),
+        ];
+
+        assert_eq!(
+            select.eval(&values).unwrap(),
+            vec![
+                OwnedValue::Utf8("travisbrown".to_string()),
+                OwnedValue::I64(12),
+                int(-(12345 - 5000) * 2 % 7),
+                OwnedValue::Utf8("123".to_string()),
+                OwnedValue::Utf8("TravisBrown-123".to_string()),
+                OwnedValue::Utf8("rav".to_string()),
+                OwnedValue::I64(11),
+                OwnedValue::I64(123),
+                OwnedValue::Null,
+                OwnedValue::I64(12),
+            ]
+        );
+    }
+
+    #[test]
+    fn eval_nulls() {
+        let select = parse("lower(screen_name), followers_count + 1").unwrap();
+
+        assert_eq!(
+            select.eval(&[Value::Null, Value::Null]).unwrap(),
+            vec![OwnedValue::Null, OwnedValue::Null]
+        );
+    }
+
+    #[test]
+    fn eval_type_mismatch() {
+        let select = parse("screen_name + 1").unwrap();
+
+        assert_eq!(
+            select.eval(&[Value::Utf8("abc")]),
+            Err(Error::TypeMismatch("Add".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("missing"),
+            Err(Error::UnknownColumn("missing".to_string()))
+        );
+        assert_eq!(
+            parse("foo(id)"),
+            Err(Error::UnknownFunction("foo".to_string()))
+        );
+        assert_eq!(
+            parse("lower(id, id)"),
+            Err(Error::InvalidArgumentCount("lower".to_string()))
+        );
+        assert_eq!(parse("id +"), Err(Error::UnexpectedEnd));
+        assert_eq!(
+            parse("cast(id AS float)"),
+            Err(Error::UnknownType("float".to_string()))
+        );
+        assert!(matches!(
+            parse("id id"),
+            Err(Error::UnexpectedToken { offset: 3, .. })
+        ));
+    }
+}
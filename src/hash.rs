@@ -0,0 +1,202 @@
+//! Hashing the logical contents of a file.
+//!
+//! The hash covers the field names and types and the decoded values of every row, but not the way
+//! the data is stored (compression, encodings, stripe boundaries, etc.), so it can be used to check
+//! that a rewritten file contains the same data as the original.
+
+use crate::parser::{Error, OrcFile};
+use crate::value::Value;
+use sha2::{Digest, Sha256};
+
+/// Whether the order of rows affects the hash.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RowOrder {
+    #[default]
+    Sensitive,
+    /// Rows are treated as a multiset (so duplicate rows still change the hash).
+    Insensitive,
+}
+
+/// A SHA-256 hash of the file's schema and rows.
+pub fn content_hash(orc_file: &mut OrcFile, row_order: RowOrder) -> Result<[u8; 32], Error> {
+    let mut hasher = Sha256::new();
+
+    for (field_index, field_name) in orc_file.get_field_names().iter().enumerate() {
+        let kind = orc_file
+            .get_column_id(field_index)
+            .and_then(|column_id| orc_file.get_footer().get_types().get(column_id as usize))
+            .ok_or(Error::InvalidMetadata)?
+            .get_kind();
+
+        update_str(&mut hasher, field_name);
+        update_str(&mut hasher, &format!("{:?}", kind));
+    }
+
+    let columns = orc_file.get_field_column_indices();
+
+    match row_order {
+        RowOrder::Sensitive => {
+            for result in orc_file.map_rows(&columns, |values| {
+                update_row(&mut hasher, values);
+                Ok::<_, Error>(())
+            })? {
+                result?;
+            }
+        }
+        RowOrder::Insensitive => {
+            // The sum of the row hashes (modulo 2^256) doesn't depend on the order of the rows.
+            let mut sum = [0; 32];
+
+            for result in orc_file.map_rows(&columns, |values| {
+                let mut row_hasher = Sha256::new();
+                update_row(&mut row_hasher, values);
+                add_assign(&mut sum, &row_hasher.finalize().into());
+                Ok::<_, Error>(())
+            })? {
+                result?;
+            }
+
+            hasher.update(sum);
+        }
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+fn update_str(hasher: &mut Sha256, value: &str) {
+    update_bytes(hasher, value.as_bytes());
+}
+
+fn update_bytes(hasher: &mut Sha256, value: &[u8]) {
+    hasher.update((value.len() as u64).to_le_bytes());
+    hasher.update(value);
+}
+
+// Each value is tagged with its type, and strings are prefixed with their length, so that
+// different rows can't have the same encoding.
+fn update_row(hasher: &mut Sha256, values: &[Value<'_>]) {
+    for value in values {
+        update_value(hasher, value);
+    }
+}
+
+fn update_value(hasher: &mut Sha256, value: &Value<'_>) {
+    match value {
+        Value::Null => hasher.update([0]),
+        Value::Bool(value) => hasher.update([1, *value as u8]),
+        Value::U64(value) => {
+            hasher.update([2]);
+            hasher.update(value.to_le_bytes());
+        }
+        // Signed and unsigned integers with the same bits hash the same way.
+        Value::I64(value) => {
+            hasher.update([2]);
+            hasher.update(value.to_le_bytes());
+        }
+        // Decimals were decoded as strings in earlier versions, and hash the same way.
+        Value::Decimal { value, scale } => {
+            hasher.update([3]);
+            update_str(hasher, &crate::decimal::format_scaled(*value, *scale));
+        }
+        Value::Utf8(value) => {
+            hasher.update([3]);
+            update_str(hasher, value);
+        }
+        Value::Bytes(value) => {
+            hasher.update([4]);
+            update_bytes(hasher, value);
+        }
+        Value::Timestamp(value) => {
+            hasher.update([6, value.is_utc() as u8]);
+            hasher.update(value.get_seconds().to_le_bytes());
+            hasher.update(value.get_nanos().to_le_bytes());
+        }
+        Value::List(value) => {
+            hasher.update([5]);
+            hasher.update((value.len() as u64).to_le_bytes());
+            for value in value.iter() {
+                update_value(hasher, &value);
+            }
+        }
+    }
+}
+
+// Little-endian addition with wrapping.
+fn add_assign(sum: &mut [u8; 32], value: &[u8; 32]) {
+    let mut carry = 0;
+
+    for (sum_byte, value_byte) in sum.iter_mut().zip(value) {
+        let total = *sum_byte as u16 + *value_byte as u16 + carry;
+        *sum_byte = total as u8;
+        carry = total >> 8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("orcrs-hash-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn same_data_same_hash() {
+        let mut zlib = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let mut none = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let mut zstd = OrcFile::open("examples/ts-10k-zstd-2020-09-20.orc").unwrap();
+
+        for row_order in [RowOrder::Sensitive, RowOrder::Insensitive] {
+            assert_eq!(
+                content_hash(&mut zlib, row_order).unwrap(),
+                content_hash(&mut none, row_order).unwrap()
+            );
+            assert_ne!(
+                content_hash(&mut zlib, row_order).unwrap(),
+                content_hash(&mut zstd, row_order).unwrap()
+            );
+        }
+
+        assert_ne!(
+            content_hash(&mut zlib, RowOrder::Sensitive).unwrap(),
+            content_hash(&mut zlib, RowOrder::Insensitive).unwrap()
+        );
+    }
+
+    #[test]
+    fn duplicate_rows() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let copies_path = temp_path("copies.orc");
+        crate::split::write_selected_stripes(
+            &mut orc_file,
+            &[0, 0],
+            std::fs::File::create(&copies_path).unwrap(),
+        )
+        .unwrap();
+
+        let mut copies = OrcFile::open(&copies_path).unwrap();
+        std::fs::remove_file(&copies_path).unwrap();
+
+        assert_ne!(
+            content_hash(&mut orc_file, RowOrder::Insensitive).unwrap(),
+            content_hash(&mut copies, RowOrder::Insensitive).unwrap()
+        );
+    }
+
+    #[test]
+    fn add_with_carry() {
+        let mut sum = [0xff; 32];
+        let mut one = [0; 32];
+        one[0] = 1;
+
+        add_assign(&mut sum, &one);
+        assert_eq!(sum, [0; 32]);
+
+        add_assign(&mut sum, &[1; 32]);
+        add_assign(&mut sum, &[0xff; 32]);
+        let mut expected = [1; 32];
+        expected[0] = 0;
+        assert_eq!(sum, expected);
+    }
+}
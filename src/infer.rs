@@ -0,0 +1,285 @@
+//! Schema inference for tabular text data (such as CSV or NDJSON).
+//!
+//! Values are observed as text, and each column gets the most specific type that fits all of its
+//! non-null values: booleans, longs, doubles, and timestamps (in ISO 8601 form) are recognized,
+//! and columns with conflicting values are promoted to strings (except that longs and doubles
+//! unify to doubles). The inferred types can then be overridden with a schema string like
+//! `id:bigint,score:double`, using the Hive type names that ORC tools print.
+//!
+//! Note that this crate can't write ORC files yet, so this is only the planning half of an import.
+
+use crate::meta::TypeKind;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    #[error("Invalid schema")]
+    InvalidSchema(String),
+    #[error("Unknown type")]
+    UnknownType(String),
+    #[error("Unknown field")]
+    UnknownField(String),
+}
+
+/// Tracks the inferred type of each column as rows are observed.
+#[derive(Clone, Debug)]
+pub struct SchemaInference {
+    field_names: Vec<String>,
+    // `None` if we've only seen nulls so far.
+    kinds: Vec<Option<TypeKind>>,
+    row_count: usize,
+}
+
+impl SchemaInference {
+    pub fn new(field_names: Vec<String>) -> Self {
+        let kinds = vec![None; field_names.len()];
+
+        Self {
+            field_names,
+            kinds,
+            row_count: 0,
+        }
+    }
+
+    /// Observe a row, where missing or empty values are treated as nulls.
+    ///
+    /// Values past the last field name are ignored.
+    pub fn observe<'a, I: IntoIterator<Item = Option<&'a str>>>(&mut self, row: I) {
+        for (kind, value) in self.kinds.iter_mut().zip(row) {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                let value_kind = infer_value_kind(value);
+
+                *kind = Some(match kind {
+                    Some(kind) => unify(*kind, value_kind),
+                    None => value_kind,
+                });
+            }
+        }
+
+        self.row_count += 1;
+    }
+
+    pub fn get_row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// The inferred schema, where columns with only null values are strings.
+    pub fn finish(self) -> Vec<(String, TypeKind)> {
+        self.field_names
+            .into_iter()
+            .zip(self.kinds)
+            .map(|(field_name, kind)| (field_name, kind.unwrap_or(TypeKind::String)))
+            .collect()
+    }
+}
+
+/// The most specific type for a single (non-null) value.
+pub fn infer_value_kind(value: &str) -> TypeKind {
+    if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        TypeKind::Boolean
+    } else if value.parse::<i64>().is_ok() {
+        TypeKind::Long
+    } else if is_decimal_number(value) && value.parse::<f64>().is_ok() {
+        TypeKind::Double
+    } else if is_timestamp(value) {
+        TypeKind::Timestamp
+    } else {
+        TypeKind::String
+    }
+}
+
+fn unify(left: TypeKind, right: TypeKind) -> TypeKind {
+    match (left, right) {
+        (left, right) if left == right => left,
+        (TypeKind::Long, TypeKind::Double) | (TypeKind::Double, TypeKind::Long) => TypeKind::Double,
+        _ => TypeKind::String,
+    }
+}
+
+// Rust's float parsing also accepts values like "inf" and "NaN", which we want to treat as strings.
+fn is_decimal_number(value: &str) -> bool {
+    value.bytes().any(|byte| byte.is_ascii_digit())
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || b"+-.eE".contains(&byte))
+}
+
+// Accepts `YYYY-MM-DD`, followed by `T` or a space, `HH:MM:SS`, optional fractional seconds, and an
+// optional `Z` or `+HH:MM` / `-HH:MM` offset.
+fn is_timestamp(value: &str) -> bool {
+    fn digits(bytes: &[u8], start: usize, count: usize) -> bool {
+        bytes
+            .get(start..start + count)
+            .is_some_and(|digits| digits.iter().all(u8::is_ascii_digit))
+    }
+
+    let bytes = value.as_bytes();
+
+    let date_time = bytes.len() >= 19
+        && digits(bytes, 0, 4)
+        && bytes[4] == b'-'
+        && digits(bytes, 5, 2)
+        && bytes[7] == b'-'
+        && digits(bytes, 8, 2)
+        && (bytes[10] == b'T' || bytes[10] == b' ')
+        && digits(bytes, 11, 2)
+        && bytes[13] == b':'
+        && digits(bytes, 14, 2)
+        && bytes[16] == b':'
+        && digits(bytes, 17, 2);
+
+    if !date_time {
+        return false;
+    }
+
+    let mut rest = &bytes[19..];
+
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+
+    match rest {
+        [] | [b'Z'] => true,
+        [b'+' | b'-', ..] => {
+            rest.len() == 6 && digits(rest, 1, 2) && rest[3] == b':' && digits(rest, 4, 2)
+        }
+        _ => false,
+    }
+}
+
+/// Parse a comma-separated list of `name:type` pairs.
+pub fn parse_schema(input: &str) -> Result<Vec<(String, TypeKind)>, Error> {
+    input
+        .split(',')
+        .map(|field| {
+            let (name, type_name) = field
+                .split_once(':')
+                .ok_or_else(|| Error::InvalidSchema(field.to_string()))?;
+            let name = name.trim();
+
+            if name.is_empty() {
+                Err(Error::InvalidSchema(field.to_string()))
+            } else {
+                Ok((name.to_string(), parse_type_name(type_name.trim())?))
+            }
+        })
+        .collect()
+}
+
+/// Parse a Hive type name (only scalar types are supported).
+pub fn parse_type_name(input: &str) -> Result<TypeKind, Error> {
+    match input.to_ascii_lowercase().as_str() {
+        "boolean" => Ok(TypeKind::Boolean),
+        "tinyint" => Ok(TypeKind::Byte),
+        "smallint" => Ok(TypeKind::Short),
+        "int" => Ok(TypeKind::Int),
+        "bigint" | "long" => Ok(TypeKind::Long),
+        "float" => Ok(TypeKind::Float),
+        "double" => Ok(TypeKind::Double),
+        "string" => Ok(TypeKind::String),
+        "binary" => Ok(TypeKind::Binary),
+        "timestamp" => Ok(TypeKind::Timestamp),
+        "timestamp with local time zone" => Ok(TypeKind::TimestampInstant),
+        "date" => Ok(TypeKind::Date),
+        _ => Err(Error::UnknownType(input.to_string())),
+    }
+}
+
+/// Replace the types of the named fields in an inferred schema.
+pub fn apply_overrides(
+    schema: &mut [(String, TypeKind)],
+    overrides: &[(String, TypeKind)],
+) -> Result<(), Error> {
+    for (name, kind) in overrides {
+        let field = schema
+            .iter_mut()
+            .find(|(field_name, _)| field_name == name)
+            .ok_or_else(|| Error::UnknownField(name.to_string()))?;
+
+        field.1 = *kind;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infer_kinds() {
+        let mut inference = SchemaInference::new(
+            ["id", "score", "verified", "created", "name", "empty"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        );
+
+        let rows = [
+            ["1", "1", "true", "2020-09-20T12:00:00Z", "a", ""],
+            ["2", "2.5", "FALSE", "2020-09-20 12:00:01.123", "1", ""],
+            ["-3", "1e3", "", "2020-09-20T12:00:02+02:00", "inf", ""],
+        ];
+
+        for row in rows {
+            inference.observe(row.iter().map(|value| Some(*value)));
+        }
+
+        assert_eq!(inference.get_row_count(), 3);
+
+        let mut schema = inference.finish();
+
+        assert_eq!(
+            schema.iter().map(|(_, kind)| *kind).collect::<Vec<_>>(),
+            vec![
+                TypeKind::Long,
+                TypeKind::Double,
+                TypeKind::Boolean,
+                TypeKind::Timestamp,
+                TypeKind::String,
+                TypeKind::String
+            ]
+        );
+
+        apply_overrides(
+            &mut schema,
+            &parse_schema("id:string, score:float").unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(schema[0].1, TypeKind::String);
+        assert_eq!(schema[1].1, TypeKind::Float);
+        assert_eq!(
+            apply_overrides(&mut schema, &parse_schema("other:int").unwrap()),
+            Err(Error::UnknownField("other".to_string()))
+        );
+
+        assert_eq!(
+            parse_schema("created:timestamp, updated:timestamp with local time zone"),
+            Ok(vec![
+                ("created".to_string(), TypeKind::Timestamp),
+                ("updated".to_string(), TypeKind::TimestampInstant)
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_invalid_schema() {
+        assert_eq!(
+            parse_schema("id"),
+            Err(Error::InvalidSchema("id".to_string()))
+        );
+        assert_eq!(
+            parse_schema("id:uuid"),
+            Err(Error::UnknownType("uuid".to_string()))
+        );
+        assert!(!is_timestamp("2020-09-20T12:00:00+0200"));
+        assert!(!is_timestamp("2020-09-20"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Deduplicating string values as shared `Arc<str>`s.
+//!
+//! Decoded columns borrow their strings, so keeping values past the lifetime of a column normally
+//! means allocating a `String` for every row. An interner instead returns one allocation for each
+//! distinct value, which matters for columns with many repeated values (like country codes). For
+//! dictionary-encoded columns, each dictionary entry is only looked up once.
+
+use crate::column::Column;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A table of shared strings.
+///
+/// An interner can be reused across columns and stripes (so that values are shared between them),
+/// or created for each stripe to limit how long values are kept in the table.
+#[derive(Clone, Debug, Default)]
+pub struct StringInterner {
+    values: HashSet<Arc<str>>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of distinct values in the table.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        match self.values.get(value) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(value);
+                self.values.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// The interned values of a string column (with `None` for nulls), or `None` if the column
+    /// doesn't contain strings.
+    pub fn intern_column(&mut self, column: &Column) -> Option<Vec<Option<Arc<str>>>> {
+        match column {
+            Column::Utf8Dictionary {
+                data,
+                dictionary,
+                indices,
+            } => {
+                let entries = indices
+                    .iter()
+                    .map(|(start, end)| self.intern(&dictionary[*start..*end]))
+                    .collect::<Vec<_>>();
+
+                Some(
+                    data.iter()
+                        .map(|index| {
+                            usize::try_from(*index)
+                                .ok()
+                                .and_then(|index| entries.get(index))
+                                .cloned()
+                        })
+                        .collect(),
+                )
+            }
+            Column::Utf8Direct { data, indices } => Some(
+                indices
+                    .iter()
+                    .map(|range| range.map(|(start, end)| self.intern(&data[start..end])))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Remove all values from the table (values that have already been returned are unaffected).
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::Utf8Policy;
+
+    #[test]
+    fn intern_columns() {
+        let dictionary_column = Column::make_utf8_dictionary_column(
+            Some(vec![1, 0, 0, 0]),
+            vec![1, 0, 1],
+            "fooéa".as_bytes().to_vec(),
+            vec![3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+        let direct_column = Column::make_utf8_direct_column(
+            None,
+            "fooéafoo".as_bytes().to_vec(),
+            vec![3, 3, 3],
+            Utf8Policy::Strict,
+        )
+        .unwrap();
+
+        let mut interner = StringInterner::new();
+        let dictionary_values = interner.intern_column(&dictionary_column).unwrap();
+        let direct_values = interner.intern_column(&direct_column).unwrap();
+
+        assert_eq!(interner.len(), 2);
+        assert_eq!(
+            dictionary_values
+                .iter()
+                .map(|value| value.as_deref())
+                .collect::<Vec<_>>(),
+            vec![None, Some("éa"), Some("foo"), Some("éa")]
+        );
+        assert_eq!(
+            direct_values
+                .iter()
+                .map(|value| value.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("foo"), Some("éa"), Some("foo")]
+        );
+        assert!(Arc::ptr_eq(
+            dictionary_values[1].as_ref().unwrap(),
+            direct_values[1].as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            direct_values[0].as_ref().unwrap(),
+            direct_values[2].as_ref().unwrap()
+        ));
+        assert!(interner
+            .intern_column(&Column::make_i64_column(vec![1], &[]))
+            .is_none());
+    }
+}
@@ -1,11 +1,44 @@
+pub mod acid;
+pub mod analyze;
+pub mod bitmap;
+pub mod cache;
+pub mod cast;
+pub mod codegen;
 pub mod column;
+#[cfg(feature = "json")]
+pub mod compare;
 pub mod compress;
 pub mod de;
+pub mod decimal;
+pub mod dedupe;
+#[cfg(feature = "json")]
+pub mod expr;
+#[cfg(feature = "hash")]
+pub mod hash;
+pub mod infer;
+pub mod intern;
+pub mod limits;
+#[cfg(feature = "lzo")]
+pub mod lzo;
+pub mod memory;
+pub mod meta;
+pub mod metrics;
 pub mod parser;
+pub mod progress;
 pub mod proto;
+pub mod repair;
 pub mod rle;
+pub mod schema;
+pub mod ser;
+#[cfg(feature = "sort")]
+pub mod sort;
+pub mod split;
+pub mod stats;
+pub mod time;
 pub mod value;
+pub mod verify;
 
 pub use column::Column;
+pub use compress::Decompressor;
 pub use parser::OrcFile;
-pub use value::Value;
+pub use value::{OwnedValue, Value};
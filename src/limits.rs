@@ -0,0 +1,109 @@
+//! Resource limits for reading untrusted files.
+//!
+//! The sizes declared in ORC metadata are used to decide how much to read and allocate, so a crafted
+//! file can make a reader use far more memory than its own size would suggest. These limits are
+//! checked before that happens, and a violation is reported as
+//! [`crate::parser::Error::LimitExceeded`] (or [`crate::compress::Error::ChunkTooLarge`] for
+//! decompressed chunks). By default there are no limits.
+//!
+//! See [`crate::parser::OrcFile::open_with_limits`].
+
+/// A kind of limit.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Limit {
+    /// The on-disk length of the file footer, metadata section, or a stripe footer.
+    MetadataLen,
+    StripeCount,
+    DictionaryEntries,
+    StringLen,
+    /// The total number of elements of a list column in a stripe.
+    ListElements,
+    /// The decoded column memory reserved in a [`crate::memory::MemoryPool`].
+    PoolMemory,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseLimits {
+    max_metadata_len: Option<u64>,
+    max_stripe_count: Option<u64>,
+    max_dictionary_entries: Option<u64>,
+    max_string_len: Option<u64>,
+    max_list_elements: Option<u64>,
+    max_chunk_len: Option<usize>,
+}
+
+impl ParseLimits {
+    /// No limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits that should be reasonable for most files (metadata up to 16 MiB, a million stripes,
+    /// a million dictionary entries per stripe, strings up to 16 MiB, 64 million list elements per
+    /// stripe, and decompressed chunks up to 64 MiB).
+    pub fn conservative() -> Self {
+        Self {
+            max_metadata_len: Some(16 << 20),
+            max_stripe_count: Some(1 << 20),
+            max_dictionary_entries: Some(1 << 20),
+            max_string_len: Some(16 << 20),
+            max_list_elements: Some(1 << 26),
+            max_chunk_len: Some(64 << 20),
+        }
+    }
+
+    pub fn with_max_metadata_len(mut self, max_metadata_len: u64) -> Self {
+        self.max_metadata_len = Some(max_metadata_len);
+        self
+    }
+
+    pub fn with_max_stripe_count(mut self, max_stripe_count: u64) -> Self {
+        self.max_stripe_count = Some(max_stripe_count);
+        self
+    }
+
+    pub fn with_max_dictionary_entries(mut self, max_dictionary_entries: u64) -> Self {
+        self.max_dictionary_entries = Some(max_dictionary_entries);
+        self
+    }
+
+    pub fn with_max_string_len(mut self, max_string_len: u64) -> Self {
+        self.max_string_len = Some(max_string_len);
+        self
+    }
+
+    /// Limit the total number of elements of a list column in a stripe (which is the row count
+    /// of its element column).
+    pub fn with_max_list_elements(mut self, max_list_elements: u64) -> Self {
+        self.max_list_elements = Some(max_list_elements);
+        self
+    }
+
+    /// Limit the decompressed size of each compression chunk.
+    pub fn with_max_chunk_len(mut self, max_chunk_len: usize) -> Self {
+        self.max_chunk_len = Some(max_chunk_len);
+        self
+    }
+
+    pub fn get_max_chunk_len(&self) -> Option<usize> {
+        self.max_chunk_len
+    }
+
+    /// Check a value against the given limit, returning the maximum if it's exceeded.
+    pub(crate) fn check(&self, limit: Limit, value: u64) -> Result<(), u64> {
+        let max = match limit {
+            Limit::MetadataLen => self.max_metadata_len,
+            Limit::StripeCount => self.max_stripe_count,
+            Limit::DictionaryEntries => self.max_dictionary_entries,
+            Limit::StringLen => self.max_string_len,
+            Limit::ListElements => self.max_list_elements,
+            // Checked by the pool itself.
+            Limit::PoolMemory => None,
+        };
+
+        match max {
+            Some(max) if value > max => Err(max),
+            _ => Ok(()),
+        }
+    }
+}
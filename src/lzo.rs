@@ -0,0 +1,258 @@
+//! Decompression of LZO1X data.
+//!
+//! ORC's LZO codec stores each compressed chunk as a raw LZO1X stream (with no header or length),
+//! which ends with an end-of-stream marker. Instructions alternate between back-references to the
+//! output and runs of literal bytes, and the low two bits of each back-reference give the number of
+//! literals that follow it (with longer runs encoded as separate instructions).
+
+// The maximum distance of a two-byte match, which is added to the distance of three-byte matches
+// that follow a long literal run.
+const M2_MAX_OFFSET: usize = 0x0800;
+// The base distance of matches with an extended distance.
+const M4_BASE_OFFSET: usize = 0x4000;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The input ends before the end-of-stream marker.
+    #[error("Truncated LZO input")]
+    Truncated,
+    /// The instruction at the given offset refers to bytes before the start of the output.
+    #[error("Invalid LZO match distance")]
+    InvalidDistance { offset: usize },
+    /// There are bytes after the end-of-stream marker.
+    #[error("Unexpected input after the end of the LZO stream")]
+    TrailingInput,
+}
+
+struct Input<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Input<'a> {
+    fn peek(&self) -> Result<u8, Error> {
+        self.bytes
+            .get(self.position)
+            .copied()
+            .ok_or(Error::Truncated)
+    }
+
+    fn next(&mut self) -> Result<usize, Error> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Ok(byte as usize)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(Error::Truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn next_u16(&mut self) -> Result<usize, Error> {
+        Ok(self.next()? | (self.next()? << 8))
+    }
+
+    // A length that didn't fit in its instruction, stored as a run of zero bytes (each adding 255)
+    // and a final non-zero byte.
+    fn extended_len(&mut self, base: usize) -> Result<usize, Error> {
+        let mut len = base;
+
+        loop {
+            match self.next()? {
+                0 => len += 255,
+                byte => return Ok(len + byte),
+            }
+        }
+    }
+}
+
+/// Decompress an LZO1X stream, appending the result to `output`.
+///
+/// If a maximum length is given, decompression stops (successfully) as soon as the output is
+/// longer, so that the caller can report the chunk as too large.
+pub fn decompress(input: &[u8], output: &mut Vec<u8>, max_len: Option<usize>) -> Result<(), Error> {
+    let output_start = output.len();
+    let mut input = Input {
+        bytes: input,
+        position: 0,
+    };
+    // The number of literals after the previous instruction (or 4 for four or more), which
+    // determines how short matches are encoded.
+    let mut state = 0;
+
+    // The stream may start with a literal run.
+    if input.peek()? > 17 {
+        let len = input.next()? - 17;
+        output.extend_from_slice(input.take(len)?);
+        state = len.min(4);
+    }
+
+    loop {
+        if max_len.is_some_and(|max_len| output.len() - output_start > max_len) {
+            return Ok(());
+        }
+
+        let offset = input.position;
+        let instruction = input.next()?;
+
+        // The distance back from the end of the output, the match length, and the number of
+        // literals that follow.
+        let (distance, len, literal_len) = if instruction < 16 {
+            match state {
+                0 => {
+                    let len = match instruction {
+                        0 => input.extended_len(15)?,
+                        len => len,
+                    } + 3;
+                    output.extend_from_slice(input.take(len)?);
+                    state = 4;
+                    continue;
+                }
+                4 => (
+                    1 + M2_MAX_OFFSET + (instruction >> 2) + (input.next()? << 2),
+                    3,
+                    instruction & 3,
+                ),
+                _ => (
+                    1 + (instruction >> 2) + (input.next()? << 2),
+                    2,
+                    instruction & 3,
+                ),
+            }
+        } else if instruction >= 64 {
+            (
+                1 + ((instruction >> 2) & 7) + (input.next()? << 3),
+                (instruction >> 5) + 1,
+                instruction & 3,
+            )
+        } else if instruction >= 32 {
+            let len = match instruction & 31 {
+                0 => input.extended_len(31)?,
+                len => len,
+            } + 2;
+            let trailer = input.next_u16()?;
+
+            (1 + (trailer >> 2), len, trailer & 3)
+        } else {
+            let len = match instruction & 7 {
+                0 => input.extended_len(7)?,
+                len => len,
+            } + 2;
+            let trailer = input.next_u16()?;
+            let distance = ((instruction & 8) << 11) + (trailer >> 2);
+
+            // A zero distance marks the end of the stream.
+            if distance == 0 {
+                return if input.position == input.bytes.len() {
+                    Ok(())
+                } else {
+                    Err(Error::TrailingInput)
+                };
+            }
+
+            (M4_BASE_OFFSET + distance, len, trailer & 3)
+        };
+
+        let start = output
+            .len()
+            .checked_sub(distance)
+            .filter(|start| *start >= output_start)
+            .ok_or(Error::InvalidDistance { offset })?;
+
+        if distance >= len {
+            output.extend_from_within(start..start + len);
+        } else {
+            // The match overlaps the bytes it produces.
+            for index in start..start + len {
+                output.push(output[index]);
+            }
+        }
+
+        output.extend_from_slice(input.take(literal_len)?);
+        state = literal_len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The end-of-stream marker.
+    const END: [u8; 3] = [0x11, 0x00, 0x00];
+
+    fn decompress_all(input: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut output = vec![];
+        decompress(input, &mut output, None).map(|_| output)
+    }
+
+    #[test]
+    fn literals() {
+        let mut input = vec![17 + 5];
+        input.extend(b"hello");
+        input.extend(END);
+        assert_eq!(decompress_all(&input), Ok(b"hello".to_vec()));
+
+        // A long literal run after a match, with an extended length.
+        let mut input = vec![17 + 1, b'a', 2 << 5, 0];
+        input.extend([0x00, 0x00, 1]);
+        input.extend([b'b'; 15 + 255 + 1 + 3]);
+        input.extend(END);
+        let mut expected = vec![b'a'; 4];
+        expected.extend([b'b'; 15 + 255 + 1 + 3]);
+        assert_eq!(decompress_all(&input), Ok(expected));
+    }
+
+    #[test]
+    fn matches() {
+        // A two-byte match after three literals.
+        let mut input = vec![17 + 3, b'a', b'b', b'c', 2 << 2, 0];
+        input.extend(END);
+        assert_eq!(decompress_all(&input), Ok(b"abcab".to_vec()));
+
+        // A six-byte match at distance three.
+        let mut input = vec![17 + 3, b'a', b'b', b'c', (5 << 5) | (2 << 2), 0];
+        input.extend(END);
+        assert_eq!(decompress_all(&input), Ok(b"abcabcabc".to_vec()));
+
+        // A match with an extended length, followed by one literal.
+        let mut input = vec![17 + 3, b'a', b'b', b'c', 32, 0, 5, (2 << 2) | 1, 0, b'z'];
+        input.extend(END);
+        let mut expected = b"abc".repeat(99)[..3 + 31 + 255 + 5 + 2].to_vec();
+        expected.push(b'z');
+        assert_eq!(decompress_all(&input), Ok(expected));
+    }
+
+    #[test]
+    fn invalid() {
+        // The match distance is eleven, with only three bytes of output.
+        let mut input = vec![17 + 3, b'a', b'b', b'c', (5 << 5) | (2 << 2), 1];
+        input.extend(END);
+        assert_eq!(
+            decompress_all(&input),
+            Err(Error::InvalidDistance { offset: 4 })
+        );
+
+        assert_eq!(decompress_all(&[17 + 3, b'a', b'b']), Err(Error::Truncated));
+        assert_eq!(decompress_all(&[17 + 1, b'a']), Err(Error::Truncated));
+        assert_eq!(decompress_all(&[]), Err(Error::Truncated));
+
+        let mut input = vec![17 + 1, b'a'];
+        input.extend(END);
+        input.push(0);
+        assert_eq!(decompress_all(&input), Err(Error::TrailingInput));
+    }
+
+    #[test]
+    fn max_len() {
+        let mut input = vec![17 + 3, b'a', b'b', b'c', (5 << 5) | (2 << 2), 0];
+        input.extend(END);
+
+        let mut output = vec![];
+        assert_eq!(decompress(&input, &mut output, Some(4)), Ok(()));
+        assert_eq!(output, b"abcabcabc");
+    }
+}
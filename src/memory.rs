@@ -0,0 +1,165 @@
+//! Sharing a memory budget for decoded columns between readers.
+//!
+//! A pool is attached to each file with [`crate::parser::OrcFile::set_memory_pool`] (clones of a
+//! pool share the same budget). Row scans reserve the estimated size of each stripe's decoded
+//! columns (see [`crate::column::Column::get_data_size`]) after decoding them and release it when
+//! they move on to the next stripe or are dropped, so the budget bounds the decoded data held by
+//! all scans together, with each scan possibly exceeding it by the one stripe it's decoding.
+//!
+//! Columns returned directly by `read_column` and columns in a column cache aren't counted.
+
+use crate::limits::Limit;
+use crate::parser::Error;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// What happens when a reservation doesn't fit in the pool.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PoolFullPolicy {
+    /// Fail with [`Error::LimitExceeded`].
+    #[default]
+    Fail,
+    /// Wait for other readers to release memory (a reservation larger than the whole pool still
+    /// fails).
+    Wait,
+}
+
+/// A shared budget for decoded column memory.
+#[derive(Clone, Debug)]
+pub struct MemoryPool {
+    state: Arc<(Mutex<usize>, Condvar)>,
+    max_size: usize,
+    policy: PoolFullPolicy,
+}
+
+impl MemoryPool {
+    /// A pool allowing at most `max_size` bytes of decoded data to be reserved.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            state: Arc::new((Mutex::new(0), Condvar::new())),
+            max_size,
+            policy: PoolFullPolicy::default(),
+        }
+    }
+
+    /// Use the given policy for this handle (other clones of the pool keep their own policies).
+    pub fn with_policy(mut self, policy: PoolFullPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn get_max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn get_policy(&self) -> PoolFullPolicy {
+        self.policy
+    }
+
+    /// The number of bytes currently reserved by all readers using the pool.
+    pub fn get_used(&self) -> usize {
+        *self.lock()
+    }
+
+    pub(crate) fn reserve(&self, size: usize) -> Result<MemoryReservation, Error> {
+        let exceeded = |used: usize| Error::LimitExceeded {
+            limit: Limit::PoolMemory,
+            value: (used + size) as u64,
+            max: self.max_size as u64,
+        };
+
+        if size > self.max_size {
+            return Err(exceeded(0));
+        }
+
+        let mut used = self.lock();
+
+        while *used + size > self.max_size {
+            match self.policy {
+                PoolFullPolicy::Fail => return Err(exceeded(*used)),
+                PoolFullPolicy::Wait => {
+                    used = self
+                        .state
+                        .1
+                        .wait(used)
+                        .unwrap_or_else(|error| error.into_inner());
+                }
+            }
+        }
+
+        *used += size;
+
+        Ok(MemoryReservation {
+            pool: self.clone(),
+            size,
+        })
+    }
+
+    // The count is always left in a valid state, so a panic in another thread doesn't matter.
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.state
+            .0
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// Memory reserved in a pool, which is released when this is dropped.
+#[derive(Debug)]
+pub struct MemoryReservation {
+    pool: MemoryPool,
+    size: usize,
+}
+
+impl MemoryReservation {
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        *self.pool.lock() -= self.size;
+        self.pool.state.1.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_and_release() {
+        let pool = MemoryPool::new(100);
+        let first = pool.reserve(60).unwrap();
+        assert_eq!(pool.get_used(), 60);
+
+        assert!(matches!(
+            pool.reserve(50),
+            Err(Error::LimitExceeded {
+                limit: Limit::PoolMemory,
+                value: 110,
+                max: 100
+            })
+        ));
+        assert!(pool.reserve(101).is_err());
+
+        drop(first);
+        assert_eq!(pool.get_used(), 0);
+        assert!(pool.reserve(100).is_ok());
+    }
+
+    #[test]
+    fn wait_for_release() {
+        let pool = MemoryPool::new(100).with_policy(PoolFullPolicy::Wait);
+        let first = pool.reserve(60).unwrap();
+
+        let waiting_pool = pool.clone();
+        let waiting = std::thread::spawn(move || waiting_pool.reserve(50).map(|_| ()));
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        drop(first);
+
+        assert!(waiting.join().unwrap().is_ok());
+        assert_eq!(pool.get_used(), 0);
+    }
+}
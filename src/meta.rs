@@ -0,0 +1,883 @@
+//! Owned representations of ORC file metadata.
+//!
+//! These types mirror the parts of the ORC protobuf messages that we expose publicly, so that the
+//! generated protobuf code can change without affecting users of this crate.
+
+use crate::proto::orc_proto;
+
+// The protobuf value of the Brotli compression kind added in ORC 2.0 (which our generated code
+// doesn't include).
+const BROTLI_COMPRESSION_VALUE: i32 = 6;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompressionKind {
+    None,
+    Zlib,
+    Snappy,
+    Lzo,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+impl CompressionKind {
+    pub(crate) fn from_proto(kind: orc_proto::CompressionKind) -> Self {
+        match kind {
+            orc_proto::CompressionKind::NONE => Self::None,
+            orc_proto::CompressionKind::ZLIB => Self::Zlib,
+            orc_proto::CompressionKind::SNAPPY => Self::Snappy,
+            orc_proto::CompressionKind::LZO => Self::Lzo,
+            orc_proto::CompressionKind::LZ4 => Self::Lz4,
+            orc_proto::CompressionKind::ZSTD => Self::Zstd,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TypeKind {
+    Boolean,
+    Byte,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    Binary,
+    /// A date and time without a time zone (decoded as milliseconds since the Unix epoch, as if
+    /// the wall-clock time were in UTC).
+    Timestamp,
+    List,
+    Map,
+    Struct,
+    Union,
+    Decimal,
+    Date,
+    Varchar,
+    Char,
+    /// An instant in time (decoded as milliseconds since the Unix epoch in UTC).
+    TimestampInstant,
+}
+
+impl TypeKind {
+    /// Whether this is either of the timestamp kinds.
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self, Self::Timestamp | Self::TimestampInstant)
+    }
+
+    pub(crate) fn from_proto(kind: orc_proto::type_::Kind) -> Self {
+        use orc_proto::type_::Kind;
+
+        match kind {
+            Kind::BOOLEAN => Self::Boolean,
+            Kind::BYTE => Self::Byte,
+            Kind::SHORT => Self::Short,
+            Kind::INT => Self::Int,
+            Kind::LONG => Self::Long,
+            Kind::FLOAT => Self::Float,
+            Kind::DOUBLE => Self::Double,
+            Kind::STRING => Self::String,
+            Kind::BINARY => Self::Binary,
+            Kind::TIMESTAMP => Self::Timestamp,
+            Kind::LIST => Self::List,
+            Kind::MAP => Self::Map,
+            Kind::STRUCT => Self::Struct,
+            Kind::UNION => Self::Union,
+            Kind::DECIMAL => Self::Decimal,
+            Kind::DATE => Self::Date,
+            Kind::VARCHAR => Self::Varchar,
+            Kind::CHAR => Self::Char,
+            Kind::TIMESTAMP_INSTANT => Self::TimestampInstant,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PostScript {
+    footer_length: u64,
+    compression: CompressionKind,
+    compression_block_size: Option<u64>,
+    version: Vec<u32>,
+    metadata_length: u64,
+    writer_version: Option<u32>,
+    stripe_statistics_length: Option<u64>,
+    magic: Option<String>,
+}
+
+impl PostScript {
+    pub(crate) fn from_proto(postscript: &orc_proto::PostScript) -> Self {
+        Self {
+            footer_length: postscript.footerLength(),
+            compression: match postscript.compression.map(|kind| kind.enum_value()) {
+                Some(Err(BROTLI_COMPRESSION_VALUE)) => CompressionKind::Brotli,
+                _ => CompressionKind::from_proto(postscript.compression()),
+            },
+            compression_block_size: postscript.compressionBlockSize,
+            version: postscript.version.clone(),
+            metadata_length: postscript.metadataLength(),
+            writer_version: postscript.writerVersion,
+            stripe_statistics_length: postscript.stripeStatisticsLength,
+            magic: postscript.magic.clone(),
+        }
+    }
+
+    pub fn get_footer_length(&self) -> u64 {
+        self.footer_length
+    }
+
+    pub fn get_compression(&self) -> CompressionKind {
+        self.compression
+    }
+
+    pub fn get_compression_block_size(&self) -> Option<u64> {
+        self.compression_block_size
+    }
+
+    pub fn get_version(&self) -> &[u32] {
+        &self.version
+    }
+
+    pub fn get_metadata_length(&self) -> u64 {
+        self.metadata_length
+    }
+
+    pub fn get_writer_version(&self) -> Option<u32> {
+        self.writer_version
+    }
+
+    pub fn get_stripe_statistics_length(&self) -> Option<u64> {
+        self.stripe_statistics_length
+    }
+
+    pub fn get_magic(&self) -> Option<&str> {
+        self.magic.as_deref()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Footer {
+    header_length: u64,
+    content_length: u64,
+    stripes: Vec<StripeInformation>,
+    types: Vec<ColumnType>,
+    user_metadata: Vec<(String, Vec<u8>)>,
+    number_of_rows: u64,
+    row_index_stride: Option<u32>,
+    writer: Option<u32>,
+    software_version: Option<String>,
+    synthetic_root: bool,
+}
+
+impl Footer {
+    /// If the root type isn't a struct (as in files of primitives written by Spark, for example),
+    /// the types are read as if the root were wrapped in a struct with a single unnamed field, so
+    /// that the root value can be read as the first field, as in any other file.
+    pub(crate) fn from_proto(footer: &orc_proto::Footer) -> Self {
+        let mut types = footer
+            .types
+            .iter()
+            .map(ColumnType::from_proto)
+            .collect::<Vec<_>>();
+        let synthetic_root = types
+            .first()
+            .is_some_and(|root| root.kind != TypeKind::Struct);
+
+        if synthetic_root {
+            for column_type in &mut types {
+                for subtype in &mut column_type.subtypes {
+                    *subtype += 1;
+                }
+            }
+
+            types.insert(
+                0,
+                ColumnType {
+                    kind: TypeKind::Struct,
+                    subtypes: vec![1],
+                    field_names: vec![String::new()],
+                    maximum_length: None,
+                    precision: None,
+                    scale: None,
+                },
+            );
+        }
+
+        Self {
+            header_length: footer.headerLength(),
+            content_length: footer.contentLength(),
+            stripes: footer
+                .stripes
+                .iter()
+                .map(StripeInformation::from_proto)
+                .collect(),
+            types,
+            user_metadata: footer
+                .metadata
+                .iter()
+                .map(|item| (item.name().to_string(), item.value().to_vec()))
+                .collect(),
+            number_of_rows: footer.numberOfRows(),
+            row_index_stride: footer.rowIndexStride,
+            writer: footer.writer,
+            software_version: footer.softwareVersion.clone(),
+            synthetic_root,
+        }
+    }
+
+    /// Whether the file's root type isn't a struct, so that the types start with a synthetic
+    /// root struct (see `from_proto`), and column ids are one greater than the ids in the file.
+    pub fn has_synthetic_root(&self) -> bool {
+        self.synthetic_root
+    }
+
+    pub fn get_header_length(&self) -> u64 {
+        self.header_length
+    }
+
+    pub fn get_content_length(&self) -> u64 {
+        self.content_length
+    }
+
+    pub fn get_stripes(&self) -> &[StripeInformation] {
+        &self.stripes
+    }
+
+    pub fn get_types(&self) -> &[ColumnType] {
+        &self.types
+    }
+
+    pub fn get_user_metadata(&self) -> &[(String, Vec<u8>)] {
+        &self.user_metadata
+    }
+
+    pub fn get_number_of_rows(&self) -> u64 {
+        self.number_of_rows
+    }
+
+    pub fn get_row_index_stride(&self) -> Option<u32> {
+        self.row_index_stride
+    }
+
+    pub fn get_writer(&self) -> Option<u32> {
+        self.writer
+    }
+
+    pub fn get_software_version(&self) -> Option<&str> {
+        self.software_version.as_deref()
+    }
+
+    /// The column id for a dotted path of field names (such as `row.location`), starting from the
+    /// root struct.
+    pub fn resolve_path(&self, path: &str) -> Option<u32> {
+        let mut column_id = 0;
+
+        for field_name in path.split('.') {
+            let column_type = self.types.get(column_id as usize)?;
+
+            if column_type.get_kind() != TypeKind::Struct {
+                return None;
+            }
+
+            let field_index = column_type
+                .get_field_names()
+                .iter()
+                .position(|name| name == field_name)?;
+            column_id = *column_type.get_subtypes().get(field_index)?;
+        }
+
+        Some(column_id)
+    }
+
+    /// The ids of the leaf columns under the given column (or the column itself if it's a leaf),
+    /// in schema order.
+    ///
+    /// The leaves are the only columns with value streams, so these are the columns that need to be
+    /// read to project a nested column. Lists count as leaves, since their elements are read with
+    /// them.
+    pub fn get_leaf_column_ids(&self, column_id: u32) -> Vec<u32> {
+        let mut leaf_column_ids = vec![];
+        let mut pending = vec![column_id];
+
+        while let Some(column_id) = pending.pop() {
+            match self.types.get(column_id as usize) {
+                Some(column_type)
+                    if column_type.get_kind() != TypeKind::List
+                        && !column_type.get_subtypes().is_empty() =>
+                {
+                    pending.extend(column_type.get_subtypes().iter().rev());
+                }
+                _ => leaf_column_ids.push(column_id),
+            }
+        }
+
+        leaf_column_ids
+    }
+
+    /// The dotted path of field names from the root struct to the given column (the inverse of
+    /// `resolve_path`).
+    ///
+    /// Columns that aren't reached only through struct fields (such as list elements) have no path.
+    pub fn get_column_path(&self, column_id: u32) -> Option<String> {
+        let mut field_names = vec![];
+        let mut current = column_id;
+
+        while current != 0 {
+            let (parent, column_type) = self
+                .types
+                .iter()
+                .enumerate()
+                .find(|(_, column_type)| column_type.get_subtypes().contains(&current))?;
+
+            if column_type.get_kind() != TypeKind::Struct {
+                return None;
+            }
+
+            let field_index = column_type
+                .get_subtypes()
+                .iter()
+                .position(|subtype| *subtype == current)?;
+            field_names.push(column_type.get_field_names().get(field_index)?.as_str());
+            current = parent as u32;
+        }
+
+        field_names.reverse();
+
+        Some(field_names.join(".")).filter(|path| !path.is_empty())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StripeInformation {
+    offset: u64,
+    index_length: u64,
+    data_length: u64,
+    footer_length: u64,
+    number_of_rows: u64,
+}
+
+impl StripeInformation {
+    pub(crate) fn from_proto(stripe: &orc_proto::StripeInformation) -> Self {
+        Self {
+            offset: stripe.offset(),
+            index_length: stripe.indexLength(),
+            data_length: stripe.dataLength(),
+            footer_length: stripe.footerLength(),
+            number_of_rows: stripe.numberOfRows(),
+        }
+    }
+
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn get_index_length(&self) -> u64 {
+        self.index_length
+    }
+
+    pub fn get_data_length(&self) -> u64 {
+        self.data_length
+    }
+
+    pub fn get_footer_length(&self) -> u64 {
+        self.footer_length
+    }
+
+    pub fn get_number_of_rows(&self) -> u64 {
+        self.number_of_rows
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColumnType {
+    kind: TypeKind,
+    subtypes: Vec<u32>,
+    field_names: Vec<String>,
+    maximum_length: Option<u32>,
+    precision: Option<u32>,
+    scale: Option<u32>,
+}
+
+impl ColumnType {
+    pub(crate) fn from_proto(column_type: &orc_proto::Type) -> Self {
+        Self {
+            kind: TypeKind::from_proto(column_type.kind()),
+            subtypes: column_type.subtypes.clone(),
+            field_names: column_type.fieldNames.clone(),
+            maximum_length: column_type.maximumLength,
+            precision: column_type.precision,
+            scale: column_type.scale,
+        }
+    }
+
+    pub fn get_kind(&self) -> TypeKind {
+        self.kind
+    }
+
+    pub fn get_subtypes(&self) -> &[u32] {
+        &self.subtypes
+    }
+
+    pub fn get_field_names(&self) -> &[String] {
+        &self.field_names
+    }
+
+    /// The declared maximum length of a `CHAR` or `VARCHAR` type.
+    pub fn get_maximum_length(&self) -> Option<u32> {
+        self.maximum_length
+    }
+
+    /// The declared precision of a `DECIMAL` type.
+    pub fn get_precision(&self) -> Option<u32> {
+        self.precision
+    }
+
+    /// The declared scale of a `DECIMAL` type (legacy writers may omit it, in which case each
+    /// value has its own scale).
+    pub fn get_scale(&self) -> Option<u32> {
+        self.scale
+    }
+}
+
+/// File or stripe-level statistics for a column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStatistics {
+    number_of_values: Option<u64>,
+    has_null: Option<bool>,
+    range: Option<ValueRange>,
+    bytes_on_disk: Option<u64>,
+    total_length: Option<u64>,
+}
+
+impl ColumnStatistics {
+    pub(crate) fn from_proto(statistics: &orc_proto::ColumnStatistics) -> Self {
+        Self {
+            number_of_values: statistics.numberOfValues,
+            has_null: statistics.hasNull,
+            range: ValueRange::from_proto(statistics),
+            bytes_on_disk: statistics.bytesOnDisk,
+            total_length: statistics
+                .stringStatistics
+                .as_ref()
+                .and_then(|string_statistics| string_statistics.sum)
+                .or_else(|| {
+                    statistics
+                        .binaryStatistics
+                        .as_ref()
+                        .and_then(|binary_statistics| binary_statistics.sum)
+                })
+                .map(|sum| sum as u64),
+        }
+    }
+
+    /// The number of non-null values.
+    pub fn get_number_of_values(&self) -> Option<u64> {
+        self.number_of_values
+    }
+
+    pub fn has_null(&self) -> Option<bool> {
+        self.has_null
+    }
+
+    pub fn get_range(&self) -> Option<&ValueRange> {
+        self.range.as_ref()
+    }
+
+    pub fn get_bytes_on_disk(&self) -> Option<u64> {
+        self.bytes_on_disk
+    }
+
+    /// The total length in bytes of the values of a string or binary column.
+    pub fn get_total_length(&self) -> Option<u64> {
+        self.total_length
+    }
+}
+
+/// The minimum and maximum values recorded in column statistics.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRange {
+    Integer(i64, i64),
+    Double(f64, f64),
+    String(String, String),
+    Decimal(String, String),
+    /// Days since the Unix epoch.
+    Date(i32, i32),
+    /// Milliseconds since the Unix epoch.
+    Timestamp(i64, i64),
+}
+
+impl ValueRange {
+    // Returns `None` if the statistics don't include both a minimum and maximum.
+    fn from_proto(statistics: &orc_proto::ColumnStatistics) -> Option<Self> {
+        if let Some(int_statistics) = statistics.intStatistics.as_ref() {
+            Some(Self::Integer(
+                int_statistics.minimum?,
+                int_statistics.maximum?,
+            ))
+        } else if let Some(double_statistics) = statistics.doubleStatistics.as_ref() {
+            Some(Self::Double(
+                double_statistics.minimum?,
+                double_statistics.maximum?,
+            ))
+        } else if let Some(string_statistics) = statistics.stringStatistics.as_ref() {
+            Some(Self::String(
+                string_statistics.minimum.clone()?,
+                string_statistics.maximum.clone()?,
+            ))
+        } else if let Some(decimal_statistics) = statistics.decimalStatistics.as_ref() {
+            Some(Self::Decimal(
+                decimal_statistics.minimum.clone()?,
+                decimal_statistics.maximum.clone()?,
+            ))
+        } else if let Some(date_statistics) = statistics.dateStatistics.as_ref() {
+            Some(Self::Date(
+                date_statistics.minimum?,
+                date_statistics.maximum?,
+            ))
+        } else if let Some(timestamp_statistics) = statistics.timestampStatistics.as_ref() {
+            Some(Self::Timestamp(
+                timestamp_statistics.minimumUtc?,
+                timestamp_statistics.maximumUtc?,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StreamKind {
+    Present,
+    Data,
+    Length,
+    DictionaryData,
+    DictionaryCount,
+    Secondary,
+    RowIndex,
+    BloomFilter,
+    BloomFilterUtf8,
+    EncryptedIndex,
+    EncryptedData,
+    StripeStatistics,
+    FileStatistics,
+}
+
+impl StreamKind {
+    pub(crate) fn from_proto(kind: orc_proto::stream::Kind) -> Self {
+        use orc_proto::stream::Kind;
+
+        match kind {
+            Kind::PRESENT => Self::Present,
+            Kind::DATA => Self::Data,
+            Kind::LENGTH => Self::Length,
+            Kind::DICTIONARY_DATA => Self::DictionaryData,
+            Kind::DICTIONARY_COUNT => Self::DictionaryCount,
+            Kind::SECONDARY => Self::Secondary,
+            Kind::ROW_INDEX => Self::RowIndex,
+            Kind::BLOOM_FILTER => Self::BloomFilter,
+            Kind::BLOOM_FILTER_UTF8 => Self::BloomFilterUtf8,
+            Kind::ENCRYPTED_INDEX => Self::EncryptedIndex,
+            Kind::ENCRYPTED_DATA => Self::EncryptedData,
+            Kind::STRIPE_STATISTICS => Self::StripeStatistics,
+            Kind::FILE_STATISTICS => Self::FileStatistics,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EncodingKind {
+    Direct,
+    Dictionary,
+    DirectV2,
+    DictionaryV2,
+}
+
+impl EncodingKind {
+    pub(crate) fn from_proto(kind: orc_proto::column_encoding::Kind) -> Self {
+        use orc_proto::column_encoding::Kind;
+
+        match kind {
+            Kind::DIRECT => Self::Direct,
+            Kind::DICTIONARY => Self::Dictionary,
+            Kind::DIRECT_V2 => Self::DirectV2,
+            Kind::DICTIONARY_V2 => Self::DictionaryV2,
+        }
+    }
+}
+
+/// The encoding of one column in one stripe.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ColumnEncoding {
+    kind: EncodingKind,
+    dictionary_size: Option<u32>,
+}
+
+impl ColumnEncoding {
+    pub(crate) fn from_proto(encoding: &orc_proto::ColumnEncoding) -> Self {
+        Self {
+            kind: EncodingKind::from_proto(encoding.kind()),
+            dictionary_size: encoding.dictionarySize,
+        }
+    }
+
+    pub fn get_kind(&self) -> EncodingKind {
+        self.kind
+    }
+
+    /// The number of dictionary entries (only provided for dictionary encodings).
+    pub fn get_dictionary_size(&self) -> Option<u32> {
+        self.dictionary_size
+    }
+
+    pub fn is_dictionary(&self) -> bool {
+        matches!(
+            self.kind,
+            EncodingKind::Dictionary | EncodingKind::DictionaryV2
+        )
+    }
+}
+
+/// A stream in a stripe's data, as listed in the stripe footer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct StreamInformation {
+    kind: StreamKind,
+    column_id: u32,
+    length: u64,
+}
+
+impl StreamInformation {
+    pub(crate) fn from_proto(stream: &orc_proto::Stream) -> Self {
+        Self {
+            kind: StreamKind::from_proto(stream.kind()),
+            column_id: stream.column(),
+            length: stream.length(),
+        }
+    }
+
+    pub fn get_kind(&self) -> StreamKind {
+        self.kind
+    }
+
+    pub fn get_column_id(&self) -> u32 {
+        self.column_id
+    }
+
+    /// The stored (compressed) length of the stream.
+    pub fn get_length(&self) -> u64 {
+        self.length
+    }
+}
+
+/// The streams and column encodings of one stripe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StripeFooter {
+    streams: Vec<StreamInformation>,
+    columns: Vec<ColumnEncoding>,
+    writer_timezone: Option<String>,
+}
+
+impl StripeFooter {
+    pub(crate) fn from_proto(stripe_footer: &orc_proto::StripeFooter) -> Self {
+        Self {
+            streams: stripe_footer
+                .streams
+                .iter()
+                .map(StreamInformation::from_proto)
+                .collect(),
+            columns: stripe_footer
+                .columns
+                .iter()
+                .map(ColumnEncoding::from_proto)
+                .collect(),
+            writer_timezone: stripe_footer.writerTimezone.clone(),
+        }
+    }
+
+    /// The stripe's streams, in file order.
+    pub fn get_streams(&self) -> &[StreamInformation] {
+        &self.streams
+    }
+
+    /// The encoding of each column, indexed by column id (the root struct is 0).
+    pub fn get_columns(&self) -> &[ColumnEncoding] {
+        &self.columns
+    }
+
+    pub fn get_writer_timezone(&self) -> Option<&str> {
+        self.writer_timezone.as_deref()
+    }
+}
+
+/// How one column is stored in one stripe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColumnLayout {
+    stripe_index: usize,
+    column_id: u32,
+    encoding: Option<ColumnEncoding>,
+    streams: Vec<(StreamKind, u64)>,
+}
+
+impl ColumnLayout {
+    pub(crate) fn from_stripe_footer(
+        stripe_index: usize,
+        column_id: u32,
+        stripe_footer: &orc_proto::StripeFooter,
+    ) -> Self {
+        Self {
+            stripe_index,
+            column_id,
+            encoding: stripe_footer
+                .columns
+                .get(column_id as usize)
+                .map(ColumnEncoding::from_proto),
+            streams: stripe_footer
+                .streams
+                .iter()
+                .filter(|stream| stream.column() == column_id)
+                .map(|stream| (StreamKind::from_proto(stream.kind()), stream.length()))
+                .collect(),
+        }
+    }
+
+    pub fn get_stripe_index(&self) -> usize {
+        self.stripe_index
+    }
+
+    pub fn get_column_id(&self) -> u32 {
+        self.column_id
+    }
+
+    /// The column encoding, if the stripe footer includes one.
+    pub fn get_encoding(&self) -> Option<EncodingKind> {
+        self.encoding.map(|encoding| encoding.get_kind())
+    }
+
+    pub fn get_dictionary_size(&self) -> Option<u32> {
+        self.encoding
+            .and_then(|encoding| encoding.get_dictionary_size())
+    }
+
+    /// The kind and stored (compressed) length of each of the column's streams, in file order.
+    pub fn get_streams(&self) -> &[(StreamKind, u64)] {
+        &self.streams
+    }
+
+    /// The total stored length of the column's streams (including index streams).
+    pub fn get_len(&self) -> u64 {
+        self.streams.iter().map(|(_, len)| len).sum()
+    }
+}
+
+/// A top-level field of a file's schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldInfo {
+    index: usize,
+    column_id: u32,
+    kind: TypeKind,
+    maximum_length: Option<u32>,
+    precision: Option<u32>,
+    scale: Option<u32>,
+    has_null: Option<bool>,
+}
+
+impl FieldInfo {
+    pub(crate) fn new(
+        index: usize,
+        column_id: u32,
+        column_type: &ColumnType,
+        has_null: Option<bool>,
+    ) -> Self {
+        Self {
+            index,
+            column_id,
+            kind: column_type.get_kind(),
+            maximum_length: column_type.get_maximum_length(),
+            precision: column_type.get_precision(),
+            scale: column_type.get_scale(),
+            has_null,
+        }
+    }
+
+    /// The field index (which is also the column index for top-level fields).
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_column_id(&self) -> u32 {
+        self.column_id
+    }
+
+    pub fn get_kind(&self) -> TypeKind {
+        self.kind
+    }
+
+    /// The declared maximum length of a `CHAR` or `VARCHAR` field.
+    pub fn get_maximum_length(&self) -> Option<u32> {
+        self.maximum_length
+    }
+
+    /// The declared precision of a `DECIMAL` field.
+    pub fn get_precision(&self) -> Option<u32> {
+        self.precision
+    }
+
+    /// The declared scale of a `DECIMAL` field.
+    pub fn get_scale(&self) -> Option<u32> {
+        self.scale
+    }
+
+    /// Whether the file statistics say that the field has any null values (`None` if the file
+    /// doesn't have statistics).
+    ///
+    /// ORC types don't have nullability, so this is only a hint about this file.
+    pub fn has_null(&self) -> Option<bool> {
+        self.has_null
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn struct_type(field_names: &[&str], subtypes: &[u32]) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(orc_proto::type_::Kind::STRUCT);
+        column_type.fieldNames = field_names.iter().map(|name| name.to_string()).collect();
+        column_type.subtypes = subtypes.to_vec();
+        column_type
+    }
+
+    fn scalar_type(kind: orc_proto::type_::Kind) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(kind);
+        column_type
+    }
+
+    #[test]
+    fn resolve_paths_and_leaves() {
+        use orc_proto::type_::Kind;
+
+        let mut footer = orc_proto::Footer::new();
+        footer.types = vec![
+            struct_type(&["id", "user"], &[1, 2]),
+            scalar_type(Kind::LONG),
+            struct_type(&["name", "location"], &[3, 4]),
+            scalar_type(Kind::STRING),
+            scalar_type(Kind::STRING),
+        ];
+        let footer = Footer::from_proto(&footer);
+
+        assert_eq!(footer.resolve_path("id"), Some(1));
+        assert_eq!(footer.resolve_path("user"), Some(2));
+        assert_eq!(footer.resolve_path("user.location"), Some(4));
+        assert_eq!(footer.resolve_path("user.email"), None);
+        assert_eq!(footer.resolve_path("id.value"), None);
+        assert_eq!(footer.resolve_path(""), None);
+        assert_eq!(footer.get_leaf_column_ids(0), vec![1, 3, 4]);
+        assert_eq!(footer.get_leaf_column_ids(2), vec![3, 4]);
+        assert_eq!(footer.get_leaf_column_ids(4), vec![4]);
+        assert_eq!(footer.get_column_path(4).as_deref(), Some("user.location"));
+        assert_eq!(footer.get_column_path(1).as_deref(), Some("id"));
+        assert_eq!(footer.get_column_path(0), None);
+        assert_eq!(footer.get_column_path(5), None);
+    }
+}
@@ -0,0 +1,76 @@
+//! Counters and timings collected while reading column data.
+//!
+//! See [`crate::parser::OrcFile::get_metrics`] and
+//! [`crate::parser::OrcFile::set_metrics_callback`].
+
+use std::ops::AddAssign;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ReadMetrics {
+    columns_read: u64,
+    bytes_read: u64,
+    bytes_decompressed: u64,
+    values_decoded: u64,
+    stream_duration: Duration,
+    decode_duration: Duration,
+}
+
+impl ReadMetrics {
+    /// The number of column chunks (one column in one stripe) that have been read.
+    pub fn get_columns_read(&self) -> u64 {
+        self.columns_read
+    }
+
+    /// The number of stream bytes read from disk (including compression chunk headers).
+    pub fn get_bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// The number of bytes produced by decompressing the streams.
+    pub fn get_bytes_decompressed(&self) -> u64 {
+        self.bytes_decompressed
+    }
+
+    /// The number of values decoded, including nulls.
+    pub fn get_values_decoded(&self) -> u64 {
+        self.values_decoded
+    }
+
+    /// Time spent reading and decompressing streams.
+    ///
+    /// Boolean and present streams are decoded as they are read, so this includes their decoding.
+    pub fn get_stream_duration(&self) -> Duration {
+        self.stream_duration
+    }
+
+    /// Time spent decoding values and building columns (outside of stream reading).
+    pub fn get_decode_duration(&self) -> Duration {
+        self.decode_duration
+    }
+
+    pub(crate) fn record_stream(&mut self, len: u64, decompressed_len: u64, duration: Duration) {
+        self.bytes_read += len;
+        self.bytes_decompressed += decompressed_len;
+        self.stream_duration += duration;
+    }
+
+    /// Record a completed column read, given the total time spent on it (these metrics should only
+    /// include the streams for this column).
+    pub(crate) fn record_column(&mut self, value_count: u64, duration: Duration) {
+        self.columns_read += 1;
+        self.values_decoded += value_count;
+        self.decode_duration += duration.saturating_sub(self.stream_duration);
+    }
+}
+
+impl AddAssign<&ReadMetrics> for ReadMetrics {
+    fn add_assign(&mut self, other: &ReadMetrics) {
+        self.columns_read += other.columns_read;
+        self.bytes_read += other.bytes_read;
+        self.bytes_decompressed += other.bytes_decompressed;
+        self.values_decoded += other.values_decoded;
+        self.stream_duration += other.stream_duration;
+        self.decode_duration += other.decode_duration;
+    }
+}
@@ -1,26 +1,39 @@
-use crate::proto::orc_proto::{
-    column_encoding::Kind as ColumnEncodingKind, stream::Kind as StreamKind,
-    type_::Kind as TypeKind, CompressionKind, Footer, PostScript, StripeFooter,
-};
+use crate::proto::orc_proto::{self, stream::Kind as StreamKind};
 use crate::{
-    column::{BoolWriter, Column, PresentInfo, PresentInfoWriter},
-    compress::{self, Decompressor},
-    rle::{byte::ByteWriter, IntegerRleVersion},
-    value::Value,
+    bitmap::Bitmap,
+    cache::ColumnCache,
+    column::{BoolWriter, Column, PresentInfo, PresentInfoWriter, Utf8Policy},
+    compress::{self, CodecRegistry, DecompressionContext, Decompressor},
+    de::{FieldNameMatching, ValueConverters},
+    limits::{Limit, ParseLimits},
+    memory::{MemoryPool, MemoryReservation},
+    meta::{
+        ColumnEncoding, ColumnLayout, ColumnStatistics, CompressionKind, EncodingKind, FieldInfo,
+        Footer, PostScript, StripeFooter, TypeKind,
+    },
+    metrics::ReadMetrics,
+    progress::{CancellationToken, ScanProgress},
+    rle::{
+        byte::{ByteDecoder, ByteWriter},
+        IntegerRleVersion,
+    },
+    schema::{Incompatibility, OrcSchema, Schema},
+    stats::StatsCollector,
+    time::Timestamp,
+    value::{OwnedValue, Value},
+    verify::Mismatch,
 };
 use protobuf::Message;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 const POSTSCRIPT_BUFFER_LEN: usize = 256;
 const POSTSCRIPT_LEN_LEN: u64 = 1;
-const SUPPORTED_COMPRESSION_KINDS: [CompressionKind; 3] = [
-    CompressionKind::ZSTD,
-    CompressionKind::ZLIB,
-    CompressionKind::NONE,
-];
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -38,6 +51,8 @@ pub enum Error {
     InvalidMetadata,
     #[error("Invalid column index")]
     InvalidColumnIndex(usize),
+    #[error("Invalid field path")]
+    InvalidPath(String),
     #[error("Invalid value")]
     InvalidValue {
         stripe_index: usize,
@@ -45,22 +60,107 @@ pub enum Error {
         row_index: usize,
     },
     #[error("Invalid integer encoding")]
-    InvalidIntegerEncoding,
+    InvalidIntegerEncoding(#[from] crate::rle::DecodeError),
     #[error("Invalid dictionary size")]
     InvalidDictionarySize { expected: u32, actual: u32 },
+    #[error("Invalid string")]
+    InvalidString(#[from] crate::column::InvalidString),
+    #[error("Invalid decimal")]
+    InvalidDecimal(#[from] crate::decimal::Error),
+    #[error("Verification failed")]
+    VerificationFailed {
+        stripe_index: Option<usize>,
+        column_index: Option<usize>,
+        mismatch: Mismatch,
+    },
+    #[error("Limit exceeded")]
+    LimitExceeded { limit: Limit, value: u64, max: u64 },
+    #[error("Tail doesn't match file")]
+    TailMismatch { expected_len: u64, actual_len: u64 },
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Incompatible schema")]
+    IncompatibleSchema(Vec<Incompatibility>),
+}
+
+/// A coarse classification of errors, for deciding whether to retry, skip the file, or alert.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorCategory {
+    /// Reading failed for reasons outside the file's contents (and may succeed if retried).
+    Io,
+    /// The file is truncated, malformed, or contains invalid values.
+    Corruption,
+    /// The file uses a feature (a type or compression kind) that isn't supported.
+    Unsupported,
+    /// The file exceeds a configured resource limit.
+    Limits,
+    /// The file was used incorrectly (e.g. an invalid column index or field path), or the read
+    /// was cancelled.
+    Usage,
+}
+
+impl Error {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::Io(error) => io_error_category(error),
+            Error::Compress(error) => error.category(),
+            Error::UnsupportedType(_) => ErrorCategory::Unsupported,
+            Error::LimitExceeded { .. } => ErrorCategory::Limits,
+            Error::InvalidState
+            | Error::InvalidColumnIndex(_)
+            | Error::InvalidPath(_)
+            | Error::TailMismatch { .. }
+            | Error::Cancelled
+            | Error::IncompatibleSchema(_) => ErrorCategory::Usage,
+            Error::Protobuf(_)
+            | Error::InvalidMetadata
+            | Error::InvalidValue { .. }
+            | Error::InvalidIntegerEncoding(_)
+            | Error::InvalidDictionarySize { .. }
+            | Error::InvalidString(_)
+            | Error::InvalidDecimal(_)
+            | Error::VerificationFailed { .. } => ErrorCategory::Corruption,
+        }
+    }
+}
+
+// Decompression errors are wrapped in I/O errors when they're read through a decompressor, and
+// reading past the end of a truncated file is also reported as an I/O error.
+pub(crate) fn io_error_category(error: &std::io::Error) -> ErrorCategory {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<compress::Error>())
+    {
+        Some(inner) => inner.category(),
+        None => match error.kind() {
+            std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::InvalidData => {
+                ErrorCategory::Corruption
+            }
+            _ => ErrorCategory::Io,
+        },
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StripeInfo {
+    index: usize,
     row_count: usize,
     data_start: u64,
     data_len: u64,
-    columns: Vec<ColumnInfo>,
+    column_count: usize,
+    // The data stream lengths for each column (indexed by column index, so without the root).
+    // These are only checked against the column type when the column is read.
+    streams: Vec<ColumnDataStreamInfo>,
+    encodings: Vec<ColumnEncoding>,
 }
 
 impl StripeInfo {
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
     pub fn get_column_count(&self) -> usize {
-        self.columns.len()
+        self.column_count
     }
 
     pub fn get_row_count(&self) -> usize {
@@ -70,21 +170,32 @@ impl StripeInfo {
     pub fn get_data_len(&self) -> u64 {
         self.data_len
     }
+
+    /// The encoding of each column in the stripe, indexed by column ID (including the root).
+    pub fn column_encodings(&self) -> &[ColumnEncoding] {
+        &self.encodings
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum ColumnInfo {
     Bool {
         offset: u64,
         present_len: Option<u64>,
         data_len: u64,
     },
-    U64 {
+    Integer {
         offset: u64,
         present_len: Option<u64>,
         data_len: u64,
         version: IntegerRleVersion,
     },
+    /// Byte columns, which are byte run-length encoded (and decoded as integers).
+    Byte {
+        offset: u64,
+        present_len: Option<u64>,
+        data_len: u64,
+    },
     Utf8Direct {
         offset: u64,
         present_len: Option<u64>,
@@ -92,6 +203,13 @@ enum ColumnInfo {
         length_len: u64,
         version: IntegerRleVersion,
     },
+    Binary {
+        offset: u64,
+        present_len: Option<u64>,
+        data_len: u64,
+        length_len: u64,
+        version: IntegerRleVersion,
+    },
     Utf8Dictionary {
         offset: u64,
         present_len: Option<u64>,
@@ -101,723 +219,5263 @@ enum ColumnInfo {
         version: IntegerRleVersion,
         dictionary_size: u32,
     },
+    Timestamp {
+        offset: u64,
+        present_len: Option<u64>,
+        data_len: u64,
+        secondary_len: u64,
+        version: IntegerRleVersion,
+        // Whether this is a `TIMESTAMP_INSTANT` column.
+        utc: bool,
+    },
+    Decimal {
+        offset: u64,
+        present_len: Option<u64>,
+        data_len: u64,
+        secondary_len: u64,
+        version: IntegerRleVersion,
+        // The scale of the column type (values are rescaled to it).
+        scale: Option<u32>,
+    },
+    /// Decimals with a precision of at most 18 written by ORCv2 writers (such as C++ writers using
+    /// the pre-2.0 file version), as integers with the scale of the column type.
+    Decimal64 {
+        offset: u64,
+        present_len: Option<u64>,
+        data_len: u64,
+        scale: u32,
+    },
+    Struct {
+        offset: u64,
+        present_len: Option<u64>,
+    },
+    /// Lists, whose elements are the rows of the child column.
+    List {
+        offset: u64,
+        present_len: Option<u64>,
+        length_len: u64,
+        version: IntegerRleVersion,
+        child_id: usize,
+        // The end of the child column's streams (which follow the list's own streams).
+        child_end: u64,
+    },
+}
+
+impl ColumnInfo {
+    // The offset (from the start of the stripe's data) and length of the streams that are read.
+    fn span(&self) -> (u64, u64) {
+        match self {
+            Self::Bool {
+                offset,
+                present_len,
+                data_len,
+            }
+            | Self::Byte {
+                offset,
+                present_len,
+                data_len,
+            }
+            | Self::Integer {
+                offset,
+                present_len,
+                data_len,
+                ..
+            } => (*offset, present_len.unwrap_or(0) + data_len),
+            Self::Utf8Direct {
+                offset,
+                present_len,
+                data_len,
+                length_len,
+                ..
+            }
+            | Self::Binary {
+                offset,
+                present_len,
+                data_len,
+                length_len,
+                ..
+            } => (*offset, present_len.unwrap_or(0) + data_len + length_len),
+            Self::Utf8Dictionary {
+                offset,
+                present_len,
+                data_len,
+                dictionary_data_len,
+                length_len,
+                ..
+            } => (
+                *offset,
+                present_len.unwrap_or(0) + data_len + length_len + dictionary_data_len,
+            ),
+            Self::Timestamp {
+                offset,
+                present_len,
+                data_len,
+                secondary_len,
+                ..
+            }
+            | Self::Decimal {
+                offset,
+                present_len,
+                data_len,
+                secondary_len,
+                ..
+            } => (*offset, present_len.unwrap_or(0) + data_len + secondary_len),
+            Self::Decimal64 {
+                offset,
+                present_len,
+                data_len,
+                ..
+            } => (*offset, present_len.unwrap_or(0) + data_len),
+            Self::Struct {
+                offset,
+                present_len,
+            } => (*offset, present_len.unwrap_or(0)),
+            Self::List {
+                offset, child_end, ..
+            } => (*offset, child_end - offset),
+        }
+    }
+}
+
+/// A range of bytes that will be read for a column in a stripe.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ByteRange {
+    stripe_index: usize,
+    column_index: usize,
+    start: u64,
+    len: u64,
+}
+
+impl ByteRange {
+    pub fn get_stripe_index(&self) -> usize {
+        self.stripe_index
+    }
+
+    pub fn get_column_index(&self) -> usize {
+        self.column_index
+    }
+
+    /// The offset of the first byte in the file.
+    pub fn get_start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn get_len(&self) -> u64 {
+        self.len
+    }
+
+    /// The offset after the last byte in the file.
+    pub fn get_end(&self) -> u64 {
+        self.start + self.len
+    }
+}
+
+/// The serialized end of an ORC file (the metadata section, footer, and postscript).
+///
+/// This can be cached and passed to `OrcFile::open_with_tail` to open the same file again without
+/// reading or parsing its tail.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrcTail {
+    file_len: u64,
+    bytes: Vec<u8>,
+}
+
+impl OrcTail {
+    /// Reconstruct a tail from the values returned by `get_file_len` and `get_bytes`.
+    pub fn new(file_len: u64, bytes: Vec<u8>) -> Self {
+        Self { file_len, bytes }
+    }
+
+    /// The length of the file the tail was read from.
+    pub fn get_file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
 }
 
+type MetricsCallback = Arc<dyn Fn(&ReadMetrics) + Send + Sync>;
+type ProgressCallback = Box<dyn FnMut(&ScanProgress) + Send>;
+
 pub struct OrcFile {
     file: Option<File>,
+    // Reused across streams to avoid reallocating decompression state.
+    context: DecompressionContext,
+    // The end of the stream currently being read, for verification.
+    stream_end: Option<u64>,
+    // The length and start time of the stream currently being read.
+    stream_start: Option<(u64, Instant)>,
+    metrics: ReadMetrics,
+    // The metrics for the column currently being read.
+    column_metrics: ReadMetrics,
+    metrics_callback: Option<MetricsCallback>,
+    strict: bool,
+    utf8_policy: Utf8Policy,
+    empty_strings_as_nulls: bool,
+    trim_char_padding: bool,
+    skip_unsupported_columns: bool,
+    column_cache: Option<ColumnCache>,
+    memory_pool: Option<MemoryPool>,
+    limits: ParseLimits,
+    codecs: CodecRegistry,
+    stripe_statistics: Option<Arc<Vec<orc_proto::StripeStatistics>>>,
+    // Stripe footers are loaded on demand.
+    stripe_footers: Vec<Option<orc_proto::StripeFooter>>,
+    path: PathBuf,
     pub file_len: u64,
+    metadata: Arc<FileMetadata>,
+}
+
+// The parsed file tail, which is shared by handles created with `try_clone`.
+struct FileMetadata {
     postscript: PostScript,
     footer: Footer,
-    type_kinds: Vec<TypeKind>,
+    statistics: Vec<ColumnStatistics>,
+    schema: OrcSchema,
     field_names: Vec<String>,
     field_name_map: HashMap<String, usize>,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default)]
 struct ColumnDataStreamInfo {
+    // The offset of the column's first data stream from the start of the stripe's data.
+    offset: u64,
     present_len: u64,
     data_len: u64,
     dictionary_data_len: u64,
     length_len: u64,
+    secondary_len: u64,
 }
 
 impl ColumnDataStreamInfo {
     fn len(&self) -> u64 {
-        self.present_len + self.data_len + self.dictionary_data_len + self.length_len
+        self.present_len
+            + self.data_len
+            + self.dictionary_data_len
+            + self.length_len
+            + self.secondary_len
     }
 }
 
 impl OrcFile {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<OrcFile, Error> {
+        Self::open_with_limits(path, ParseLimits::default())
+    }
+
+    /// Open a file, enforcing the given limits while reading its metadata and data.
+    pub fn open_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: ParseLimits,
+    ) -> Result<OrcFile, Error> {
+        Self::open_with_codecs(path, limits, CodecRegistry::default())
+    }
+
+    /// Open a file, using the registered codecs to decompress its metadata and data (in
+    /// preference to the built-in ones).
+    pub fn open_with_codecs<P: AsRef<Path>>(
+        path: P,
+        limits: ParseLimits,
+        codecs: CodecRegistry,
+    ) -> Result<OrcFile, Error> {
         let metadata = std::fs::metadata(path.as_ref())?;
         let file_len = metadata.len();
 
-        let mut file = File::open(path)?;
+        let mut file = File::open(path.as_ref())?;
         let (postscript, postscript_len) = Self::read_postscript(&mut file, file_len)?;
+        Self::check_compression(&postscript, &codecs)?;
+        Self::check_limit(&limits, Limit::MetadataLen, postscript.get_footer_length())?;
+        Self::check_limit(
+            &limits,
+            Limit::MetadataLen,
+            postscript.get_metadata_length(),
+        )?;
+
+        let (footer, statistics, file) = Self::read_footer(
+            file,
+            &postscript.get_compression(),
+            postscript_len,
+            postscript.get_footer_length(),
+            &limits,
+            &codecs,
+        )?;
+        Self::check_limit(
+            &limits,
+            Limit::StripeCount,
+            footer.get_stripes().len() as u64,
+        )?;
+
+        let mut orc_file = Self::from_tail_parts(
+            file,
+            path.as_ref(),
+            file_len,
+            postscript,
+            footer,
+            statistics,
+            None,
+        )?;
+        orc_file.limits = limits;
+        orc_file.codecs = codecs;
+
+        Ok(orc_file)
+    }
 
-        if !SUPPORTED_COMPRESSION_KINDS.contains(&postscript.compression()) {
-            Err(compress::Error::UnsupportedCompression(postscript.compression()).into())
-        } else {
-            let (footer, file) = Self::read_footer(
-                file,
-                &postscript.compression(),
-                postscript_len,
-                postscript.footerLength(),
+    pub fn get_limits(&self) -> &ParseLimits {
+        &self.limits
+    }
+
+    pub fn get_codecs(&self) -> &CodecRegistry {
+        &self.codecs
+    }
+
+    fn check_limit(limits: &ParseLimits, limit: Limit, value: u64) -> Result<(), Error> {
+        limits
+            .check(limit, value)
+            .map_err(|max| Error::LimitExceeded { limit, value, max })
+    }
+
+    /// Open a file using a tail previously returned by `read_tail`, without reading or parsing
+    /// the end of the file.
+    ///
+    /// Fails if the file's length has changed since the tail was read. The limits and codecs are
+    /// used as in `open_with_codecs`.
+    pub fn open_with_tail<P: AsRef<Path>>(
+        path: P,
+        tail: &OrcTail,
+        limits: ParseLimits,
+        codecs: CodecRegistry,
+    ) -> Result<OrcFile, Error> {
+        let file = File::open(path.as_ref())?;
+        let file_len = file.metadata()?.len();
+
+        if file_len != tail.file_len {
+            return Err(Error::TailMismatch {
+                expected_len: tail.file_len,
+                actual_len: file_len,
+            });
+        }
+
+        let bytes = &tail.bytes;
+        let postscript_len = *bytes.last().ok_or(Error::InvalidMetadata)?;
+        let postscript_start = bytes
+            .len()
+            .checked_sub(POSTSCRIPT_LEN_LEN as usize + postscript_len as usize)
+            .ok_or(Error::InvalidMetadata)?;
+        let postscript_proto =
+            orc_proto::PostScript::parse_from_bytes(&bytes[postscript_start..bytes.len() - 1])?;
+        let postscript = PostScript::from_proto(&postscript_proto);
+        Self::check_compression(&postscript, &codecs)?;
+        Self::check_limit(&limits, Limit::MetadataLen, postscript.get_footer_length())?;
+        Self::check_limit(
+            &limits,
+            Limit::MetadataLen,
+            postscript.get_metadata_length(),
+        )?;
+
+        let footer_start = (postscript_start as u64)
+            .checked_sub(postscript.get_footer_length())
+            .ok_or(Error::InvalidMetadata)?;
+        let metadata_start = footer_start
+            .checked_sub(postscript.get_metadata_length())
+            .ok_or(Error::InvalidMetadata)?;
+
+        let compression = postscript.get_compression();
+        let (footer_proto, cursor) = Self::read_message_from_file::<_, orc_proto::Footer>(
+            std::io::Cursor::new(bytes.as_slice()),
+            &compression,
+            SeekFrom::Start(footer_start),
+            postscript.get_footer_length(),
+            &limits,
+            &codecs,
+        )?;
+
+        let stripe_statistics = if postscript.get_metadata_length() > 0 {
+            let (metadata, _) = Self::read_message_from_file::<_, orc_proto::Metadata>(
+                cursor,
+                &compression,
+                SeekFrom::Start(metadata_start),
+                postscript.get_metadata_length(),
+                &limits,
+                &codecs,
             )?;
+            metadata.stripeStats
+        } else {
+            vec![]
+        };
 
-            let type_kinds = Self::extract_column_type_kinds(&footer)?;
-            let field_names = footer
-                .types
-                .get(0)
-                .ok_or(Error::InvalidMetadata)?
-                .fieldNames
-                .to_vec();
+        let (footer, statistics) = Self::parse_footer(&footer_proto);
+        Self::check_limit(
+            &limits,
+            Limit::StripeCount,
+            footer.get_stripes().len() as u64,
+        )?;
 
-            let mut field_names_with_indices = field_names
-                .iter()
-                .enumerate()
-                .map(|(i, field_name)| (field_name.to_string(), i))
-                .collect::<Vec<_>>();
+        let mut orc_file = Self::from_tail_parts(
+            file,
+            path.as_ref(),
+            file_len,
+            postscript,
+            footer,
+            statistics,
+            Some(Arc::new(stripe_statistics)),
+        )?;
+        orc_file.limits = limits;
+        orc_file.codecs = codecs;
+
+        Ok(orc_file)
+    }
+
+    /// Read the serialized end of the file (including the metadata section), which can be cached
+    /// and passed to `open_with_tail`.
+    pub fn read_tail(&mut self) -> Result<OrcTail, Error> {
+        let mut file = self.take_file()?;
+        let postscript_result = Self::read_postscript_proto(&mut file, self.file_len);
+        self.file = Some(file);
+        let (postscript, postscript_len) = postscript_result?;
+
+        Self::check_limit(&self.limits, Limit::MetadataLen, postscript.footerLength())?;
+        Self::check_limit(&self.limits, Limit::MetadataLen, postscript.metadataLength())?;
+
+        let tail_len = (POSTSCRIPT_LEN_LEN + postscript_len as u64)
+            .checked_add(postscript.footerLength())
+            .and_then(|len| len.checked_add(postscript.metadataLength()))
+            .ok_or(Error::InvalidMetadata)?;
+        let tail_start = self
+            .file_len
+            .checked_sub(tail_len)
+            .ok_or(Error::InvalidMetadata)?;
+
+        let mut bytes = Vec::with_capacity(tail_len as usize);
+        self.copy_bytes(tail_start, tail_len, &mut bytes)?;
+
+        Ok(OrcTail {
+            file_len: self.file_len,
+            bytes,
+        })
+    }
+
+    fn check_compression(postscript: &PostScript, codecs: &CodecRegistry) -> Result<(), Error> {
+        if codecs.is_supported(postscript.get_compression()) {
+            Ok(())
+        } else {
+            Err(compress::Error::UnsupportedCompression(postscript.get_compression()).into())
+        }
+    }
+
+    fn from_tail_parts(
+        file: File,
+        path: &Path,
+        file_len: u64,
+        postscript: PostScript,
+        footer: Footer,
+        statistics: Vec<ColumnStatistics>,
+        mut stripe_statistics: Option<Arc<Vec<orc_proto::StripeStatistics>>>,
+    ) -> Result<OrcFile, Error> {
+        let schema = Self::extract_schema(&footer)?;
+
+        if footer.has_synthetic_root() {
+            if let Some(stripe_statistics) = &mut stripe_statistics {
+                Self::add_synthetic_root_statistics(
+                    Arc::make_mut(stripe_statistics).as_mut_slice(),
+                );
+            }
+        }
 
-            // A field name may be repeated, in which case the map points to the first instance.
-            field_names_with_indices.reverse();
+        let field_names = footer
+            .get_types()
+            .first()
+            .ok_or(Error::InvalidMetadata)?
+            .get_field_names()
+            .to_vec();
 
-            let field_name_map = field_names_with_indices.into_iter().collect();
+        let mut field_names_with_indices = field_names
+            .iter()
+            .enumerate()
+            .map(|(i, field_name)| (field_name.to_string(), i))
+            .collect::<Vec<_>>();
 
-            Ok(OrcFile {
-                file: Some(file),
-                file_len,
+        // A field name may be repeated, in which case the map points to the first instance.
+        field_names_with_indices.reverse();
+
+        let field_name_map = field_names_with_indices.into_iter().collect();
+
+        Ok(OrcFile {
+            file: Some(file),
+            context: DecompressionContext::new(),
+            stream_end: None,
+            stream_start: None,
+            metrics: ReadMetrics::default(),
+            column_metrics: ReadMetrics::default(),
+            metrics_callback: None,
+            strict: false,
+            utf8_policy: Utf8Policy::default(),
+            empty_strings_as_nulls: false,
+            trim_char_padding: false,
+            skip_unsupported_columns: false,
+            column_cache: None,
+            memory_pool: None,
+            limits: ParseLimits::default(),
+            codecs: CodecRegistry::default(),
+            stripe_statistics,
+            stripe_footers: vec![None; footer.get_stripes().len()],
+            path: path.to_path_buf(),
+            file_len,
+            metadata: Arc::new(FileMetadata {
                 postscript,
                 footer,
-                type_kinds,
+                statistics,
+                schema,
                 field_names,
                 field_name_map,
-            })
-        }
+            }),
+        })
     }
 
     pub fn get_field_names(&self) -> &[String] {
-        &self.field_names
+        &self.metadata.field_names
     }
 
-    pub fn map_rows<T, E: From<Error>, F>(
-        &mut self,
-        columns: &[usize],
-        f: F,
-    ) -> Result<MappedRows<'_, F>, Error>
-    where
-        F: FnMut(&[Value<'_>]) -> Result<T, E>,
-    {
-        let stripe_info = self.get_stripe_info()?;
-
-        Ok(MappedRows::new(self, stripe_info, columns.to_vec(), f))
+    /// Look up a top-level column by field name (if a name is repeated, the first is used).
+    pub fn get_field_index(&self, field_name: &str) -> Option<usize> {
+        self.metadata.field_name_map.get(field_name).copied()
     }
 
-    pub fn deserialize<T: serde::de::DeserializeOwned>(
-        &mut self,
-    ) -> Box<dyn Iterator<Item = Result<T, crate::de::Error>> + '_> {
-        let required_field_names = crate::de::get_field_names::<T>();
-        let mut missing_field_names = vec![];
-        let mut field_name_indices = Vec::with_capacity(required_field_names.len());
+    /// The type of each top-level field (in the same order as the field names).
+    pub fn get_field_types(&self) -> Vec<TypeKind> {
+        (0..self.metadata.field_names.len())
+            .filter_map(|field_index| self.get_field_type(field_index))
+            .collect()
+    }
 
-        for field_name in required_field_names {
-            match self.field_name_map.get(*field_name) {
-                Some(index) => {
-                    field_name_indices.push(*index);
-                }
-                None => {
-                    missing_field_names.push(field_name.to_string());
-                }
-            }
-        }
+    /// Look up a top-level field by name (if a name is repeated, the first is used).
+    pub fn get_field(&self, field_name: &str) -> Option<FieldInfo> {
+        let index = self.get_field_index(field_name)?;
+        let column_id = self.get_column_id(index)?;
+
+        Some(FieldInfo::new(
+            index,
+            column_id,
+            self.metadata.footer.get_types().get(column_id as usize)?,
+            self.metadata
+                .statistics
+                .get(column_id as usize)
+                .and_then(|statistics| statistics.has_null()),
+        ))
+    }
 
-        if missing_field_names.is_empty() {
-            match self.map_rows(&field_name_indices, |row| {
-                T::deserialize(&mut crate::de::RowDe::new(row))
-            }) {
-                Ok(iter) => Box::new(iter),
-                Err(error) => Box::new(std::iter::once_with(|| Err(error.into()))),
-            }
-        } else {
-            Box::new(std::iter::once_with(|| {
-                Err(crate::de::ErrorKind::InvalidFieldNames(missing_field_names).into())
-            }))
-        }
+    fn get_field_type(&self, field_index: usize) -> Option<TypeKind> {
+        self.get_column_id(field_index)
+            .and_then(|column_id| self.metadata.footer.get_types().get(column_id as usize))
+            .map(|column_type| column_type.get_kind())
     }
 
-    fn read_null_runs(
-        &mut self,
-        start: u64,
-        len: u64,
-        row_count: usize,
-    ) -> Result<Vec<u64>, Error> {
-        let pos = SeekFrom::Start(start);
-        let mut decompressor =
-            Decompressor::open(self.take_file()?, self.postscript.compression(), pos, len)?;
-        let present_info_writer = PresentInfoWriter::new(row_count);
-        let mut byte_writer = ByteWriter::new(present_info_writer);
-        std::io::copy(&mut decompressor, &mut byte_writer)?;
-        self.file = Some(decompressor.into_inner());
-        Ok(byte_writer.into_inner().into_inner())
+    /// Split the file into independent readers for each stripe.
+    ///
+    /// Each reader has its own file handle, so the readers can be sent to different threads.
+    pub fn into_stripe_readers(mut self) -> Result<Vec<StripeReader>, Error> {
+        self.get_stripe_info()?
+            .into_iter()
+            .enumerate()
+            .map(|(stripe_index, stripe_info)| {
+                Ok(StripeReader {
+                    file: self.try_clone()?,
+                    stripe_index,
+                    stripe_info,
+                })
+            })
+            .collect()
     }
 
-    fn read_u64s(
-        &mut self,
-        start: u64,
-        len: u64,
-        version: IntegerRleVersion,
-        signed: bool,
-    ) -> Result<Vec<u64>, Error> {
-        let pos = SeekFrom::Start(start);
-        let mut decompressor =
-            Decompressor::open(self.take_file()?, self.postscript.compression(), pos, len)?;
+    /// Open a new handle for the same file without re-reading the metadata.
+    ///
+    /// The new handle reopens the file by path (so that it has its own position), shares the parsed
+    /// footer, and copies the settings and any stripe metadata that has already been loaded. It
+    /// starts with empty metrics (and an empty column cache, if there is one), and uses the same
+    /// memory pool. Handles can be sent to other threads.
+    pub fn try_clone(&self) -> Result<OrcFile, Error> {
+        Ok(OrcFile {
+            file: Some(File::open(&self.path)?),
+            context: DecompressionContext::new(),
+            stream_end: None,
+            stream_start: None,
+            metrics: ReadMetrics::default(),
+            column_metrics: ReadMetrics::default(),
+            metrics_callback: self.metrics_callback.clone(),
+            strict: self.strict,
+            utf8_policy: self.utf8_policy,
+            empty_strings_as_nulls: self.empty_strings_as_nulls,
+            trim_char_padding: self.trim_char_padding,
+            skip_unsupported_columns: self.skip_unsupported_columns,
+            column_cache: self
+                .column_cache
+                .as_ref()
+                .map(|cache| ColumnCache::new(cache.get_max_size())),
+            memory_pool: self.memory_pool.clone(),
+            limits: self.limits,
+            codecs: self.codecs.clone(),
+            stripe_statistics: self.stripe_statistics.clone(),
+            stripe_footers: self.stripe_footers.clone(),
+            path: self.path.clone(),
+            file_len: self.file_len,
+            metadata: self.metadata.clone(),
+        })
+    }
 
-        let mut bytes = vec![];
-        decompressor.read_to_end(&mut bytes)?;
+    /// Enable or disable strict verification of decoded data against the file metadata.
+    ///
+    /// In strict mode, reading checks that stream lengths, value counts, dictionary sizes, and
+    /// stripe statistics agree with what is actually decoded, and fails with a
+    /// `VerificationFailed` error describing the first mismatch.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.clear_column_cache();
+    }
 
-        let values = if version == IntegerRleVersion::V1 {
-            crate::rle::intv1::decode_u64s(&bytes, None, signed)
-        } else {
-            crate::rle::intv2::decode_u64s(&bytes, None, signed)
-        }
-        .ok_or(Error::InvalidIntegerEncoding)?;
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
 
-        self.file = Some(decompressor.into_inner());
+    /// Set how string values that aren't valid UTF-8 are handled (by default they're an error).
+    pub fn set_utf8_policy(&mut self, utf8_policy: Utf8Policy) {
+        self.utf8_policy = utf8_policy;
+        self.clear_column_cache();
+    }
 
-        Ok(values)
+    pub fn get_utf8_policy(&self) -> Utf8Policy {
+        self.utf8_policy
     }
 
-    pub fn read_column(&mut self, stripe: &StripeInfo, column_id: usize) -> Result<Column, Error> {
-        if let Some(column_info) = stripe.columns.get(column_id) {
-            match column_info {
-                ColumnInfo::Bool {
-                    offset,
-                    present_len,
-                    data_len,
-                } => {
-                    let null_runs = match present_len {
-                        Some(len) => Some(self.read_null_runs(
-                            stripe.data_start + offset,
-                            *len,
-                            stripe.row_count,
-                        )?),
-
-                        None => None,
-                    };
-
-                    let present_info = PresentInfo::new(null_runs);
-
-                    let data_pos =
-                        SeekFrom::Start(stripe.data_start + offset + present_len.unwrap_or(0));
-                    let mut decompressor = Decompressor::open(
-                        self.take_file()?,
-                        self.postscript.compression(),
-                        data_pos,
-                        *data_len,
-                    )?;
+    /// Read empty string values as nulls (for files written by tools that use empty strings
+    /// instead of nulls).
+    pub fn set_empty_strings_as_nulls(&mut self, empty_strings_as_nulls: bool) {
+        self.empty_strings_as_nulls = empty_strings_as_nulls;
+        self.clear_column_cache();
+    }
 
-                    let bool_writer = BoolWriter::new(stripe.row_count, present_info);
-                    let mut byte_writer = ByteWriter::new(bool_writer);
-                    std::io::copy(&mut decompressor, &mut byte_writer)?;
-                    self.file = Some(decompressor.into_inner());
-                    Ok(byte_writer.into_inner().finish())
-                }
-                ColumnInfo::U64 {
-                    offset,
-                    present_len,
-                    data_len,
-                    version,
-                } => {
-                    let null_runs = match present_len {
-                        Some(len) => Some(self.read_null_runs(
-                            stripe.data_start + offset,
-                            *len,
-                            stripe.row_count,
-                        )?),
-                        None => None,
-                    };
-
-                    let values = self.read_u64s(
-                        stripe.data_start + offset + present_len.unwrap_or(0),
-                        *data_len,
-                        *version,
-                        true,
-                    )?;
+    pub fn get_empty_strings_as_nulls(&self) -> bool {
+        self.empty_strings_as_nulls
+    }
 
-                    Ok(Column::make_u64_column(
-                        values,
-                        &null_runs.unwrap_or_default(),
-                    ))
-                }
-                ColumnInfo::Utf8Dictionary {
-                    offset,
-                    present_len,
-                    data_len,
-                    dictionary_data_len,
-                    length_len,
-                    version,
-                    dictionary_size,
-                } => {
-                    let null_runs = match present_len {
-                        Some(len) => Some(self.read_null_runs(
-                            stripe.data_start + offset,
-                            *len,
-                            stripe.row_count,
-                        )?),
-                        None => None,
-                    };
-
-                    let data = self.read_u64s(
-                        stripe.data_start + offset + present_len.unwrap_or(0),
-                        *data_len,
-                        *version,
-                        false,
-                    )?;
+    /// Strip the trailing spaces that pad `CHAR(n)` values to their full length (by default the
+    /// padding is preserved).
+    pub fn set_trim_char_padding(&mut self, trim_char_padding: bool) {
+        self.trim_char_padding = trim_char_padding;
+        self.clear_column_cache();
+    }
 
-                    let lengths = self.read_u64s(
-                        stripe.data_start + offset + present_len.unwrap_or(0) + data_len,
-                        *length_len,
-                        *version,
-                        false,
-                    )?;
+    pub fn get_trim_char_padding(&self) -> bool {
+        self.trim_char_padding
+    }
 
-                    let pos = SeekFrom::Start(
-                        stripe.data_start
-                            + offset
-                            + present_len.unwrap_or(0)
-                            + data_len
-                            + length_len,
-                    );
-                    let mut decompressor = Decompressor::open(
-                        self.take_file()?,
-                        self.postscript.compression(),
-                        pos,
-                        *dictionary_data_len,
-                    )?;
+    /// Read columns of unsupported types as nulls instead of failing (by default reading them is
+    /// an error), so that every field can be included in `map_rows` or `deserialize`.
+    ///
+    /// See `get_unsupported_field_indices` for the fields that are affected.
+    pub fn set_skip_unsupported_columns(&mut self, skip_unsupported_columns: bool) {
+        self.skip_unsupported_columns = skip_unsupported_columns;
+        self.clear_column_cache();
+    }
 
-                    let mut dictionary_bytes = vec![];
-                    decompressor.read_to_end(&mut dictionary_bytes)?;
+    pub fn get_skip_unsupported_columns(&self) -> bool {
+        self.skip_unsupported_columns
+    }
 
-                    self.file = Some(decompressor.into_inner());
+    /// Keep decoded columns in memory, so that reading a column again returns a copy instead of
+    /// decoding it again (`None`, the default, disables caching).
+    ///
+    /// The cache is cleared when the strict mode, UTF-8 policy, or handling of empty strings,
+    /// `CHAR` padding, or unsupported columns is changed.
+    pub fn set_column_cache(&mut self, column_cache: Option<ColumnCache>) {
+        self.column_cache = column_cache;
+    }
 
-                    if *dictionary_size != lengths.len() as u32 {
-                        Err(Error::InvalidDictionarySize {
-                            expected: *dictionary_size,
-                            actual: lengths.len() as u32,
-                        })
-                    } else {
-                        Ok(Column::make_utf8_dictionary_column(
-                            null_runs,
-                            data,
-                            dictionary_bytes,
-                            lengths,
-                        ))
-                    }
-                }
-                ColumnInfo::Utf8Direct {
-                    offset,
-                    present_len,
-                    data_len,
-                    length_len,
-                    version,
-                } => {
-                    let null_runs = match present_len {
-                        Some(len) => Some(self.read_null_runs(
-                            stripe.data_start + offset,
-                            *len,
-                            stripe.row_count,
-                        )?),
-                        None => None,
-                    };
-
-                    let pos =
-                        SeekFrom::Start(stripe.data_start + offset + present_len.unwrap_or(0));
-                    let mut decompressor = Decompressor::open(
-                        self.take_file()?,
-                        self.postscript.compression(),
-                        pos,
-                        *data_len,
-                    )?;
+    pub fn get_column_cache(&self) -> Option<&ColumnCache> {
+        self.column_cache.as_ref()
+    }
 
-                    let mut data_bytes = vec![];
-                    decompressor.read_to_end(&mut data_bytes)?;
+    /// Reserve the decoded columns of each stripe in a row scan in a shared memory pool (`None`,
+    /// the default, means no limit).
+    ///
+    /// See [`crate::memory`] for what is counted.
+    pub fn set_memory_pool(&mut self, memory_pool: Option<MemoryPool>) {
+        self.memory_pool = memory_pool;
+    }
 
-                    self.file = Some(decompressor.into_inner());
+    pub fn get_memory_pool(&self) -> Option<&MemoryPool> {
+        self.memory_pool.as_ref()
+    }
 
-                    let lengths = self.read_u64s(
-                        stripe.data_start + offset + present_len.unwrap_or(0) + data_len,
-                        *length_len,
-                        *version,
-                        false,
-                    )?;
+    // Reserve memory for a stripe's decoded columns, if there's a pool.
+    fn reserve_memory(&self, columns: &[Column]) -> Result<Option<MemoryReservation>, Error> {
+        self.memory_pool
+            .as_ref()
+            .map(|memory_pool| memory_pool.reserve(columns.iter().map(Column::get_data_size).sum()))
+            .transpose()
+    }
 
-                    Ok(Column::make_utf8_direct_column(
-                        null_runs, data_bytes, lengths,
-                    ))
-                }
-            }
-        } else {
-            Err(Error::InvalidColumnIndex(column_id))
+    fn clear_column_cache(&mut self) {
+        if let Some(column_cache) = &mut self.column_cache {
+            column_cache.clear();
         }
     }
 
-    fn read_message<M: Message>(&mut self, pos: SeekFrom, len: u64) -> Result<M, Error> {
-        let file = self.take_file()?;
-        let (message, file) =
-            Self::read_message_from_file(file, &self.postscript.compression(), pos, len)?;
-        self.file = Some(file);
-        Ok(message)
+    /// The metrics for all column data read through this handle.
+    ///
+    /// Handles created by `into_stripe_readers` start with empty metrics.
+    pub fn get_metrics(&self) -> &ReadMetrics {
+        &self.metrics
     }
 
-    fn take_file(&mut self) -> Result<File, Error> {
-        self.file.take().ok_or(Error::InvalidState)
+    pub fn reset_metrics(&mut self) {
+        self.metrics = ReadMetrics::default();
     }
 
-    fn read_message_from_file<M: Message>(
-        file: File,
-        compression: &CompressionKind,
-        pos: SeekFrom,
-        len: u64,
-    ) -> Result<(M, File), Error> {
-        let mut decompressor = Decompressor::open(file, *compression, pos, len)?;
-        let message = Message::parse_from_reader(&mut decompressor)?;
-        let file = decompressor.into_inner();
-
-        Ok((message, file))
+    /// Set a function that is called with the metrics for each column chunk after it is read.
+    ///
+    /// The callback is shared with any handles created by `into_stripe_readers`, and may be called
+    /// from several threads.
+    pub fn set_metrics_callback<F: Fn(&ReadMetrics) + Send + Sync + 'static>(&mut self, f: F) {
+        self.metrics_callback = Some(Arc::new(f));
     }
 
-    fn read_postscript(file: &mut File, file_len: u64) -> Result<(PostScript, u8), Error> {
-        let bytes_to_read = std::cmp::min(POSTSCRIPT_BUFFER_LEN, file_len as usize) as usize;
+    fn record_column_metrics(&mut self, value_count: usize, start: Instant) {
+        let mut column_metrics = std::mem::take(&mut self.column_metrics);
+        column_metrics.record_column(value_count as u64, start.elapsed());
+        self.metrics += &column_metrics;
 
-        let mut buffer = Vec::with_capacity(bytes_to_read);
-        file.seek(SeekFrom::End(-(bytes_to_read as i64)))?;
-        file.read_to_end(&mut buffer)?;
+        if let Some(callback) = &self.metrics_callback {
+            callback(&column_metrics);
+        }
+    }
 
-        if bytes_to_read == 0 {
-            Err(Error::InvalidMetadata)
+    fn verify<F: FnOnce() -> Mismatch>(&self, valid: bool, mismatch: F) -> Result<(), Error> {
+        if self.strict && !valid {
+            Err(Error::VerificationFailed {
+                stripe_index: None,
+                column_index: None,
+                mismatch: mismatch(),
+            })
         } else {
-            let postscript_len = buffer[bytes_to_read - 1];
-            let postscript_start = bytes_to_read - 1 - postscript_len as usize;
-            let postscript_bytes = &buffer[postscript_start..bytes_to_read - 1];
-
-            Ok((
-                PostScript::parse_from_bytes(postscript_bytes)?,
-                postscript_len,
-            ))
+            Ok(())
         }
     }
 
-    fn read_footer(
-        file: File,
-        compression: &CompressionKind,
-        postscript_len: u8,
-        footer_len: u64,
-    ) -> Result<(Footer, File), Error> {
-        let footer_offset = (postscript_len as u64 + footer_len + POSTSCRIPT_LEN_LEN) as i64;
+    fn verify_value_count(
+        &self,
+        stream: &'static str,
+        expected: usize,
+        actual: usize,
+    ) -> Result<(), Error> {
+        self.verify(expected == actual, || Mismatch::ValueCount {
+            stream,
+            expected: expected as u64,
+            actual: actual as u64,
+        })
+    }
+
+    /// The statistics for a column in a stripe, if present (the metadata is loaded on first use).
+    fn get_column_statistics(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: usize,
+    ) -> Result<Option<&orc_proto::ColumnStatistics>, Error> {
+        if self.stripe_statistics.is_none() {
+            let (_, _, metadata) = self.read_tail_messages()?;
+            let mut stripe_statistics = metadata
+                .map(|metadata| metadata.stripeStats)
+                .unwrap_or_default();
+
+            if self.metadata.footer.has_synthetic_root() {
+                Self::add_synthetic_root_statistics(&mut stripe_statistics);
+            }
+
+            self.stripe_statistics = Some(Arc::new(stripe_statistics));
+        }
+
+        // Column statistics include the root struct.
+        Ok(self
+            .stripe_statistics
+            .as_ref()
+            .and_then(|stripe_statistics| stripe_statistics.get(stripe.index))
+            .and_then(|statistics| statistics.colStats.get(column_id + 1)))
+    }
+
+    fn verify_statistics(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: usize,
+        column: &Column,
+    ) -> Result<(), Error> {
+        if let Some(statistics) = self.get_column_statistics(stripe, column_id)? {
+            crate::verify::check_statistics(column, stripe.row_count, statistics).map_err(
+                |mismatch| Error::VerificationFailed {
+                    stripe_index: None,
+                    column_index: None,
+                    mismatch,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Indicates whether this file uses the Hive ACID event layout.
+    pub fn is_acid(&self) -> bool {
+        crate::acid::is_acid_schema(&self.metadata.footer)
+    }
+
+    pub fn map_rows<T, E: From<Error>, F>(
+        &mut self,
+        columns: &[usize],
+        f: F,
+    ) -> Result<MappedRows<'_, F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        self.map_rows_in_stripes(0..self.stripe_footers.len(), columns, f)
+    }
+
+    /// Map over the rows in a range of stripes.
+    ///
+    /// Stripe footers are only read when the rows of that stripe are reached, so stripes outside
+    /// the range are never touched.
+    pub fn map_rows_in_stripes<T, E: From<Error>, F>(
+        &mut self,
+        stripes: Range<usize>,
+        columns: &[usize],
+        f: F,
+    ) -> Result<MappedRows<'_, F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        self.verify_row_count()?;
+
+        if stripes.end > self.stripe_footers.len() {
+            Err(Error::InvalidMetadata)
+        } else {
+            Ok(MappedRows {
+                cursor: RowCursor::new(self, stripes, columns.to_vec()),
+                f,
+            })
+        }
+    }
+
+    /// The byte ranges that reading the given columns in a range of stripes will read, in file
+    /// order.
+    ///
+    /// There's one range for each column in each stripe (columns with no data are skipped), and
+    /// adjacent ranges aren't merged. Planning reads the stripe footers (which aren't included in
+    /// the ranges), and fails for any column that couldn't be read. The column cache is ignored.
+    pub fn plan_ranges(
+        &mut self,
+        stripes: Range<usize>,
+        columns: &[usize],
+    ) -> Result<Vec<ByteRange>, Error> {
+        if stripes.end > self.stripe_footers.len() {
+            return Err(Error::InvalidMetadata);
+        }
+
+        let mut ranges = vec![];
+
+        for stripe_index in stripes {
+            let stripe = self.read_stripe_info(stripe_index)?;
+
+            for column_index in columns {
+                let (offset, len) = self.column_info(&stripe, *column_index)?.span();
+
+                if len > 0 {
+                    ranges.push(ByteRange {
+                        stripe_index,
+                        column_index: *column_index,
+                        start: stripe.data_start + offset,
+                        len,
+                    });
+                }
+            }
+        }
+
+        ranges.sort_by_key(|range| range.start);
+
+        Ok(ranges)
+    }
+
+    /// Map over rows with the function running in parallel (using Rayon).
+    ///
+    /// Stripes are read one at a time, and the function is applied to batches of each stripe's rows
+    /// in parallel. The results are returned in row order, with no more than one stripe's results
+    /// held in memory.
+    #[cfg(feature = "rayon")]
+    pub fn par_map_rows<T, E, F>(
+        &mut self,
+        columns: &[usize],
+        f: F,
+    ) -> Result<ParMappedRows<'_, T, E, F>, Error>
+    where
+        T: Send,
+        E: From<Error> + Send,
+        F: Fn(&[Value<'_>]) -> Result<T, E> + Sync,
+    {
+        self.verify_row_count()?;
+
+        Ok(ParMappedRows {
+            stripes: 0..self.stripe_footers.len(),
+            file: self,
+            columns: columns.to_vec(),
+            f,
+            results: vec![].into_iter(),
+        })
+    }
 
-        Self::read_message_from_file(file, compression, SeekFrom::End(-footer_offset), footer_len)
+    /// Iterate over the rows of the given columns without a closure.
+    ///
+    /// Rows are read with `while let Some(row) = cursor.next_row()? { ... }`.
+    pub fn row_cursor(&mut self, columns: &[usize]) -> Result<RowCursor<'_>, Error> {
+        self.verify_row_count()?;
+
+        Ok(RowCursor::new(
+            self,
+            0..self.stripe_footers.len(),
+            columns.to_vec(),
+        ))
     }
 
-    fn extract_column_type_kinds(footer: &Footer) -> Result<Vec<TypeKind>, Error> {
-        // We currently only support structs with scalar fields (and only a few types).
-        footer
-            .types
+    /// Map over rows, selecting columns by ORC column id (see `read_column_by_id`).
+    pub fn map_rows_by_id<T, E: From<Error>, F>(
+        &mut self,
+        column_ids: &[u32],
+        f: F,
+    ) -> Result<MappedRows<'_, F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        let columns = column_ids
             .iter()
-            .skip(1)
-            .map(|type_value| {
-                let kind = type_value.kind();
-                if kind == TypeKind::LONG
-                    || kind == TypeKind::INT
-                    || kind == TypeKind::STRING
-                    || kind == TypeKind::BOOLEAN
-                {
-                    Ok(kind)
+            .map(|column_id| Self::column_id_to_index(*column_id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.map_rows(&columns, f)
+    }
+
+    /// Map over the rows that match a predicate, decoding output columns only where needed.
+    ///
+    /// For each stripe, the filter columns are decoded and the predicate is evaluated for every
+    /// row. The output columns are only decoded for stripes with at least one matching row (and
+    /// columns that are also filter columns aren't decoded twice), so selective filters over wide
+    /// rows can skip most of the decoding work.
+    pub fn filter_map_rows<T, E: From<Error>, P, F>(
+        &mut self,
+        filter_columns: &[usize],
+        predicate: P,
+        output_columns: &[usize],
+        f: F,
+    ) -> Result<FilteredRows<'_, P, F>, Error>
+    where
+        P: FnMut(&[Value<'_>]) -> bool,
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        self.verify_row_count()?;
+
+        Ok(FilteredRows {
+            stripes: 0..self.stripe_footers.len(),
+            file: self,
+            filter_columns: filter_columns.to_vec(),
+            output_columns: output_columns.to_vec(),
+            predicate,
+            f,
+            data: vec![],
+            output_indices: vec![],
+            selection: Bitmap::new(),
+            stripe_index: 0,
+            current_row: 0,
+            memory: None,
+        })
+    }
+
+    pub fn deserialize<T: serde::de::DeserializeOwned>(
+        &mut self,
+    ) -> Box<dyn Iterator<Item = Result<T, crate::de::Error>> + '_> {
+        self.deserialize_with(&FieldNameMatching::new())
+    }
+
+    /// Deserialize rows, resolving struct field names to ORC field names with the given options.
+    ///
+    /// Struct field names (or serde renames) can also be dotted paths to nested fields.
+    pub fn deserialize_with<T: serde::de::DeserializeOwned>(
+        &mut self,
+        matching: &FieldNameMatching,
+    ) -> Box<dyn Iterator<Item = Result<T, crate::de::Error>> + '_> {
+        self.deserialize_with_converters(matching, &ValueConverters::new())
+    }
+
+    /// Deserialize rows, converting the values of some fields before they're deserialized.
+    pub fn deserialize_with_converters<T: serde::de::DeserializeOwned>(
+        &mut self,
+        matching: &FieldNameMatching,
+        converters: &ValueConverters,
+    ) -> Box<dyn Iterator<Item = Result<T, crate::de::Error>> + '_> {
+        let required_field_names = crate::de::get_field_names::<T>();
+        let mut missing_field_names = vec![];
+        let mut column_ids = Vec::with_capacity(required_field_names.len());
+
+        for field_name in required_field_names {
+            // Names that don't match a top-level field may be dotted paths to nested fields.
+            let column_id = match self.metadata.field_name_map.get(*field_name) {
+                Some(index) => self.get_column_id(*index),
+                None => matching
+                    .resolve(field_name, &self.metadata.field_names)
+                    .and_then(|index| self.get_column_id(index))
+                    .or_else(|| self.metadata.footer.resolve_path(field_name)),
+            };
+
+            match column_id {
+                Some(column_id) => {
+                    column_ids.push(column_id);
+                }
+                None => {
+                    missing_field_names.push(field_name.to_string());
+                }
+            }
+        }
+
+        let row_converters = converters.select(required_field_names);
+        let has_converters = row_converters.iter().any(Option::is_some);
+
+        if missing_field_names.is_empty() {
+            match self.map_rows_by_id(&column_ids, move |row| {
+                if !has_converters {
+                    return T::deserialize(&mut crate::de::RowDe::new(row));
+                }
+
+                let converted = crate::de::convert_row(&row_converters, row)?;
+                let values = row
+                    .iter()
+                    .zip(&converted)
+                    .map(|(value, converted)| {
+                        converted.as_ref().map_or(*value, OwnedValue::as_value)
+                    })
+                    .collect::<Vec<_>>();
+
+                T::deserialize(&mut crate::de::RowDe::new(&values))
+            }) {
+                Ok(iter) => Box::new(iter),
+                Err(error) => Box::new(std::iter::once_with(|| Err(error.into()))),
+            }
+        } else {
+            Box::new(std::iter::once_with(|| {
+                Err(crate::de::ErrorKind::InvalidFieldNames(missing_field_names).into())
+            }))
+        }
+    }
+
+    fn read_null_runs(
+        &mut self,
+        start: u64,
+        len: u64,
+        row_count: usize,
+    ) -> Result<Vec<u64>, Error> {
+        let pos = SeekFrom::Start(start);
+        let mut decompressor = self.open_stream(pos, len)?;
+        let present_info_writer = PresentInfoWriter::new(row_count);
+        let mut byte_writer = ByteWriter::new(present_info_writer);
+        std::io::copy(&mut decompressor, &mut byte_writer)?;
+        self.close_stream(decompressor)?;
+
+        let present_info_writer = byte_writer.into_inner();
+        // The last byte may be padded.
+        let bit_count = present_info_writer.get_bit_count() as usize;
+        self.verify(bit_count >= row_count && bit_count < row_count + 8, || {
+            Mismatch::ValueCount {
+                stream: "PRESENT",
+                expected: row_count as u64,
+                actual: bit_count as u64,
+            }
+        })?;
+
+        Ok(present_info_writer.into_inner())
+    }
+
+    /// Read the null runs from a column's present stream (if it has one).
+    ///
+    /// If the stripe statistics say that the column has no nulls, the stream is skipped (except in
+    /// strict mode, where we want to check this claim).
+    fn read_present(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: usize,
+        offset: u64,
+        present_len: Option<u64>,
+    ) -> Result<Option<Vec<u64>>, Error> {
+        match present_len {
+            Some(len) => {
+                let has_null = self.strict
+                    || self
+                        .get_column_statistics(stripe, column_id)?
+                        .and_then(|statistics| statistics.hasNull)
+                        .unwrap_or(true);
+
+                if has_null {
+                    Ok(Some(self.read_null_runs(
+                        stripe.data_start + offset,
+                        len,
+                        stripe.row_count,
+                    )?))
                 } else {
-                    Err(Error::UnsupportedType(kind))
+                    Ok(None)
                 }
-            })
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn read_u64s(
+        &mut self,
+        start: u64,
+        len: u64,
+        version: IntegerRleVersion,
+        signed: bool,
+    ) -> Result<Vec<u64>, Error> {
+        let pos = SeekFrom::Start(start);
+        let mut decompressor = self.open_stream(pos, len)?;
+
+        let mut bytes = vec![];
+        decompressor.read_to_end(&mut bytes)?;
+        self.close_stream(decompressor)?;
+
+        let values = if version == IntegerRleVersion::V1 {
+            crate::rle::intv1::decode_u64s(&bytes, None, signed)
+        } else {
+            crate::rle::intv2::decode_u64s(&bytes, None, signed)
+        }?;
+
+        Ok(values)
+    }
+
+    // Read a signed integer stream (the signed decoders return the two's complement bit patterns).
+    fn read_i64s(
+        &mut self,
+        start: u64,
+        len: u64,
+        version: IntegerRleVersion,
+    ) -> Result<Vec<i64>, Error> {
+        Ok(self
+            .read_u64s(start, len, version, true)?
+            .into_iter()
+            .map(|value| value as i64)
+            .collect())
+    }
+
+    /// Read a column by ORC column id.
+    ///
+    /// Column ids number the types in the footer in pre-order, so the root struct is column 0, and
+    /// the leaves of nested types can be addressed directly. Note that the indices used by
+    /// `read_column` and `map_rows` are the column id minus one, which only coincide with field
+    /// indices when no field before them is nested.
+    pub fn read_column_by_id(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: u32,
+    ) -> Result<Column, Error> {
+        self.read_column(stripe, Self::column_id_to_index(column_id)?)
+    }
+
+    /// The ORC column id for a top-level field.
+    pub fn get_column_id(&self, field_index: usize) -> Option<u32> {
+        self.metadata
+            .footer
+            .get_types()
+            .first()
+            .and_then(|root| root.get_subtypes().get(field_index))
+            .copied()
+    }
+
+    /// The column index (as used by `read_column` and `map_rows`) of each top-level field.
+    ///
+    /// These are the field indices unless a nested field comes before another field, since the
+    /// columns of nested types are numbered before the following field.
+    pub fn get_field_column_indices(&self) -> Vec<usize> {
+        (0..self.metadata.field_names.len())
+            .filter_map(|field_index| self.get_column_id(field_index))
+            .filter_map(|column_id| Self::column_id_to_index(column_id).ok())
             .collect()
     }
 
-    pub fn get_postscript(&self) -> &PostScript {
-        &self.postscript
+    /// The ORC column id for a top-level field name or a dotted path to a nested field (such as
+    /// `row.location`).
+    ///
+    /// A top-level field whose name contains a dot takes precedence over a nested path.
+    pub fn get_column_id_by_path(&self, path: &str) -> Option<u32> {
+        match self.get_field_index(path) {
+            Some(field_index) => self.get_column_id(field_index),
+            None => self.metadata.footer.resolve_path(path),
+        }
+    }
+
+    /// Map over rows given top-level field names or dotted paths to nested fields.
+    ///
+    /// Nested fields are flattened into the row, and a path to a struct is expanded to all of its
+    /// leaf fields (in schema order), so only leaf columns are decoded.
+    pub fn map_rows_by_path<T, E: From<Error>, F>(
+        &mut self,
+        paths: &[&str],
+        f: F,
+    ) -> Result<MappedRows<'_, F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        let mut column_ids = vec![];
+
+        for path in paths {
+            let column_id = self
+                .get_column_id_by_path(path)
+                .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+
+            column_ids.extend(self.metadata.footer.get_leaf_column_ids(column_id));
+        }
+
+        self.map_rows_by_id(&column_ids, f)
+    }
+
+    /// Map over rows projected onto a target schema (for reading files written with different
+    /// versions of a schema as a single dataset).
+    ///
+    /// Fields are matched by name, and each row has the values of the target schema's fields in
+    /// order. Fields that aren't in the file are read as nulls, fields that are only in the file
+    /// are skipped, and fields with narrower types (such as `INT` for `BIGINT`, or `CHAR` for
+    /// `STRING`) are read as the wider type. Fails if a field's type in the file can't be widened
+    /// to the target type (see `Schema::check_compatible`).
+    pub fn map_rows_with_schema<T, E: From<Error>, F>(
+        &mut self,
+        schema: &Schema,
+        f: F,
+    ) -> Result<ProjectedRows<'_, F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        let file_schema =
+            Schema::from_footer(&self.metadata.footer).ok_or(Error::InvalidMetadata)?;
+        let conflicts = file_schema
+            .check_compatible(schema)
+            .into_iter()
+            .filter(Incompatibility::is_breaking)
+            .collect::<Vec<_>>();
+
+        if !conflicts.is_empty() {
+            return Err(Error::IncompatibleSchema(conflicts));
+        }
+
+        let mut column_ids = vec![];
+        // The position of each target field in the columns that are read, if it's in the file.
+        let positions = schema
+            .get_fields()
+            .iter()
+            .map(|(field_name, _)| {
+                self.get_field_index(field_name)
+                    .and_then(|field_index| self.get_column_id(field_index))
+                    .map(|column_id| {
+                        column_ids.push(column_id);
+                        column_ids.len() - 1
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let MappedRows { cursor, f } = self.map_rows_by_id(&column_ids, f)?;
+
+        Ok(ProjectedRows {
+            cursor,
+            positions,
+            f,
+        })
+    }
+
+    // The root struct (column id 0) can't be read as a column.
+    fn column_id_to_index(column_id: u32) -> Result<usize, Error> {
+        (column_id as usize)
+            .checked_sub(1)
+            .ok_or(Error::InvalidColumnIndex(column_id as usize))
+    }
+
+    pub fn read_column(&mut self, stripe: &StripeInfo, column_id: usize) -> Result<Column, Error> {
+        if let Some(column) = self
+            .column_cache
+            .as_mut()
+            .and_then(|column_cache| column_cache.get(stripe.index, column_id))
+        {
+            return Ok(column);
+        }
+
+        let start = Instant::now();
+        let mut result = self.read_column_data(stripe, column_id);
+        self.record_column_metrics(stripe.row_count, start);
+
+        if self.skip_unsupported_columns && matches!(result, Err(Error::UnsupportedType(_))) {
+            result = Ok(Column::make_null_column(stripe.row_count));
+        } else if self.strict {
+            if let Ok(column) = &result {
+                if let Err(error) = self.verify_statistics(stripe, column_id, column) {
+                    result = Err(error);
+                }
+            }
+        }
+
+        // These happen after verification, since the statistics include the padding and count the
+        // empty strings as values.
+        if self.trim_char_padding && self.get_column_kind(column_id) == Some(TypeKind::Char) {
+            if let Ok(column) = &mut result {
+                column.trim_trailing_spaces();
+            }
+        }
+
+        if self.empty_strings_as_nulls {
+            if let Ok(column) = &mut result {
+                column.replace_empty_strings_with_nulls();
+            }
+        }
+
+        if let (Some(column_cache), Ok(column)) = (&mut self.column_cache, &result) {
+            column_cache.insert(stripe.index, column_id, column);
+        }
+
+        // Add the coordinates to any verification failure.
+        result.map_err(|error| match error {
+            Error::VerificationFailed { mismatch, .. } => Error::VerificationFailed {
+                stripe_index: Some(stripe.index),
+                column_index: Some(column_id),
+                mismatch,
+            },
+            other => other,
+        })
+    }
+
+    fn check_string_lengths(&self, lengths: &[u64]) -> Result<(), Error> {
+        match lengths.iter().max() {
+            Some(max_len) => Self::check_limit(&self.limits, Limit::StringLen, *max_len),
+            None => Ok(()),
+        }
+    }
+
+    // The sum of the values of a LENGTH stream, failing at the first length that overflows it.
+    fn total_length(stripe: &StripeInfo, column_id: usize, lengths: &[u64]) -> Result<u64, Error> {
+        lengths
+            .iter()
+            .enumerate()
+            .try_fold(0u64, |total, (row_index, len)| {
+                total.checked_add(*len).ok_or(Error::InvalidValue {
+                    stripe_index: stripe.index,
+                    column_index: column_id,
+                    row_index,
+                })
+            })
+    }
+
+    // The number of non-null values, given the null runs from the present stream.
+    fn present_count(null_runs: &Option<Vec<u64>>, row_count: usize) -> usize {
+        match null_runs {
+            Some(null_runs) => null_runs.len().saturating_sub(1),
+            None => row_count,
+        }
+    }
+
+    fn read_column_data(&mut self, stripe: &StripeInfo, column_id: usize) -> Result<Column, Error> {
+        let column_info = self.column_info(stripe, column_id)?;
+
+        match &column_info {
+            ColumnInfo::Bool {
+                offset,
+                present_len,
+                data_len,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let value_count = Self::present_count(&null_runs, stripe.row_count);
+                let present_info = PresentInfo::new(null_runs);
+
+                let data_pos =
+                    SeekFrom::Start(stripe.data_start + offset + present_len.unwrap_or(0));
+                let mut decompressor = self.open_stream(data_pos, *data_len)?;
+
+                let bool_writer = BoolWriter::new(stripe.row_count, present_info);
+                let mut byte_writer = ByteWriter::new(bool_writer);
+                std::io::copy(&mut decompressor, &mut byte_writer)?;
+                self.close_stream(decompressor)?;
+
+                let bool_writer = byte_writer.into_inner();
+                // The last byte may be padded.
+                let bit_count = bool_writer.get_value_count();
+                self.verify(
+                    bit_count >= value_count && bit_count < value_count + 8,
+                    || Mismatch::ValueCount {
+                        stream: "DATA",
+                        expected: value_count as u64,
+                        actual: bit_count as u64,
+                    },
+                )?;
+
+                Ok(bool_writer.finish())
+            }
+            ColumnInfo::Integer {
+                offset,
+                present_len,
+                data_len,
+                version,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let values = self.read_i64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0),
+                    *data_len,
+                    *version,
+                )?;
+
+                self.verify_value_count(
+                    "DATA",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    values.len(),
+                )?;
+
+                Ok(Column::make_i64_column(
+                    values,
+                    &null_runs.unwrap_or_default(),
+                ))
+            }
+            ColumnInfo::Byte {
+                offset,
+                present_len,
+                data_len,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let pos = SeekFrom::Start(stripe.data_start + offset + present_len.unwrap_or(0));
+                let mut decompressor = self.open_stream(pos, *data_len)?;
+
+                let mut bytes = vec![];
+                decompressor.read_to_end(&mut bytes)?;
+                self.close_stream(decompressor)?;
+
+                let mut values = Vec::with_capacity(bytes.len());
+
+                for value in ByteDecoder::new(&bytes) {
+                    match value {
+                        // Bytes are signed.
+                        Ok(value) => values.push(value as i8 as i64),
+                        Err(error) => {
+                            let values = values.into_iter().map(|value| value as u64).collect();
+                            return Err(crate::rle::DecodeError { error, values }.into());
+                        }
+                    }
+                }
+
+                self.verify_value_count(
+                    "DATA",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    values.len(),
+                )?;
+
+                Ok(Column::make_i64_column(
+                    values,
+                    &null_runs.unwrap_or_default(),
+                ))
+            }
+            ColumnInfo::Utf8Dictionary {
+                offset,
+                present_len,
+                data_len,
+                dictionary_data_len,
+                length_len,
+                version,
+                dictionary_size,
+            } => {
+                Self::check_limit(
+                    &self.limits,
+                    Limit::DictionaryEntries,
+                    *dictionary_size as u64,
+                )?;
+
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let data = self.read_u64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0),
+                    *data_len,
+                    *version,
+                    false,
+                )?;
+
+                let lengths = self.read_u64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0) + data_len,
+                    *length_len,
+                    *version,
+                    false,
+                )?;
+                self.check_string_lengths(&lengths)?;
+
+                let pos = SeekFrom::Start(
+                    stripe.data_start + offset + present_len.unwrap_or(0) + data_len + length_len,
+                );
+                let mut decompressor = self.open_stream(pos, *dictionary_data_len)?;
+
+                let mut dictionary_bytes = vec![];
+                decompressor.read_to_end(&mut dictionary_bytes)?;
+
+                self.close_stream(decompressor)?;
+
+                self.verify_value_count(
+                    "DATA",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    data.len(),
+                )?;
+                if self.strict {
+                    self.verify_value_count(
+                        "DICTIONARY_DATA",
+                        Self::total_length(stripe, column_id, &lengths)? as usize,
+                        dictionary_bytes.len(),
+                    )?;
+                }
+                if let Some((row_index, index)) = data
+                    .iter()
+                    .enumerate()
+                    .find(|(_, index)| **index >= *dictionary_size as u64)
+                {
+                    self.verify(false, || Mismatch::DictionaryIndex {
+                        row_index,
+                        index: *index,
+                        dictionary_size: *dictionary_size,
+                    })?;
+                }
+
+                if *dictionary_size != lengths.len() as u32 {
+                    Err(Error::InvalidDictionarySize {
+                        expected: *dictionary_size,
+                        actual: lengths.len() as u32,
+                    })
+                } else {
+                    Ok(Column::make_utf8_dictionary_column(
+                        null_runs,
+                        data,
+                        dictionary_bytes,
+                        lengths,
+                        self.utf8_policy,
+                    )?)
+                }
+            }
+            ColumnInfo::Utf8Direct {
+                offset,
+                present_len,
+                data_len,
+                length_len,
+                version,
+            }
+            | ColumnInfo::Binary {
+                offset,
+                present_len,
+                data_len,
+                length_len,
+                version,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                // The lengths are read first so that they can be checked before the data.
+                let lengths = self.read_u64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0) + data_len,
+                    *length_len,
+                    *version,
+                    false,
+                )?;
+                self.check_string_lengths(&lengths)?;
+
+                let pos = SeekFrom::Start(stripe.data_start + offset + present_len.unwrap_or(0));
+                let mut decompressor = self.open_stream(pos, *data_len)?;
+
+                let mut data_bytes = vec![];
+                decompressor.read_to_end(&mut data_bytes)?;
+
+                self.close_stream(decompressor)?;
+
+                self.verify_value_count(
+                    "LENGTH",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    lengths.len(),
+                )?;
+                if self.strict {
+                    self.verify_value_count(
+                        "DATA",
+                        Self::total_length(stripe, column_id, &lengths)? as usize,
+                        data_bytes.len(),
+                    )?;
+                }
+
+                if matches!(column_info, ColumnInfo::Binary { .. }) {
+                    Ok(Column::make_binary_column(null_runs, data_bytes, lengths))
+                } else {
+                    Ok(Column::make_utf8_direct_column(
+                        null_runs,
+                        data_bytes,
+                        lengths,
+                        self.utf8_policy,
+                    )?)
+                }
+            }
+            ColumnInfo::Timestamp {
+                offset,
+                present_len,
+                data_len,
+                secondary_len,
+                version,
+                utc,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+                let data_start = stripe.data_start + offset + present_len.unwrap_or(0);
+
+                let seconds = self.read_u64s(data_start, *data_len, *version, true)?;
+                let nanos =
+                    self.read_u64s(data_start + data_len, *secondary_len, *version, false)?;
+
+                let value_count = Self::present_count(&null_runs, stripe.row_count);
+                self.verify_value_count("DATA", value_count, seconds.len())?;
+                self.verify_value_count("SECONDARY", value_count, nanos.len())?;
+
+                let values = seconds
+                    .iter()
+                    .zip(&nanos)
+                    .enumerate()
+                    .map(|(row_index, (seconds, nanos))| {
+                        Timestamp::decode(*seconds as i64, *nanos, *utc).ok_or(
+                            Error::InvalidValue {
+                                stripe_index: stripe.index,
+                                column_index: column_id,
+                                row_index,
+                            },
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Column::make_timestamp_column(
+                    values,
+                    &null_runs.unwrap_or_default(),
+                ))
+            }
+            ColumnInfo::Decimal {
+                offset,
+                present_len,
+                data_len,
+                secondary_len,
+                version,
+                scale,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+                let data_start = stripe.data_start + offset + present_len.unwrap_or(0);
+
+                // The unscaled values are varints of any length, with the scale of each value in
+                // the secondary stream.
+                let mut decompressor = self.open_stream(SeekFrom::Start(data_start), *data_len)?;
+                let mut data_bytes = vec![];
+                decompressor.read_to_end(&mut data_bytes)?;
+                self.close_stream(decompressor)?;
+
+                let values = crate::decimal::decode_varints(&data_bytes)?;
+                let scales =
+                    self.read_u64s(data_start + data_len, *secondary_len, *version, true)?;
+
+                let value_count = Self::present_count(&null_runs, stripe.row_count);
+                self.verify_value_count("DATA", value_count, values.len())?;
+                self.verify_value_count("SECONDARY", value_count, scales.len())?;
+
+                let values = values
+                    .iter()
+                    .zip(&scales)
+                    .map(|(value, value_scale)| {
+                        crate::decimal::rescale(*value, *value_scale as i64, *scale)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Column::make_decimal_column(
+                    values,
+                    &null_runs.unwrap_or_default(),
+                ))
+            }
+            ColumnInfo::Decimal64 {
+                offset,
+                present_len,
+                data_len,
+                scale,
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let values = self.read_u64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0),
+                    *data_len,
+                    IntegerRleVersion::V2,
+                    true,
+                )?;
+
+                self.verify_value_count(
+                    "DATA",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    values.len(),
+                )?;
+
+                let values = values
+                    .into_iter()
+                    .map(|value| crate::decimal::rescale(value as i64 as i128, *scale as i64, None))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Column::make_decimal_column(
+                    values,
+                    &null_runs.unwrap_or_default(),
+                ))
+            }
+            ColumnInfo::Struct { .. } => Err(Error::UnsupportedType(TypeKind::Struct)),
+            ColumnInfo::List {
+                offset,
+                present_len,
+                length_len,
+                version,
+                child_id,
+                ..
+            } => {
+                let null_runs = self.read_present(stripe, column_id, *offset, *present_len)?;
+
+                let lengths = self.read_u64s(
+                    stripe.data_start + offset + present_len.unwrap_or(0),
+                    *length_len,
+                    *version,
+                    false,
+                )?;
+
+                self.verify_value_count(
+                    "LENGTH",
+                    Self::present_count(&null_runs, stripe.row_count),
+                    lengths.len(),
+                )?;
+
+                // The child column has a row for each element.
+                let element_count = Self::total_length(stripe, column_id, &lengths)?;
+                Self::check_limit(&self.limits, Limit::ListElements, element_count)?;
+
+                let element_stripe = StripeInfo {
+                    row_count: element_count as usize,
+                    ..stripe.clone()
+                };
+                let values = self.read_column_data(&element_stripe, *child_id)?;
+
+                if self.strict {
+                    self.verify_statistics(&element_stripe, *child_id, &values)?;
+                }
+
+                Ok(Column::make_list_column(null_runs, lengths, values))
+            }
+        }
+    }
+
+    /// Read the null runs for a nested struct column (or `None` if the struct is never null).
+    pub(crate) fn read_struct_null_runs(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: usize,
+    ) -> Result<Option<Vec<u64>>, Error> {
+        let start = Instant::now();
+        let result = self.read_struct_null_runs_data(stripe, column_id);
+        self.record_column_metrics(stripe.row_count, start);
+        result
+    }
+
+    fn read_struct_null_runs_data(
+        &mut self,
+        stripe: &StripeInfo,
+        column_id: usize,
+    ) -> Result<Option<Vec<u64>>, Error> {
+        match self.column_info(stripe, column_id)? {
+            ColumnInfo::Struct {
+                offset,
+                present_len,
+            } => self.read_present(stripe, column_id, offset, present_len),
+            _ => Err(Error::InvalidMetadata),
+        }
+    }
+
+    pub(crate) fn read_message<M: Message>(
+        &mut self,
+        pos: SeekFrom,
+        len: u64,
+    ) -> Result<M, Error> {
+        Self::check_limit(&self.limits, Limit::MetadataLen, len)?;
+
+        let file = self.take_file()?;
+        let (message, file) = Self::read_message_from_file(
+            file,
+            &self.metadata.postscript.get_compression(),
+            pos,
+            len,
+            &self.limits,
+            &self.codecs,
+        )?;
+        self.file = Some(file);
+        Ok(message)
+    }
+
+    /// Read the raw postscript, footer, and metadata (if present) messages.
+    pub(crate) fn read_tail_messages(
+        &mut self,
+    ) -> Result<
+        (
+            orc_proto::PostScript,
+            orc_proto::Footer,
+            Option<orc_proto::Metadata>,
+        ),
+        Error,
+    > {
+        let mut file = self.take_file()?;
+        let postscript_result = Self::read_postscript_proto(&mut file, self.file_len);
+        self.file = Some(file);
+        let (postscript, postscript_len) = postscript_result?;
+
+        let footer_offset = postscript_len as u64 + postscript.footerLength() + POSTSCRIPT_LEN_LEN;
+        let footer = self.read_message(
+            SeekFrom::End(-(footer_offset as i64)),
+            postscript.footerLength(),
+        )?;
+
+        let metadata = if postscript.metadataLength() > 0 {
+            let metadata_offset = footer_offset + postscript.metadataLength();
+            Some(self.read_message(
+                SeekFrom::End(-(metadata_offset as i64)),
+                postscript.metadataLength(),
+            )?)
+        } else {
+            None
+        };
+
+        Ok((postscript, footer, metadata))
+    }
+
+    /// Copy raw bytes from the file to the given writer.
+    pub(crate) fn copy_bytes<W: Write>(
+        &mut self,
+        start: u64,
+        len: u64,
+        output: &mut W,
+    ) -> Result<(), Error> {
+        let mut file = self.take_file()?;
+        let result = file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| std::io::copy(&mut (&mut file).take(len), output));
+        self.file = Some(file);
+
+        if result? == len {
+            Ok(())
+        } else {
+            Err(Error::InvalidMetadata)
+        }
+    }
+
+    fn take_file(&mut self) -> Result<File, Error> {
+        self.file.take().ok_or(Error::InvalidState)
+    }
+
+    fn open_stream(&mut self, pos: SeekFrom, len: u64) -> Result<Decompressor<File>, Error> {
+        self.stream_end = match pos {
+            SeekFrom::Start(start) => Some(start + len),
+            _ => None,
+        };
+        self.stream_start = Some((len, Instant::now()));
+        self.context
+            .set_max_output_len(self.limits.get_max_chunk_len());
+        self.context.set_codecs(self.codecs.clone());
+
+        Ok(Decompressor::open_with_context(
+            self.take_file()?,
+            std::mem::take(&mut self.context),
+            self.metadata.postscript.get_compression(),
+            pos,
+            len,
+        )?)
+    }
+
+    fn close_stream(&mut self, decompressor: Decompressor<File>) -> Result<(), Error> {
+        if let Some((len, start)) = self.stream_start.take() {
+            self.column_metrics.record_stream(
+                len,
+                decompressor.get_decompressed_len(),
+                start.elapsed(),
+            );
+        }
+
+        let (mut file, context) = decompressor.into_parts();
+
+        let verification = match self.stream_end.take() {
+            Some(stream_end) if self.strict => {
+                let position = file.stream_position()?;
+                self.verify(position == stream_end, || Mismatch::StreamLength {
+                    expected: stream_end,
+                    actual: position,
+                })
+            }
+            _ => Ok(()),
+        };
+
+        self.file = Some(file);
+        self.context = context;
+
+        verification
+    }
+
+    fn read_message_from_file<R: Read + Seek, M: Message>(
+        file: R,
+        compression: &CompressionKind,
+        pos: SeekFrom,
+        len: u64,
+        limits: &ParseLimits,
+        codecs: &CodecRegistry,
+    ) -> Result<(M, R), Error> {
+        let mut context = DecompressionContext::new();
+        context.set_max_output_len(limits.get_max_chunk_len());
+        context.set_codecs(codecs.clone());
+
+        let mut decompressor =
+            Decompressor::open_with_context(file, context, *compression, pos, len)?;
+        let message = Message::parse_from_reader(&mut decompressor)?;
+        let file = decompressor.into_inner();
+
+        Ok((message, file))
+    }
+
+    fn read_postscript(file: &mut File, file_len: u64) -> Result<(PostScript, u8), Error> {
+        let (postscript, postscript_len) = Self::read_postscript_proto(file, file_len)?;
+
+        Ok((PostScript::from_proto(&postscript), postscript_len))
+    }
+
+    fn read_postscript_proto(
+        file: &mut File,
+        file_len: u64,
+    ) -> Result<(orc_proto::PostScript, u8), Error> {
+        let bytes_to_read = std::cmp::min(POSTSCRIPT_BUFFER_LEN, file_len as usize) as usize;
+
+        let mut buffer = Vec::with_capacity(bytes_to_read);
+        file.seek(SeekFrom::End(-(bytes_to_read as i64)))?;
+        file.read_to_end(&mut buffer)?;
+
+        if bytes_to_read == 0 {
+            Err(Error::InvalidMetadata)
+        } else {
+            let postscript_len = buffer[bytes_to_read - 1];
+            let postscript_start = bytes_to_read - 1 - postscript_len as usize;
+            let postscript_bytes = &buffer[postscript_start..bytes_to_read - 1];
+
+            let postscript = orc_proto::PostScript::parse_from_bytes(postscript_bytes)?;
+
+            Ok((postscript, postscript_len))
+        }
+    }
+
+    fn read_footer(
+        file: File,
+        compression: &CompressionKind,
+        postscript_len: u8,
+        footer_len: u64,
+        limits: &ParseLimits,
+        codecs: &CodecRegistry,
+    ) -> Result<(Footer, Vec<ColumnStatistics>, File), Error> {
+        let footer_offset = (postscript_len as u64 + footer_len + POSTSCRIPT_LEN_LEN) as i64;
+
+        let (footer, file) = Self::read_message_from_file::<_, orc_proto::Footer>(
+            file,
+            compression,
+            SeekFrom::End(-footer_offset),
+            footer_len,
+            limits,
+            codecs,
+        )?;
+
+        let (footer, statistics) = Self::parse_footer(&footer);
+
+        Ok((footer, statistics, file))
+    }
+
+    fn parse_footer(footer: &orc_proto::Footer) -> (Footer, Vec<ColumnStatistics>) {
+        let parsed = Footer::from_proto(footer);
+        let mut statistics = footer
+            .statistics
+            .iter()
+            .map(ColumnStatistics::from_proto)
+            .collect::<Vec<_>>();
+
+        // The synthetic root struct has as many values as the actual root.
+        if parsed.has_synthetic_root() {
+            if let Some(root_statistics) = statistics.first().cloned() {
+                statistics.insert(0, root_statistics);
+            }
+        }
+
+        (parsed, statistics)
+    }
+
+    fn add_synthetic_root_statistics(stripe_statistics: &mut [orc_proto::StripeStatistics]) {
+        for statistics in stripe_statistics {
+            if let Some(root_statistics) = statistics.colStats.first().cloned() {
+                statistics.colStats.insert(0, root_statistics);
+            }
+        }
+    }
+
+    /// The type tree of the file.
+    ///
+    /// Unsupported types are only rejected when a column is read, so that the supported columns of
+    /// a file can still be read.
+    fn extract_schema(footer: &Footer) -> Result<OrcSchema, Error> {
+        OrcSchema::from_footer(footer).ok_or(Error::InvalidMetadata)
+    }
+
+    /// The type tree of the file, starting from the root struct.
+    pub fn get_schema(&self) -> &OrcSchema {
+        &self.metadata.schema
+    }
+
+    /// The indices of the top-level fields whose types (or element types) can't be decoded.
+    ///
+    /// Reading these columns is an error unless unsupported columns are skipped (see
+    /// `set_skip_unsupported_columns`).
+    pub fn get_unsupported_field_indices(&self) -> Vec<usize> {
+        fn is_supported(schema: &OrcSchema) -> bool {
+            match schema.get_kind() {
+                TypeKind::List => schema.get_children().iter().all(is_supported),
+                TypeKind::Float
+                | TypeKind::Double
+                | TypeKind::Date
+                | TypeKind::Map
+                | TypeKind::Struct
+                | TypeKind::Union => false,
+                _ => true,
+            }
+        }
+
+        self.metadata
+            .schema
+            .get_children()
+            .iter()
+            .enumerate()
+            .filter(|(_, schema)| !is_supported(schema))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // The kind of a column by column index (so without the root).
+    fn get_column_kind(&self, column_id: usize) -> Option<TypeKind> {
+        self.metadata
+            .schema
+            .get_column(column_id as u32 + 1)
+            .map(OrcSchema::get_kind)
+    }
+
+    pub fn get_postscript(&self) -> &PostScript {
+        &self.metadata.postscript
+    }
+
+    pub fn get_footer(&self) -> &Footer {
+        &self.metadata.footer
+    }
+
+    /// The file-level statistics for each column, indexed by column id (the root struct is 0).
+    ///
+    /// This is empty if the writer didn't include statistics.
+    pub fn get_file_statistics(&self) -> &[ColumnStatistics] {
+        &self.metadata.statistics
+    }
+
+    pub fn get_stripe_footers(&mut self) -> Result<Vec<StripeFooter>, Error> {
+        (0..self.stripe_footers.len())
+            .map(|stripe_index| self.get_stripe_footer(stripe_index))
+            .collect()
+    }
+
+    pub fn get_stripe_footer(&mut self, stripe_index: usize) -> Result<StripeFooter, Error> {
+        Ok(StripeFooter::from_proto(
+            self.load_stripe_footer(stripe_index)?,
+        ))
+    }
+
+    // Stripe footers are read and decompressed the first time they're needed.
+    fn load_stripe_footer(
+        &mut self,
+        stripe_index: usize,
+    ) -> Result<&orc_proto::StripeFooter, Error> {
+        if self
+            .stripe_footers
+            .get(stripe_index)
+            .ok_or(Error::InvalidMetadata)?
+            .is_none()
+        {
+            let stripe_info = self.metadata.footer.get_stripes()[stripe_index];
+            let footer_start = stripe_info.get_offset()
+                + stripe_info.get_index_length()
+                + stripe_info.get_data_length();
+            let footer_len = stripe_info.get_footer_length();
+
+            let mut stripe_footer: orc_proto::StripeFooter =
+                self.read_message(SeekFrom::Start(footer_start), footer_len)?;
+
+            // Renumber the columns to match the types (see `Footer::has_synthetic_root`).
+            if self.metadata.footer.has_synthetic_root() {
+                for stream in &mut stripe_footer.streams {
+                    stream.set_column(stream.column() + 1);
+                }
+
+                let mut root_encoding = orc_proto::ColumnEncoding::new();
+                root_encoding.set_kind(orc_proto::column_encoding::Kind::DIRECT);
+                stripe_footer.columns.insert(0, root_encoding);
+            }
+
+            self.stripe_footers[stripe_index] = Some(stripe_footer);
+        }
+
+        self.stripe_footers[stripe_index]
+            .as_ref()
+            .ok_or(Error::InvalidState)
+    }
+
+    /// The storage layout of every column in every stripe (in stripe order, then column id order,
+    /// starting with the root struct).
+    ///
+    /// This reads all stripe footers, but no column data.
+    pub fn column_layout(&mut self) -> Result<Vec<ColumnLayout>, Error> {
+        let column_count = self.metadata.footer.get_types().len() as u32;
+        let mut layouts = vec![];
+
+        for stripe_index in 0..self.metadata.footer.get_stripes().len() {
+            let stripe_footer = self.load_stripe_footer(stripe_index)?;
+
+            layouts.extend((0..column_count).map(|column_id| {
+                ColumnLayout::from_stripe_footer(stripe_index, column_id, stripe_footer)
+            }));
+        }
+
+        Ok(layouts)
+    }
+
+    /// Read the footer for a single stripe (if it hasn't already been read) and build its info.
+    ///
+    /// Unlike `get_stripe_info`, this doesn't check that the stripe row counts match the footer.
+    pub fn read_stripe_info(&mut self, stripe_index: usize) -> Result<StripeInfo, Error> {
+        self.load_stripe_footer(stripe_index)?;
+
+        match &self.stripe_footers[stripe_index] {
+            Some(stripe_footer) => self.make_stripe_info(stripe_index, stripe_footer),
+            None => Err(Error::InvalidState),
+        }
+    }
+
+    pub fn get_stripe_info(&mut self) -> Result<Vec<StripeInfo>, Error> {
+        self.verify_row_count()?;
+
+        (0..self.stripe_footers.len())
+            .map(|stripe_index| self.read_stripe_info(stripe_index))
+            .collect()
+    }
+
+    fn verify_row_count(&self) -> Result<(), Error> {
+        let stripe_row_count = self
+            .metadata
+            .footer
+            .get_stripes()
+            .iter()
+            .map(|stripe| stripe.get_number_of_rows())
+            .sum::<u64>();
+
+        self.verify(
+            stripe_row_count == self.metadata.footer.get_number_of_rows(),
+            || Mismatch::RowCount {
+                expected: self.metadata.footer.get_number_of_rows(),
+                actual: stripe_row_count,
+            },
+        )
+    }
+
+    fn make_stripe_info(
+        &self,
+        i: usize,
+        stripe_footer: &orc_proto::StripeFooter,
+    ) -> Result<StripeInfo, Error> {
+        let stripe_orig_info = self
+            .metadata
+            .footer
+            .get_stripes()
+            .get(i)
+            .ok_or(Error::InvalidMetadata)?;
+        let row_count = stripe_orig_info.get_number_of_rows() as usize;
+        let data_start = stripe_orig_info.get_offset() + stripe_orig_info.get_index_length();
+        let data_len = stripe_orig_info.get_data_length();
+
+        let column_count = stripe_footer
+            .columns
+            .len()
+            .saturating_sub(1)
+            .min(self.metadata.schema.get_column_count() - 1);
+        let mut streams = vec![ColumnDataStreamInfo::default(); stripe_footer.columns.len()];
+
+        for stream in &stripe_footer.streams {
+            let kind = stream.kind();
+            let column_id = stream.column() as usize;
+            let length = stream.length();
+            let stream_info = match kind {
+                StreamKind::DATA
+                | StreamKind::LENGTH
+                | StreamKind::PRESENT
+                | StreamKind::DICTIONARY_DATA
+                | StreamKind::SECONDARY => column_id
+                    .checked_sub(1)
+                    .and_then(|index| streams.get_mut(index))
+                    .ok_or(Error::InvalidMetadata)?,
+                _ => continue,
+            };
+
+            match kind {
+                StreamKind::DATA => stream_info.data_len = length,
+                StreamKind::LENGTH => stream_info.length_len = length,
+                StreamKind::PRESENT => stream_info.present_len = length,
+                StreamKind::SECONDARY => stream_info.secondary_len = length,
+                _ => stream_info.dictionary_data_len = length,
+            }
+        }
+
+        let mut current_offset = 0;
+
+        for stream_info in &mut streams {
+            stream_info.offset = current_offset;
+            current_offset += stream_info.len();
+        }
+
+        Ok(StripeInfo {
+            index: i,
+            row_count,
+            data_start,
+            data_len,
+            column_count,
+            streams,
+            encodings: stripe_footer
+                .columns
+                .iter()
+                .map(ColumnEncoding::from_proto)
+                .collect(),
+        })
+    }
+
+    /// Check a column's streams and encoding against its type, and find its streams.
+    ///
+    /// This is only done for columns that are read, so that planning a stripe of a wide file is
+    /// cheap.
+    fn column_info(&self, stripe: &StripeInfo, column_id: usize) -> Result<ColumnInfo, Error> {
+        if column_id >= stripe.column_count {
+            return Err(Error::InvalidColumnIndex(column_id));
+        }
+
+        let stream_info = &stripe.streams[column_id];
+        let encoding = stripe.encodings[column_id + 1];
+        let offset = stream_info.offset;
+        let present_len = if stream_info.present_len == 0 {
+            None
+        } else {
+            Some(stream_info.present_len)
+        };
+
+        let kind = self
+            .get_column_kind(column_id)
+            .ok_or(Error::InvalidMetadata)?;
+
+        match (kind, encoding.get_kind()) {
+            (
+                TypeKind::Long | TypeKind::Int | TypeKind::Short,
+                encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2),
+            ) if stream_info.dictionary_data_len == 0 && stream_info.length_len == 0 => {
+                Ok(ColumnInfo::Integer {
+                    offset,
+                    present_len,
+                    data_len: stream_info.data_len,
+                    version: encoding_kind.into(),
+                })
+            }
+            (TypeKind::Long | TypeKind::Int | TypeKind::Short, _) => Err(Error::InvalidMetadata),
+            (TypeKind::Byte, EncodingKind::Direct)
+                if stream_info.dictionary_data_len == 0 && stream_info.length_len == 0 =>
+            {
+                Ok(ColumnInfo::Byte {
+                    offset,
+                    present_len,
+                    data_len: stream_info.data_len,
+                })
+            }
+            (TypeKind::Byte, _) => Err(Error::InvalidMetadata),
+            (TypeKind::Boolean, EncodingKind::Direct) => {
+                if stream_info.dictionary_data_len != 0 || stream_info.length_len != 0 {
+                    Err(Error::InvalidMetadata)
+                } else {
+                    Ok(ColumnInfo::Bool {
+                        offset,
+                        present_len,
+                        data_len: stream_info.data_len,
+                    })
+                }
+            }
+            (
+                TypeKind::String | TypeKind::Char | TypeKind::Varchar,
+                encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2),
+            ) => {
+                if stream_info.dictionary_data_len != 0 {
+                    Err(Error::InvalidMetadata)
+                } else {
+                    Ok(ColumnInfo::Utf8Direct {
+                        offset,
+                        present_len,
+                        data_len: stream_info.data_len,
+                        length_len: stream_info.length_len,
+                        version: encoding_kind.into(),
+                    })
+                }
+            }
+            (TypeKind::Binary, encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2))
+                if stream_info.dictionary_data_len == 0 =>
+            {
+                Ok(ColumnInfo::Binary {
+                    offset,
+                    present_len,
+                    data_len: stream_info.data_len,
+                    length_len: stream_info.length_len,
+                    version: encoding_kind.into(),
+                })
+            }
+            (TypeKind::Binary, _) => Err(Error::InvalidMetadata),
+            (
+                TypeKind::String | TypeKind::Char | TypeKind::Varchar,
+                encoding_kind @ (EncodingKind::Dictionary | EncodingKind::DictionaryV2),
+            ) => Ok(ColumnInfo::Utf8Dictionary {
+                offset,
+                present_len,
+                data_len: stream_info.data_len,
+                dictionary_data_len: stream_info.dictionary_data_len,
+                length_len: stream_info.length_len,
+                version: encoding_kind.into(),
+                dictionary_size: encoding.get_dictionary_size().unwrap_or_default(),
+            }),
+            (
+                kind @ (TypeKind::Timestamp | TypeKind::TimestampInstant),
+                encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2),
+            ) if stream_info.dictionary_data_len == 0 && stream_info.length_len == 0 => {
+                Ok(ColumnInfo::Timestamp {
+                    offset,
+                    present_len,
+                    data_len: stream_info.data_len,
+                    secondary_len: stream_info.secondary_len,
+                    version: encoding_kind.into(),
+                    utc: kind == TypeKind::TimestampInstant,
+                })
+            }
+            (TypeKind::Timestamp | TypeKind::TimestampInstant, _) => Err(Error::InvalidMetadata),
+            (
+                TypeKind::Decimal,
+                encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2),
+            ) if stream_info.dictionary_data_len == 0 && stream_info.length_len == 0 => {
+                let column_type = self.metadata.footer.get_types().get(column_id + 1);
+                let precision = column_type.and_then(|column_type| column_type.get_precision());
+                let scale = column_type.and_then(|column_type| column_type.get_scale());
+
+                // The integer encoding has no scale stream (while legacy decimal columns with
+                // values always have one).
+                if encoding_kind == EncodingKind::DirectV2
+                    && stream_info.secondary_len == 0
+                    && stream_info.data_len > 0
+                    && precision.is_some_and(|precision| precision > 0 && precision <= 18)
+                {
+                    Ok(ColumnInfo::Decimal64 {
+                        offset,
+                        present_len,
+                        data_len: stream_info.data_len,
+                        scale: scale.unwrap_or(0),
+                    })
+                } else {
+                    Ok(ColumnInfo::Decimal {
+                        offset,
+                        present_len,
+                        data_len: stream_info.data_len,
+                        secondary_len: stream_info.secondary_len,
+                        version: encoding_kind.into(),
+                        scale,
+                    })
+                }
+            }
+            (TypeKind::Decimal, _) => Err(Error::InvalidMetadata),
+            (TypeKind::Struct, EncodingKind::Direct) => Ok(ColumnInfo::Struct {
+                offset,
+                present_len,
+            }),
+            (TypeKind::List, encoding_kind @ (EncodingKind::Direct | EncodingKind::DirectV2))
+                if stream_info.data_len == 0
+                    && stream_info.dictionary_data_len == 0
+                    && stream_info.secondary_len == 0 =>
+            {
+                let child_id = self
+                    .metadata
+                    .footer
+                    .get_types()
+                    .get(column_id + 1)
+                    .and_then(|column_type| column_type.get_subtypes().first())
+                    .and_then(|child_id| (*child_id as usize).checked_sub(1))
+                    .filter(|child_id| *child_id > column_id)
+                    .ok_or(Error::InvalidMetadata)?;
+                let (child_offset, child_len) = self.column_info(stripe, child_id)?.span();
+
+                Ok(ColumnInfo::List {
+                    offset,
+                    present_len,
+                    length_len: stream_info.length_len,
+                    version: encoding_kind.into(),
+                    child_id,
+                    child_end: child_offset + child_len,
+                })
+            }
+            (TypeKind::List, _) => Err(Error::InvalidMetadata),
+            (kind, _) => Err(Error::UnsupportedType(kind)),
+        }
+    }
+}
+
+/// A cursor over rows whose values borrow from the cursor's decoded column data.
+///
+/// Each row must be dropped before the next one is requested, and after an error no more rows are
+/// returned.
+pub struct RowCursor<'a> {
+    file: &'a mut OrcFile,
+    // The current stripe is at the start of the range.
+    stripes: Range<usize>,
+    // The info for the current stripe, once it has been loaded.
+    stripe_info: Option<StripeInfo>,
+    columns: Vec<usize>,
+    data: Vec<Column>,
+    current_row: usize,
+    row_numbers: bool,
+    // The file-level row number of the first row in the current stripe.
+    stripe_first_row: u64,
+    progress: ScanProgress,
+    progress_callback: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+    stats_collector: Option<StatsCollector>,
+    // The pool memory reserved for the current stripe's column data.
+    memory: Option<MemoryReservation>,
+}
+
+impl<'a> RowCursor<'a> {
+    fn new(file: &'a mut OrcFile, stripes: Range<usize>, columns: Vec<usize>) -> RowCursor<'a> {
+        let row_count = file
+            .get_footer()
+            .get_stripes()
+            .get(stripes.clone())
+            .unwrap_or_default()
+            .iter()
+            .map(|stripe| stripe.get_number_of_rows())
+            .sum();
+
+        Self {
+            file,
+            progress: ScanProgress::new(stripes.len(), row_count),
+            stripes,
+            stripe_info: None,
+            columns,
+            data: vec![],
+            current_row: 0,
+            row_numbers: false,
+            stripe_first_row: 0,
+            progress_callback: None,
+            cancellation: None,
+            stats_collector: None,
+            memory: None,
+        }
+    }
+
+    /// Call a function with the progress of the scan each time a stripe has been finished.
+    pub fn with_progress_callback<F: FnMut(&ScanProgress) + Send + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.progress_callback = Some(Box::new(f));
+        self
+    }
+
+    /// Stop the scan with [`Error::Cancelled`] when the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Add the values of the selected columns to the collector's statistics as stripes are loaded.
+    pub fn with_stats_collector(mut self, stats_collector: StatsCollector) -> Self {
+        self.stats_collector = Some(stats_collector);
+        self
+    }
+
+    /// The progress of the scan (only updated when a stripe has been finished).
+    pub fn get_progress(&self) -> &ScanProgress {
+        &self.progress
+    }
+
+    /// Start each row with its file-level row number (counting from zero), as a `U64` value.
+    ///
+    /// Row numbers don't depend on the range of stripes being read, so they can be used to match
+    /// rows from different reads.
+    pub fn with_row_numbers(mut self) -> Self {
+        self.row_numbers = true;
+        self
+    }
+
+    /// The metrics for the underlying file (including any reads before the cursor was created).
+    pub fn get_metrics(&self) -> &ReadMetrics {
+        self.file.get_metrics()
+    }
+
+    pub fn next_row(&mut self) -> Result<Option<Row<'_>>, Error> {
+        while !self.stripes.is_empty() {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(|token| token.is_cancelled())
+            {
+                // Unrecoverable error.
+                self.stripes.start = self.stripes.end;
+                return Err(Error::Cancelled);
+            }
+
+            let row_count = match &self.stripe_info {
+                Some(stripe_info) => stripe_info.row_count,
+                None => {
+                    if let Err(error) = self.load_stripe() {
+                        // Unrecoverable error.
+                        self.stripes.start = self.stripes.end;
+                        return Err(error);
+                    }
+                    continue;
+                }
+            };
+
+            if self.current_row >= row_count {
+                self.data.clear();
+                self.memory = None;
+                self.stripe_info = None;
+                self.stripes.start += 1;
+                self.current_row = 0;
+
+                self.progress.add_stripe(row_count as u64);
+                if let Some(callback) = self.progress_callback.as_mut() {
+                    callback(&self.progress);
+                }
+            } else {
+                let row_index = self.current_row;
+                self.current_row += 1;
+
+                let mut values = Vec::with_capacity(self.data.len() + 1);
+
+                if self.row_numbers {
+                    values.push(Value::U64(self.stripe_first_row + row_index as u64));
+                }
+
+                for (column, column_index) in self.data.iter().zip(&self.columns) {
+                    match column.get(row_index) {
+                        Some(value) => values.push(value),
+                        None => {
+                            let stripe_index = self.stripes.start;
+
+                            // Unrecoverable error.
+                            self.stripes.start = self.stripes.end;
+                            return Err(Error::InvalidValue {
+                                stripe_index,
+                                column_index: *column_index,
+                                row_index,
+                            });
+                        }
+                    }
+                }
+
+                return Ok(Some(Row { values }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Load the current stripe's info and column data.
+    fn load_stripe(&mut self) -> Result<(), Error> {
+        let stripe_info = self.file.read_stripe_info(self.stripes.start)?;
+
+        for i in &self.columns {
+            self.data.push(self.file.read_column(&stripe_info, *i)?);
+        }
+
+        self.memory = self.file.reserve_memory(&self.data)?;
+
+        if let Some(stats_collector) = &self.stats_collector {
+            stats_collector.add_columns(&self.data);
+        }
+
+        self.stripe_first_row = self.file.get_footer().get_stripes()[..self.stripes.start]
+            .iter()
+            .map(|stripe| stripe.get_number_of_rows())
+            .sum();
+        self.stripe_info = Some(stripe_info);
+
+        Ok(())
+    }
+}
+
+/// The values of a row from a `RowCursor` (this dereferences to a slice of values).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Row<'a> {
+    values: Vec<Value<'a>>,
+}
+
+impl<'a> std::ops::Deref for Row<'a> {
+    type Target = [Value<'a>];
+
+    fn deref(&self) -> &[Value<'a>] {
+        &self.values
+    }
+}
+
+pub struct MappedRows<'a, F> {
+    cursor: RowCursor<'a>,
+    f: F,
+}
+
+impl<F> MappedRows<'_, F> {
+    /// The metrics for the underlying file (including any reads before these rows were mapped).
+    pub fn get_metrics(&self) -> &ReadMetrics {
+        self.cursor.get_metrics()
+    }
+
+    /// Pass each row's file-level row number to the function as a leading `U64` value (see
+    /// [`RowCursor::with_row_numbers`]).
+    pub fn with_row_numbers(mut self) -> Self {
+        self.cursor = self.cursor.with_row_numbers();
+        self
+    }
+
+    /// See [`RowCursor::with_progress_callback`].
+    pub fn with_progress_callback<C: FnMut(&ScanProgress) + Send + 'static>(
+        mut self,
+        f: C,
+    ) -> Self {
+        self.cursor = self.cursor.with_progress_callback(f);
+        self
+    }
+
+    /// See [`RowCursor::with_cancellation`].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cursor = self.cursor.with_cancellation(token);
+        self
+    }
+
+    pub fn get_progress(&self) -> &ScanProgress {
+        self.cursor.get_progress()
+    }
+
+    /// See [`RowCursor::with_stats_collector`].
+    pub fn with_stats_collector(mut self, stats_collector: StatsCollector) -> Self {
+        self.cursor = self.cursor.with_stats_collector(stats_collector);
+        self
+    }
+}
+
+impl<T, E, F> Iterator for MappedRows<'_, F>
+where
+    E: From<Error>,
+    F: FnMut(&[Value<'_>]) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next_row() {
+            Ok(Some(row)) => Some((self.f)(&row)),
+            Ok(None) => None,
+            Err(error) => Some(Err(E::from(error))),
+        }
+    }
+}
+
+/// Rows projected onto a target schema (see [`OrcFile::map_rows_with_schema`]).
+pub struct ProjectedRows<'a, F> {
+    cursor: RowCursor<'a>,
+    // The position in the row of each target field, or `None` for missing fields.
+    positions: Vec<Option<usize>>,
+    f: F,
+}
+
+impl<F> ProjectedRows<'_, F> {
+    /// The metrics for the underlying file (including any reads before these rows were mapped).
+    pub fn get_metrics(&self) -> &ReadMetrics {
+        self.cursor.get_metrics()
+    }
+}
+
+impl<T, E, F> Iterator for ProjectedRows<'_, F>
+where
+    E: From<Error>,
+    F: FnMut(&[Value<'_>]) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next_row() {
+            Ok(Some(row)) => {
+                let values = self
+                    .positions
+                    .iter()
+                    .map(|position| position.map_or(Value::Null, |position| row[position]))
+                    .collect::<Vec<_>>();
+
+                Some((self.f)(&values))
+            }
+            Ok(None) => None,
+            Err(error) => Some(Err(E::from(error))),
+        }
+    }
+}
+
+/// The minimum number of rows that are mapped together in a parallel task.
+#[cfg(feature = "rayon")]
+const PAR_MIN_BATCH_LEN: usize = 1024;
+
+#[cfg(feature = "rayon")]
+pub struct ParMappedRows<'a, T, E, F> {
+    file: &'a mut OrcFile,
+    // The stripes that haven't been loaded yet.
+    stripes: Range<usize>,
+    columns: Vec<usize>,
+    f: F,
+    // The remaining results for the current stripe.
+    results: std::vec::IntoIter<Result<T, E>>,
+}
+
+#[cfg(feature = "rayon")]
+impl<T, E, F> ParMappedRows<'_, T, E, F>
+where
+    T: Send,
+    E: From<Error> + Send,
+    F: Fn(&[Value<'_>]) -> Result<T, E> + Sync,
+{
+    fn map_stripe(&mut self, stripe_index: usize) -> Result<Vec<Result<T, E>>, Error> {
+        use rayon::prelude::*;
+
+        let stripe_info = self.file.read_stripe_info(stripe_index)?;
+        let data = self
+            .columns
+            .iter()
+            .map(|i| self.file.read_column(&stripe_info, *i))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Held until the stripe has been mapped.
+        let _memory = self.file.reserve_memory(&data)?;
+        let columns = &self.columns;
+        let f = &self.f;
+
+        Ok((0..stripe_info.row_count)
+            .into_par_iter()
+            .with_min_len(PAR_MIN_BATCH_LEN)
+            .map(|row_index| {
+                let values = data
+                    .iter()
+                    .zip(columns)
+                    .map(|(column, column_index)| {
+                        column.get(row_index).ok_or(Error::InvalidValue {
+                            stripe_index,
+                            column_index: *column_index,
+                            row_index,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                f(&values)
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, E, F> Iterator for ParMappedRows<'_, T, E, F>
+where
+    T: Send,
+    E: From<Error> + Send,
+    F: Fn(&[Value<'_>]) -> Result<T, E> + Sync,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.results.next() {
+                return Some(result);
+            }
+
+            let stripe_index = self.stripes.next()?;
+
+            match self.map_stripe(stripe_index) {
+                Ok(results) => self.results = results.into_iter(),
+                Err(error) => {
+                    // Unrecoverable error.
+                    self.stripes.start = self.stripes.end;
+                    return Some(Err(E::from(error)));
+                }
+            }
+        }
+    }
+}
+
+pub struct FilteredRows<'a, P, F> {
+    file: &'a mut OrcFile,
+    // The stripes that haven't been loaded yet.
+    stripes: Range<usize>,
+    filter_columns: Vec<usize>,
+    output_columns: Vec<usize>,
+    predicate: P,
+    f: F,
+    // The decoded filter columns for the current stripe, followed by any other output columns.
+    data: Vec<Column>,
+    // The position in `data` of each output column.
+    output_indices: Vec<usize>,
+    // Which rows of the current stripe match the predicate.
+    selection: Bitmap,
+    stripe_index: usize,
+    current_row: usize,
+    // The pool memory reserved for `data`.
+    memory: Option<MemoryReservation>,
+}
+
+impl<P, F> FilteredRows<'_, P, F>
+where
+    P: FnMut(&[Value<'_>]) -> bool,
+{
+    fn load_stripe(&mut self, stripe_index: usize) -> Result<(), Error> {
+        self.data.clear();
+        self.memory = None;
+        self.selection = Bitmap::new();
+        self.stripe_index = stripe_index;
+        self.current_row = 0;
+
+        let stripe_info = self.file.read_stripe_info(stripe_index)?;
+
+        for i in &self.filter_columns {
+            self.data.push(self.file.read_column(&stripe_info, *i)?);
+        }
+
+        let mut selection = Bitmap::with_capacity(stripe_info.row_count);
+        let mut values = Vec::with_capacity(self.data.len());
+
+        for row_index in 0..stripe_info.row_count {
+            values.clear();
+
+            for (column, column_index) in self.data.iter().zip(&self.filter_columns) {
+                values.push(column.get(row_index).ok_or(Error::InvalidValue {
+                    stripe_index,
+                    column_index: *column_index,
+                    row_index,
+                })?);
+            }
+
+            selection.push((self.predicate)(&values));
+        }
+
+        if selection.count_ones() > 0 {
+            self.output_indices.clear();
+
+            for i in &self.output_columns {
+                let index = match self.filter_columns.iter().position(|column| column == i) {
+                    Some(index) => index,
+                    None => {
+                        self.data.push(self.file.read_column(&stripe_info, *i)?);
+                        self.data.len() - 1
+                    }
+                };
+
+                self.output_indices.push(index);
+            }
+
+            self.selection = selection;
+        }
+
+        self.memory = self.file.reserve_memory(&self.data)?;
+
+        Ok(())
+    }
+}
+
+impl<T, E, P, F> Iterator for FilteredRows<'_, P, F>
+where
+    E: From<Error>,
+    P: FnMut(&[Value<'_>]) -> bool,
+    F: FnMut(&[Value<'_>]) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current_row >= self.selection.len() {
+                let stripe_index = self.stripes.next()?;
+
+                if let Err(error) = self.load_stripe(stripe_index) {
+                    // Unrecoverable error.
+                    self.stripes.start = self.stripes.end;
+                    self.selection = Bitmap::new();
+                    return Some(Err(E::from(error)));
+                }
+            } else {
+                let row_index = self.current_row;
+                self.current_row += 1;
+
+                if self.selection[row_index] {
+                    let mut values = Vec::with_capacity(self.output_indices.len());
+
+                    for (index, column_index) in
+                        self.output_indices.iter().zip(&self.output_columns)
+                    {
+                        match self.data[*index].get(row_index) {
+                            Some(value) => values.push(value),
+                            None => {
+                                let error = Error::InvalidValue {
+                                    stripe_index: self.stripe_index,
+                                    column_index: *column_index,
+                                    row_index,
+                                };
+
+                                // Unrecoverable error.
+                                self.stripes.start = self.stripes.end;
+                                self.selection = Bitmap::new();
+                                return Some(Err(E::from(error)));
+                            }
+                        }
+                    }
+
+                    return Some((self.f)(&values));
+                }
+            }
+        }
+    }
+}
+
+/// A reader for a single stripe that owns its own file handle.
+pub struct StripeReader {
+    file: OrcFile,
+    stripe_index: usize,
+    stripe_info: StripeInfo,
+}
+
+impl StripeReader {
+    pub fn get_stripe_index(&self) -> usize {
+        self.stripe_index
+    }
+
+    pub fn get_stripe_info(&self) -> &StripeInfo {
+        &self.stripe_info
+    }
+
+    pub fn get_field_names(&self) -> &[String] {
+        self.file.get_field_names()
+    }
+
+    pub fn read_column(&mut self, column_id: usize) -> Result<Column, Error> {
+        self.file.read_column(&self.stripe_info, column_id)
+    }
+
+    pub fn get_metrics(&self) -> &ReadMetrics {
+        self.file.get_metrics()
+    }
+
+    /// Decode the given columns and map over the rows of this stripe.
+    pub fn map_rows<T, E: From<Error>, F>(
+        mut self,
+        columns: &[usize],
+        f: F,
+    ) -> Result<StripeRows<F>, Error>
+    where
+        F: FnMut(&[Value<'_>]) -> Result<T, E>,
+    {
+        let data = columns
+            .iter()
+            .map(|column_id| self.read_column(*column_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let memory = self.file.reserve_memory(&data)?;
+
+        Ok(StripeRows {
+            stripe_index: self.stripe_index,
+            row_count: self.stripe_info.get_row_count(),
+            columns: columns.to_vec(),
+            f,
+            data,
+            current_row: 0,
+            _memory: memory,
+        })
+    }
+}
+
+/// The mapped rows of a single stripe, which owns its decoded column data.
+pub struct StripeRows<F> {
+    stripe_index: usize,
+    row_count: usize,
+    columns: Vec<usize>,
+    f: F,
+    data: Vec<Column>,
+    current_row: usize,
+    // The pool memory reserved for `data`, released when the rows are dropped.
+    _memory: Option<MemoryReservation>,
+}
+
+impl<T, E, F> Iterator for StripeRows<F>
+where
+    E: From<Error>,
+    F: FnMut(&[Value<'_>]) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_row >= self.row_count {
+            None
+        } else {
+            let mut values = Vec::with_capacity(self.data.len());
+
+            for (column, column_index) in self.data.iter().zip(&self.columns) {
+                match column.get(self.current_row) {
+                    Some(value) => values.push(value),
+                    None => {
+                        let error = Error::InvalidValue {
+                            stripe_index: self.stripe_index,
+                            column_index: *column_index,
+                            row_index: self.current_row,
+                        };
+
+                        // Unrecoverable error.
+                        self.current_row = self.row_count;
+                        return Some(Err(E::from(error)));
+                    }
+                }
+            }
+
+            self.current_row += 1;
+            Some((self.f)(&values))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        meta::{EncodingKind, StreamKind, ValueRange},
+        proto::orc_proto,
+        value::{OwnedValue, Value},
+    };
+    use serde_derive::Deserialize;
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    const TS_10K_EXAMPLE_PATH: &str = "examples/ts-10k-zstd-2020-09-20.orc";
+    const TS_1K_ZLIB_PATH: &str = "examples/ts-1k-zlib-2020-09-20.orc";
+    const TS_1K_NONE_PATH: &str = "examples/ts-1k-none-2020-09-20.orc";
+    const TS_1K_SNAPPY_PATH: &str = "examples/ts-1k-snappy-2020-09-20.orc";
+    const TS_1K_JSON_PATH: &str = "examples/ts-1k-2020-09-20.ndjson";
+    const TS_FIELD_NAMES: [&str; 11] = [
+        "id",
+        "status_id",
+        "timestamp",
+        "screen_name",
+        "name",
+        "url",
+        "location",
+        "description",
+        "profile_image_url",
+        "verified",
+        "followers_count",
+    ];
+
+    #[test]
+    fn get_postscript() {
+        let orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let postscript = orc_file.get_postscript();
+
+        let mut expected = orc_proto::PostScript::default();
+        expected.set_footerLength(1065);
+        expected.set_compression(orc_proto::CompressionKind::ZSTD);
+        expected.set_compressionBlockSize(262144);
+        expected.version = vec![0, 12];
+        expected.set_metadataLength(909);
+        expected.set_writerVersion(9);
+        expected.set_magic("ORC".to_string());
+
+        assert_eq!(*postscript, PostScript::from_proto(&expected));
+        assert_eq!(postscript.get_compression(), CompressionKind::Zstd);
+
+        // Brotli isn't in the generated protobuf enum.
+        expected.compression = Some(protobuf::EnumOrUnknown::from_i32(6));
+        assert_eq!(
+            PostScript::from_proto(&expected).get_compression(),
+            CompressionKind::Brotli
+        );
+    }
+
+    #[test]
+    fn get_field_index() {
+        let orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+
+        for (i, field_name) in TS_FIELD_NAMES.iter().enumerate() {
+            assert_eq!(orc_file.get_field_index(field_name), Some(i));
+        }
+        assert_eq!(orc_file.get_field_index("missing"), None);
+    }
+
+    #[test]
+    fn get_field() {
+        let orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let field_types = orc_file.get_field_types();
+
+        assert_eq!(field_types.len(), TS_FIELD_NAMES.len());
+        assert_eq!(field_types[0], TypeKind::Long);
+        assert_eq!(field_types[3], TypeKind::String);
+
+        let id = orc_file.get_field("id").unwrap();
+        assert_eq!(id.get_index(), 0);
+        assert_eq!(id.get_column_id(), 1);
+        assert_eq!(id.get_kind(), TypeKind::Long);
+        assert_eq!(id.has_null(), Some(false));
+
+        let url = orc_file.get_field("url").unwrap();
+        assert_eq!(url.get_kind(), TypeKind::String);
+        assert_eq!(url.has_null(), Some(true));
+
+        assert!(orc_file.get_field("missing").is_none());
+    }
+
+    #[test]
+    fn open_with_tail() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let tail = orc_file.read_tail().unwrap();
+        let tail = OrcTail::new(tail.get_file_len(), tail.get_bytes().to_vec());
+        let mut cached = OrcFile::open_with_tail(
+            TS_1K_ZLIB_PATH,
+            &tail,
+            ParseLimits::default(),
+            CodecRegistry::default(),
+        )
+        .unwrap();
+
+        assert_eq!(cached.get_postscript(), orc_file.get_postscript());
+        assert_eq!(cached.get_footer(), orc_file.get_footer());
+        assert_eq!(cached.get_file_statistics(), orc_file.get_file_statistics());
+        // The stripe statistics come from the tail.
+        assert!(cached.stripe_statistics.is_some());
+
+        let columns = (0..TS_FIELD_NAMES.len()).collect::<Vec<_>>();
+        let read_rows = |orc_file: &mut OrcFile| {
+            orc_file
+                .map_rows(&columns, |values| {
+                    Ok::<_, Error>(values.iter().map(|value| format!("{:?}", value)).collect())
+                })
+                .unwrap()
+                .collect::<Result<Vec<Vec<String>>, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(read_rows(&mut cached), read_rows(&mut orc_file));
+        assert!(matches!(
+            OrcFile::open_with_tail(
+                TS_1K_NONE_PATH,
+                &tail,
+                ParseLimits::default(),
+                CodecRegistry::default()
+            ),
+            Err(Error::TailMismatch { .. })
+        ));
+
+        // The limits apply to the cached tail and are kept for reading the data.
+        let limits = ParseLimits::default().with_max_metadata_len(16);
+        assert!(matches!(
+            OrcFile::open_with_tail(
+                TS_1K_ZLIB_PATH,
+                &tail,
+                limits,
+                CodecRegistry::default()
+            ),
+            Err(Error::LimitExceeded {
+                limit: Limit::MetadataLen,
+                max: 16,
+                ..
+            })
+        ));
+
+        let limits = ParseLimits::default().with_max_string_len(1);
+        let mut cached = OrcFile::open_with_tail(
+            TS_1K_ZLIB_PATH,
+            &tail,
+            limits,
+            CodecRegistry::default(),
+        )
+        .unwrap();
+        assert_eq!(cached.get_limits(), &limits);
+        assert!(cached
+            .map_rows(&columns, |_| Ok::<_, Error>(()))
+            .unwrap()
+            .any(|result| matches!(result, Err(Error::LimitExceeded { .. }))));
+
+        // The tail is checked against the limits before it's read.
+        let mut orc_file =
+            OrcFile::open_with_limits(TS_1K_ZLIB_PATH, ParseLimits::default()).unwrap();
+        orc_file.limits = ParseLimits::default().with_max_metadata_len(16);
+        assert!(matches!(
+            orc_file.read_tail(),
+            Err(Error::LimitExceeded {
+                limit: Limit::MetadataLen,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn error_categories() {
+        let category = |result: Result<OrcFile, Error>| result.err().map(|error| error.category());
+
+        assert_eq!(
+            category(OrcFile::open("examples/missing.orc")),
+            Some(ErrorCategory::Io)
+        );
+        assert_eq!(
+            category(OrcFile::open("examples/ts-1k-snappy-2020-09-20.orc")),
+            Some(ErrorCategory::Unsupported)
+        );
+        assert_eq!(
+            category(OrcFile::open_with_limits(
+                TS_10K_EXAMPLE_PATH,
+                ParseLimits::new().with_max_stripe_count(0)
+            )),
+            Some(ErrorCategory::Limits)
+        );
+
+        let mut orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let stripe = orc_file.read_stripe_info(0).unwrap();
+        assert_eq!(
+            orc_file
+                .read_column(&stripe, 100)
+                .err()
+                .map(|error| error.category()),
+            Some(ErrorCategory::Usage)
+        );
+
+        // Decompression errors are categorized the same way when they're wrapped in I/O errors.
+        let error = Error::Io(
+            compress::Error::ChunkTooLarge {
+                offset: 0,
+                max_len: 1024,
+            }
+            .into(),
+        );
+        assert_eq!(error.category(), ErrorCategory::Limits);
+
+        let truncated_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-truncated.orc",
+            std::process::id()
+        ));
+        let bytes = std::fs::read(TS_1K_NONE_PATH).unwrap();
+        std::fs::write(&truncated_path, &bytes[..bytes.len() / 2]).unwrap();
+        let result = OrcFile::open(&truncated_path);
+        std::fs::remove_file(&truncated_path).unwrap();
+        assert_eq!(category(result), Some(ErrorCategory::Corruption));
+    }
+
+    #[test]
+    fn parse_limits() {
+        let open = |limits| OrcFile::open_with_limits(TS_10K_EXAMPLE_PATH, limits);
+        let read_column = |limits, column| {
+            let mut orc_file = open(limits)?;
+            let stripe = orc_file.read_stripe_info(0)?;
+            orc_file.read_column(&stripe, column)
+        };
+
+        assert!(matches!(
+            open(ParseLimits::new().with_max_metadata_len(16)),
+            Err(Error::LimitExceeded {
+                limit: Limit::MetadataLen,
+                max: 16,
+                ..
+            })
+        ));
+        assert!(matches!(
+            open(ParseLimits::new().with_max_stripe_count(0)),
+            Err(Error::LimitExceeded {
+                limit: Limit::StripeCount,
+                value: 1,
+                max: 0
+            })
+        ));
+        assert!(matches!(
+            read_column(ParseLimits::new().with_max_dictionary_entries(100), 6),
+            Err(Error::LimitExceeded {
+                limit: Limit::DictionaryEntries,
+                ..
+            })
+        ));
+        assert!(matches!(
+            read_column(ParseLimits::new().with_max_string_len(8), 3),
+            Err(Error::LimitExceeded {
+                limit: Limit::StringLen,
+                ..
+            })
+        ));
+        assert!(matches!(
+            read_column(ParseLimits::new().with_max_chunk_len(1024), 7),
+            Err(Error::Compress(compress::Error::ChunkTooLarge { .. }))
+        ));
+
+        // Stripe footers are checked when they're loaded.
+        let mut orc_file = open(ParseLimits::new()).unwrap();
+        let footer_len = orc_file.get_footer().get_stripes()[0].get_footer_length();
+        orc_file.limits = ParseLimits::new().with_max_metadata_len(footer_len - 1);
+        assert!(matches!(
+            orc_file.read_stripe_info(0),
+            Err(Error::LimitExceeded {
+                limit: Limit::MetadataLen,
+                ..
+            })
+        ));
+
+        for column in 0..TS_FIELD_NAMES.len() {
+            assert!(read_column(ParseLimits::conservative(), column).is_ok());
+        }
+    }
+
+    #[test]
+    fn column_layout() {
+        let mut orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let layouts = orc_file.column_layout().unwrap();
+        let stripe = orc_file.get_footer().get_stripes()[0];
+
+        assert_eq!(layouts.len(), TS_FIELD_NAMES.len() + 1);
+        assert_eq!(
+            layouts.iter().map(ColumnLayout::get_len).sum::<u64>(),
+            stripe.get_index_length() + stripe.get_data_length()
+        );
+
+        // The location column is dictionary-encoded.
+        let location = &layouts[7];
+        assert_eq!(location.get_encoding(), Some(EncodingKind::DictionaryV2));
+        assert!(location.get_dictionary_size().unwrap() > 0);
+        assert!(location
+            .get_streams()
+            .iter()
+            .any(|(kind, _)| *kind == StreamKind::DictionaryData));
+    }
+
+    #[test]
+    fn stripe_footers() {
+        let mut orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let layouts = orc_file.column_layout().unwrap();
+        let stripe_footers = orc_file.get_stripe_footers().unwrap();
+
+        assert_eq!(stripe_footers.len(), 1);
+        assert_eq!(stripe_footers[0], orc_file.get_stripe_footer(0).unwrap());
+        assert_eq!(stripe_footers[0].get_columns().len(), layouts.len());
+
+        for layout in &layouts {
+            let column_id = layout.get_column_id();
+
+            assert_eq!(
+                stripe_footers[0]
+                    .get_columns()
+                    .get(column_id as usize)
+                    .map(|encoding| encoding.get_kind()),
+                layout.get_encoding()
+            );
+            assert_eq!(
+                stripe_footers[0]
+                    .get_streams()
+                    .iter()
+                    .filter(|stream| stream.get_column_id() == column_id)
+                    .map(|stream| (stream.get_kind(), stream.get_length()))
+                    .collect::<Vec<_>>(),
+                layout.get_streams()
+            );
+        }
+
+        assert!(orc_file.get_stripe_footer(1).is_err());
+    }
+
+    /// Write an uncompressed file with one stripe and `column_count` long columns, where every
+    /// value in a column is the column index.
+    fn write_wide_file<P: AsRef<Path>>(path: P, column_count: u32, row_count: u8) {
+        use orc_proto::{column_encoding, stream, type_, CompressionKind};
+
+        let mut data = vec![];
+        let mut stripe_footer = orc_proto::StripeFooter::new();
+        let mut root_encoding = orc_proto::ColumnEncoding::new();
+        root_encoding.set_kind(column_encoding::Kind::DIRECT);
+        stripe_footer.columns.push(root_encoding);
+
+        for column_index in 0..column_count {
+            // A version 1 run of repeated values (with a delta of zero).
+            let start = data.len();
+            data.extend([row_count - 3, 0]);
+            integer_encoding::VarIntWriter::write_varint(&mut data, column_index as i64).unwrap();
+
+            let mut data_stream = orc_proto::Stream::new();
+            data_stream.set_kind(stream::Kind::DATA);
+            data_stream.set_column(column_index + 1);
+            data_stream.set_length((data.len() - start) as u64);
+            stripe_footer.streams.push(data_stream);
+
+            let mut encoding = orc_proto::ColumnEncoding::new();
+            encoding.set_kind(column_encoding::Kind::DIRECT);
+            stripe_footer.columns.push(encoding);
+        }
+
+        let stripe_footer_bytes = stripe_footer.write_to_bytes().unwrap();
+
+        let mut stripe = orc_proto::StripeInformation::new();
+        stripe.set_offset(3);
+        stripe.set_indexLength(0);
+        stripe.set_dataLength(data.len() as u64);
+        stripe.set_footerLength(stripe_footer_bytes.len() as u64);
+        stripe.set_numberOfRows(row_count as u64);
+
+        let mut root_type = orc_proto::Type::new();
+        root_type.set_kind(type_::Kind::STRUCT);
+
+        let mut footer = orc_proto::Footer::new();
+        footer.set_headerLength(3);
+        footer.set_contentLength(3 + data.len() as u64 + stripe_footer_bytes.len() as u64);
+        footer.set_numberOfRows(row_count as u64);
+        footer.stripes.push(stripe);
+        footer.types.push(root_type);
+
+        for column_index in 0..column_count {
+            let mut column_type = orc_proto::Type::new();
+            column_type.set_kind(type_::Kind::LONG);
+            footer.types.push(column_type);
+            footer.types[0].subtypes.push(column_index + 1);
+            footer.types[0]
+                .fieldNames
+                .push(format!("column_{}", column_index));
+        }
+
+        let footer_bytes = footer.write_to_bytes().unwrap();
+
+        let mut postscript = orc_proto::PostScript::new();
+        postscript.set_footerLength(footer_bytes.len() as u64);
+        postscript.set_compression(CompressionKind::NONE);
+        postscript.set_metadataLength(0);
+        postscript.version = vec![0, 12];
+        postscript.set_magic("ORC".to_string());
+        let postscript_bytes = postscript.write_to_bytes().unwrap();
+
+        let mut file = File::create(path).unwrap();
+        file.write_all(b"ORC").unwrap();
+        file.write_all(&data).unwrap();
+        file.write_all(&stripe_footer_bytes).unwrap();
+        file.write_all(&footer_bytes).unwrap();
+        file.write_all(&postscript_bytes).unwrap();
+        file.write_all(&[postscript_bytes.len() as u8]).unwrap();
+    }
+
+    #[test]
+    fn wide_schema() {
+        let path =
+            std::env::temp_dir().join(format!("orcrs-parser-test-{}-wide.orc", std::process::id()));
+        write_wide_file(&path, 5_000, 10);
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        assert_eq!(stripe_info.get_column_count(), 5_000);
+
+        let column = orc_file.read_column(&stripe_info, 4_321).unwrap();
+        assert_eq!(column.to_vec_opt::<u64>(), Some(vec![Some(4_321); 10]));
+
+        let rows = orc_file
+            .map_rows(&[0, 4_999], |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[9], vec![OwnedValue::I64(0), OwnedValue::I64(4_999)]);
+    }
+
+    // The streams of a column, in file order.
+    type TestStreams = Vec<(orc_proto::stream::Kind, Vec<u8>)>;
+
+    /// Write an uncompressed file with one stripe and the given top-level columns (which all have
+    /// the given encoding).
+    fn write_single_stripe_file<P: AsRef<Path>>(
+        path: P,
+        row_count: u64,
+        encoding_kind: orc_proto::column_encoding::Kind,
+        columns: Vec<(&str, orc_proto::Type, TestStreams)>,
+    ) {
+        use orc_proto::{column_encoding, type_};
+
+        let mut data = vec![];
+        let mut stripe_footer = orc_proto::StripeFooter::new();
+        let mut root_type = orc_proto::Type::new();
+        root_type.set_kind(type_::Kind::STRUCT);
+        let mut types = vec![root_type];
+
+        for (column_id, (name, column_type, streams)) in columns.into_iter().enumerate() {
+            for (kind, bytes) in streams {
+                let mut stream = orc_proto::Stream::new();
+                stream.set_kind(kind);
+                stream.set_column(column_id as u32 + 1);
+                stream.set_length(bytes.len() as u64);
+                stripe_footer.streams.push(stream);
+                data.extend(bytes);
+            }
+
+            types[0].subtypes.push(column_id as u32 + 1);
+            types[0].fieldNames.push(name.to_string());
+            types.push(column_type);
+        }
+
+        for column_id in 0..types.len() {
+            let mut encoding = orc_proto::ColumnEncoding::new();
+            encoding.set_kind(if column_id == 0 {
+                column_encoding::Kind::DIRECT
+            } else {
+                encoding_kind
+            });
+            stripe_footer.columns.push(encoding);
+        }
+
+        write_stripe_file(path, row_count, &data, &stripe_footer, types);
+    }
+
+    // Write a file with a single uncompressed stripe.
+    pub(crate) fn write_stripe_file<P: AsRef<Path>>(
+        path: P,
+        row_count: u64,
+        data: &[u8],
+        stripe_footer: &orc_proto::StripeFooter,
+        types: Vec<orc_proto::Type>,
+    ) {
+        use orc_proto::CompressionKind;
+
+        let stripe_footer_bytes = stripe_footer.write_to_bytes().unwrap();
+
+        let mut stripe = orc_proto::StripeInformation::new();
+        stripe.set_offset(3);
+        stripe.set_indexLength(0);
+        stripe.set_dataLength(data.len() as u64);
+        stripe.set_footerLength(stripe_footer_bytes.len() as u64);
+        stripe.set_numberOfRows(row_count);
+
+        let mut footer = orc_proto::Footer::new();
+        footer.set_headerLength(3);
+        footer.set_contentLength(3 + data.len() as u64 + stripe_footer_bytes.len() as u64);
+        footer.set_numberOfRows(row_count);
+        footer.stripes.push(stripe);
+        footer.types = types;
+
+        let footer_bytes = footer.write_to_bytes().unwrap();
+
+        let mut postscript = orc_proto::PostScript::new();
+        postscript.set_footerLength(footer_bytes.len() as u64);
+        postscript.set_compression(CompressionKind::NONE);
+        postscript.set_metadataLength(0);
+        postscript.version = vec![0, 12];
+        postscript.set_magic("ORC".to_string());
+        let postscript_bytes = postscript.write_to_bytes().unwrap();
+
+        let mut file = File::create(path).unwrap();
+        file.write_all(b"ORC").unwrap();
+        file.write_all(data).unwrap();
+        file.write_all(&stripe_footer_bytes).unwrap();
+        file.write_all(&footer_bytes).unwrap();
+        file.write_all(&postscript_bytes).unwrap();
+        file.write_all(&[postscript_bytes.len() as u8]).unwrap();
+    }
+
+    fn test_column_type(kind: orc_proto::type_::Kind) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(kind);
+        column_type
+    }
+
+    // A present stream for four rows where the second is null.
+    const SECOND_ROW_NULL: [u8; 2] = [0xff, 0b1011_0000];
+
+    // A long column with four sevens (as a version 1 run), for checking the offsets of the
+    // preceding columns.
+    fn sevens_column() -> (&'static str, orc_proto::Type, TestStreams) {
+        use integer_encoding::VarIntWriter;
+
+        let mut data = vec![1, 0];
+        data.write_varint(7i64).unwrap();
+
+        (
+            "count",
+            test_column_type(orc_proto::type_::Kind::LONG),
+            vec![(orc_proto::stream::Kind::DATA, data)],
+        )
+    }
+
+    #[test]
+    fn timestamps() {
+        use integer_encoding::VarIntWriter;
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-timestamps.orc",
+            std::process::id()
+        ));
+
+        // Version 1 literal runs of three values: 2020-09-20T12:00:00.123456789,
+        // 1969-12-31T23:59:58.500, and 2015-01-01T00:00:00.
+        let mut seconds = vec![0xfd];
+        for value in [180_532_800i64, -1_420_070_401, 0] {
+            seconds.write_varint(value).unwrap();
+        }
+        let mut nanos = vec![0xfd];
+        for value in [123_456_789u64 << 3, (5 << 3) | 7, 0] {
+            nanos.write_varint(value).unwrap();
+        }
+        let streams = vec![
+            (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+            (StreamKind::DATA, seconds),
+            (StreamKind::SECONDARY, nanos),
+        ];
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                ("local", test_column_type(Kind::TIMESTAMP), streams.clone()),
+                ("utc", test_column_type(Kind::TIMESTAMP_INSTANT), streams),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            orc_file.get_field_types(),
+            vec![
+                TypeKind::Timestamp,
+                TypeKind::TimestampInstant,
+                TypeKind::Long
+            ]
+        );
+
+        let rows = orc_file
+            .map_rows(&[0, 1, 2], |values| {
+                Ok::<_, Error>((
+                    values[0].as_nullable_timestamp().unwrap(),
+                    values[1].as_nullable_timestamp().unwrap(),
+                    values[2].as_i64(),
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let expected = [
+            Some((1_600_603_200, 123_456_789)),
+            None,
+            Some((-2, 500_000_000)),
+            Some((1_420_070_400, 0)),
+        ];
+
+        assert_eq!(
+            rows,
+            expected
+                .iter()
+                .map(|value| (
+                    value.and_then(|(seconds, nanos)| Timestamp::new(seconds, nanos, false)),
+                    value.and_then(|(seconds, nanos)| Timestamp::new(seconds, nanos, true)),
+                    Some(7)
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            rows[0].0.unwrap().to_string(),
+            "2020-09-20T12:00:00.123456789"
+        );
+        assert_eq!(rows[2].1.unwrap().to_string(), "1969-12-31T23:59:58.500Z");
+        assert_eq!(rows[2].1.unwrap().to_millis(), Some(-1_500));
+    }
+
+    #[test]
+    fn legacy_decimals() {
+        use integer_encoding::VarIntWriter;
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-decimals.orc",
+            std::process::id()
+        ));
+
+        // 12.34, -0.5 (with a smaller scale than the type), and a value larger than 64 bits.
+        let mut values = vec![];
+        for value in [1234i64, -5] {
+            values.write_varint(value).unwrap();
+        }
+        // The zigzag encoding of 2^70 + 1.
+        values.extend([
+            0x82, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02,
+        ]);
+        let mut scales = vec![0xfd];
+        for scale in [2i64, 1, 2] {
+            scales.write_varint(scale).unwrap();
+        }
+
+        let mut column_type = test_column_type(Kind::DECIMAL);
+        column_type.set_precision(38);
+        column_type.set_scale(2);
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "price",
+                    column_type,
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, values),
+                        (StreamKind::SECONDARY, scales),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows = orc_file
+            .map_rows(&[0, 1], |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let decimal = |value| OwnedValue::Decimal { value, scale: 2 };
+        assert_eq!(
+            rows,
+            vec![
+                vec![decimal(1234), OwnedValue::I64(7)],
+                vec![OwnedValue::Null, OwnedValue::I64(7)],
+                vec![decimal(-50), OwnedValue::I64(7)],
+                vec![decimal(1180591620717411303425), OwnedValue::I64(7)],
+            ]
+        );
+        assert_eq!(
+            rows[3][0].as_value().to_decimal_string(),
+            Some("11805916207174113034.25".to_string())
+        );
+    }
+
+    #[test]
+    fn char_padding() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path =
+            std::env::temp_dir().join(format!("orcrs-parser-test-{}-char.orc", std::process::id()));
+
+        let mut column_type = test_column_type(Kind::CHAR);
+        column_type.set_maximumLength(3);
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "code",
+                    column_type,
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, b"ab    a b".to_vec()),
+                        (StreamKind::LENGTH, vec![0x00, 0, 3]),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+
+        assert_eq!(orc_file.get_field_types()[0], TypeKind::Char);
+        assert_eq!(
+            orc_file.read_column(&stripe_info, 0).unwrap().to_vec_opt(),
+            Some(vec![
+                Some("ab ".to_string()),
+                None,
+                Some("   ".to_string()),
+                Some("a b".to_string())
+            ])
+        );
+
+        orc_file.set_trim_char_padding(true);
+        assert_eq!(
+            orc_file.read_column(&stripe_info, 0).unwrap().to_vec_opt(),
+            Some(vec![
+                Some("ab".to_string()),
+                None,
+                Some(String::new()),
+                Some("a b".to_string())
+            ])
+        );
+
+        orc_file.set_empty_strings_as_nulls(true);
+        assert_eq!(
+            orc_file.read_column(&stripe_info, 0).unwrap().to_vec_opt(),
+            Some(vec![
+                Some("ab".to_string()),
+                None,
+                None,
+                Some("a b".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn varchars() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-varchar.orc",
+            std::process::id()
+        ));
+
+        let mut column_type = test_column_type(Kind::VARCHAR);
+        column_type.set_maximumLength(5);
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "name",
+                    column_type,
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, b"ab abcdex".to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 3, 5, 1]),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let schema = crate::schema::Schema::from_footer(orc_file.get_footer()).unwrap();
+        assert_eq!(schema.get_fields()[0].1, TypeKind::Varchar);
+        assert_eq!(schema.get_maximum_length(0), Some(5));
+        assert_eq!(schema.get_maximum_length(1), None);
+
+        let field = orc_file.get_field("name").unwrap();
+        assert_eq!(field.get_maximum_length(), Some(5));
+        assert_eq!((field.get_precision(), field.get_scale()), (None, None));
+
+        // Only CHAR padding is trimmed.
+        orc_file.set_trim_char_padding(true);
+
+        let rows = orc_file
+            .map_rows(&[0, 1], |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let utf8 = |value: &str| OwnedValue::Utf8(value.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                vec![utf8("ab "), OwnedValue::I64(7)],
+                vec![OwnedValue::Null, OwnedValue::I64(7)],
+                vec![utf8("abcde"), OwnedValue::I64(7)],
+                vec![utf8("x"), OwnedValue::I64(7)],
+            ]
+        );
+    }
+
+    #[test]
+    fn map_rows_with_schema() {
+        use crate::schema::Incompatibility;
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-evolution.orc",
+            std::process::id()
+        ));
+
+        let (count_name, mut count_type, count_streams) = sevens_column();
+        count_type.set_kind(Kind::INT);
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "name",
+                    test_column_type(Kind::CHAR),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, b"ab abcdex".to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 3, 5, 1]),
+                    ],
+                ),
+                (count_name, count_type, count_streams),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let schema = Schema::new(vec![
+            ("count".to_string(), TypeKind::Long),
+            ("email".to_string(), TypeKind::String),
+            ("name".to_string(), TypeKind::String),
+        ]);
+        let rows = orc_file
+            .map_rows_with_schema(&schema, |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let utf8 = |value: &str| OwnedValue::Utf8(value.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                vec![OwnedValue::I64(7), OwnedValue::Null, utf8("ab ")],
+                vec![OwnedValue::I64(7), OwnedValue::Null, OwnedValue::Null],
+                vec![OwnedValue::I64(7), OwnedValue::Null, utf8("abcde")],
+                vec![OwnedValue::I64(7), OwnedValue::Null, utf8("x")],
+            ]
+        );
+
+        // Narrowing isn't supported.
+        let schema = Schema::new(vec![("count".to_string(), TypeKind::Short)]);
+        assert!(matches!(
+            orc_file.map_rows_with_schema(&schema, |_| Ok::<_, Error>(())),
+            Err(Error::IncompatibleSchema(conflicts)) if conflicts == [Incompatibility::TypeConflict {
+                name: "count".to_string(),
+                from: TypeKind::Int,
+                to: TypeKind::Short,
+            }]
+        ));
+    }
+
+    #[test]
+    fn bytes() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-bytes.orc",
+            std::process::id()
+        ));
+
+        // A literal run of 127, -128, and -1.
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "level",
+                    test_column_type(Kind::BYTE),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, vec![0xfd, 0x7f, 0x80, 0xff]),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+
+        assert_eq!(
+            orc_file.read_column(&stripe_info, 0).unwrap().to_vec_opt(),
+            Some(vec![Some(127i64), None, Some(-128), Some(-1)])
+        );
+        assert_eq!(
+            orc_file.read_column(&stripe_info, 1).unwrap().to_vec_opt(),
+            Some(vec![Some(7i64); 4])
+        );
+    }
+
+    #[test]
+    fn shorts() {
+        use integer_encoding::VarIntWriter;
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Row {
+            delta: Option<i16>,
+            count: i64,
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-shorts.orc",
+            std::process::id()
+        ));
+
+        // A version 1 literal run of three signed values.
+        let mut data = vec![0xfd];
+        for value in [-32_768i64, 12, 32_767] {
+            data.write_varint(value).unwrap();
+        }
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "delta",
+                    test_column_type(Kind::SHORT),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, data),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows = orc_file
+            .deserialize::<Row>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    delta: Some(-32_768),
+                    count: 7
+                },
+                Row {
+                    delta: None,
+                    count: 7
+                },
+                Row {
+                    delta: Some(12),
+                    count: 7
+                },
+                Row {
+                    delta: Some(32_767),
+                    count: 7
+                },
+            ]
+        );
+
+        // Values that don't fit in the target type can't be deserialized.
+        #[derive(Debug, serde::Deserialize)]
+        struct NarrowRow {
+            #[allow(dead_code)]
+            delta: Option<i8>,
+        }
+
+        assert!(orc_file.deserialize::<NarrowRow>().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn non_struct_root() {
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-non-struct-root.orc",
+            std::process::id()
+        ));
+
+        // The root is a long column (column id 0 in the file) with four sevens.
+        let (_, column_type, streams) = sevens_column();
+        let mut stripe_footer = orc_proto::StripeFooter::new();
+        let mut data = vec![];
+
+        for (kind, bytes) in streams {
+            let mut stream = orc_proto::Stream::new();
+            stream.set_kind(kind);
+            stream.set_column(0);
+            stream.set_length(bytes.len() as u64);
+            stripe_footer.streams.push(stream);
+            data.extend(bytes);
+        }
+
+        let mut encoding = orc_proto::ColumnEncoding::new();
+        encoding.set_kind(orc_proto::column_encoding::Kind::DIRECT);
+        stripe_footer.columns.push(encoding);
+
+        write_stripe_file(&path, 4, &data, &stripe_footer, vec![column_type]);
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        assert!(orc_file.get_footer().has_synthetic_root());
+        assert_eq!(orc_file.get_field_names(), [""]);
+        assert_eq!(orc_file.get_field_types(), vec![TypeKind::Long]);
+        assert_eq!(orc_file.get_column_id(0), Some(1));
+
+        let values = orc_file
+            .map_rows(&[0], |values| Ok::<_, Error>(values[0].as_i64()))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(values, vec![Some(7); 4]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lists() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Row {
+            count: i64,
+            tags: Option<Vec<String>>,
+        }
+
+        let flat_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-lists-flat.orc",
+            std::process::id()
+        ));
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-lists.orc",
+            std::process::id()
+        ));
+
+        // The lists are ["a", "bc"], null, [], and ["d"] (with the lengths as version 1 literal
+        // runs). The element column is written as a third field and then moved into the list.
+        write_single_stripe_file(
+            &flat_path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                sevens_column(),
+                (
+                    "tags",
+                    test_column_type(Kind::LIST),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 2, 0, 1]),
+                    ],
+                ),
+                (
+                    "element",
+                    test_column_type(Kind::STRING),
+                    vec![
+                        (StreamKind::DATA, b"abcd".to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 1, 2, 1]),
+                    ],
+                ),
+            ],
+        );
+
+        let mut flat_file = OrcFile::open(&flat_path).unwrap();
+        crate::split::write_selected_stripes_with_footer(
+            &mut flat_file,
+            &[0],
+            File::create(&path).unwrap(),
+            None,
+            |footer| {
+                footer.types[0].subtypes.truncate(2);
+                footer.types[0].fieldNames.truncate(2);
+                footer.types[2].subtypes.push(3);
+            },
+        )
+        .unwrap();
+        std::fs::remove_file(&flat_path).unwrap();
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The list's range includes its elements.
+        let ranges = orc_file.plan_ranges(0..1, &[1]).unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].get_len(), 14);
+
+        let utf8 = |value: &str| OwnedValue::Utf8(value.to_string());
+        let expected = vec![
+            OwnedValue::List(vec![utf8("a"), utf8("bc")]),
+            OwnedValue::Null,
+            OwnedValue::List(vec![]),
+            OwnedValue::List(vec![utf8("d")]),
+        ];
+
+        let values = orc_file
+            .map_rows_by_path(&["tags"], |values| {
+                assert_eq!(values.len(), 1);
+                Ok::<_, Error>(values[0].to_owned_value())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(values, expected);
+
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        let column = orc_file.read_column(&stripe_info, 1).unwrap();
+        let list = column.get(0).and_then(|value| value.as_list()).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(1), Some(Value::Utf8("bc")));
+        assert_eq!(list.get(2), None);
+        assert_eq!(column.get(0).unwrap().to_owned_value(), expected[0]);
+        #[cfg(feature = "json")]
+        assert_eq!(list.to_json_string(), r#"["a","bc"]"#);
+
+        let rows = orc_file
+            .deserialize::<Row>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    count: 7,
+                    tags: Some(vec!["a".to_string(), "bc".to_string()])
+                },
+                Row {
+                    count: 7,
+                    tags: None
+                },
+                Row {
+                    count: 7,
+                    tags: Some(vec![])
+                },
+                Row {
+                    count: 7,
+                    tags: Some(vec!["d".to_string()])
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn list_first_field() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Row {
+            tags: Option<Vec<String>>,
+            count: i64,
+        }
+
+        let flat_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-list-first-flat.orc",
+            std::process::id()
+        ));
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-list-first.orc",
+            std::process::id()
+        ));
+
+        // The same lists as above, but with the list as the first field, so that the element
+        // column comes before the second field.
+        write_single_stripe_file(
+            &flat_path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "tags",
+                    test_column_type(Kind::LIST),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 2, 0, 1]),
+                    ],
+                ),
+                (
+                    "element",
+                    test_column_type(Kind::STRING),
+                    vec![
+                        (StreamKind::DATA, b"abcd".to_vec()),
+                        (StreamKind::LENGTH, vec![0xfd, 1, 2, 1]),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut flat_file = OrcFile::open(&flat_path).unwrap();
+        crate::split::write_selected_stripes_with_footer(
+            &mut flat_file,
+            &[0],
+            File::create(&path).unwrap(),
+            None,
+            |footer| {
+                footer.types[0].subtypes.remove(1);
+                footer.types[0].fieldNames.remove(1);
+                footer.types[1].subtypes.push(2);
+            },
+        )
+        .unwrap();
+        std::fs::remove_file(&flat_path).unwrap();
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(orc_file.get_field_names(), ["tags", "count"]);
+        assert_eq!(orc_file.get_field_column_indices(), vec![0, 2]);
+        assert_eq!(
+            orc_file.get_field_types(),
+            vec![TypeKind::List, TypeKind::Long]
+        );
+
+        let rows = orc_file
+            .deserialize::<Row>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    tags: Some(vec!["a".to_string(), "bc".to_string()]),
+                    count: 7
+                },
+                Row {
+                    tags: None,
+                    count: 7
+                },
+                Row {
+                    tags: Some(vec![]),
+                    count: 7
+                },
+                Row {
+                    tags: Some(vec!["d".to_string()]),
+                    count: 7
+                },
+            ]
+        );
+
+        // The number of elements is checked before the element column is read.
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        orc_file.limits = ParseLimits::new().with_max_list_elements(2);
+        assert!(matches!(
+            orc_file.read_column(&stripe_info, 0),
+            Err(Error::LimitExceeded {
+                limit: Limit::ListElements,
+                value: 3,
+                max: 2
+            })
+        ));
+        orc_file.limits = ParseLimits::new().with_max_list_elements(3);
+        assert!(orc_file.read_column(&stripe_info, 0).is_ok());
+    }
+
+    #[test]
+    fn binary() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-binary.orc",
+            std::process::id()
+        ));
+
+        // Values that aren't valid UTF-8 (and an empty value), with a version 1 literal run of
+        // lengths.
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                (
+                    "payload",
+                    test_column_type(Kind::BINARY),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, vec![0xff, 0xfe, 0x00, 0x61, 0x80]),
+                        (StreamKind::LENGTH, vec![0xfd, 2, 0, 3]),
+                    ],
+                ),
+                sevens_column(),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        let column = orc_file.read_column(&stripe_info, 0).unwrap();
+        assert_eq!(
+            column.to_vec_opt::<Vec<u8>>(),
+            Some(vec![
+                Some(vec![0xff, 0xfe]),
+                None,
+                Some(vec![]),
+                Some(vec![0x00, 0x61, 0x80])
+            ])
+        );
+
+        let rows = orc_file
+            .map_rows(&[0, 1], |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows[3],
+            vec![
+                OwnedValue::Bytes(vec![0x00, 0x61, 0x80]),
+                OwnedValue::I64(7)
+            ]
+        );
+    }
+
+    #[test]
+    fn decimal64() {
+        use orc_proto::{stream::Kind as StreamKind, type_::Kind};
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-decimal64.orc",
+            std::process::id()
+        ));
+
+        // A version 2 direct run of three zigzag-encoded 64-bit values: 12.34, -0.05, and
+        // 1234567890123456.78.
+        let mut values = vec![0x7e, 0x02];
+        for value in [1234i64, -5, 123_456_789_012_345_678] {
+            values.extend(((value << 1) ^ (value >> 63)).to_be_bytes());
+        }
+
+        let decimal_type = |precision, scale| {
+            let mut column_type = test_column_type(Kind::DECIMAL);
+            column_type.set_precision(precision);
+            column_type.set_scale(scale);
+            column_type
+        };
+
+        write_single_stripe_file(
+            &path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT_V2,
+            vec![
+                (
+                    "price",
+                    decimal_type(18, 2),
+                    vec![
+                        (StreamKind::PRESENT, SECOND_ROW_NULL.to_vec()),
+                        (StreamKind::DATA, values),
+                    ],
+                ),
+                // A version 2 short repeat run of four sevens.
+                (
+                    "count",
+                    decimal_type(5, 0),
+                    vec![(StreamKind::DATA, vec![0x01, 0x0e])],
+                ),
+            ],
+        );
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let price = orc_file.get_field("price").unwrap();
+        assert_eq!(
+            (price.get_precision(), price.get_scale()),
+            (Some(18), Some(2))
+        );
+        assert_eq!(price.get_maximum_length(), None);
+
+        let schema = crate::schema::Schema::from_footer(orc_file.get_footer()).unwrap();
+        assert_eq!(
+            (schema.get_precision(1), schema.get_scale(1)),
+            (Some(5), Some(0))
+        );
+
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        assert!(matches!(
+            orc_file.column_info(&stripe_info, 0),
+            Ok(ColumnInfo::Decimal64 { scale: 2, .. })
+        ));
+
+        let rows = orc_file
+            .map_rows(&[0, 1], |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let decimal = |value, scale| OwnedValue::Decimal { value, scale };
+        assert_eq!(
+            rows,
+            vec![
+                vec![decimal(1234, 2), decimal(7, 0)],
+                vec![OwnedValue::Null, decimal(7, 0)],
+                vec![decimal(-5, 2), decimal(7, 0)],
+                vec![decimal(123456789012345678, 2), decimal(7, 0)],
+            ]
+        );
+        assert_eq!(
+            rows[2][0].as_value().to_decimal_string(),
+            Some("-0.05".to_string())
+        );
+    }
+
+    #[test]
+    fn unsupported_types() {
+        use orc_proto::type_::Kind;
+
+        let path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-unsupported.orc",
+            std::process::id()
+        ));
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        crate::split::write_selected_stripes_with_footer(
+            &mut orc_file,
+            &[0],
+            File::create(&path).unwrap(),
+            None,
+            |footer| footer.types[3].set_kind(Kind::DOUBLE),
+        )
+        .unwrap();
+
+        // The file can be opened, and only the unsupported column can't be read.
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+
+        assert!(orc_file.read_column(&stripe_info, 1).is_ok());
+        assert!(matches!(
+            orc_file.read_column(&stripe_info, 2),
+            Err(Error::UnsupportedType(TypeKind::Double))
+        ));
+        assert_eq!(
+            orc_file
+                .map_rows(&[0, 3], |_| Ok::<_, Error>(()))
+                .unwrap()
+                .count() as u64,
+            orc_file.get_footer().get_number_of_rows()
+        );
+        assert_eq!(orc_file.get_unsupported_field_indices(), vec![2]);
+
+        // Unsupported columns can be read as nulls instead.
+        orc_file.set_skip_unsupported_columns(true);
+        let column = orc_file.read_column(&stripe_info, 2).unwrap();
+        assert!((0..stripe_info.get_row_count()).all(|row| column.get(row) == Some(Value::Null)));
+        assert_eq!(column.get(stripe_info.get_row_count()), None);
+
+        let all_columns = (0..orc_file.get_field_names().len()).collect::<Vec<_>>();
+        assert!(orc_file
+            .map_rows(&all_columns, |values| Ok::<_, Error>(
+                values[2] == Value::Null
+            ))
+            .unwrap()
+            .all(|result| result.unwrap()));
+
+        // A nested field doesn't have to be the last field. The struct's child column is written
+        // as a second field and then moved into the struct.
+        let flat_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-nested-first-flat.orc",
+            std::process::id()
+        ));
+        let (_, long_type, long_streams) = sevens_column();
+        write_single_stripe_file(
+            &flat_path,
+            4,
+            orc_proto::column_encoding::Kind::DIRECT,
+            vec![
+                ("pair", test_column_type(Kind::STRUCT), vec![]),
+                ("x", long_type, long_streams),
+                sevens_column(),
+            ],
+        );
+
+        let mut flat_file = OrcFile::open(&flat_path).unwrap();
+        crate::split::write_selected_stripes_with_footer(
+            &mut flat_file,
+            &[0],
+            File::create(&path).unwrap(),
+            None,
+            |footer| {
+                footer.types[0].subtypes.remove(1);
+                footer.types[0].fieldNames.remove(1);
+                footer.types[1].subtypes.push(2);
+                footer.types[1].fieldNames.push("x".to_string());
+            },
+        )
+        .unwrap();
+        std::fs::remove_file(&flat_path).unwrap();
+
+        let mut orc_file = OrcFile::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+
+        assert_eq!(orc_file.get_field_names(), ["pair", "count"]);
+        assert_eq!(orc_file.get_field_column_indices(), vec![0, 2]);
+        assert_eq!(orc_file.get_unsupported_field_indices(), vec![0]);
+        assert!(matches!(
+            orc_file.read_column(&stripe_info, 0),
+            Err(Error::UnsupportedType(TypeKind::Struct))
+        ));
+
+        let rows = orc_file
+            .map_rows_by_path(&["count", "pair.x"], |values| {
+                Ok::<_, Error>(
+                    values
+                        .iter()
+                        .map(|value| value.to_owned_value())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows
+            .iter()
+            .all(|row| row[0] == row[1] && row[0] != OwnedValue::Null));
+
+        // The leading struct can be skipped while the rest of the fields are read.
+        orc_file.set_skip_unsupported_columns(true);
+        let rows = orc_file
+            .map_rows(&orc_file.get_field_column_indices(), |values| {
+                Ok::<_, Error>(
+                    values
+                        .iter()
+                        .map(|value| value.to_owned_value())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(rows, vec![vec![OwnedValue::Null, OwnedValue::I64(7)]; 4]);
+    }
+
+    #[test]
+    fn column_cache() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        orc_file.set_column_cache(Some(ColumnCache::new(usize::MAX)));
+
+        let read_rows = |orc_file: &mut OrcFile, columns: &[usize]| {
+            orc_file
+                .map_rows(columns, |values| {
+                    Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+                })
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        let first_pass = read_rows(&mut orc_file, &[0, 3, 9]);
+        let bytes_read = orc_file.get_metrics().get_bytes_read();
+        assert_eq!(orc_file.get_column_cache().unwrap().len(), 3);
+
+        // The overlapping columns are served from the cache.
+        let second_pass = read_rows(&mut orc_file, &[3, 9, 10]);
+        let column_cache = orc_file.get_column_cache().unwrap();
+        assert_eq!(column_cache.get_hit_count(), 2);
+        assert_eq!(column_cache.get_miss_count(), 4);
+        assert!(orc_file.get_metrics().get_bytes_read() > bytes_read);
+
+        let bytes_read = orc_file.get_metrics().get_bytes_read();
+        let third_pass = read_rows(&mut orc_file, &[0, 3, 9]);
+        assert_eq!(orc_file.get_metrics().get_bytes_read(), bytes_read);
+        assert_eq!(third_pass, first_pass);
+        assert_eq!(
+            second_pass
+                .iter()
+                .map(|row| row[..2].to_vec())
+                .collect::<Vec<_>>(),
+            first_pass
+                .iter()
+                .map(|row| row[1..].to_vec())
+                .collect::<Vec<_>>()
+        );
+
+        orc_file.set_utf8_policy(Utf8Policy::Lossy);
+        assert!(orc_file.get_column_cache().unwrap().is_empty());
+    }
+
+    #[test]
+    fn column_encodings() {
+        let mut orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        let encodings = stripe_info.column_encodings();
+
+        assert_eq!(encodings.len(), TS_FIELD_NAMES.len() + 1);
+        assert_eq!(encodings[0].get_kind(), EncodingKind::Direct);
+        assert_eq!(encodings[1].get_kind(), EncodingKind::DirectV2);
+        assert!(!encodings[1].is_dictionary());
+        assert_eq!(encodings[1].get_dictionary_size(), None);
+
+        let layouts = orc_file.column_layout().unwrap();
+        assert!(encodings[7].is_dictionary());
+        assert_eq!(
+            encodings[7].get_dictionary_size(),
+            layouts[7].get_dictionary_size()
+        );
+    }
+
+    #[test]
+    fn file_statistics() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let total_length = orc_file
+            .map_rows(&[3], |values| {
+                Ok::<_, Error>(match values[0] {
+                    Value::Utf8(value) => value.len() as u64,
+                    _ => 0,
+                })
+            })
+            .unwrap()
+            .sum::<Result<u64, _>>()
+            .unwrap();
+        let row_count = orc_file.get_footer().get_number_of_rows();
+        let statistics = orc_file.get_file_statistics();
+
+        assert_eq!(statistics.len(), TS_FIELD_NAMES.len() + 1);
+        assert_eq!(statistics[1].get_number_of_values(), Some(row_count));
+        assert!(matches!(
+            statistics[1].get_range(),
+            Some(ValueRange::Integer(min, max)) if min <= max
+        ));
+        assert!(matches!(
+            statistics[4].get_range(),
+            Some(ValueRange::String(min, max)) if min <= max
+        ));
+        assert_eq!(statistics[4].get_total_length(), Some(total_length));
+        assert_eq!(statistics[1].get_total_length(), None);
+    }
+
+    #[test]
+    fn map_rows_by_path() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+
+        assert_eq!(orc_file.get_column_id_by_path("screen_name"), Some(4));
+        assert_eq!(orc_file.get_column_id_by_path("screen_name.first"), None);
+        assert_eq!(
+            orc_file
+                .get_schema()
+                .get_path("screen_name")
+                .map(|schema| (schema.get_column_id(), schema.get_kind())),
+            Some((4, TypeKind::String))
+        );
+
+        let by_path = orc_file
+            .map_rows_by_path(&["verified", "id"], |values| {
+                Ok::<_, Error>(format!("{:?}", values))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let by_index = orc_file
+            .map_rows(&[9, 0], |values| Ok::<_, Error>(format!("{:?}", values)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(by_path, by_index);
+        assert!(matches!(
+            orc_file.map_rows_by_path(&["id", "user.id"], |_| Ok::<_, Error>(())),
+            Err(Error::InvalidPath(path)) if path == "user.id"
+        ));
+    }
+
+    #[test]
+    fn read_column_by_id() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let stripe_info = orc_file.get_stripe_info().unwrap();
+        let field_index = orc_file.get_field_index("screen_name").unwrap();
+        let column_id = orc_file.get_column_id(field_index).unwrap();
+
+        assert_eq!(column_id, 4);
+        assert_eq!(orc_file.get_column_id(TS_FIELD_NAMES.len()), None);
+
+        let by_index = orc_file.read_column(&stripe_info[0], field_index).unwrap();
+        let by_id = orc_file
+            .read_column_by_id(&stripe_info[0], column_id)
+            .unwrap();
+
+        assert_eq!(
+            (0..stripe_info[0].get_row_count())
+                .map(|row| by_index.get(row))
+                .collect::<Vec<_>>(),
+            (0..stripe_info[0].get_row_count())
+                .map(|row| by_id.get(row))
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            orc_file.read_column_by_id(&stripe_info[0], 0),
+            Err(Error::InvalidColumnIndex(0))
+        ));
+
+        let screen_names = orc_file
+            .map_rows_by_id(&[column_id], |values| {
+                Ok::<_, Error>(values[0].to_owned_value())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            screen_names.len() as u64,
+            orc_file.get_footer().get_number_of_rows()
+        );
+    }
+
+    #[test]
+    fn read_columns_strict() {
+        for path in [TS_10K_EXAMPLE_PATH, TS_1K_ZLIB_PATH, TS_1K_NONE_PATH] {
+            let mut orc_file = OrcFile::open(path).unwrap();
+            orc_file.set_strict(true);
+
+            for stripe in orc_file.get_stripe_info().unwrap() {
+                for column_id in 0..stripe.get_column_count() {
+                    orc_file.read_column(&stripe, column_id).unwrap();
+                }
+            }
+
+            assert!(!orc_file.stripe_statistics.unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn total_length_overflow() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let stripe_info = orc_file.get_stripe_info().unwrap();
+
+        assert_eq!(
+            OrcFile::total_length(&stripe_info[0], 4, &[1, 2, 3]).unwrap(),
+            6
+        );
+        assert!(matches!(
+            OrcFile::total_length(&stripe_info[0], 4, &[1, u64::MAX, 3]),
+            Err(Error::InvalidValue {
+                stripe_index: 0,
+                column_index: 4,
+                row_index: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn read_metrics() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let callback_metrics = Arc::new(std::sync::Mutex::new(vec![]));
+        let callback_metrics_clone = callback_metrics.clone();
+        orc_file.set_metrics_callback(move |metrics| {
+            callback_metrics_clone.lock().unwrap().push(*metrics);
+        });
+
+        let stripe_info = orc_file.get_stripe_info().unwrap();
+        let column_ids = [0, 3, 9];
+
+        for stripe in &stripe_info {
+            for column_id in column_ids {
+                orc_file.read_column(stripe, column_id).unwrap();
+            }
+        }
+
+        let metrics = *orc_file.get_metrics();
+        let callback_metrics = callback_metrics.lock().unwrap();
+        let data_len: u64 = stripe_info.iter().map(|stripe| stripe.get_data_len()).sum();
+
+        assert_eq!(callback_metrics.len(), stripe_info.len() * column_ids.len());
+        assert_eq!(metrics.get_columns_read(), callback_metrics.len() as u64);
+        assert_eq!(
+            metrics.get_values_decoded(),
+            orc_file.get_footer().get_number_of_rows() * column_ids.len() as u64
+        );
+        assert!(metrics.get_bytes_read() > 0 && metrics.get_bytes_read() < data_len);
+        assert!(metrics.get_bytes_decompressed() > metrics.get_bytes_read());
+        assert_eq!(
+            metrics.get_bytes_read(),
+            callback_metrics
+                .iter()
+                .map(|metrics| metrics.get_bytes_read())
+                .sum::<u64>()
+        );
+
+        orc_file.reset_metrics();
+        assert_eq!(orc_file.get_metrics(), &ReadMetrics::default());
+    }
+
+    #[test]
+    fn skip_present_without_nulls() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let stripe_info = orc_file.get_stripe_info().unwrap();
+        // The url column has nulls.
+        let column_id = 5;
+
+        orc_file.read_column(&stripe_info[0], column_id).unwrap();
+        let bytes_read = orc_file.get_metrics().get_bytes_read();
+        orc_file.reset_metrics();
+
+        // Pretend the statistics say there are no nulls.
+        Arc::make_mut(orc_file.stripe_statistics.as_mut().unwrap())[0].colStats[column_id + 1]
+            .set_hasNull(false);
+
+        orc_file.read_column(&stripe_info[0], column_id).unwrap();
+        assert!(orc_file.get_metrics().get_bytes_read() < bytes_read);
+        orc_file.reset_metrics();
+
+        // In strict mode the present stream is always read.
+        orc_file.set_strict(true);
+        assert!(orc_file.read_column(&stripe_info[0], column_id).is_err());
+        assert_eq!(orc_file.get_metrics().get_bytes_read(), bytes_read);
     }
 
-    pub fn get_footer(&self) -> &Footer {
-        &self.footer
+    #[test]
+    fn load_stripe_footers_lazily() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let stripe_count = orc_file.get_footer().get_stripes().len();
+        assert!(orc_file.stripe_footers.iter().all(Option::is_none));
+
+        let rows = orc_file
+            .map_rows_in_stripes(0..0, &[0], |_| Ok::<_, Error>(()))
+            .unwrap()
+            .count();
+        assert_eq!(rows, 0);
+        assert!(orc_file.stripe_footers.iter().all(Option::is_none));
+
+        assert!(orc_file
+            .map_rows_in_stripes(0..stripe_count + 1, &[0], |_| Ok::<_, Error>(()))
+            .is_err());
+
+        let rows = orc_file
+            .map_rows_in_stripes(0..stripe_count, &[0], |_| Ok::<_, Error>(()))
+            .unwrap()
+            .count();
+        assert_eq!(rows as u64, orc_file.get_footer().get_number_of_rows());
+        assert!(orc_file.stripe_footers.iter().all(Option::is_some));
     }
 
-    pub fn get_stripe_footers(&mut self) -> Result<Vec<StripeFooter>, Error> {
-        let stripe_count = self.footer.stripes.len();
-        let mut stripe_footers = Vec::with_capacity(stripe_count);
+    #[test]
+    fn filter_map_rows() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let expected = orc_file
+            .map_rows(&[3, 9, 10], |values| {
+                Ok::<_, Error>(
+                    values
+                        .iter()
+                        .map(|value| value.to_owned_value())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap()
+            .filter(|values| values.as_ref().unwrap()[1] == OwnedValue::Bool(true))
+            .map(|values| {
+                let values = values.unwrap();
+                vec![values[2].clone(), values[0].clone()]
+            })
+            .collect::<Vec<_>>();
 
-        for i in 0..stripe_count {
-            let stripe_info = &self.footer.stripes[i];
-            let footer_start =
-                stripe_info.offset() + stripe_info.indexLength() + stripe_info.dataLength();
-            let footer_len = stripe_info.footerLength();
+        let result = orc_file
+            .filter_map_rows(
+                &[9, 3],
+                |values| values[0] == Value::Bool(true),
+                &[10, 3],
+                |values| {
+                    Ok::<_, Error>(
+                        values
+                            .iter()
+                            .map(|value| value.to_owned_value())
+                            .collect::<Vec<_>>(),
+                    )
+                },
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
 
-            let stripe_footer = self.read_message(SeekFrom::Start(footer_start), footer_len)?;
+        assert!(!expected.is_empty());
+        assert_eq!(result, expected);
 
-            stripe_footers.push(stripe_footer);
-        }
+        // If nothing matches, the output columns are never decoded.
+        orc_file.reset_metrics();
+        let result = orc_file
+            .filter_map_rows(&[9], |_| false, &[3, 10], |_| Ok::<_, Error>(()))
+            .unwrap()
+            .count();
 
-        Ok(stripe_footers)
+        assert_eq!(result, 0);
+        assert_eq!(
+            orc_file.get_metrics().get_columns_read(),
+            orc_file.get_footer().get_stripes().len() as u64
+        );
     }
 
-    pub fn get_stripe_info(&mut self) -> Result<Vec<StripeInfo>, Error> {
-        let stripe_footers = self.get_stripe_footers()?;
+    #[test]
+    fn row_cursor() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let expected = orc_file
+            .map_rows(&[0, 3], |values| {
+                Ok::<_, Error>((values[0].as_i64().unwrap(), values[1].to_owned_value()))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
 
-        stripe_footers
-            .iter()
-            .enumerate()
-            .map(|(i, stripe_footer)| {
-                let stripe_orig_info = &self.footer.stripes[i];
-                let row_count = stripe_orig_info.numberOfRows() as usize;
-                let data_start = stripe_orig_info.offset() + stripe_orig_info.indexLength();
-                let data_len = stripe_orig_info.dataLength();
-
-                let column_count = stripe_footer.columns.len();
-                let mut column_data_stream_infos =
-                    vec![ColumnDataStreamInfo::default(); column_count];
-
-                for stream in &stripe_footer.streams {
-                    let kind = stream.kind();
-                    let column_id = stream.column() as usize;
-                    let length = stream.length();
-                    match kind {
-                        StreamKind::DATA => {
-                            column_data_stream_infos[column_id - 1].data_len = length;
-                        }
-                        StreamKind::LENGTH => {
-                            column_data_stream_infos[column_id - 1].length_len = length;
-                        }
-                        StreamKind::PRESENT => {
-                            column_data_stream_infos[column_id - 1].present_len = length;
-                        }
-                        StreamKind::DICTIONARY_DATA => {
-                            column_data_stream_infos[column_id - 1].dictionary_data_len = length;
-                        }
-                        _ => {}
-                    }
-                }
+        let mut cursor = orc_file.row_cursor(&[0, 3]).unwrap();
+        let mut result = vec![];
 
-                let mut current_offset = 0;
+        while let Some(row) = cursor.next_row().unwrap() {
+            result.push((row[0].as_i64().unwrap(), row[1].to_owned_value()));
+        }
 
-                let columns = stripe_footer
-                    .columns
-                    .iter()
-                    .skip(1) // Skip the struct column
-                    .zip(&self.type_kinds)
-                    .zip(column_data_stream_infos)
-                    .map(|((column_encoding, type_kind), stream_info)| {
-                        let result = match (type_kind, column_encoding.kind()) {
-                            (TypeKind::LONG | TypeKind::INT, encoding_kind) => {
-                                if stream_info.dictionary_data_len != 0
-                                    || stream_info.length_len != 0
-                                    || (encoding_kind != ColumnEncodingKind::DIRECT
-                                        && encoding_kind != ColumnEncodingKind::DIRECT_V2)
-                                {
-                                    Err(Error::InvalidMetadata)
-                                } else {
-                                    Ok(ColumnInfo::U64 {
-                                        offset: current_offset,
-                                        present_len: if stream_info.present_len == 0 {
-                                            None
-                                        } else {
-                                            Some(stream_info.present_len)
-                                        },
-                                        data_len: stream_info.data_len,
-                                        version: encoding_kind.into(),
-                                    })
-                                }
-                            }
-                            (TypeKind::BOOLEAN, ColumnEncodingKind::DIRECT) => {
-                                if stream_info.dictionary_data_len != 0
-                                    || stream_info.length_len != 0
-                                {
-                                    Err(Error::InvalidMetadata)
-                                } else {
-                                    Ok(ColumnInfo::Bool {
-                                        offset: current_offset,
-                                        present_len: if stream_info.present_len == 0 {
-                                            None
-                                        } else {
-                                            Some(stream_info.present_len)
-                                        },
-                                        data_len: stream_info.data_len,
-                                    })
-                                }
-                            }
-                            (
-                                TypeKind::STRING,
-                                encoding_kind @ (ColumnEncodingKind::DIRECT
-                                | ColumnEncodingKind::DIRECT_V2),
-                            ) => {
-                                if stream_info.dictionary_data_len != 0 {
-                                    Err(Error::InvalidMetadata)
-                                } else {
-                                    Ok(ColumnInfo::Utf8Direct {
-                                        offset: current_offset,
-                                        present_len: if stream_info.present_len == 0 {
-                                            None
-                                        } else {
-                                            Some(stream_info.present_len)
-                                        },
-                                        data_len: stream_info.data_len,
-                                        length_len: stream_info.length_len,
-                                        version: encoding_kind.into(),
-                                    })
-                                }
-                            }
-                            (
-                                TypeKind::STRING,
-                                encoding_kind @ (ColumnEncodingKind::DICTIONARY
-                                | ColumnEncodingKind::DICTIONARY_V2),
-                            ) => Ok(ColumnInfo::Utf8Dictionary {
-                                offset: current_offset,
-                                present_len: if stream_info.present_len == 0 {
-                                    None
-                                } else {
-                                    Some(stream_info.present_len)
-                                },
-                                data_len: stream_info.data_len,
-                                dictionary_data_len: stream_info.dictionary_data_len,
-                                length_len: stream_info.length_len,
-                                version: encoding_kind.into(),
-                                dictionary_size: column_encoding.dictionarySize(),
-                            }),
-                            (kind, _) => Err(Error::UnsupportedType(*kind)),
-                        };
-                        current_offset += stream_info.len();
-                        result
-                    })
-                    .collect::<Result<Vec<ColumnInfo>, Error>>()?;
+        assert_eq!(result, expected);
+        assert!(cursor.next_row().unwrap().is_none());
 
-                Ok(StripeInfo {
-                    row_count,
-                    data_start,
-                    data_len,
-                    columns,
-                })
+        let mut cursor = orc_file.row_cursor(&[TS_FIELD_NAMES.len()]).unwrap();
+        assert!(matches!(
+            cursor.next_row(),
+            Err(Error::InvalidColumnIndex(_))
+        ));
+        assert!(cursor.next_row().unwrap().is_none());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_map_rows() {
+        let mut orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let columns = [0, 3, 9];
+        let expected = orc_file
+            .map_rows(&columns, |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
             })
-            .collect()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let result = orc_file
+            .par_map_rows(&columns, |values| {
+                Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(result, expected);
+
+        let results = orc_file
+            .par_map_rows(&[TS_FIELD_NAMES.len()], |_| Ok::<_, Error>(()))
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert!(matches!(results[..], [Err(Error::InvalidColumnIndex(_))]));
     }
-}
 
-pub struct MappedRows<'a, F> {
-    file: &'a mut OrcFile,
-    stripe_info: Vec<StripeInfo>,
-    columns: Vec<usize>,
-    f: F,
-    data: Vec<Column>,
-    current_stripe: usize,
-    current_row: usize,
-}
+    #[test]
+    fn stats_collector() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+        let columns = [0, 3, 5];
+        let stats_collector = StatsCollector::new();
 
-impl<'a, F> MappedRows<'a, F> {
-    fn new(
-        file: &'a mut OrcFile,
-        stripe_info: Vec<StripeInfo>,
-        columns: Vec<usize>,
-        f: F,
-    ) -> MappedRows<'a, F> {
-        Self {
-            file,
-            stripe_info,
-            columns,
-            f,
-            data: vec![],
-            current_stripe: 0,
-            current_row: 0,
+        let count = orc_file
+            .map_rows(&columns, |_| Ok::<_, Error>(()))
+            .unwrap()
+            .with_stats_collector(stats_collector.clone())
+            .count();
+        let statistics = stats_collector.get_statistics();
+
+        assert_eq!(count as u64, orc_file.get_footer().get_number_of_rows());
+        assert_eq!(statistics.len(), columns.len());
+
+        for (column_index, computed) in columns.iter().zip(&statistics) {
+            let stored = &orc_file.get_file_statistics()[column_index + 1];
+
+            assert_eq!(
+                Some(computed.get_number_of_values()),
+                stored.get_number_of_values()
+            );
+            assert_eq!(Some(computed.get_null_count() > 0), stored.has_null());
+            assert_eq!(computed.get_range(), stored.get_range());
         }
     }
-}
 
-impl<T, E, F> Iterator for MappedRows<'_, F>
-where
-    E: From<Error>,
-    F: FnMut(&[Value<'_>]) -> Result<T, E>,
-{
-    type Item = Result<T, E>;
+    #[test]
+    fn plan_ranges() {
+        let mut orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let columns = [0, 3, 9];
+        let ranges = orc_file.plan_ranges(0..1, &columns).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+
+        assert_eq!(ranges.len(), columns.len());
+        assert!(ranges
+            .windows(2)
+            .all(|pair| pair[0].get_end() <= pair[1].get_start()));
+        assert!(ranges.iter().all(|range| range.get_stripe_index() == 0
+            && range.get_start() >= stripe_info.data_start
+            && range.get_end() <= stripe_info.data_start + stripe_info.get_data_len()));
+
+        // Without compression, reading the columns reads exactly the planned bytes.
+        let before = orc_file.get_metrics().get_bytes_read();
+        for column_index in columns {
+            orc_file.read_column(&stripe_info, column_index).unwrap();
+        }
+        assert_eq!(
+            orc_file.get_metrics().get_bytes_read() - before,
+            ranges.iter().map(ByteRange::get_len).sum::<u64>()
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_stripe >= self.stripe_info.len() {
-            None
-        } else {
-            let stripe_info = &self.stripe_info[self.current_stripe];
+        assert!(orc_file.plan_ranges(0..2, &columns).is_err());
+        assert!(orc_file.plan_ranges(0..1, &[100]).is_err());
+    }
 
-            if self.current_row >= stripe_info.get_row_count() {
-                self.data.clear();
-                self.current_stripe += 1;
-                self.current_row = 0;
-                self.next()
-            } else {
-                if self.current_row == 0 {
-                    for i in &self.columns {
-                        let column = match self.file.read_column(stripe_info, *i) {
-                            Ok(column) => column,
-                            Err(error) => {
-                                // Unrecoverable error.
-                                self.current_stripe = self.stripe_info.len();
-                                return Some(Err(E::from(error)));
-                            }
-                        };
-                        self.data.push(column);
-                    }
-                }
+    #[test]
+    fn memory_pool() {
+        let mut orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let stripe_info = orc_file.read_stripe_info(0).unwrap();
+        let columns = [0, 3, 5];
+        let stripe_size = columns
+            .iter()
+            .map(|i| {
+                orc_file
+                    .read_column(&stripe_info, *i)
+                    .unwrap()
+                    .get_data_size()
+            })
+            .sum::<usize>();
 
-                let mut values = Vec::with_capacity(self.data.len());
+        let memory_pool = MemoryPool::new(stripe_size);
+        orc_file.set_memory_pool(Some(memory_pool.clone()));
+        let mut other_file = orc_file.try_clone().unwrap();
 
-                for (column, column_index) in self.data.iter().zip(&self.columns) {
-                    match column.get(self.current_row) {
-                        Some(value) => values.push(value),
-                        None => {
-                            let error = Error::InvalidValue {
-                                stripe_index: self.current_stripe,
-                                column_index: *column_index,
-                                row_index: self.current_row,
-                            };
+        let mut cursor = orc_file.row_cursor(&columns).unwrap();
+        assert!(cursor.next_row().unwrap().is_some());
+        assert_eq!(memory_pool.get_used(), stripe_size);
 
-                            // Unrecoverable error.
-                            self.current_stripe = self.stripe_info.len();
-                            return Some(Err(E::from(error)));
-                        }
-                    }
-                }
+        // The other handle shares the pool, which is full.
+        let mut other_rows = other_file
+            .map_rows(&columns, |_| Ok::<_, Error>(()))
+            .unwrap();
+        assert!(matches!(
+            other_rows.next(),
+            Some(Err(Error::LimitExceeded {
+                limit: Limit::PoolMemory,
+                ..
+            }))
+        ));
 
-                self.current_row += 1;
-                Some((self.f)(&values))
-            }
-        }
+        while cursor.next_row().unwrap().is_some() {}
+        assert_eq!(memory_pool.get_used(), 0);
+
+        let count = other_file
+            .map_rows(&columns, |_| Ok::<_, Error>(()))
+            .unwrap()
+            .count();
+        assert_eq!(count as u64, other_file.get_footer().get_number_of_rows());
+        assert_eq!(memory_pool.get_used(), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        proto::orc_proto::{CompressionKind, PostScript},
-        value::Value,
-    };
-    use serde_derive::Deserialize;
-    use std::collections::HashSet;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    #[test]
+    fn progress_and_cancellation() {
+        let mut orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let row_count = orc_file.get_footer().get_number_of_rows();
+
+        let copies_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-progress.orc",
+            std::process::id()
+        ));
+        crate::split::write_selected_stripes(
+            &mut orc_file,
+            &[0, 0, 0],
+            File::create(&copies_path).unwrap(),
+        )
+        .unwrap();
+
+        let mut copies = OrcFile::open(&copies_path).unwrap();
+        std::fs::remove_file(&copies_path).unwrap();
+
+        let reports = Arc::new(std::sync::Mutex::new(vec![]));
+        let callback_reports = reports.clone();
+        let count = copies
+            .map_rows_in_stripes(1..3, &[0], |_| Ok::<_, Error>(()))
+            .unwrap()
+            .with_progress_callback(move |progress| {
+                callback_reports.lock().unwrap().push(*progress)
+            })
+            .count();
+        let reports = reports.lock().unwrap();
+
+        assert_eq!(count as u64, row_count * 2);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].get_stripes_processed(), 1);
+        assert_eq!(reports[0].get_rows_processed(), row_count);
+        assert_eq!(reports[1].get_stripe_count(), 2);
+        assert_eq!(reports[1].get_rows_processed(), row_count * 2);
+        assert_eq!(reports[1].get_fraction(), 1.0);
+
+        // Cancel from inside the scan after a few rows.
+        let token = CancellationToken::new();
+        let results = copies
+            .map_rows(&[0], |_| {
+                token.cancel();
+                Ok::<_, Error>(())
+            })
+            .unwrap()
+            .with_cancellation(token.clone())
+            .collect::<Vec<_>>();
 
-    const TS_10K_EXAMPLE_PATH: &str = "examples/ts-10k-zstd-2020-09-20.orc";
-    const TS_1K_ZLIB_PATH: &str = "examples/ts-1k-zlib-2020-09-20.orc";
-    const TS_1K_NONE_PATH: &str = "examples/ts-1k-none-2020-09-20.orc";
-    const TS_1K_JSON_PATH: &str = "examples/ts-1k-2020-09-20.ndjson";
-    const TS_FIELD_NAMES: [&str; 11] = [
-        "id",
-        "status_id",
-        "timestamp",
-        "screen_name",
-        "name",
-        "url",
-        "location",
-        "description",
-        "profile_image_url",
-        "verified",
-        "followers_count",
-    ];
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::Cancelled)));
+    }
 
     #[test]
-    fn get_postscript() {
-        let orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
-        let postscript = orc_file.get_postscript();
+    fn row_numbers() {
+        let mut orc_file = OrcFile::open(TS_1K_NONE_PATH).unwrap();
+        let row_count = orc_file.get_footer().get_number_of_rows();
+
+        // Build a file with three copies of the only stripe.
+        let copies_path = std::env::temp_dir().join(format!(
+            "orcrs-parser-test-{}-row-numbers.orc",
+            std::process::id()
+        ));
+        crate::split::write_selected_stripes(
+            &mut orc_file,
+            &[0, 0, 0],
+            File::create(&copies_path).unwrap(),
+        )
+        .unwrap();
+
+        let mut copies = OrcFile::open(&copies_path).unwrap();
+        std::fs::remove_file(&copies_path).unwrap();
+
+        let result = copies
+            .map_rows_in_stripes(1..3, &[0], |values| {
+                Ok::<_, Error>((values[0].as_u64().unwrap(), values[1].to_owned_value()))
+            })
+            .unwrap()
+            .with_row_numbers()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = orc_file
+            .map_rows(&[0], |values| Ok::<_, Error>(values[0].to_owned_value()))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
 
-        let mut expected = PostScript::default();
-        expected.set_footerLength(1065);
-        expected.set_compression(CompressionKind::ZSTD);
-        expected.set_compressionBlockSize(262144);
-        expected.version = vec![0, 12];
-        expected.set_metadataLength(909);
-        expected.set_writerVersion(9);
-        expected.set_magic("ORC".to_string());
+        assert_eq!(result.len() as u64, 2 * row_count);
+        assert!(result
+            .iter()
+            .zip((row_count..).zip(expected.iter().cycle()))
+            .all(
+                |((row_number, value), (expected_row_number, expected_value))| {
+                    *row_number == expected_row_number && value == expected_value
+                }
+            ));
 
-        assert_eq!(*postscript, expected);
+        let mut cursor = copies.row_cursor(&[0]).unwrap().with_row_numbers();
+        assert_eq!(cursor.next_row().unwrap().unwrap()[0], Value::U64(0));
     }
 
     #[test]
@@ -825,9 +5483,9 @@ mod tests {
         let orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
         let footer = orc_file.get_footer();
 
-        assert_eq!(footer.headerLength(), 3);
-        assert_eq!(footer.contentLength(), 937322);
-        assert_eq!(footer.stripes.len(), 1);
+        assert_eq!(footer.get_header_length(), 3);
+        assert_eq!(footer.get_content_length(), 937322);
+        assert_eq!(footer.get_stripes().len(), 1);
     }
 
     #[test]
@@ -838,9 +5496,9 @@ mod tests {
         for stripe in orc_file.get_stripe_info().unwrap() {
             let column = orc_file.read_column(&stripe, 0).unwrap();
 
-            for row_index in 0..stripe.get_row_count() as usize {
+            for row_index in 0..stripe.get_row_count() {
                 match column.get(row_index).unwrap() {
-                    Value::U64(value) => {
+                    Value::I64(value) => {
                         user_ids.insert(value);
                     }
                     other => {
@@ -862,7 +5520,7 @@ mod tests {
         for stripe in orc_file.get_stripe_info().unwrap() {
             let column = orc_file.read_column(&stripe, 4).unwrap();
 
-            for row_index in 0..stripe.get_row_count() as usize {
+            for row_index in 0..stripe.get_row_count() {
                 match column.get(row_index).unwrap() {
                     Value::Utf8(value) => {
                         names.insert(value.to_string());
@@ -890,7 +5548,7 @@ mod tests {
         for stripe in orc_file.get_stripe_info().unwrap() {
             let column = orc_file.read_column(&stripe, 6).unwrap();
 
-            for row_index in 0..stripe.get_row_count() as usize {
+            for row_index in 0..stripe.get_row_count() {
                 match column.get(row_index).unwrap() {
                     Value::Utf8(value) => {
                         locations.insert(value.to_string());
@@ -917,7 +5575,7 @@ mod tests {
         for stripe in orc_file.get_stripe_info().unwrap() {
             let column = orc_file.read_column(&stripe, 9).unwrap();
 
-            for row_index in 0..stripe.get_row_count() as usize {
+            for row_index in 0..stripe.get_row_count() {
                 match column.get(row_index).unwrap() {
                     Value::Bool(value) => {
                         if value {
@@ -941,7 +5599,7 @@ mod tests {
 
         let result = orc_file
             .map_rows(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10], |values| {
-                let id = values.get(0).and_then(|value| value.as_u64()).unwrap();
+                let id = values.first().and_then(|value| value.as_i64()).unwrap();
 
                 // Let a couple of rows through successfully.
                 if id == 762674860875276288 || id == 1237648870118580224 {
@@ -962,20 +5620,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stripe_readers_across_threads() {
+        let mut orc_file = OrcFile::open(TS_10K_EXAMPLE_PATH).unwrap();
+        let expected = orc_file
+            .map_rows(&[0, 3], |values| {
+                Ok::<_, Error>(values[0].as_i64().unwrap())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let handles = orc_file
+            .into_stripe_readers()
+            .unwrap()
+            .into_iter()
+            .map(|reader| {
+                std::thread::spawn(move || {
+                    reader
+                        .map_rows(&[0, 3], |values| {
+                            Ok::<_, Error>(values[0].as_i64().unwrap())
+                        })
+                        .unwrap()
+                        .collect::<Result<Vec<_>, _>>()
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let result = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_try_clone_across_threads() {
+        let orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+
+        let handles = (0..4)
+            .map(|_| {
+                let mut clone = orc_file.try_clone().unwrap();
+                assert!(Arc::ptr_eq(&clone.metadata, &orc_file.metadata));
+
+                std::thread::spawn(move || {
+                    clone
+                        .map_rows(&[3], |values| Ok::<_, Error>(values[0].to_owned_value()))
+                        .unwrap()
+                        .collect::<Result<Vec<_>, _>>()
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let results = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results[0].len() as u64,
+            orc_file.get_footer().get_number_of_rows()
+        );
+        assert!(results.iter().all(|result| *result == results[0]));
+    }
+
     #[test]
     fn test_compression_ts_1k_zlib() {
-        test_compression_ts_1k(CompressionKind::ZLIB);
+        test_compression_ts_1k(CompressionKind::Zlib);
+    }
+
+    struct SnappyCodec;
+
+    impl compress::BlockCodec for SnappyCodec {
+        fn decompress(
+            &self,
+            input: &[u8],
+            output: &mut Vec<u8>,
+            _max_len: Option<usize>,
+        ) -> Result<(), std::io::Error> {
+            output.extend(snap::raw::Decoder::new().decompress_vec(input)?);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_compression_ts_1k_snappy_codec() {
+        let mut codecs = CodecRegistry::new();
+        codecs.register(CompressionKind::Snappy, SnappyCodec);
+
+        let orc_file =
+            OrcFile::open_with_codecs(TS_1K_SNAPPY_PATH, ParseLimits::default(), codecs).unwrap();
+        assert_eq!(
+            orc_file.get_postscript().get_compression(),
+            CompressionKind::Snappy
+        );
+        assert!(orc_file.get_codecs().is_supported(CompressionKind::Snappy));
+
+        let columns = (0..TS_FIELD_NAMES.len()).collect::<Vec<_>>();
+        let read_rows = |mut orc_file: OrcFile| {
+            orc_file
+                .map_rows(&columns, |values| {
+                    Ok::<_, Error>(values.iter().map(Value::to_owned_value).collect::<Vec<_>>())
+                })
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        let expected = read_rows(OrcFile::open(TS_1K_NONE_PATH).unwrap());
+        assert_eq!(read_rows(orc_file.try_clone().unwrap()), expected);
+        assert_eq!(read_rows(orc_file), expected);
     }
 
     #[test]
     fn test_compression_ts_1k_none() {
-        test_compression_ts_1k(CompressionKind::NONE);
+        test_compression_ts_1k(CompressionKind::None);
     }
 
     fn test_compression_ts_1k(compression: CompressionKind) {
         let orc_file_path = match compression {
-            CompressionKind::ZLIB => TS_1K_ZLIB_PATH,
-            CompressionKind::NONE => TS_1K_NONE_PATH,
+            CompressionKind::Zlib => TS_1K_ZLIB_PATH,
+            CompressionKind::None => TS_1K_NONE_PATH,
             other => panic!("No example data for compression type {:?}", other),
         };
         let mut orc_file = OrcFile::open(orc_file_path).unwrap();
@@ -984,9 +5752,18 @@ mod tests {
 
         let user_rows = orc_file
             .map_rows(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10], |values| {
-                let id = values.get(0).and_then(|value| value.as_u64()).unwrap();
-                let status_id = values.get(1).and_then(|value| value.as_u64()).unwrap();
-                let timestamp = values.get(2).and_then(|value| value.as_u64()).unwrap();
+                let id = values
+                    .first()
+                    .and_then(|value| value.to_u64_checked())
+                    .unwrap();
+                let status_id = values
+                    .get(1)
+                    .and_then(|value| value.to_u64_checked())
+                    .unwrap();
+                let timestamp = values
+                    .get(2)
+                    .and_then(|value| value.to_u64_checked())
+                    .unwrap();
                 let screen_name = values.get(3).and_then(|value| value.as_string()).unwrap();
                 let name = values
                     .get(4)
@@ -1014,7 +5791,7 @@ mod tests {
                     .unwrap();
                 let followers_count = values
                     .get(10)
-                    .and_then(|value| value.as_nullable_u64())
+                    .and_then(|value| value.as_nullable_i64())
                     .unwrap();
 
                 Ok(UserRow {
@@ -1042,18 +5819,18 @@ mod tests {
 
     #[test]
     fn test_deserialize_ts_1k_zlib() {
-        test_deserialize_ts_1k(CompressionKind::ZLIB);
+        test_deserialize_ts_1k(CompressionKind::Zlib);
     }
 
     #[test]
     fn test_deserialize_ts_1k_none() {
-        test_deserialize_ts_1k(CompressionKind::NONE);
+        test_deserialize_ts_1k(CompressionKind::None);
     }
 
     fn test_deserialize_ts_1k(compression: CompressionKind) {
         let orc_file_path = match compression {
-            CompressionKind::ZLIB => TS_1K_ZLIB_PATH,
-            CompressionKind::NONE => TS_1K_NONE_PATH,
+            CompressionKind::Zlib => TS_1K_ZLIB_PATH,
+            CompressionKind::None => TS_1K_NONE_PATH,
             other => panic!("No example data for compression type {:?}", other),
         };
 
@@ -1079,6 +5856,91 @@ mod tests {
         assert!(result[0].is_err());
     }
 
+    #[test]
+    fn test_deserialize_with_field_name_matching() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+
+        assert!(orc_file
+            .deserialize::<CamelCaseUserRow>()
+            .next()
+            .unwrap()
+            .is_err());
+
+        let result = orc_file
+            .deserialize_with::<CamelCaseUserRow>(&FieldNameMatching::new().ignore_case_style())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        for (result, expected) in result.iter().zip(load_ts_1k_json()) {
+            assert_eq!(result.ID, expected.id);
+            assert_eq!(result.screenName, expected.screen_name);
+            assert_eq!(result.FollowersCount, expected.followers_count);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_with_converters() {
+        let mut orc_file = OrcFile::open(TS_1K_ZLIB_PATH).unwrap();
+
+        // The first 41 bits of a Twitter ID are milliseconds since the Twitter epoch.
+        let converters = ValueConverters::new()
+            .with_converter("status_id", |value| match value {
+                Value::I64(id) => Ok(OwnedValue::I64((id >> 22) + 1288834974657)),
+                other => Err(format!("Expected an ID: {:?}", other)),
+            })
+            .with_converter("screen_name", |value| {
+                value
+                    .as_str()
+                    .map(|value| OwnedValue::Utf8(value.to_uppercase()))
+                    .ok_or_else(|| "Expected a string".to_string())
+            })
+            .with_converter("url", |value| {
+                Ok(OwnedValue::Utf8(
+                    value.as_str().unwrap_or("none").to_string(),
+                ))
+            });
+
+        let result = orc_file
+            .deserialize_with_converters::<ConvertedUserRow>(&FieldNameMatching::new(), &converters)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        for (result, expected) in result.iter().zip(load_ts_1k_json()) {
+            assert_eq!(result.id, expected.id);
+            assert_eq!(result.status_id, (expected.status_id >> 22) + 1288834974657);
+            assert_eq!(result.screen_name, expected.screen_name.to_uppercase());
+            assert_eq!(result.url, expected.url.as_deref().unwrap_or("none"));
+        }
+
+        let converters = ValueConverters::new()
+            .with_converter("screen_name", |_| Err("Unsupported".to_string()));
+        let result = orc_file
+            .deserialize_with_converters::<ConvertedUserRow>(&FieldNameMatching::new(), &converters)
+            .next()
+            .unwrap();
+
+        assert!(matches!(
+            result,
+            Err(ref error) if error.to_string() == "field 2: Conversion error"
+        ));
+    }
+
+    #[derive(Deserialize, Debug, Eq, PartialEq)]
+    struct ConvertedUserRow {
+        id: u64,
+        status_id: u64,
+        screen_name: String,
+        url: String,
+    }
+
+    #[derive(Deserialize, Debug, Eq, PartialEq)]
+    #[allow(non_snake_case)]
+    struct CamelCaseUserRow {
+        ID: u64,
+        screenName: String,
+        FollowersCount: Option<u32>,
+    }
+
     #[derive(Deserialize, Debug, Eq, PartialEq)]
     struct BadUserRow {
         user_id: u64,
@@ -1106,7 +5968,8 @@ mod tests {
         reader
             .lines()
             .map(|line| {
-                serde_json::from_str(&line.as_ref().unwrap()).expect(&format!("bad: {:?}", line))
+                serde_json::from_str(line.as_ref().unwrap())
+                    .unwrap_or_else(|_| panic!("bad: {:?}", line))
             })
             .collect()
     }
@@ -0,0 +1,108 @@
+//! Reporting progress and cancelling long scans.
+//!
+//! See [`crate::parser::RowCursor::with_progress_callback`] and
+//! [`crate::parser::RowCursor::with_cancellation`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How much of a scan has been completed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ScanProgress {
+    stripes_processed: usize,
+    stripe_count: usize,
+    rows_processed: u64,
+    row_count: u64,
+}
+
+impl ScanProgress {
+    pub(crate) fn new(stripe_count: usize, row_count: u64) -> Self {
+        Self {
+            stripe_count,
+            row_count,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn add_stripe(&mut self, row_count: u64) {
+        self.stripes_processed += 1;
+        self.rows_processed += row_count;
+    }
+
+    pub fn get_stripes_processed(&self) -> usize {
+        self.stripes_processed
+    }
+
+    /// The number of stripes in the scan (not necessarily in the file).
+    pub fn get_stripe_count(&self) -> usize {
+        self.stripe_count
+    }
+
+    pub fn get_rows_processed(&self) -> u64 {
+        self.rows_processed
+    }
+
+    /// The number of rows in the scan (not necessarily in the file).
+    pub fn get_row_count(&self) -> u64 {
+        self.row_count
+    }
+
+    /// The fraction of rows processed (or of stripes, if the scan has no rows).
+    pub fn get_fraction(&self) -> f64 {
+        if self.row_count > 0 {
+            self.rows_processed as f64 / self.row_count as f64
+        } else if self.stripe_count > 0 {
+            self.stripes_processed as f64 / self.stripe_count as f64
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A flag that can be shared with other threads to stop a scan.
+///
+/// Scans check the token before each row, and return [`crate::parser::Error::Cancelled`] (and then
+/// no more rows) once it's been cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn fraction() {
+        let mut progress = ScanProgress::new(2, 10);
+        assert_eq!(progress.get_fraction(), 0.0);
+
+        progress.add_stripe(4);
+        assert_eq!(progress.get_fraction(), 0.4);
+        assert_eq!(ScanProgress::new(0, 0).get_fraction(), 1.0);
+    }
+}
@@ -1,5 +1,5 @@
-// This file is generated by rust-protobuf 3.2.0. Do not edit
-// .proto file is parsed by protoc 3.21.12
+// This file is generated by rust-protobuf 3.7.2. Do not edit
+// .proto file is parsed by protoc
 // @generated
 
 // https://github.com/rust-lang/rust-clippy/issues/702
@@ -9,7 +9,6 @@
 #![allow(unused_attributes)]
 #![cfg_attr(rustfmt, rustfmt::skip)]
 
-#![allow(box_pointers)]
 #![allow(dead_code)]
 #![allow(missing_docs)]
 #![allow(non_camel_case_types)]
@@ -23,10 +22,10 @@
 
 /// Generated files are compatible only with the same version
 /// of protobuf runtime.
-const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_2_0;
+const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_7_2;
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.IntegerStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct IntegerStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.IntegerStatistics.minimum)
@@ -240,8 +239,8 @@ impl ::protobuf::reflect::ProtobufValue for IntegerStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.DoubleStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct DoubleStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.DoubleStatistics.minimum)
@@ -455,8 +454,8 @@ impl ::protobuf::reflect::ProtobufValue for DoubleStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StringStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StringStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StringStatistics.minimum)
@@ -812,8 +811,8 @@ impl ::protobuf::reflect::ProtobufValue for StringStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.BucketStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct BucketStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.BucketStatistics.count)
@@ -933,8 +932,8 @@ impl ::protobuf::reflect::ProtobufValue for BucketStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.DecimalStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct DecimalStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.DecimalStatistics.minimum)
@@ -1199,8 +1198,8 @@ impl ::protobuf::reflect::ProtobufValue for DecimalStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.DateStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct DateStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.DateStatistics.minimum)
@@ -1377,8 +1376,8 @@ impl ::protobuf::reflect::ProtobufValue for DateStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.TimestampStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct TimestampStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.TimestampStatistics.minimum)
@@ -1703,8 +1702,8 @@ impl ::protobuf::reflect::ProtobufValue for TimestampStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.BinaryStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct BinaryStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.BinaryStatistics.sum)
@@ -1844,8 +1843,8 @@ impl ::protobuf::reflect::ProtobufValue for BinaryStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.CollectionStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct CollectionStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.CollectionStatistics.minChildren)
@@ -2059,8 +2058,8 @@ impl ::protobuf::reflect::ProtobufValue for CollectionStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.ColumnStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct ColumnStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.ColumnStatistics.numberOfValues)
@@ -2445,8 +2444,8 @@ impl ::protobuf::reflect::ProtobufValue for ColumnStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.RowIndexEntry)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct RowIndexEntry {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.RowIndexEntry.positions)
@@ -2585,8 +2584,8 @@ impl ::protobuf::reflect::ProtobufValue for RowIndexEntry {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.RowIndex)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct RowIndex {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.RowIndex.entry)
@@ -2708,8 +2707,8 @@ impl ::protobuf::reflect::ProtobufValue for RowIndex {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.BloomFilter)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct BloomFilter {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.BloomFilter.numHashFunctions)
@@ -2922,8 +2921,8 @@ impl ::protobuf::reflect::ProtobufValue for BloomFilter {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.BloomFilterIndex)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct BloomFilterIndex {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.BloomFilterIndex.bloomFilter)
@@ -3045,8 +3044,8 @@ impl ::protobuf::reflect::ProtobufValue for BloomFilterIndex {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.Stream)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct Stream {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.Stream.kind)
@@ -3322,6 +3321,25 @@ pub mod stream {
             }
         }
 
+        fn from_str(str: &str) -> ::std::option::Option<Kind> {
+            match str {
+                "PRESENT" => ::std::option::Option::Some(Kind::PRESENT),
+                "DATA" => ::std::option::Option::Some(Kind::DATA),
+                "LENGTH" => ::std::option::Option::Some(Kind::LENGTH),
+                "DICTIONARY_DATA" => ::std::option::Option::Some(Kind::DICTIONARY_DATA),
+                "DICTIONARY_COUNT" => ::std::option::Option::Some(Kind::DICTIONARY_COUNT),
+                "SECONDARY" => ::std::option::Option::Some(Kind::SECONDARY),
+                "ROW_INDEX" => ::std::option::Option::Some(Kind::ROW_INDEX),
+                "BLOOM_FILTER" => ::std::option::Option::Some(Kind::BLOOM_FILTER),
+                "BLOOM_FILTER_UTF8" => ::std::option::Option::Some(Kind::BLOOM_FILTER_UTF8),
+                "ENCRYPTED_INDEX" => ::std::option::Option::Some(Kind::ENCRYPTED_INDEX),
+                "ENCRYPTED_DATA" => ::std::option::Option::Some(Kind::ENCRYPTED_DATA),
+                "STRIPE_STATISTICS" => ::std::option::Option::Some(Kind::STRIPE_STATISTICS),
+                "FILE_STATISTICS" => ::std::option::Option::Some(Kind::FILE_STATISTICS),
+                _ => ::std::option::Option::None
+            }
+        }
+
         const VALUES: &'static [Kind] = &[
             Kind::PRESENT,
             Kind::DATA,
@@ -3378,8 +3396,8 @@ pub mod stream {
     }
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.ColumnEncoding)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct ColumnEncoding {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.ColumnEncoding.kind)
@@ -3628,6 +3646,16 @@ pub mod column_encoding {
             }
         }
 
+        fn from_str(str: &str) -> ::std::option::Option<Kind> {
+            match str {
+                "DIRECT" => ::std::option::Option::Some(Kind::DIRECT),
+                "DICTIONARY" => ::std::option::Option::Some(Kind::DICTIONARY),
+                "DIRECT_V2" => ::std::option::Option::Some(Kind::DIRECT_V2),
+                "DICTIONARY_V2" => ::std::option::Option::Some(Kind::DICTIONARY_V2),
+                _ => ::std::option::Option::None
+            }
+        }
+
         const VALUES: &'static [Kind] = &[
             Kind::DIRECT,
             Kind::DICTIONARY,
@@ -3661,8 +3689,8 @@ pub mod column_encoding {
     }
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StripeEncryptionVariant)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StripeEncryptionVariant {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StripeEncryptionVariant.streams)
@@ -3803,8 +3831,8 @@ impl ::protobuf::reflect::ProtobufValue for StripeEncryptionVariant {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StripeFooter)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StripeFooter {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StripeFooter.streams)
@@ -4018,8 +4046,8 @@ impl ::protobuf::reflect::ProtobufValue for StripeFooter {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StringPair)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StringPair {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StringPair.key)
@@ -4230,8 +4258,8 @@ impl ::protobuf::reflect::ProtobufValue for StringPair {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.Type)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct Type {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.Type.kind)
@@ -4616,6 +4644,31 @@ pub mod type_ {
             }
         }
 
+        fn from_str(str: &str) -> ::std::option::Option<Kind> {
+            match str {
+                "BOOLEAN" => ::std::option::Option::Some(Kind::BOOLEAN),
+                "BYTE" => ::std::option::Option::Some(Kind::BYTE),
+                "SHORT" => ::std::option::Option::Some(Kind::SHORT),
+                "INT" => ::std::option::Option::Some(Kind::INT),
+                "LONG" => ::std::option::Option::Some(Kind::LONG),
+                "FLOAT" => ::std::option::Option::Some(Kind::FLOAT),
+                "DOUBLE" => ::std::option::Option::Some(Kind::DOUBLE),
+                "STRING" => ::std::option::Option::Some(Kind::STRING),
+                "BINARY" => ::std::option::Option::Some(Kind::BINARY),
+                "TIMESTAMP" => ::std::option::Option::Some(Kind::TIMESTAMP),
+                "LIST" => ::std::option::Option::Some(Kind::LIST),
+                "MAP" => ::std::option::Option::Some(Kind::MAP),
+                "STRUCT" => ::std::option::Option::Some(Kind::STRUCT),
+                "UNION" => ::std::option::Option::Some(Kind::UNION),
+                "DECIMAL" => ::std::option::Option::Some(Kind::DECIMAL),
+                "DATE" => ::std::option::Option::Some(Kind::DATE),
+                "VARCHAR" => ::std::option::Option::Some(Kind::VARCHAR),
+                "CHAR" => ::std::option::Option::Some(Kind::CHAR),
+                "TIMESTAMP_INSTANT" => ::std::option::Option::Some(Kind::TIMESTAMP_INSTANT),
+                _ => ::std::option::Option::None
+            }
+        }
+
         const VALUES: &'static [Kind] = &[
             Kind::BOOLEAN,
             Kind::BYTE,
@@ -4664,8 +4717,8 @@ pub mod type_ {
     }
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StripeInformation)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StripeInformation {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StripeInformation.offset)
@@ -5008,8 +5061,8 @@ impl ::protobuf::reflect::ProtobufValue for StripeInformation {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.UserMetadataItem)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct UserMetadataItem {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.UserMetadataItem.name)
@@ -5220,8 +5273,8 @@ impl ::protobuf::reflect::ProtobufValue for UserMetadataItem {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.StripeStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct StripeStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.StripeStatistics.colStats)
@@ -5343,8 +5396,8 @@ impl ::protobuf::reflect::ProtobufValue for StripeStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.Metadata)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct Metadata {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.Metadata.stripeStats)
@@ -5466,8 +5519,8 @@ impl ::protobuf::reflect::ProtobufValue for Metadata {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.ColumnarStripeStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct ColumnarStripeStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.ColumnarStripeStatistics.colStats)
@@ -5589,8 +5642,8 @@ impl ::protobuf::reflect::ProtobufValue for ColumnarStripeStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.FileStatistics)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct FileStatistics {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.FileStatistics.column)
@@ -5712,8 +5765,8 @@ impl ::protobuf::reflect::ProtobufValue for FileStatistics {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.DataMask)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct DataMask {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.DataMask.name)
@@ -5905,8 +5958,8 @@ impl ::protobuf::reflect::ProtobufValue for DataMask {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.EncryptionKey)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct EncryptionKey {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.EncryptionKey.keyName)
@@ -6140,8 +6193,8 @@ impl ::protobuf::reflect::ProtobufValue for EncryptionKey {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.EncryptionVariant)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct EncryptionVariant {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.EncryptionVariant.root)
@@ -6445,8 +6498,8 @@ impl ::protobuf::reflect::ProtobufValue for EncryptionVariant {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.Encryption)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct Encryption {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.Encryption.mask)
@@ -6646,8 +6699,8 @@ impl ::protobuf::reflect::ProtobufValue for Encryption {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.Footer)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct Footer {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.Footer.headerLength)
@@ -7124,8 +7177,8 @@ impl ::protobuf::reflect::ProtobufValue for Footer {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.PostScript)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct PostScript {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.PostScript.footerLength)
@@ -7524,8 +7577,8 @@ impl ::protobuf::reflect::ProtobufValue for PostScript {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
-#[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:orc.proto.FileTail)
+#[derive(PartialEq,Clone,Default,Debug)]
 pub struct FileTail {
     // message fields
     // @@protoc_insertion_point(field:orc.proto.FileTail.postscript)
@@ -7767,6 +7820,15 @@ impl ::protobuf::Enum for EncryptionAlgorithm {
         }
     }
 
+    fn from_str(str: &str) -> ::std::option::Option<EncryptionAlgorithm> {
+        match str {
+            "UNKNOWN_ENCRYPTION" => ::std::option::Option::Some(EncryptionAlgorithm::UNKNOWN_ENCRYPTION),
+            "AES_CTR_128" => ::std::option::Option::Some(EncryptionAlgorithm::AES_CTR_128),
+            "AES_CTR_256" => ::std::option::Option::Some(EncryptionAlgorithm::AES_CTR_256),
+            _ => ::std::option::Option::None
+        }
+    }
+
     const VALUES: &'static [EncryptionAlgorithm] = &[
         EncryptionAlgorithm::UNKNOWN_ENCRYPTION,
         EncryptionAlgorithm::AES_CTR_128,
@@ -7831,6 +7893,17 @@ impl ::protobuf::Enum for KeyProviderKind {
         }
     }
 
+    fn from_str(str: &str) -> ::std::option::Option<KeyProviderKind> {
+        match str {
+            "UNKNOWN" => ::std::option::Option::Some(KeyProviderKind::UNKNOWN),
+            "HADOOP" => ::std::option::Option::Some(KeyProviderKind::HADOOP),
+            "AWS" => ::std::option::Option::Some(KeyProviderKind::AWS),
+            "GCP" => ::std::option::Option::Some(KeyProviderKind::GCP),
+            "AZURE" => ::std::option::Option::Some(KeyProviderKind::AZURE),
+            _ => ::std::option::Option::None
+        }
+    }
+
     const VALUES: &'static [KeyProviderKind] = &[
         KeyProviderKind::UNKNOWN,
         KeyProviderKind::HADOOP,
@@ -7891,6 +7964,15 @@ impl ::protobuf::Enum for CalendarKind {
         }
     }
 
+    fn from_str(str: &str) -> ::std::option::Option<CalendarKind> {
+        match str {
+            "UNKNOWN_CALENDAR" => ::std::option::Option::Some(CalendarKind::UNKNOWN_CALENDAR),
+            "JULIAN_GREGORIAN" => ::std::option::Option::Some(CalendarKind::JULIAN_GREGORIAN),
+            "PROLEPTIC_GREGORIAN" => ::std::option::Option::Some(CalendarKind::PROLEPTIC_GREGORIAN),
+            _ => ::std::option::Option::None
+        }
+    }
+
     const VALUES: &'static [CalendarKind] = &[
         CalendarKind::UNKNOWN_CALENDAR,
         CalendarKind::JULIAN_GREGORIAN,
@@ -7958,6 +8040,18 @@ impl ::protobuf::Enum for CompressionKind {
         }
     }
 
+    fn from_str(str: &str) -> ::std::option::Option<CompressionKind> {
+        match str {
+            "NONE" => ::std::option::Option::Some(CompressionKind::NONE),
+            "ZLIB" => ::std::option::Option::Some(CompressionKind::ZLIB),
+            "SNAPPY" => ::std::option::Option::Some(CompressionKind::SNAPPY),
+            "LZO" => ::std::option::Option::Some(CompressionKind::LZO),
+            "LZ4" => ::std::option::Option::Some(CompressionKind::LZ4),
+            "ZSTD" => ::std::option::Option::Some(CompressionKind::ZSTD),
+            _ => ::std::option::Option::None
+        }
+    }
+
     const VALUES: &'static [CompressionKind] = &[
         CompressionKind::NONE,
         CompressionKind::ZLIB,
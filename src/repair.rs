@@ -0,0 +1,143 @@
+//! Recovering the readable stripes of a damaged ORC file.
+//!
+//! Each stripe is checked by decoding all of its columns in strict mode, and the stripes that pass
+//! are copied into a new file (see [`crate::split::write_selected_stripes`]). This requires the
+//! file's postscript and footer to be intact.
+
+use crate::meta::TypeKind;
+use crate::parser::{Error, OrcFile};
+use std::io::Write;
+use std::ops::Range;
+
+/// A stripe that couldn't be decoded.
+#[derive(Debug)]
+pub struct LostStripe {
+    stripe_index: usize,
+    rows: Range<u64>,
+    error: Error,
+}
+
+impl LostStripe {
+    pub fn get_stripe_index(&self) -> usize {
+        self.stripe_index
+    }
+
+    /// The range of file-level row numbers in this stripe.
+    pub fn get_rows(&self) -> &Range<u64> {
+        &self.rows
+    }
+
+    /// The first error encountered while decoding the stripe.
+    pub fn get_error(&self) -> &Error {
+        &self.error
+    }
+}
+
+/// Decode every stripe and return the ones that fail.
+pub fn find_lost_stripes(orc_file: &OrcFile) -> Result<Vec<LostStripe>, Error> {
+    let mut lost_stripes = vec![];
+    let mut row_start = 0;
+
+    for (stripe_index, stripe) in orc_file.get_footer().get_stripes().iter().enumerate() {
+        let rows = row_start..row_start + stripe.get_number_of_rows();
+        row_start = rows.end;
+
+        // A failed read may leave the handle unusable, so each stripe gets a new one.
+        let mut stripe_file = orc_file.try_clone()?;
+        stripe_file.set_strict(true);
+
+        if let Err(error) = check_stripe(&mut stripe_file, stripe_index) {
+            lost_stripes.push(LostStripe {
+                stripe_index,
+                rows,
+                error,
+            });
+        }
+    }
+
+    Ok(lost_stripes)
+}
+
+/// Copy the stripes that can be decoded into a new file, returning the ones that were dropped.
+pub fn repair<W: Write>(orc_file: &mut OrcFile, output: W) -> Result<Vec<LostStripe>, Error> {
+    let lost_stripes = find_lost_stripes(orc_file)?;
+    let stripes = (0..orc_file.get_footer().get_stripes().len())
+        .filter(|index| {
+            !lost_stripes
+                .iter()
+                .any(|lost_stripe| lost_stripe.stripe_index == *index)
+        })
+        .collect::<Vec<_>>();
+
+    crate::split::write_selected_stripes(orc_file, &stripes, output)?;
+
+    Ok(lost_stripes)
+}
+
+fn check_stripe(orc_file: &mut OrcFile, stripe_index: usize) -> Result<(), Error> {
+    let stripe = orc_file.read_stripe_info(stripe_index)?;
+
+    for column_id in 0..stripe.get_column_count() {
+        match orc_file.read_column(&stripe, column_id) {
+            // Nested structs (as in ACID files) only have a present stream.
+            Err(Error::UnsupportedType(TypeKind::Struct)) => {
+                orc_file.read_struct_null_runs(&stripe, column_id)?;
+            }
+            other => {
+                other?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("orcrs-repair-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn drop_damaged_stripe() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let row_count = orc_file.get_footer().get_number_of_rows();
+
+        // Build a file with three copies of the only stripe.
+        let copies_path = temp_path("copies.orc");
+        crate::split::write_selected_stripes(
+            &mut orc_file,
+            &[0, 0, 0],
+            std::fs::File::create(&copies_path).unwrap(),
+        )
+        .unwrap();
+
+        // Overwrite the data of the second copy.
+        let mut bytes = std::fs::read(&copies_path).unwrap();
+        let copies = OrcFile::open(&copies_path).unwrap();
+        let stripe = copies.get_footer().get_stripes()[1];
+        let start = (stripe.get_offset() + stripe.get_index_length()) as usize;
+        let len = stripe.get_data_length() as usize;
+        bytes[start..start + len].fill(0xff);
+        std::fs::write(&copies_path, bytes).unwrap();
+
+        let mut damaged = OrcFile::open(&copies_path).unwrap();
+        let repaired_path = temp_path("repaired.orc");
+        let lost_stripes =
+            repair(&mut damaged, std::fs::File::create(&repaired_path).unwrap()).unwrap();
+
+        let repaired = OrcFile::open(&repaired_path).unwrap();
+        let repaired_lost_stripes = find_lost_stripes(&repaired).unwrap();
+        std::fs::remove_file(&copies_path).unwrap();
+        std::fs::remove_file(&repaired_path).unwrap();
+
+        assert_eq!(lost_stripes.len(), 1);
+        assert_eq!(lost_stripes[0].get_stripe_index(), 1);
+        assert_eq!(lost_stripes[0].get_rows(), &(row_count..2 * row_count));
+        assert_eq!(repaired.get_footer().get_number_of_rows(), 2 * row_count);
+        assert!(repaired_lost_stripes.is_empty());
+    }
+}
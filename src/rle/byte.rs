@@ -1,7 +1,98 @@
+//! ORC's byte run-length encoding, which is also used for boolean streams.
+//!
+//! Each run is either a single byte repeated between 3 and 130 times, or up to 128 literal bytes.
+
 use std::io::{Error, Write};
 
 const MIN_REPEAT_LEN: u8 = 3;
+const MAX_LITERAL_LEN: usize = 128;
+
+/// An iterator over the bytes of a byte run-length encoded stream.
+///
+/// After an error the iterator is exhausted.
+pub struct ByteDecoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    repeated: Option<(u8, u8)>,
+    literal_remaining: u8,
+}
+
+impl<'a> ByteDecoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            repeated: None,
+            literal_remaining: 0,
+        }
+    }
+
+    /// The byte offset of the first run that hasn't been decoded yet.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn fail(&mut self, offset: usize) -> Option<Result<u8, super::Error>> {
+        self.position = self.bytes.len();
+        self.repeated = None;
+        self.literal_remaining = 0;
+        Some(Err(super::Error::InvalidRun {
+            offset,
+            header: None,
+        }))
+    }
+}
+
+impl Iterator for ByteDecoder<'_> {
+    type Item = Result<u8, super::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((value, remaining)) = self.repeated {
+            self.repeated = if remaining > 1 {
+                Some((value, remaining - 1))
+            } else {
+                None
+            };
+            Some(Ok(value))
+        } else if self.literal_remaining > 0 {
+            match self.bytes.get(self.position) {
+                Some(value) => {
+                    self.position += 1;
+                    self.literal_remaining -= 1;
+                    Some(Ok(*value))
+                }
+                None => {
+                    let offset = self.position;
+                    self.fail(offset)
+                }
+            }
+        } else {
+            let control = *self.bytes.get(self.position)?;
+
+            if control < 128 {
+                match self.bytes.get(self.position + 1) {
+                    Some(value) => {
+                        self.position += 2;
+                        self.repeated = Some((*value, control + MIN_REPEAT_LEN));
+                        self.next()
+                    }
+                    None => {
+                        let offset = self.position;
+                        self.fail(offset)
+                    }
+                }
+            } else {
+                self.position += 1;
+                self.literal_remaining = control.wrapping_neg();
+                self.next()
+            }
+        }
+    }
+}
 
+/// A writer that decodes byte run-length encoded input and writes the bytes to another writer.
+///
+/// Runs may be split across calls to `write`.
 pub struct ByteWriter<W: Write> {
     writer: W,
     state: ByteWriterState,
@@ -73,9 +164,40 @@ impl<W: Write> Write for ByteWriter<W> {
     }
 }
 
+/// Encode bytes as a sequence of literal runs.
+///
+/// The result is always slightly larger than the input, but this is only used for rewriting small
+/// parts of files.
+pub fn encode_literals(bytes: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len() + bytes.len() / MAX_LITERAL_LEN + 1);
+
+    for chunk in bytes.chunks(MAX_LITERAL_LEN) {
+        result.push((chunk.len() as u8).wrapping_neg());
+        result.extend_from_slice(chunk);
+    }
+
+    result
+}
+
+/// Encode booleans as a boolean stream (bits packed from the most significant, in literal runs).
+pub fn encode_bools(values: &[bool]) -> Vec<u8> {
+    let bytes = values
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, value)| byte | ((*value as u8) << (7 - i)))
+        })
+        .collect::<Vec<u8>>();
+
+    encode_literals(&bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rle::Error as RleError;
     use std::io::Write;
 
     #[test]
@@ -98,6 +220,33 @@ mod tests {
         assert_eq!(result.into_inner(), expected);
     }
 
+    #[test]
+    fn decoder() {
+        let input = [97, 0, 1, 1, 0xfe, 0x44, 0x45];
+        let mut expected = vec![0; 100];
+        expected.extend([1, 1, 1, 1, 0x44, 0x45]);
+
+        let result = ByteDecoder::new(&input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn decoder_truncated_literal() {
+        let input = [1, 1, 0xfd, 0x44];
+        let result = ByteDecoder::new(&input).collect::<Vec<_>>();
+
+        assert_eq!(result.len(), 6);
+        assert_eq!(
+            result[5],
+            Err(RleError::InvalidRun {
+                offset: 4,
+                header: None
+            })
+        );
+    }
+
     #[test]
     fn literal() {
         let input = &[0xfe, 0x44, 0x45];
@@ -107,4 +256,21 @@ mod tests {
         result.write_all(input).unwrap();
         assert_eq!(result.into_inner(), expected);
     }
+
+    #[test]
+    fn encode() {
+        let input = (0..300).map(|value| value as u8).collect::<Vec<_>>();
+        let encoded = encode_literals(&input);
+
+        assert_eq!(encoded.len(), input.len() + 3);
+        assert_eq!(
+            ByteDecoder::new(&encoded)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            input
+        );
+
+        let encoded = encode_bools(&[true, false, false, true, true, false, false, false, true]);
+        assert_eq!(encoded, vec![0xfe, 0b1001_1000, 0b1000_0000]);
+    }
 }
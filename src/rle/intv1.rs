@@ -1,49 +1,129 @@
+//! Version 1 of ORC's integer run-length encoding.
+//!
+//! Each run is either a sequence of up to 130 values that differ by a constant delta, or up to 128
+//! literal values. All values are base 128 varints, and are zigzag encoded in signed streams.
+
+use super::{DecodeError, Error, IntegerDecoder, IntegerRleVersion, RunHeader};
 use integer_encoding::VarInt;
 
 const DEFAULT_CAPACITY: usize = 1024;
 const MIN_REPEAT_LEN: u8 = 3;
+const MAX_LITERAL_LEN: usize = 128;
+
+/// Create an iterator over the values of a version 1 encoded stream.
+pub fn decoder(bytes: &[u8], signed: bool) -> IntegerDecoder<'_> {
+    IntegerDecoder::new(bytes, IntegerRleVersion::V1, signed)
+}
 
-// TODO: Actually handle signed types properly.
-pub fn decode_u64s(bytes: &[u8], expected_len: Option<usize>, signed: bool) -> Option<Vec<u64>> {
+/// Decode all values in a version 1 encoded stream.
+pub fn decode_u64s(
+    bytes: &[u8],
+    expected_len: Option<usize>,
+    signed: bool,
+) -> Result<Vec<u64>, DecodeError> {
     let mut values = Vec::with_capacity(expected_len.unwrap_or(DEFAULT_CAPACITY));
-    let mut current = bytes;
+    let mut offset = 0;
 
-    while !current.is_empty() {
-        let read_len = append_next_u64s(current, &mut values, signed)?;
-        current = &current[read_len..];
+    while offset < bytes.len() {
+        match decode_run(&bytes[offset..], &mut values, signed) {
+            Ok(read_len) => {
+                offset += read_len;
+            }
+            Err(header) => {
+                return Err(DecodeError {
+                    error: Error::InvalidRun {
+                        offset,
+                        header: header.map(RunHeader::V1),
+                    },
+                    values,
+                });
+            }
+        }
     }
 
-    Some(values)
+    Ok(values)
 }
 
-fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, _signed: bool) -> Option<usize> {
-    bytes.get(0).and_then(|first| {
-        if *first < 128 {
-            let len = first + MIN_REPEAT_LEN;
-            bytes.get(1).and_then(|second| {
-                let delta = *second as i8;
-                let (mut last_value, read_len) = u64::decode_var(&bytes[2..])?;
-
-                for _ in 0..len {
-                    current_values.push(last_value);
-                    last_value = (last_value as i64 + delta as i64) as u64;
-                }
+/// The header of a version 1 run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Header {
+    Run { len: u8, delta: i8 },
+    Literal { len: u8 },
+}
 
-                Some(read_len + 2)
-            })
-        } else {
-            let len = first.wrapping_neg();
-
-            let mut current = 1;
-            for _ in 0..len {
-                let (value, read_len) = u64::decode_var(&bytes[current..])?;
-                current_values.push(value);
-                current += read_len;
+/// Decode the run at the start of the input, returning the number of bytes read.
+///
+/// On failure no values are added, and the header is returned if it could be parsed.
+pub(crate) fn decode_run(
+    bytes: &[u8],
+    current_values: &mut Vec<u64>,
+    signed: bool,
+) -> Result<usize, Option<Header>> {
+    let first = *bytes.first().ok_or(None)?;
+
+    if first < 128 {
+        let len = first + MIN_REPEAT_LEN;
+        let delta = *bytes.get(1).ok_or(None)? as i8;
+        let header = Header::Run { len, delta };
+        let (mut last_value, read_len) = decode_varint(&bytes[2..], signed).ok_or(Some(header))?;
+
+        for _ in 0..len {
+            current_values.push(last_value);
+            last_value = (last_value as i64).wrapping_add(delta as i64) as u64;
+        }
+
+        Ok(read_len + 2)
+    } else {
+        let len = first.wrapping_neg();
+        let header = Header::Literal { len };
+        let original_len = current_values.len();
+
+        let mut current = 1;
+        for _ in 0..len {
+            match decode_varint(&bytes[current..], signed) {
+                Some((value, read_len)) => {
+                    current_values.push(value);
+                    current += read_len;
+                }
+                None => {
+                    current_values.truncate(original_len);
+                    return Err(Some(header));
+                }
             }
+        }
+
+        Ok(current)
+    }
+}
+
+/// Encode values as a sequence of literal runs.
+///
+/// Values are interpreted as in [`decode_u64s`], so signed values should be passed as their two's
+/// complement representation.
+pub fn encode_literals(values: &[u64], signed: bool) -> Vec<u8> {
+    let mut result = Vec::with_capacity(values.len() * 2);
 
-            Some(current)
+    for chunk in values.chunks(MAX_LITERAL_LEN) {
+        result.push((chunk.len() as u8).wrapping_neg());
+
+        for value in chunk {
+            if signed {
+                result.extend((*value as i64).encode_var_vec());
+            } else {
+                result.extend(value.encode_var_vec());
+            }
         }
-    })
+    }
+
+    result
+}
+
+fn decode_varint(bytes: &[u8], signed: bool) -> Option<(u64, usize)> {
+    if signed {
+        i64::decode_var(bytes).map(|(value, read_len)| (value as u64, read_len))
+    } else {
+        u64::decode_var(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -65,38 +145,105 @@ mod tests {
     const LITERAL_OUTPUT: [u64; 5] = [2, 3, 6, 7, 11];
 
     #[test]
-    fn append_next_u64s_simple_run_same() {
+    fn decode_run_simple_run_same() {
         let input = RUN_SAME_INPUT;
         let expected = RUN_SAME_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, true).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn append_next_u64s_simple_run_delta() {
+    fn decode_run_simple_run_delta() {
         let input = RUN_DELTA_INPUT;
         let expected = RUN_DELTA_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, true).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn append_next_u64s_simple_literal() {
+    fn decode_run_simple_literal() {
         let input = LITERAL_INPUT;
         let expected = LITERAL_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, true).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn decode_run_signed() {
+        // Zigzag encoded: -1, 1, -2, 2.
+        let input = [0xfc, 0x01, 0x02, 0x03, 0x04];
+        let expected = vec![-1, 1, -2, 2];
+
+        let mut result = vec![];
+        decode_run(&input, &mut result, true).unwrap();
+
+        assert_eq!(
+            result
+                .into_iter()
+                .map(|value| value as i64)
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn decoder_matches_decode_u64s() {
+        let mut input = vec![];
+        input.extend(RUN_SAME_INPUT);
+        input.extend(LITERAL_INPUT);
+
+        let result = decoder(&input, false)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(result, decode_u64s(&input, None, false).unwrap());
+    }
+
+    #[test]
+    fn decoder_truncated_run() {
+        let input = [0x61, 0x00, 0x07, 0xfb, 0x02];
+        let result = decoder(&input, false).collect::<Vec<_>>();
+
+        assert_eq!(result.len(), 101);
+        assert_eq!(
+            result[100],
+            Err(Error::InvalidRun {
+                offset: 3,
+                header: Some(RunHeader::V1(Header::Literal { len: 5 }))
+            })
+        );
+    }
+
+    #[test]
+    fn decode_u64s_error_position() {
+        let mut input = vec![];
+        input.extend(RUN_SAME_INPUT);
+        input.extend(LITERAL_INPUT);
+        input.extend([0x61, 0x00]);
+
+        let error = decode_u64s(&input, None, false).unwrap_err();
+        let mut expected_values = RUN_SAME_OUTPUT.to_vec();
+        expected_values.extend(LITERAL_OUTPUT);
+
+        assert_eq!(
+            error.error,
+            Error::InvalidRun {
+                offset: RUN_SAME_INPUT.len() + LITERAL_INPUT.len(),
+                header: Some(RunHeader::V1(Header::Run { len: 100, delta: 0 }))
+            }
+        );
+        assert_eq!(error.values, expected_values);
+    }
+
     #[test]
     fn concatenation() {
         let mut input = vec![];
@@ -122,8 +269,24 @@ mod tests {
         expected.extend(RUN_DELTA_OUTPUT);
         expected.extend(LITERAL_OUTPUT);
 
-        let result = decode_u64s(&mut input, None, false).unwrap();
+        let result = decode_u64s(&input, None, false).unwrap();
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn encode_literals_round_trip() {
+        let signed = (-150..150).map(|value| (value * 1000) as u64).collect::<Vec<_>>();
+        let unsigned = (0..150).map(|value| value * 1000).collect::<Vec<_>>();
+
+        assert_eq!(
+            decode_u64s(&encode_literals(&signed, true), None, true).unwrap(),
+            signed
+        );
+        assert_eq!(
+            decode_u64s(&encode_literals(&unsigned, false), None, false).unwrap(),
+            unsigned
+        );
+        assert_eq!(encode_literals(&LITERAL_OUTPUT, false), LITERAL_INPUT);
+    }
 }
@@ -1,21 +1,71 @@
+//! Version 2 of ORC's integer run-length encoding.
+//!
+//! Each run uses one of four sub-encodings (short repeat, direct, patched base, or delta), which
+//! is identified by the first two bits of the run header.
+
+use super::{DecodeError, Error, IntegerDecoder, IntegerRleVersion, RunHeader};
 use integer_encoding::VarInt;
 
 const DEFAULT_CAPACITY: usize = 1024;
 
-pub fn decode_u64s(bytes: &[u8], expected_len: Option<usize>, signed: bool) -> Option<Vec<u64>> {
+/// Create an iterator over the values of a version 2 encoded stream.
+pub fn decoder(bytes: &[u8], signed: bool) -> IntegerDecoder<'_> {
+    IntegerDecoder::new(bytes, IntegerRleVersion::V2, signed)
+}
+
+/// Decode all values in a version 2 encoded stream.
+pub fn decode_u64s(
+    bytes: &[u8],
+    expected_len: Option<usize>,
+    signed: bool,
+) -> Result<Vec<u64>, DecodeError> {
     let mut values = Vec::with_capacity(expected_len.unwrap_or(DEFAULT_CAPACITY));
-    let mut current = bytes;
+    let mut offset = 0;
 
-    while !current.is_empty() {
-        let read_len = append_next_u64s(current, &mut values, signed)?;
-        current = &current[read_len..];
+    while offset < bytes.len() {
+        match decode_run(&bytes[offset..], &mut values, signed) {
+            Ok(read_len) => {
+                offset += read_len;
+            }
+            Err(header) => {
+                return Err(DecodeError {
+                    error: Error::InvalidRun {
+                        offset,
+                        header: header.map(RunHeader::V2),
+                    },
+                    values,
+                });
+            }
+        }
     }
 
-    Some(values)
+    Ok(values)
 }
 
-fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, signed: bool) -> Option<usize> {
-    let (header, mut current) = parse_header(bytes)?;
+/// Decode the run at the start of the input, returning the number of bytes read.
+///
+/// On failure no values are added, and the header is returned if it could be parsed.
+pub(crate) fn decode_run(
+    bytes: &[u8],
+    current_values: &mut Vec<u64>,
+    signed: bool,
+) -> Result<usize, Option<Header>> {
+    let (header, current) = parse_header(bytes).ok_or(None)?;
+    let original_len = current_values.len();
+
+    decode_run_body(header, bytes, current, current_values, signed).ok_or_else(|| {
+        current_values.truncate(original_len);
+        Some(header)
+    })
+}
+
+fn decode_run_body(
+    header: Header,
+    bytes: &[u8],
+    mut current: usize,
+    current_values: &mut Vec<u64>,
+    signed: bool,
+) -> Option<usize> {
     current_values.reserve(header.value_count());
 
     match header {
@@ -56,50 +106,53 @@ fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, signed: bool) -
                         encoded_value
                     };
 
-                    if value == 1525349721750552576 {
-                        println!("DIRECT: {}", value);
-                    }
                     current_values.push(value);
                 }
                 Some(expected)
             }
         }
         Header::Delta { width, len } => {
-            let (base, read_len) = i64::decode_var(&bytes[current..])?;
+            // The base is zigzag encoded only for signed streams, but the delta always is.
+            let (base, read_len) = if signed {
+                i64::decode_var(&bytes[current..])?
+            } else {
+                let (base, read_len) = u64::decode_var(&bytes[current..])?;
+                (base as i64, read_len)
+            };
             current += read_len;
             let (delta, read_len) = i64::decode_var(&bytes[current..])?;
             current += read_len;
 
-            let expected = current + bits_to_bytes(width as u64 * (len as u64 - 2));
+            // The base and the first delta aren't included in the packed values.
+            let packed_len = (len as usize).saturating_sub(2);
+            let expected = current + bits_to_bytes(width as u64 * packed_len as u64);
             if bytes.len() < expected {
                 None
             } else {
-                // TODO: handle signed integer types better somewhere around here.
                 let signum = delta.signum();
-                let base = if signed {
-                    base
-                } else if base < 0 {
-                    (-base * 2) - 1
-                } else {
-                    base * 2
-                };
 
                 current_values.push(base as u64);
 
-                let mut last_value = (base + delta) as u64;
+                if len < 2 {
+                    return Some(expected);
+                }
+
+                let mut last_value = base.wrapping_add(delta) as u64;
                 current_values.push(last_value);
 
                 if width == 0 {
-                    for _ in 0..(len as usize) - 2 {
-                        last_value = (last_value as i64 + delta) as u64;
+                    for _ in 0..packed_len {
+                        last_value = (last_value as i64).wrapping_add(delta) as u64;
                         current_values.push(last_value);
                     }
                 } else {
-                    for i in 0..(len as usize) - 2 {
+                    for i in 0..packed_len {
                         let bit_offset = i as u64 * width as u64;
                         let value = read_u64_be_bits(&bytes[current..], bit_offset, width)?;
 
-                        last_value = (last_value as i64 + signum * (value as i64)) as u64;
+                        last_value = (last_value as i64)
+                            .wrapping_add(signum.wrapping_mul(value as i64))
+                            as u64;
                         current_values.push(last_value);
                     }
                 }
@@ -126,9 +179,20 @@ fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, signed: bool) -
             if bytes.len() < expected {
                 None
             } else {
-                // TODO: handle signed integer types somewhere around here.
                 let base = read_u64_be_bytes(&bytes[current..], base_width)?;
 
+                // The base uses a sign-magnitude representation in signed streams.
+                let base = if signed {
+                    let sign_mask = 1 << (base_width as u64 * 8 - 1);
+                    if base & sign_mask == 0 {
+                        base
+                    } else {
+                        (-((base & !sign_mask) as i64)) as u64
+                    }
+                } else {
+                    base
+                };
+
                 current += base_width as usize;
 
                 let mut data_values = Vec::with_capacity(len as usize);
@@ -136,19 +200,23 @@ fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, signed: bool) -
                 for i in 0..len as usize {
                     let bit_offset = i as u64 * width as u64;
                     let value = read_u64_be_bits(&bytes[current..], bit_offset, width)?;
-                    data_values.push(value + base);
+                    data_values.push(value.wrapping_add(base));
                 }
 
                 current += bits_to_bytes(width as u64 * len as u64);
 
                 let mut patch_pos = 0;
                 let patch_list_item_len = closest_fixed_bits(patch_gap_width + patch_width);
+                // Corrupt headers may describe patch list items wider than 64 bits.
+                let patch_padding = patch_list_item_len
+                    .checked_sub(patch_width)?
+                    .checked_sub(patch_gap_width)?;
 
                 for i in 0..patch_list_len as usize {
                     let bit_offset = i as u64 * patch_list_item_len as u64;
                     let patch_gap = read_u64_be_bits(
                         &bytes[current..],
-                        bit_offset + (patch_list_item_len - patch_width - patch_gap_width) as u64,
+                        bit_offset + patch_padding as u64,
                         patch_gap_width,
                     )?;
                     let patch_value = read_u64_be_bits(
@@ -158,7 +226,8 @@ fn append_next_u64s(bytes: &[u8], current_values: &mut Vec<u64>, signed: bool) -
                     )?;
 
                     patch_pos += patch_gap as usize;
-                    data_values[patch_pos] += patch_value << width;
+                    let data_value = data_values.get_mut(patch_pos)?;
+                    *data_value = data_value.wrapping_add(patch_value.checked_shl(width as u32)?);
                 }
 
                 current_values.extend(data_values);
@@ -190,18 +259,18 @@ fn parse_header(bytes: &[u8]) -> Option<(Header, usize)> {
             None
         } else {
             let width = five_bit_width((b0 >> 1) & 0b0001_1111, tag == 3);
-            let len = (((b0 & 0b0000_0001) as u16) << 8) + bytes[1] as u16 + 1;
+            let len = (((b0 & 0b0000_0001) as u16) << 8) + *bytes.get(1)? as u16 + 1;
 
             if tag == 1 {
                 Some((Header::Direct { width, len }, 2))
             } else if tag == 3 {
                 Some((Header::Delta { width, len }, 2))
             } else if tag == 2 {
-                let b2 = bytes[2];
+                let b2 = *bytes.get(2)?;
                 let base_width = (b2 >> 5 & 0b0000_0111) + 1;
                 let patch_width = five_bit_width(b2 & 0b0001_1111, false);
 
-                let b3 = bytes[3];
+                let b3 = *bytes.get(3)?;
                 let patch_gap_width = (b3 >> 5 & 0b0000_0111) + 1;
                 let patch_list_len = b3 & 0b0001_1111;
 
@@ -223,8 +292,9 @@ fn parse_header(bytes: &[u8]) -> Option<(Header, usize)> {
     }
 }
 
-#[derive(Debug)]
-enum Header {
+/// The header of a version 2 run, which identifies the sub-encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Header {
     ShortRepeat {
         width: u8,
         repeat_count: u8,
@@ -281,23 +351,24 @@ fn read_u64_be_bits(bytes: &[u8], bit_offset: u64, bit_width: u8) -> Option<u64>
     } else {
         let current_byte = (bit_offset / 8) as usize;
         let current_bit = bit_offset % 8;
-        let mut value = (bytes[current_byte] & (255 >> current_bit)) as u64;
+        // Unaligned 64-bit values may span nine bytes.
+        let mut value = (bytes[current_byte] & (255 >> current_bit)) as u128;
 
         for i in 1..(bytes_needed - current_byte) {
             value *= 256;
-            value += bytes[current_byte + i] as u64;
+            value += bytes[current_byte + i] as u128;
         }
 
         if bits_leftover != 0 {
             value >>= 8 - bits_leftover;
         }
 
-        Some(value)
+        Some(value as u64)
     }
 }
 
 fn bits_to_bytes(bit_count: u64) -> usize {
-    ((bit_count / 8) + if bit_count % 8 == 0 { 0 } else { 1 }) as usize
+    bit_count.div_ceil(8) as usize
 }
 
 const FIVE_BIT_ENCODING: [u8; 32] = [
@@ -367,49 +438,164 @@ mod tests {
     const DELTA_OUTPUT: [u64; 10] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
 
     #[test]
-    fn append_next_u64s_simple_short_repeat() {
+    fn decode_run_simple_short_repeat() {
         let input = SHORT_REPEAT_INPUT;
         let expected = SHORT_REPEAT_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, false).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn append_next_u64s_simple_direct() {
+    fn decode_run_simple_direct() {
         let input = DIRECT_INPUT;
         let expected = DIRECT_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, false).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn append_next_u64s_simple_patched_base() {
+    fn decode_run_simple_patched_base() {
         let input = PATCHED_BASE_INPUT;
         let expected = PATCHED_BASE_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, false).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn append_next_u64s_simple_delta() {
+    fn decode_run_simple_delta() {
         let input = DELTA_INPUT;
         let expected = DELTA_OUTPUT.to_vec();
 
         let mut result = vec![];
-        append_next_u64s(&input, &mut result, false).unwrap();
+        decode_run(&input, &mut result, false).unwrap();
 
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn decode_run_signed_patched_base() {
+        // The same run as above, but with the sign bit of the base set.
+        let mut input = PATCHED_BASE_INPUT;
+        input[4] |= 0x80;
+        let expected = PATCHED_BASE_OUTPUT
+            .iter()
+            .map(|value| *value as i64 - 2 * 2000)
+            .collect::<Vec<_>>();
+
+        let mut result = vec![];
+        decode_run(&input, &mut result, true).unwrap();
+
+        assert_eq!(
+            result
+                .into_iter()
+                .map(|value| value as i64)
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn decoder_matches_decode_u64s() {
+        let mut input = vec![];
+        input.extend(SHORT_REPEAT_INPUT);
+        input.extend(DELTA_INPUT);
+
+        let mut decoder = decoder(&input, false);
+        let result = decoder
+            .by_ref()
+            .take(5)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(result, SHORT_REPEAT_OUTPUT);
+        assert_eq!(decoder.position(), SHORT_REPEAT_INPUT.len());
+        assert_eq!(
+            decoder.collect::<Result<Vec<_>, _>>().unwrap(),
+            DELTA_OUTPUT
+        );
+    }
+
+    #[test]
+    fn decode_u64s_error_position() {
+        let mut input = vec![];
+        input.extend(SHORT_REPEAT_INPUT);
+        input.extend(&PATCHED_BASE_INPUT[..20]);
+
+        let error = decode_u64s(&input, None, false).unwrap_err();
+
+        assert_eq!(
+            error.error,
+            Error::InvalidRun {
+                offset: SHORT_REPEAT_INPUT.len(),
+                header: Some(RunHeader::V2(Header::PatchedBase {
+                    width: 8,
+                    len: 20,
+                    base_width: 2,
+                    patch_width: 12,
+                    patch_gap_width: 2,
+                    patch_list_len: 1
+                }))
+            }
+        );
+        assert_eq!(error.values, SHORT_REPEAT_OUTPUT);
+    }
+
+    #[test]
+    fn decode_run_patched_base_oversized_patch_list_item() {
+        // The patch gap and patch widths add up to more than 64 bits.
+        let input = [
+            0x80, 0x03, 0x1f, 0x81, 0x92, 0x91, 0xe4, 0x21, 0x1f, 0x15, 0x8b, 0xac, 0xb5, 0x2a,
+        ];
+
+        let error = decode_u64s(&input, None, false).unwrap_err();
+
+        assert!(matches!(
+            error.error,
+            Error::InvalidRun {
+                offset: 0,
+                header: Some(RunHeader::V2(Header::PatchedBase {
+                    patch_width: 64,
+                    patch_gap_width: 5,
+                    ..
+                }))
+            }
+        ));
+        assert!(error.values.is_empty());
+    }
+
+    #[test]
+    fn decode_run_patched_base_full_width_patch() {
+        // A 64-bit data width leaves no room for the patch bits.
+        let mut input = vec![0xbe, 0x00, 0x00, 0x21, 0x00];
+        input.extend([0; 8]);
+        input.push(0x00);
+
+        let mut result = vec![];
+
+        assert!(matches!(
+            decode_run(&input, &mut result, false),
+            Err(Some(Header::PatchedBase { width: 64, .. }))
+        ));
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn decode_run_truncated_header() {
+        let mut result = vec![];
+
+        assert_eq!(decode_run(&[0x5e], &mut result, false), Err(None));
+        assert!(result.is_empty());
+    }
+
     #[test]
     fn concatenation() {
         let mut input = vec![];
@@ -433,7 +619,7 @@ mod tests {
         expected.extend(PATCHED_BASE_OUTPUT);
         expected.extend(DELTA_OUTPUT);
 
-        let result = decode_u64s(&mut input, None, false).unwrap();
+        let result = decode_u64s(&input, None, false).unwrap();
 
         assert_eq!(result, expected);
     }
@@ -1,9 +1,50 @@
+//! Decoders (and minimal literal-only encoders) for the run-length encodings used in ORC streams.
+//!
+//! ORC uses a byte-level encoding for byte and boolean streams ([`byte`]) and two versions of
+//! integer run-length encoding ([`intv1`] and [`intv2`]). An encoded integer stream is a sequence
+//! of self-contained runs (the "sub-encodings" of the specification), and the decoders here never
+//! split a run: a run always starts where the previous one ended, and input that ends partway
+//! through a run is an error.
+//!
+//! Integer values are always returned as `u64`. When decoding a signed stream, the zigzag (or, for
+//! patched base runs, sign-magnitude) encoding is removed and the result is the two's complement
+//! representation of the value, so `value as i64` gives the original signed integer. When decoding
+//! an unsigned stream, the values are returned exactly as they were encoded.
+
+use crate::meta::EncodingKind;
 use crate::proto::orc_proto::column_encoding::Kind;
 
 pub mod byte;
 pub mod intv1;
 pub mod intv2;
 
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The run starting at the given byte offset couldn't be decoded.
+    ///
+    /// The header is included if it was valid.
+    #[error("Invalid run")]
+    InvalidRun {
+        offset: usize,
+        header: Option<RunHeader>,
+    },
+}
+
+/// A failure to decode an entire stream, along with the values from the preceding runs.
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+#[error("Invalid integer encoding")]
+pub struct DecodeError {
+    #[source]
+    pub error: Error,
+    pub values: Vec<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RunHeader {
+    V1(intv1::Header),
+    V2(intv2::Header),
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum IntegerRleVersion {
     V1,
@@ -20,3 +61,87 @@ impl From<Kind> for IntegerRleVersion {
         }
     }
 }
+
+impl From<EncodingKind> for IntegerRleVersion {
+    fn from(kind: EncodingKind) -> Self {
+        match kind {
+            EncodingKind::Direct | EncodingKind::Dictionary => Self::V1,
+            EncodingKind::DirectV2 | EncodingKind::DictionaryV2 => Self::V2,
+        }
+    }
+}
+
+/// An iterator over the values of an integer run-length encoded stream.
+///
+/// Values are decoded one run at a time. After an error the iterator is exhausted.
+pub struct IntegerDecoder<'a> {
+    bytes: &'a [u8],
+    version: IntegerRleVersion,
+    signed: bool,
+    position: usize,
+    values: Vec<u64>,
+    value_index: usize,
+}
+
+impl<'a> IntegerDecoder<'a> {
+    pub fn new(bytes: &'a [u8], version: IntegerRleVersion, signed: bool) -> Self {
+        Self {
+            bytes,
+            version,
+            signed,
+            position: 0,
+            values: vec![],
+            value_index: 0,
+        }
+    }
+
+    /// The byte offset of the first run that hasn't been decoded yet.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn decode_run(&mut self) -> Result<(), Error> {
+        self.values.clear();
+        self.value_index = 0;
+
+        let bytes = &self.bytes[self.position..];
+        let read_len = match self.version {
+            IntegerRleVersion::V1 => intv1::decode_run(bytes, &mut self.values, self.signed)
+                .map_err(|header| header.map(RunHeader::V1)),
+            IntegerRleVersion::V2 => intv2::decode_run(bytes, &mut self.values, self.signed)
+                .map_err(|header| header.map(RunHeader::V2)),
+        };
+
+        match read_len {
+            Ok(read_len) => {
+                self.position += read_len;
+                Ok(())
+            }
+            Err(header) => {
+                let offset = self.position;
+                self.position = self.bytes.len();
+                Err(Error::InvalidRun { offset, header })
+            }
+        }
+    }
+}
+
+impl Iterator for IntegerDecoder<'_> {
+    type Item = Result<u64, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.value_index >= self.values.len() {
+            if self.position >= self.bytes.len() {
+                return None;
+            }
+
+            if let Err(error) = self.decode_run() {
+                return Some(Err(error));
+            }
+        }
+
+        let value = self.values[self.value_index];
+        self.value_index += 1;
+        Some(Ok(value))
+    }
+}
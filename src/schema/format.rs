@@ -0,0 +1,650 @@
+//! Rendering a file's type tree as a schema for other systems.
+//!
+//! ORC columns are always nullable, so every field (and list element or map value) is rendered as
+//! nullable. Names are taken from the file as-is, except that nested Avro records are named by
+//! joining the path of field names with underscores.
+
+use crate::meta::{ColumnType, Footer, TypeKind};
+use serde_json::{json, Map, Value as Json};
+
+// Defaults used by the Java implementation when a type doesn't specify them.
+const DEFAULT_DECIMAL_PRECISION: u32 = 38;
+const DEFAULT_DECIMAL_SCALE: u32 = 10;
+const DEFAULT_VARCHAR_LENGTH: u32 = 65535;
+const DEFAULT_CHAR_LENGTH: u32 = 255;
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    #[error("Invalid column ID")]
+    InvalidColumnId(u32),
+    #[error("Missing subtype")]
+    MissingSubtype(TypeKind),
+    #[error("Unsupported root type")]
+    UnsupportedRootType(TypeKind),
+    #[error("Unsupported map key type")]
+    UnsupportedMapKey(TypeKind),
+    #[error("Invalid name")]
+    InvalidName(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchemaFormat {
+    /// The ORC type description (e.g. `struct<id:bigint,name:string>`).
+    Orc,
+    /// An Avro record schema.
+    Avro,
+    /// An Arrow schema in the JSON form used by the Arrow integration tests.
+    Arrow,
+    /// A JSON Schema (2020-12) describing each row as an object.
+    JsonSchema,
+    /// A Hive `CREATE TABLE` statement.
+    HiveDdl,
+}
+
+/// Render the schema of a file, using `name` for the table, record, or title (if the format has
+/// one).
+///
+/// Every format except ORC requires the root type to be a struct.
+pub fn format_schema(footer: &Footer, format: SchemaFormat, name: &str) -> Result<String, Error> {
+    let types = footer.get_types();
+    let root = get_type(types, 0)?;
+
+    if format != SchemaFormat::Orc && root.get_kind() != TypeKind::Struct {
+        return Err(Error::UnsupportedRootType(root.get_kind()));
+    }
+
+    match format {
+        SchemaFormat::Orc => orc_type(types, 0),
+        SchemaFormat::Avro => {
+            let schema = avro_record(types, root, name)?;
+            Ok(to_json_string(&schema))
+        }
+        SchemaFormat::Arrow => {
+            let fields = fields(root)
+                .map(|(field_name, column_id)| arrow_field(types, field_name, column_id, true))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            Ok(to_json_string(&json!({ "fields": fields })))
+        }
+        SchemaFormat::JsonSchema => {
+            let mut schema = json!({ "$schema": JSON_SCHEMA_DIALECT, "title": name });
+
+            if let (Json::Object(schema), Json::Object(row_schema)) =
+                (&mut schema, json_schema_object(types, root)?)
+            {
+                schema.extend(row_schema);
+            }
+
+            Ok(to_json_string(&schema))
+        }
+        SchemaFormat::HiveDdl => {
+            let columns = fields(root)
+                .map(|(field_name, column_id)| {
+                    Ok(format!(
+                        "  {} {}",
+                        quote_hive_name(field_name),
+                        orc_type(types, column_id)?
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            Ok(format!(
+                "CREATE TABLE {} (\n{}\n)\nSTORED AS ORC;",
+                quote_hive_name(name),
+                columns.join(",\n")
+            ))
+        }
+    }
+}
+
+fn get_type(types: &[ColumnType], column_id: u32) -> Result<&ColumnType, Error> {
+    types
+        .get(column_id as usize)
+        .ok_or(Error::InvalidColumnId(column_id))
+}
+
+/// The field names and column IDs of a struct.
+fn fields(column_type: &ColumnType) -> impl Iterator<Item = (&str, u32)> {
+    column_type
+        .get_subtypes()
+        .iter()
+        .enumerate()
+        .map(|(index, column_id)| {
+            (
+                column_type
+                    .get_field_names()
+                    .get(index)
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                *column_id,
+            )
+        })
+}
+
+fn subtype(column_type: &ColumnType, index: usize) -> Result<u32, Error> {
+    column_type
+        .get_subtypes()
+        .get(index)
+        .copied()
+        .ok_or(Error::MissingSubtype(column_type.get_kind()))
+}
+
+fn to_json_string(value: &Json) -> String {
+    // Serializing a JSON value can't fail.
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn orc_type(types: &[ColumnType], column_id: u32) -> Result<String, Error> {
+    let column_type = get_type(types, column_id)?;
+
+    Ok(match column_type.get_kind() {
+        TypeKind::Boolean => "boolean".to_string(),
+        TypeKind::Byte => "tinyint".to_string(),
+        TypeKind::Short => "smallint".to_string(),
+        TypeKind::Int => "int".to_string(),
+        TypeKind::Long => "bigint".to_string(),
+        TypeKind::Float => "float".to_string(),
+        TypeKind::Double => "double".to_string(),
+        TypeKind::String => "string".to_string(),
+        TypeKind::Binary => "binary".to_string(),
+        TypeKind::Timestamp => "timestamp".to_string(),
+        TypeKind::TimestampInstant => "timestamp with local time zone".to_string(),
+        TypeKind::Date => "date".to_string(),
+        TypeKind::Decimal => format!(
+            "decimal({},{})",
+            column_type
+                .get_precision()
+                .unwrap_or(DEFAULT_DECIMAL_PRECISION),
+            column_type.get_scale().unwrap_or(DEFAULT_DECIMAL_SCALE)
+        ),
+        TypeKind::Varchar => format!(
+            "varchar({})",
+            column_type
+                .get_maximum_length()
+                .unwrap_or(DEFAULT_VARCHAR_LENGTH)
+        ),
+        TypeKind::Char => format!(
+            "char({})",
+            column_type
+                .get_maximum_length()
+                .unwrap_or(DEFAULT_CHAR_LENGTH)
+        ),
+        TypeKind::List => format!("array<{}>", orc_type(types, subtype(column_type, 0)?)?),
+        TypeKind::Map => format!(
+            "map<{},{}>",
+            orc_type(types, subtype(column_type, 0)?)?,
+            orc_type(types, subtype(column_type, 1)?)?
+        ),
+        TypeKind::Union => format!(
+            "uniontype<{}>",
+            column_type
+                .get_subtypes()
+                .iter()
+                .map(|column_id| orc_type(types, *column_id))
+                .collect::<Result<Vec<_>, Error>>()?
+                .join(",")
+        ),
+        TypeKind::Struct => format!(
+            "struct<{}>",
+            fields(column_type)
+                .map(|(field_name, column_id)| {
+                    Ok(format!(
+                        "{}:{}",
+                        quote_orc_name(field_name),
+                        orc_type(types, column_id)?
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+                .join(",")
+        ),
+    })
+}
+
+/// Field names are only quoted if they contain characters other than letters, digits, and
+/// underscores (as in the Java implementation).
+fn quote_orc_name(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        name.to_string()
+    } else {
+        quote_hive_name(name)
+    }
+}
+
+fn quote_hive_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn is_avro_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn avro_record(
+    types: &[ColumnType],
+    column_type: &ColumnType,
+    record_name: &str,
+) -> Result<Json, Error> {
+    if !is_avro_name(record_name) {
+        return Err(Error::InvalidName(record_name.to_string()));
+    }
+
+    let fields = fields(column_type)
+        .map(|(field_name, column_id)| {
+            if !is_avro_name(field_name) {
+                return Err(Error::InvalidName(field_name.to_string()));
+            }
+
+            Ok(json!({
+                "name": field_name,
+                "type": avro_nullable(avro_type(
+                    types,
+                    column_id,
+                    &format!("{}_{}", record_name, field_name)
+                )?),
+                "default": null,
+            }))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(json!({ "type": "record", "name": record_name, "fields": fields }))
+}
+
+fn avro_nullable(schema: Json) -> Json {
+    match schema {
+        // Avro unions can't be nested, so the null branch is added to the union.
+        Json::Array(mut branches) => {
+            branches.insert(0, json!("null"));
+            Json::Array(branches)
+        }
+        other => json!(["null", other]),
+    }
+}
+
+/// The path is used for naming nested records.
+fn avro_type(types: &[ColumnType], column_id: u32, path: &str) -> Result<Json, Error> {
+    let column_type = get_type(types, column_id)?;
+
+    Ok(match column_type.get_kind() {
+        TypeKind::Boolean => json!("boolean"),
+        TypeKind::Byte | TypeKind::Short | TypeKind::Int => json!("int"),
+        TypeKind::Long => json!("long"),
+        TypeKind::Float => json!("float"),
+        TypeKind::Double => json!("double"),
+        TypeKind::String | TypeKind::Varchar | TypeKind::Char => json!("string"),
+        TypeKind::Binary => json!("bytes"),
+        TypeKind::Timestamp => json!({ "type": "long", "logicalType": "local-timestamp-micros" }),
+        TypeKind::TimestampInstant => json!({ "type": "long", "logicalType": "timestamp-micros" }),
+        TypeKind::Date => json!({ "type": "int", "logicalType": "date" }),
+        TypeKind::Decimal => json!({
+            "type": "bytes",
+            "logicalType": "decimal",
+            "precision": column_type.get_precision().unwrap_or(DEFAULT_DECIMAL_PRECISION),
+            "scale": column_type.get_scale().unwrap_or(DEFAULT_DECIMAL_SCALE),
+        }),
+        TypeKind::List => json!({
+            "type": "array",
+            "items": avro_nullable(avro_type(
+                types,
+                subtype(column_type, 0)?,
+                &format!("{}_item", path)
+            )?),
+        }),
+        TypeKind::Map => {
+            let key_kind = get_type(types, subtype(column_type, 0)?)?.get_kind();
+
+            // Avro map keys are always strings.
+            if !matches!(
+                key_kind,
+                TypeKind::String | TypeKind::Varchar | TypeKind::Char
+            ) {
+                return Err(Error::UnsupportedMapKey(key_kind));
+            }
+
+            json!({
+                "type": "map",
+                "values": avro_nullable(avro_type(
+                    types,
+                    subtype(column_type, 1)?,
+                    &format!("{}_value", path)
+                )?),
+            })
+        }
+        TypeKind::Union => Json::Array(
+            column_type
+                .get_subtypes()
+                .iter()
+                .enumerate()
+                .map(|(index, column_id)| {
+                    avro_type(types, *column_id, &format!("{}_{}", path, index))
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+        ),
+        TypeKind::Struct => avro_record(types, column_type, path)?,
+    })
+}
+
+fn arrow_field(
+    types: &[ColumnType],
+    name: &str,
+    column_id: u32,
+    nullable: bool,
+) -> Result<Json, Error> {
+    let column_type = get_type(types, column_id)?;
+    let mut children = vec![];
+
+    let arrow_type = match column_type.get_kind() {
+        TypeKind::Boolean => json!({ "name": "bool" }),
+        TypeKind::Byte => json!({ "name": "int", "bitWidth": 8, "isSigned": true }),
+        TypeKind::Short => json!({ "name": "int", "bitWidth": 16, "isSigned": true }),
+        TypeKind::Int => json!({ "name": "int", "bitWidth": 32, "isSigned": true }),
+        TypeKind::Long => json!({ "name": "int", "bitWidth": 64, "isSigned": true }),
+        TypeKind::Float => json!({ "name": "floatingpoint", "precision": "SINGLE" }),
+        TypeKind::Double => json!({ "name": "floatingpoint", "precision": "DOUBLE" }),
+        TypeKind::String | TypeKind::Varchar | TypeKind::Char => json!({ "name": "utf8" }),
+        TypeKind::Binary => json!({ "name": "binary" }),
+        TypeKind::Timestamp => json!({ "name": "timestamp", "unit": "NANOSECOND" }),
+        TypeKind::TimestampInstant => {
+            json!({ "name": "timestamp", "unit": "NANOSECOND", "timezone": "UTC" })
+        }
+        TypeKind::Date => json!({ "name": "date", "unit": "DAY" }),
+        TypeKind::Decimal => json!({
+            "name": "decimal",
+            "precision": column_type.get_precision().unwrap_or(DEFAULT_DECIMAL_PRECISION),
+            "scale": column_type.get_scale().unwrap_or(DEFAULT_DECIMAL_SCALE),
+            "bitWidth": 128,
+        }),
+        TypeKind::List => {
+            children.push(arrow_field(types, "item", subtype(column_type, 0)?, true)?);
+            json!({ "name": "list" })
+        }
+        TypeKind::Map => {
+            let entries = json!({
+                "name": "entries",
+                "nullable": false,
+                "type": { "name": "struct" },
+                "children": [
+                    arrow_field(types, "key", subtype(column_type, 0)?, false)?,
+                    arrow_field(types, "value", subtype(column_type, 1)?, true)?,
+                ],
+            });
+            children.push(entries);
+            json!({ "name": "map", "keysSorted": false })
+        }
+        TypeKind::Union => {
+            for (index, column_id) in column_type.get_subtypes().iter().enumerate() {
+                children.push(arrow_field(types, &index.to_string(), *column_id, true)?);
+            }
+
+            json!({
+                "name": "union",
+                "mode": "DENSE",
+                "typeIds": (0..children.len()).collect::<Vec<_>>(),
+            })
+        }
+        TypeKind::Struct => {
+            for (field_name, column_id) in fields(column_type) {
+                children.push(arrow_field(types, field_name, column_id, true)?);
+            }
+
+            json!({ "name": "struct" })
+        }
+    };
+
+    Ok(json!({
+        "name": name,
+        "nullable": nullable,
+        "type": arrow_type,
+        "children": children,
+    }))
+}
+
+fn json_schema_nullable(schema: Json) -> Json {
+    match schema {
+        Json::Object(mut schema) => match schema.remove("type") {
+            Some(Json::String(json_type)) => {
+                schema.insert("type".to_string(), json!([json_type, "null"]));
+                Json::Object(schema)
+            }
+            _ => match schema.remove("anyOf") {
+                Some(Json::Array(mut branches)) => {
+                    branches.push(json!({ "type": "null" }));
+                    schema.insert("anyOf".to_string(), Json::Array(branches));
+                    Json::Object(schema)
+                }
+                _ => Json::Object(schema),
+            },
+        },
+        other => other,
+    }
+}
+
+fn json_schema_object(types: &[ColumnType], column_type: &ColumnType) -> Result<Json, Error> {
+    let mut properties = Map::new();
+
+    for (field_name, column_id) in fields(column_type) {
+        properties.insert(
+            field_name.to_string(),
+            json_schema_nullable(json_schema_type(types, column_id)?),
+        );
+    }
+
+    Ok(json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    }))
+}
+
+fn json_schema_type(types: &[ColumnType], column_id: u32) -> Result<Json, Error> {
+    let column_type = get_type(types, column_id)?;
+
+    Ok(match column_type.get_kind() {
+        TypeKind::Boolean => json!({ "type": "boolean" }),
+        TypeKind::Byte => json!({ "type": "integer", "minimum": i8::MIN, "maximum": i8::MAX }),
+        TypeKind::Short => json!({ "type": "integer", "minimum": i16::MIN, "maximum": i16::MAX }),
+        TypeKind::Int => json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX }),
+        TypeKind::Long => json!({ "type": "integer", "minimum": i64::MIN, "maximum": i64::MAX }),
+        TypeKind::Float | TypeKind::Double | TypeKind::Decimal => json!({ "type": "number" }),
+        TypeKind::String => json!({ "type": "string" }),
+        TypeKind::Varchar | TypeKind::Char => json!({
+            "type": "string",
+            "maxLength": column_type.get_maximum_length().unwrap_or(
+                if column_type.get_kind() == TypeKind::Varchar {
+                    DEFAULT_VARCHAR_LENGTH
+                } else {
+                    DEFAULT_CHAR_LENGTH
+                }
+            ),
+        }),
+        TypeKind::Binary => json!({ "type": "string", "contentEncoding": "base64" }),
+        TypeKind::Timestamp | TypeKind::TimestampInstant => {
+            json!({ "type": "string", "format": "date-time" })
+        }
+        TypeKind::Date => json!({ "type": "string", "format": "date" }),
+        TypeKind::List => json!({
+            "type": "array",
+            "items": json_schema_nullable(json_schema_type(types, subtype(column_type, 0)?)?),
+        }),
+        // JSON object keys are always strings, so the key type isn't described.
+        TypeKind::Map => json!({
+            "type": "object",
+            "additionalProperties":
+                json_schema_nullable(json_schema_type(types, subtype(column_type, 1)?)?),
+        }),
+        TypeKind::Union => json!({
+            "anyOf": column_type
+                .get_subtypes()
+                .iter()
+                .map(|column_id| json_schema_type(types, *column_id))
+                .collect::<Result<Vec<_>, Error>>()?,
+        }),
+        TypeKind::Struct => json_schema_object(types, column_type)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::OrcFile;
+    use crate::proto::orc_proto;
+
+    #[test]
+    fn ts_1k_orc_and_hive() {
+        let orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let footer = orc_file.get_footer();
+
+        assert_eq!(
+            format_schema(footer, SchemaFormat::Orc, "users").unwrap(),
+            "struct<id:bigint,status_id:bigint,timestamp:bigint,screen_name:string,name:string,\
+            url:string,location:string,description:string,profile_image_url:string,\
+            verified:boolean,followers_count:int>"
+        );
+
+        let ddl = format_schema(footer, SchemaFormat::HiveDdl, "users").unwrap();
+        assert!(ddl.starts_with("CREATE TABLE `users` (\n  `id` bigint,\n"));
+        assert!(ddl.ends_with("  `followers_count` int\n)\nSTORED AS ORC;"));
+    }
+
+    #[test]
+    fn ts_1k_json_formats() {
+        let orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let footer = orc_file.get_footer();
+
+        let avro: Json =
+            serde_json::from_str(&format_schema(footer, SchemaFormat::Avro, "users").unwrap())
+                .unwrap();
+        assert_eq!(avro["name"], "users");
+        assert_eq!(avro["fields"].as_array().unwrap().len(), 11);
+        assert_eq!(
+            avro["fields"][9],
+            json!({ "name": "verified", "type": ["null", "boolean"], "default": null })
+        );
+
+        let arrow: Json =
+            serde_json::from_str(&format_schema(footer, SchemaFormat::Arrow, "users").unwrap())
+                .unwrap();
+        assert_eq!(
+            arrow["fields"][10],
+            json!({
+                "name": "followers_count",
+                "nullable": true,
+                "type": { "name": "int", "bitWidth": 32, "isSigned": true },
+                "children": [],
+            })
+        );
+
+        let json_schema: Json = serde_json::from_str(
+            &format_schema(footer, SchemaFormat::JsonSchema, "users").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json_schema["title"], "users");
+        assert_eq!(
+            json_schema["properties"]["screen_name"],
+            json!({ "type": ["string", "null"] })
+        );
+        // Properties are in column order.
+        assert_eq!(
+            json_schema["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .next()
+                .unwrap(),
+            "id"
+        );
+
+        assert_eq!(
+            format_schema(footer, SchemaFormat::Avro, "user rows"),
+            Err(Error::InvalidName("user rows".to_string()))
+        );
+    }
+
+    fn column_type(
+        kind: orc_proto::type_::Kind,
+        field_names: &[&str],
+        subtypes: &[u32],
+    ) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(kind);
+        column_type.fieldNames = field_names.iter().map(|name| name.to_string()).collect();
+        column_type.subtypes = subtypes.to_vec();
+        column_type
+    }
+
+    fn nested_footer() -> Footer {
+        use orc_proto::type_::Kind;
+
+        let mut decimal_type = column_type(Kind::DECIMAL, &[], &[]);
+        decimal_type.set_precision(10);
+        decimal_type.set_scale(2);
+
+        let mut footer = orc_proto::Footer::new();
+        footer.types = vec![
+            column_type(Kind::STRUCT, &["tags", "user", "scores"], &[1, 3, 6]),
+            column_type(Kind::LIST, &[], &[2]),
+            column_type(Kind::STRING, &[], &[]),
+            column_type(Kind::STRUCT, &["first name", "balance"], &[4, 5]),
+            column_type(Kind::STRING, &[], &[]),
+            decimal_type,
+            column_type(Kind::MAP, &[], &[7, 8]),
+            column_type(Kind::STRING, &[], &[]),
+            column_type(Kind::UNION, &[], &[9, 10]),
+            column_type(Kind::INT, &[], &[]),
+            column_type(Kind::DATE, &[], &[]),
+        ];
+        Footer::from_proto(&footer)
+    }
+
+    #[test]
+    fn nested_types() {
+        let footer = nested_footer();
+
+        assert_eq!(
+            format_schema(&footer, SchemaFormat::Orc, "nested").unwrap(),
+            "struct<tags:array<string>,user:struct<`first name`:string,balance:decimal(10,2)>,\
+            scores:map<string,uniontype<int,date>>>"
+        );
+
+        let arrow: Json =
+            serde_json::from_str(&format_schema(&footer, SchemaFormat::Arrow, "nested").unwrap())
+                .unwrap();
+        assert_eq!(arrow["fields"][2]["type"]["name"], "map");
+        assert_eq!(
+            arrow["fields"][2]["children"][0]["children"][1]["type"],
+            json!({ "name": "union", "mode": "DENSE", "typeIds": [0, 1] })
+        );
+
+        let json_schema: Json = serde_json::from_str(
+            &format_schema(&footer, SchemaFormat::JsonSchema, "nested").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            json_schema["properties"]["scores"]["additionalProperties"],
+            json!({
+                "anyOf": [
+                    { "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX },
+                    { "type": "string", "format": "date" },
+                    { "type": "null" },
+                ]
+            })
+        );
+
+        // The nested field name isn't a valid Avro name.
+        assert_eq!(
+            format_schema(&footer, SchemaFormat::Avro, "nested"),
+            Err(Error::InvalidName("first name".to_string()))
+        );
+    }
+
+    #[test]
+    fn quote_names() {
+        assert_eq!(quote_orc_name("screen_name"), "screen_name");
+        assert_eq!(quote_orc_name("screen name"), "`screen name`");
+        assert_eq!(quote_hive_name("a`b"), "`a``b`");
+        assert!(is_avro_name("_id2"));
+        assert!(!is_avro_name("2id"));
+        assert!(!is_avro_name(""));
+    }
+}
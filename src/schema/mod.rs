@@ -0,0 +1,343 @@
+//! Comparing the schemas of files that should be read together.
+//!
+//! A schema is a list of top-level field names and types. Comparing a reference schema with a newer
+//! one classifies each difference, so that a directory of part files can be checked before it's
+//! read as a single dataset, and rows can be read in a target schema with
+//! [`crate::parser::OrcFile::map_rows_with_schema`].
+
+#[cfg(feature = "json")]
+pub mod format;
+pub mod tree;
+
+pub use tree::OrcSchema;
+
+use crate::meta::{Footer, TypeKind};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Schema {
+    fields: Vec<(String, TypeKind)>,
+    // The declared attributes of each field's type.
+    attributes: Vec<TypeAttributes>,
+}
+
+// The attributes of a `CHAR`, `VARCHAR`, or `DECIMAL` type.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct TypeAttributes {
+    maximum_length: Option<u32>,
+    precision: Option<u32>,
+    scale: Option<u32>,
+}
+
+/// A difference between a reference schema and a newer schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Incompatibility {
+    /// A field that's only in the newer schema.
+    AddedField { name: String, kind: TypeKind },
+    /// A field that's only in the reference schema.
+    RemovedField { name: String, kind: TypeKind },
+    /// A field with a type that can represent all values of the reference type.
+    TypeWidening {
+        name: String,
+        from: TypeKind,
+        to: TypeKind,
+    },
+    TypeConflict {
+        name: String,
+        from: TypeKind,
+        to: TypeKind,
+    },
+    /// A removed field and an added field with the same position and type.
+    RenameCandidate {
+        from: String,
+        to: String,
+        kind: TypeKind,
+    },
+}
+
+impl Incompatibility {
+    /// Whether data in the reference schema can't be read in the newer schema (missing fields can
+    /// be read as nulls and narrower types as wider types).
+    pub fn is_breaking(&self) -> bool {
+        matches!(self, Self::TypeConflict { .. })
+    }
+}
+
+impl Schema {
+    pub fn new(fields: Vec<(String, TypeKind)>) -> Self {
+        Self {
+            attributes: vec![TypeAttributes::default(); fields.len()],
+            fields,
+        }
+    }
+
+    /// The top-level fields of a file, or `None` if the root type isn't a struct.
+    pub fn from_footer(footer: &Footer) -> Option<Self> {
+        let types = footer.get_types();
+        let root = types.first()?;
+
+        if root.get_kind() != TypeKind::Struct {
+            return None;
+        }
+
+        let (fields, attributes) = root
+            .get_field_names()
+            .iter()
+            .zip(root.get_subtypes())
+            .map(|(name, column_id)| {
+                types.get(*column_id as usize).map(|column_type| {
+                    (
+                        (name.clone(), column_type.get_kind()),
+                        TypeAttributes {
+                            maximum_length: column_type.get_maximum_length(),
+                            precision: column_type.get_precision(),
+                            scale: column_type.get_scale(),
+                        },
+                    )
+                })
+            })
+            .collect::<Option<(Vec<_>, Vec<_>)>>()?;
+
+        Some(Self { fields, attributes })
+    }
+
+    pub fn get_fields(&self) -> &[(String, TypeKind)] {
+        &self.fields
+    }
+
+    /// The declared maximum length of a `CHAR` or `VARCHAR` field (`None` for other fields, or if
+    /// the schema wasn't read from a file).
+    pub fn get_maximum_length(&self, field_index: usize) -> Option<u32> {
+        self.attributes
+            .get(field_index)
+            .and_then(|attributes| attributes.maximum_length)
+    }
+
+    /// The declared precision of a `DECIMAL` field (`None` for other fields, or if the schema
+    /// wasn't read from a file).
+    pub fn get_precision(&self, field_index: usize) -> Option<u32> {
+        self.attributes
+            .get(field_index)
+            .and_then(|attributes| attributes.precision)
+    }
+
+    /// The declared scale of a `DECIMAL` field (`None` for other fields, or if the schema wasn't
+    /// read from a file).
+    pub fn get_scale(&self, field_index: usize) -> Option<u32> {
+        self.attributes
+            .get(field_index)
+            .and_then(|attributes| attributes.scale)
+    }
+
+    /// The indices of the fields whose names satisfy the predicate (such as a regular expression
+    /// match), in schema order.
+    pub fn get_matching_field_indices<F: FnMut(&str) -> bool>(
+        &self,
+        mut predicate: F,
+    ) -> Vec<usize> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, (name, _))| predicate(name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The indices of the fields that aren't named in `names`, in schema order.
+    pub fn get_field_indices_except<S: AsRef<str>>(&self, names: &[S]) -> Vec<usize> {
+        self.get_matching_field_indices(|name| {
+            !names.iter().any(|excluded| excluded.as_ref() == name)
+        })
+    }
+
+    fn get_kind(&self, name: &str) -> Option<TypeKind> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Classify the differences between this schema and a newer one (fields are matched by name).
+    ///
+    /// Differences for fields in this schema are listed first, in order, followed by added fields.
+    pub fn check_compatible(&self, other: &Schema) -> Vec<Incompatibility> {
+        let mut incompatibilities = vec![];
+
+        for (index, (name, kind)) in self.fields.iter().enumerate() {
+            match other.get_kind(name) {
+                Some(other_kind) if other_kind == *kind => {}
+                Some(other_kind) => {
+                    incompatibilities.push(if is_widening(*kind, other_kind) {
+                        Incompatibility::TypeWidening {
+                            name: name.clone(),
+                            from: *kind,
+                            to: other_kind,
+                        }
+                    } else {
+                        Incompatibility::TypeConflict {
+                            name: name.clone(),
+                            from: *kind,
+                            to: other_kind,
+                        }
+                    });
+                }
+                None => {
+                    let renamed = other.fields.get(index).filter(|(other_name, other_kind)| {
+                        other_kind == kind && self.get_kind(other_name).is_none()
+                    });
+
+                    incompatibilities.push(match renamed {
+                        Some((other_name, _)) => Incompatibility::RenameCandidate {
+                            from: name.clone(),
+                            to: other_name.clone(),
+                            kind: *kind,
+                        },
+                        None => Incompatibility::RemovedField {
+                            name: name.clone(),
+                            kind: *kind,
+                        },
+                    });
+                }
+            }
+        }
+
+        for (name, kind) in &other.fields {
+            let is_rename = incompatibilities.iter().any(|incompatibility| {
+                matches!(incompatibility, Incompatibility::RenameCandidate { to, .. } if to == name)
+            });
+
+            if self.get_kind(name).is_none() && !is_rename {
+                incompatibilities.push(Incompatibility::AddedField {
+                    name: name.clone(),
+                    kind: *kind,
+                });
+            }
+        }
+
+        incompatibilities
+    }
+}
+
+fn is_widening(from: TypeKind, to: TypeKind) -> bool {
+    fn integer_width(kind: TypeKind) -> Option<u8> {
+        match kind {
+            TypeKind::Byte => Some(8),
+            TypeKind::Short => Some(16),
+            TypeKind::Int => Some(32),
+            TypeKind::Long => Some(64),
+            _ => None,
+        }
+    }
+
+    match (from, to) {
+        (TypeKind::Float, TypeKind::Double) => true,
+        (TypeKind::Char | TypeKind::Varchar, TypeKind::String) => true,
+        _ => matches!(
+            (integer_width(from), integer_width(to)),
+            (Some(from_width), Some(to_width)) if from_width < to_width
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::OrcFile;
+
+    fn schema(fields: &[(&str, TypeKind)]) -> Schema {
+        Schema::new(
+            fields
+                .iter()
+                .map(|(name, kind)| (name.to_string(), *kind))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn check_compatible() {
+        let reference = schema(&[
+            ("id", TypeKind::Int),
+            ("name", TypeKind::String),
+            ("count", TypeKind::Long),
+            ("verified", TypeKind::Boolean),
+            ("location", TypeKind::String),
+        ]);
+        let other = schema(&[
+            ("id", TypeKind::Long),
+            ("screen_name", TypeKind::String),
+            ("count", TypeKind::Int),
+            ("followers", TypeKind::Long),
+            ("location", TypeKind::String),
+        ]);
+
+        let incompatibilities = reference.check_compatible(&other);
+
+        assert_eq!(
+            incompatibilities,
+            vec![
+                Incompatibility::TypeWidening {
+                    name: "id".to_string(),
+                    from: TypeKind::Int,
+                    to: TypeKind::Long
+                },
+                Incompatibility::RenameCandidate {
+                    from: "name".to_string(),
+                    to: "screen_name".to_string(),
+                    kind: TypeKind::String
+                },
+                Incompatibility::TypeConflict {
+                    name: "count".to_string(),
+                    from: TypeKind::Long,
+                    to: TypeKind::Int
+                },
+                Incompatibility::RemovedField {
+                    name: "verified".to_string(),
+                    kind: TypeKind::Boolean
+                },
+                Incompatibility::AddedField {
+                    name: "followers".to_string(),
+                    kind: TypeKind::Long
+                },
+            ]
+        );
+        assert_eq!(
+            incompatibilities
+                .iter()
+                .filter(|incompatibility| incompatibility.is_breaking())
+                .count(),
+            1
+        );
+        assert!(reference.check_compatible(&reference).is_empty());
+    }
+
+    #[test]
+    fn matching_field_indices() {
+        let schema = schema(&[
+            ("id", TypeKind::Long),
+            ("profile_url", TypeKind::String),
+            ("name", TypeKind::String),
+            ("profile_image_url", TypeKind::String),
+        ]);
+
+        assert_eq!(
+            schema.get_matching_field_indices(|name| name.starts_with("profile_")),
+            vec![1, 3]
+        );
+        assert!(schema.get_matching_field_indices(|_| false).is_empty());
+        assert_eq!(
+            schema.get_field_indices_except(&["profile_url", "profile_image_url", "missing"]),
+            vec![0, 2]
+        );
+    }
+
+    #[test]
+    fn from_footer() {
+        let orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let schema = Schema::from_footer(orc_file.get_footer()).unwrap();
+
+        assert_eq!(schema.get_fields().len(), 11);
+        assert_eq!(
+            schema.get_fields()[10],
+            ("followers_count".to_string(), TypeKind::Int)
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! The type tree of a file.
+//!
+//! ORC types are stored in the footer as a flat list indexed by column id, where compound types
+//! refer to their children by id. [`OrcSchema`] links these into a tree, so that the types of a
+//! file can be walked without looking up ids.
+
+use crate::meta::{Footer, TypeKind};
+
+/// A column type and the types of its children.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrcSchema {
+    column_id: u32,
+    kind: TypeKind,
+    field_names: Vec<String>,
+    children: Vec<OrcSchema>,
+    maximum_length: Option<u32>,
+    precision: Option<u32>,
+    scale: Option<u32>,
+}
+
+impl OrcSchema {
+    /// The type tree starting from the root type (column id 0).
+    ///
+    /// Returns `None` if there are no types, if a child id isn't greater than its parent's (which
+    /// would allow cycles) or is out of range, or if a type is the child of more than one type.
+    pub fn from_footer(footer: &Footer) -> Option<Self> {
+        let mut visited = vec![false; footer.get_types().len()];
+
+        Self::from_column_id(footer, 0, &mut visited)
+    }
+
+    fn from_column_id(footer: &Footer, column_id: u32, visited: &mut [bool]) -> Option<Self> {
+        let column_type = footer.get_types().get(column_id as usize)?;
+
+        if std::mem::replace(visited.get_mut(column_id as usize)?, true) {
+            return None;
+        }
+
+        let children = column_type
+            .get_subtypes()
+            .iter()
+            .map(|child_id| {
+                if *child_id > column_id {
+                    Self::from_column_id(footer, *child_id, visited)
+                } else {
+                    None
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            column_id,
+            kind: column_type.get_kind(),
+            field_names: column_type.get_field_names().to_vec(),
+            children,
+            maximum_length: column_type.get_maximum_length(),
+            precision: column_type.get_precision(),
+            scale: column_type.get_scale(),
+        })
+    }
+
+    pub fn get_column_id(&self) -> u32 {
+        self.column_id
+    }
+
+    pub fn get_kind(&self) -> TypeKind {
+        self.kind
+    }
+
+    /// The child types (the fields of a struct, the element of a list, the key and value of a map,
+    /// or the variants of a union).
+    pub fn get_children(&self) -> &[OrcSchema] {
+        &self.children
+    }
+
+    /// The field names of a struct (in the same order as the children), or an empty slice.
+    pub fn get_field_names(&self) -> &[String] {
+        &self.field_names
+    }
+
+    /// The declared maximum length of a `CHAR` or `VARCHAR` type.
+    pub fn get_maximum_length(&self) -> Option<u32> {
+        self.maximum_length
+    }
+
+    /// The declared precision of a `DECIMAL` type.
+    pub fn get_precision(&self) -> Option<u32> {
+        self.precision
+    }
+
+    /// The declared scale of a `DECIMAL` type.
+    pub fn get_scale(&self) -> Option<u32> {
+        self.scale
+    }
+
+    /// The fields of a struct as pairs of names and types.
+    pub fn get_fields(&self) -> impl Iterator<Item = (&str, &OrcSchema)> {
+        self.field_names
+            .iter()
+            .map(String::as_str)
+            .zip(&self.children)
+    }
+
+    /// Look up a field of a struct by name (if a name is repeated, the first is used).
+    pub fn get_field(&self, field_name: &str) -> Option<&OrcSchema> {
+        self.get_fields()
+            .find(|(name, _)| *name == field_name)
+            .map(|(_, child)| child)
+    }
+
+    /// Look up a nested field by a dotted path of field names (such as `row.location`).
+    pub fn get_path(&self, path: &str) -> Option<&OrcSchema> {
+        path.split('.')
+            .try_fold(self, |schema, field_name| schema.get_field(field_name))
+    }
+
+    /// Look up the type of a column in this tree by column id.
+    pub fn get_column(&self, column_id: u32) -> Option<&OrcSchema> {
+        // Types are normally numbered in pre-order, so the column is under the last child with a
+        // lower id, but other orders are possible.
+        self.find_pre_order(column_id)
+            .or_else(|| self.iter().find(|schema| schema.column_id == column_id))
+    }
+
+    fn find_pre_order(&self, column_id: u32) -> Option<&OrcSchema> {
+        if column_id == self.column_id {
+            Some(self)
+        } else {
+            self.children
+                .iter()
+                .rev()
+                .find(|child| child.column_id <= column_id)?
+                .find_pre_order(column_id)
+        }
+    }
+
+    /// The number of types in the tree (including this one).
+    pub fn get_column_count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Iterate over the types in the tree (starting with this one) in pre-order.
+    pub fn iter(&self) -> impl Iterator<Item = &OrcSchema> {
+        let mut pending = vec![self];
+
+        std::iter::from_fn(move || {
+            let schema = pending.pop()?;
+            pending.extend(schema.children.iter().rev());
+            Some(schema)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::orc_proto::{self, type_::Kind};
+
+    fn column_type(kind: Kind, field_names: &[&str], subtypes: &[u32]) -> orc_proto::Type {
+        let mut column_type = orc_proto::Type::new();
+        column_type.set_kind(kind);
+        column_type.fieldNames = field_names.iter().map(|name| name.to_string()).collect();
+        column_type.subtypes = subtypes.to_vec();
+        column_type
+    }
+
+    #[test]
+    fn tree() {
+        let mut footer = orc_proto::Footer::new();
+        footer.types = vec![
+            column_type(Kind::STRUCT, &["id", "user", "tags"], &[1, 2, 5]),
+            column_type(Kind::LONG, &[], &[]),
+            column_type(Kind::STRUCT, &["name", "balance"], &[3, 4]),
+            column_type(Kind::VARCHAR, &[], &[]),
+            column_type(Kind::DECIMAL, &[], &[]),
+            column_type(Kind::LIST, &[], &[6]),
+            column_type(Kind::STRING, &[], &[]),
+        ];
+        footer.types[3].maximumLength = Some(20);
+        footer.types[4].precision = Some(10);
+        footer.types[4].scale = Some(2);
+
+        let schema = OrcSchema::from_footer(&Footer::from_proto(&footer)).unwrap();
+
+        assert_eq!(schema.get_kind(), TypeKind::Struct);
+        assert_eq!(schema.get_field_names(), ["id", "user", "tags"]);
+        assert_eq!(schema.get_column_count(), 7);
+        assert_eq!(
+            schema
+                .iter()
+                .map(OrcSchema::get_column_id)
+                .collect::<Vec<_>>(),
+            (0..7).collect::<Vec<_>>()
+        );
+
+        let name = schema.get_path("user.name").unwrap();
+        assert_eq!(name.get_column_id(), 3);
+        assert_eq!(name.get_maximum_length(), Some(20));
+
+        let balance = schema.get_column(4).unwrap();
+        assert_eq!(balance.get_kind(), TypeKind::Decimal);
+        assert_eq!(
+            (balance.get_precision(), balance.get_scale()),
+            (Some(10), Some(2))
+        );
+
+        let tags = schema.get_field("tags").unwrap();
+        assert_eq!(tags.get_children()[0].get_kind(), TypeKind::String);
+        assert_eq!(schema.get_column(6), Some(&tags.get_children()[0]));
+        assert_eq!(schema.get_column(7), None);
+        assert_eq!(schema.get_path("user.email"), None);
+        assert_eq!(schema.get_path("id.value"), None);
+
+        // Children must have greater ids than their parents.
+        footer.types[5].subtypes = vec![0];
+        assert_eq!(OrcSchema::from_footer(&Footer::from_proto(&footer)), None);
+    }
+}
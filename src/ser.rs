@@ -0,0 +1,120 @@
+//! Serde serialization for decoded values and rows.
+//!
+//! A [`Value`] serializes as the corresponding primitive (with nulls as `none`), and a [`RowSer`]
+//! serializes a row as a map from field names to values.
+
+use crate::value::{OwnedValue, Value};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::U64(value) => serializer.serialize_u64(*value),
+            Self::I64(value) => serializer.serialize_i64(*value),
+            // Formats like JSON don't have exact decimal numbers.
+            Self::Decimal { value, scale } => {
+                serializer.serialize_str(&crate::decimal::format_scaled(*value, *scale))
+            }
+            Self::Timestamp(value) => serializer.serialize_str(&value.to_string()),
+            Self::Utf8(value) => serializer.serialize_str(value),
+            Self::Bytes(value) => serializer.serialize_bytes(value),
+            Self::List(value) => serializer.collect_seq(value.iter()),
+            Self::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for OwnedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_value().serialize(serializer)
+    }
+}
+
+/// A row of values paired with their field names.
+///
+/// If there are more field names than values (or vice versa), the extra items are ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct RowSer<'a, 'v> {
+    field_names: &'a [String],
+    values: &'a [Value<'v>],
+}
+
+impl<'a, 'v> RowSer<'a, 'v> {
+    pub fn new(field_names: &'a [String], values: &'a [Value<'v>]) -> Self {
+        Self {
+            field_names,
+            values,
+        }
+    }
+}
+
+impl Serialize for RowSer<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = self.field_names.len().min(self.values.len());
+        let mut map = serializer.serialize_map(Some(len))?;
+
+        for (field_name, value) in self.field_names.iter().zip(self.values) {
+            map.serialize_entry(field_name, value)?;
+        }
+
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::OrcFile;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn serialize_values() {
+        let values = [
+            Value::Bool(true),
+            Value::U64(123),
+            Value::Utf8("abc"),
+            Value::Null,
+        ];
+
+        assert_eq!(
+            serde_json::to_string(&values).unwrap(),
+            r#"[true,123,"abc",null]"#
+        );
+    }
+
+    #[test]
+    fn serialize_row() {
+        let field_names = vec!["id".to_string(), "name".to_string()];
+        let values = [Value::U64(1), Value::Null];
+
+        assert_eq!(
+            serde_json::to_string(&RowSer::new(&field_names, &values)).unwrap(),
+            r#"{"id":1,"name":null}"#
+        );
+    }
+
+    #[test]
+    fn serialize_file_rows() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-zlib-2020-09-20.orc").unwrap();
+        let field_names = orc_file.get_field_names().to_vec();
+        let columns = orc_file.get_field_column_indices();
+
+        let rows = orc_file
+            .map_rows(&columns, |values| {
+                serde_json::to_value(RowSer::new(&field_names, values))
+                    .map_err(Box::<dyn std::error::Error>::from)
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let expected = BufReader::new(File::open("examples/ts-1k-2020-09-20.ndjson").unwrap())
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(&line.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(rows, expected);
+    }
+}
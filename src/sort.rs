@@ -0,0 +1,502 @@
+//! Sorting rows by key with an external merge sort.
+//!
+//! Rows are sorted in memory in runs of a bounded size. If there is more than one run, each run is
+//! spilled to an anonymous temporary file, and the runs are merged. The temporary files are removed
+//! when they're dropped, so nothing is left behind if the sort fails.
+//!
+//! At most [`MAX_MERGE_FAN_IN`] runs are merged at once, so that the number of open temporary files
+//! stays small. When that many runs have been spilled, they're merged into a single longer run, and
+//! runs merged in this way are themselves merged when there are enough of them.
+
+use crate::time::Timestamp;
+use crate::value::OwnedValue;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+/// The maximum number of runs that are merged at once.
+pub const MAX_MERGE_FAN_IN: usize = 64;
+
+/// A sort key and the formatted record to write.
+pub type SortRow = (Vec<OwnedValue>, Vec<String>);
+
+/// Sort rows by key with a stable external merge sort, passing each record to `write` in order.
+///
+/// Rows are sorted in memory in runs of at most `max_rows_in_memory`, which must be at least one.
+/// If there is more than one run, the runs are spilled to temporary files and merged.
+pub fn sort_rows<I, W, E>(
+    rows: I,
+    reverse: bool,
+    max_rows_in_memory: usize,
+    mut write: W,
+) -> Result<(), E>
+where
+    I: Iterator<Item = Result<SortRow, E>>,
+    W: FnMut(Vec<String>) -> Result<(), E>,
+    E: From<std::io::Error>,
+{
+    if max_rows_in_memory == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "max_rows_in_memory must be at least one",
+        )
+        .into());
+    }
+
+    let sort_run = |run: &mut Vec<SortRow>| {
+        if reverse {
+            run.sort_by(|(a, _), (b, _)| b.cmp(a));
+        } else {
+            run.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+    };
+
+    let mut run = Vec::new();
+    let mut spilled_runs = SpilledRuns::default();
+
+    for row in rows {
+        run.push(row?);
+
+        if run.len() >= max_rows_in_memory {
+            sort_run(&mut run);
+            spilled_runs.push(spill_run(&run)?, reverse)?;
+            run.clear();
+        }
+    }
+
+    sort_run(&mut run);
+
+    if spilled_runs.is_empty() {
+        for (_, record) in run {
+            write(record)?;
+        }
+        return Ok(());
+    }
+
+    if !run.is_empty() {
+        spilled_runs.push(spill_run(&run)?, reverse)?;
+    }
+    drop(run);
+
+    merge_runs(spilled_runs.into_runs(reverse)?, reverse, |(_, record)| {
+        write(record)
+    })
+}
+
+/// Spilled runs grouped in levels, where each run in a level above the first was merged from
+/// `MAX_MERGE_FAN_IN` runs of the level below, so the runs of each level come before the runs of
+/// the levels below it.
+#[derive(Default)]
+struct SpilledRuns {
+    levels: Vec<Vec<BufReader<File>>>,
+}
+
+impl SpilledRuns {
+    fn is_empty(&self) -> bool {
+        self.levels.iter().all(Vec::is_empty)
+    }
+
+    // Add a run after all other runs, merging any level that fills up.
+    fn push(&mut self, mut run: BufReader<File>, reverse: bool) -> Result<(), std::io::Error> {
+        let mut level = 0;
+
+        loop {
+            match self.levels.get_mut(level) {
+                Some(runs) => runs.push(run),
+                None => self.levels.push(vec![run]),
+            }
+
+            if self.levels[level].len() < MAX_MERGE_FAN_IN {
+                return Ok(());
+            }
+
+            run = merge_into_run(std::mem::take(&mut self.levels[level]), reverse)?;
+            level += 1;
+        }
+    }
+
+    // All runs in order, after merging the earliest until there are few enough for a single merge.
+    fn into_runs(self, reverse: bool) -> Result<Vec<BufReader<File>>, std::io::Error> {
+        let mut runs = self.levels.into_iter().rev().flatten().collect::<Vec<_>>();
+
+        while runs.len() > MAX_MERGE_FAN_IN {
+            let merged = merge_into_run(runs.drain(..MAX_MERGE_FAN_IN).collect(), reverse)?;
+            runs.insert(0, merged);
+        }
+
+        Ok(runs)
+    }
+}
+
+// Merge sorted runs, passing each row to `write` in order.
+fn merge_runs<W, E>(mut runs: Vec<BufReader<File>>, reverse: bool, mut write: W) -> Result<(), E>
+where
+    W: FnMut(SortRow) -> Result<(), E>,
+    E: From<std::io::Error>,
+{
+    let mut heap = BinaryHeap::new();
+
+    for (run_index, reader) in runs.iter_mut().enumerate() {
+        if let Some((key, record)) = read_spilled_row(reader)? {
+            heap.push(MergeEntry::new(key, run_index, reverse, record));
+        }
+    }
+
+    while let Some(entry) = heap.pop() {
+        let run_index = entry.run_index;
+        write((entry.key, entry.record))?;
+
+        if let Some((key, record)) = read_spilled_row(&mut runs[run_index])? {
+            heap.push(MergeEntry::new(key, run_index, reverse, record));
+        }
+    }
+
+    Ok(())
+}
+
+// Merge sorted runs into a single run in a new temporary file.
+fn merge_into_run(
+    runs: Vec<BufReader<File>>,
+    reverse: bool,
+) -> Result<BufReader<File>, std::io::Error> {
+    let mut writer = RunWriter::new()?;
+    merge_runs(runs, reverse, |row| writer.write_row(&row))?;
+    writer.finish()
+}
+
+/// A row from a spilled run, ordered so that the next row to write is the greatest.
+///
+/// Ties are broken by run index, so that the merge is stable.
+struct MergeEntry {
+    key: Vec<OwnedValue>,
+    reverse: bool,
+    run_index: usize,
+    record: Vec<String>,
+}
+
+impl MergeEntry {
+    fn new(key: Vec<OwnedValue>, run_index: usize, reverse: bool, record: Vec<String>) -> Self {
+        Self {
+            key,
+            reverse,
+            run_index,
+            record,
+        }
+    }
+}
+
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let key_ordering = if self.reverse {
+            self.key.cmp(&other.key)
+        } else {
+            other.key.cmp(&self.key)
+        };
+
+        key_ordering.then_with(|| other.run_index.cmp(&self.run_index))
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
+
+// Write a sorted run to a temporary file, returning a reader positioned at its start.
+fn spill_run(run: &[SortRow]) -> Result<BufReader<File>, std::io::Error> {
+    let mut writer = RunWriter::new()?;
+
+    for row in run {
+        writer.write_row(row)?;
+    }
+
+    writer.finish()
+}
+
+// Writes the rows of a run to a temporary file.
+struct RunWriter {
+    writer: BufWriter<File>,
+}
+
+impl RunWriter {
+    fn new() -> Result<Self, std::io::Error> {
+        Ok(Self {
+            writer: BufWriter::new(tempfile::tempfile()?),
+        })
+    }
+
+    fn write_row(&mut self, (key, record): &SortRow) -> Result<(), std::io::Error> {
+        write_len(&mut self.writer, key.len())?;
+        for value in key {
+            write_key_value(&mut self.writer, value)?;
+        }
+
+        write_len(&mut self.writer, record.len())?;
+        for field in record {
+            write_bytes(&mut self.writer, field.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    // Return a reader positioned at the start of the run.
+    fn finish(self) -> Result<BufReader<File>, std::io::Error> {
+        let mut file = self.writer.into_inner().map_err(|error| error.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+
+        Ok(BufReader::new(file))
+    }
+}
+
+fn read_spilled_row<R: Read>(reader: &mut R) -> Result<Option<SortRow>, std::io::Error> {
+    let key_len = match read_len(reader) {
+        Ok(len) => len,
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    };
+
+    let key = (0..key_len)
+        .map(|_| read_key_value(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let record_len = read_len(reader)?;
+    let record = (0..record_len)
+        .map(|_| read_string(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some((key, record)))
+}
+
+fn write_key_value<W: Write>(writer: &mut W, value: &OwnedValue) -> Result<(), std::io::Error> {
+    match value {
+        OwnedValue::Null => writer.write_all(&[0]),
+        OwnedValue::Bool(value) => writer.write_all(&[1, *value as u8]),
+        OwnedValue::U64(value) => {
+            writer.write_all(&[2])?;
+            writer.write_all(&value.to_le_bytes())
+        }
+        OwnedValue::Utf8(value) => {
+            writer.write_all(&[3])?;
+            write_bytes(writer, value.as_bytes())
+        }
+        OwnedValue::Bytes(value) => {
+            writer.write_all(&[4])?;
+            write_bytes(writer, value)
+        }
+        OwnedValue::I64(value) => {
+            writer.write_all(&[5])?;
+            writer.write_all(&value.to_le_bytes())
+        }
+        OwnedValue::List(values) => {
+            writer.write_all(&[6])?;
+            write_len(writer, values.len())?;
+            values
+                .iter()
+                .try_for_each(|value| write_key_value(writer, value))
+        }
+        OwnedValue::Decimal { value, scale } => {
+            writer.write_all(&[7])?;
+            writer.write_all(&value.to_le_bytes())?;
+            writer.write_all(&[*scale])
+        }
+        OwnedValue::Timestamp(value) => {
+            writer.write_all(&[8, value.is_utc() as u8])?;
+            writer.write_all(&value.get_seconds().to_le_bytes())?;
+            writer.write_all(&value.get_nanos().to_le_bytes())
+        }
+    }
+}
+
+fn read_key_value<R: Read>(reader: &mut R) -> Result<OwnedValue, std::io::Error> {
+    let mut tag = [0; 1];
+    reader.read_exact(&mut tag)?;
+
+    Ok(match tag[0] {
+        0 => OwnedValue::Null,
+        1 => {
+            reader.read_exact(&mut tag)?;
+            OwnedValue::Bool(tag[0] != 0)
+        }
+        2 => {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            OwnedValue::U64(u64::from_le_bytes(bytes))
+        }
+        3 => OwnedValue::Utf8(read_string(reader)?),
+        4 => OwnedValue::Bytes(read_bytes(reader)?),
+        5 => {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            OwnedValue::I64(i64::from_le_bytes(bytes))
+        }
+        6 => {
+            let len = read_len(reader)?;
+            OwnedValue::List(
+                (0..len)
+                    .map(|_| read_key_value(reader))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        }
+        7 => {
+            let mut bytes = [0; 16];
+            reader.read_exact(&mut bytes)?;
+            reader.read_exact(&mut tag)?;
+            OwnedValue::Decimal {
+                value: i128::from_le_bytes(bytes),
+                scale: tag[0],
+            }
+        }
+        8 => {
+            let mut seconds = [0; 8];
+            let mut nanos = [0; 4];
+            reader.read_exact(&mut tag)?;
+            reader.read_exact(&mut seconds)?;
+            reader.read_exact(&mut nanos)?;
+            Timestamp::new(
+                i64::from_le_bytes(seconds),
+                u32::from_le_bytes(nanos),
+                tag[0] != 0,
+            )
+            .map(OwnedValue::Timestamp)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?
+        }
+        _ => return Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
+    })
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> Result<(), std::io::Error> {
+    writer.write_all(&(len as u64).to_le_bytes())
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), std::io::Error> {
+    write_len(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn read_len<R: Read>(reader: &mut R) -> Result<usize, std::io::Error> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes) as usize)
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, std::io::Error> {
+    let mut bytes = vec![0; read_len(reader)?];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, std::io::Error> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows with keys that repeat every five rows, so that the stability of the sort is visible.
+    fn test_rows(count: usize) -> impl Iterator<Item = Result<SortRow, std::io::Error>> {
+        (0..count).map(|index| {
+            Ok((
+                vec![OwnedValue::I64((index % 5) as i64)],
+                vec![index.to_string()],
+            ))
+        })
+    }
+
+    fn sorted(count: usize, reverse: bool, max_rows_in_memory: usize) -> Vec<String> {
+        let mut records = vec![];
+
+        sort_rows(test_rows(count), reverse, max_rows_in_memory, |record| {
+            records.extend(record);
+            Ok::<_, std::io::Error>(())
+        })
+        .unwrap();
+
+        records
+    }
+
+    #[test]
+    fn sort_with_spilled_runs() {
+        let expected = [0, 5, 10, 15, 1, 6, 11, 16, 2, 7, 12, 17, 3, 8, 13, 4, 9, 14]
+            .iter()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>();
+
+        // Three runs, with a partial final run.
+        assert_eq!(sorted(18, false, 7), expected);
+        assert_eq!(sorted(18, false, 100), expected);
+
+        let expected_reverse = [4, 9, 14, 3, 8, 13, 2, 7, 12, 17, 1, 6, 11, 16, 0, 5, 10, 15]
+            .iter()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(sorted(18, true, 7), expected_reverse);
+        assert_eq!(sorted(18, true, 100), expected_reverse);
+    }
+
+    #[test]
+    fn sort_with_merged_runs() {
+        // One run per row, so that levels of runs are merged before the final merge. The larger
+        // count leaves two nearly full levels, so the final merge has to merge some runs first.
+        for count in [MAX_MERGE_FAN_IN * 3 + 5, MAX_MERGE_FAN_IN * MAX_MERGE_FAN_IN - 1] {
+            for reverse in [false, true] {
+                assert_eq!(sorted(count, reverse, 1), sorted(count, reverse, count));
+            }
+        }
+    }
+
+    #[test]
+    fn sort_errors() {
+        let rows = test_rows(10).chain(std::iter::once(Err(std::io::Error::other("failed"))));
+        let mut records = vec![];
+
+        let result = sort_rows(rows, false, 7, |record| {
+            records.push(record);
+            Ok(())
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+        assert!(records.is_empty());
+
+        let result = sort_rows(test_rows(10), false, 0, |_| Ok::<_, std::io::Error>(()));
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn spilled_key_values() {
+        let key = vec![
+            OwnedValue::Null,
+            OwnedValue::Bool(true),
+            OwnedValue::U64(u64::MAX),
+            OwnedValue::I64(-1),
+            OwnedValue::Utf8("abc".to_string()),
+            OwnedValue::Bytes(vec![0, 255]),
+            OwnedValue::Decimal {
+                value: -12_345,
+                scale: 2,
+            },
+            OwnedValue::Timestamp(Timestamp::new(-2, 500_000_000, true).unwrap()),
+            OwnedValue::List(vec![OwnedValue::I64(1), OwnedValue::Null]),
+        ];
+        let run = vec![(key, vec!["a".to_string(), "b".to_string()])];
+
+        let mut reader = spill_run(&run).unwrap();
+
+        assert_eq!(read_spilled_row(&mut reader).unwrap().as_ref(), run.first());
+        assert!(read_spilled_row(&mut reader).unwrap().is_none());
+    }
+}
@@ -0,0 +1,540 @@
+//! Splitting ORC files into smaller files by copying whole stripes.
+//!
+//! Stripes are self-contained, so they can be copied byte-for-byte into a new file, which only
+//! needs a new footer and postscript. The new footer doesn't include file-level column statistics
+//! (since these can't be derived from the copied stripes without decoding them), but stripe-level
+//! statistics are preserved.
+//!
+//! Copied stripes can optionally be padded so that they don't straddle block boundaries (see
+//! [`BlockPadding`]). Stripes can't be resized without re-encoding them, so a stripe that doesn't
+//! fit and would need too much padding is written across the boundary.
+//!
+//! Internally the streams of individual columns can also be replaced while copying, with all
+//! other streams copied as they are.
+
+use crate::meta::{CompressionKind, StripeInformation};
+use crate::parser::{Error, OrcFile};
+use crate::proto::orc_proto;
+use protobuf::Message;
+use std::io::{Read, SeekFrom, Write};
+use std::ops::Range;
+
+const MAGIC: &[u8] = b"ORC";
+// The maximum length of a compression chunk (the header has 23 bits for the length).
+const MAX_CHUNK_LEN: usize = (1 << 23) - 1;
+// The default of the Java writer's `orc.block.padding.tolerance` option.
+const DEFAULT_PADDING_TOLERANCE: f64 = 0.05;
+
+/// Block alignment for copied stripes, like the block padding of the Java writer.
+///
+/// If a stripe would cross a block boundary, and the rest of the current block is no larger than
+/// the tolerance (as a fraction of the block size), the block is filled with zeros before the
+/// stripe is written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockPadding {
+    block_size: u64,
+    tolerance: f64,
+}
+
+impl BlockPadding {
+    pub fn new(block_size: u64) -> Self {
+        Self {
+            block_size,
+            tolerance: DEFAULT_PADDING_TOLERANCE,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn get_block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    pub fn get_tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// The number of bytes of padding to write before a stripe of length `len` at `offset`.
+    pub fn get_padding(&self, offset: u64, len: u64) -> u64 {
+        if self.block_size == 0 {
+            return 0;
+        }
+
+        let remaining = self.block_size - offset % self.block_size;
+        let max_padding = (self.tolerance * self.block_size as f64) as u64;
+
+        if len > remaining && remaining < self.block_size && remaining <= max_padding {
+            remaining
+        } else {
+            0
+        }
+    }
+}
+
+/// Group consecutive stripes so that each group has at most `max_rows` rows.
+///
+/// A stripe with more than `max_rows` rows will be in a group by itself.
+pub fn plan_by_rows(stripes: &[StripeInformation], max_rows: u64) -> Vec<Range<usize>> {
+    let mut groups = vec![];
+    let mut start = 0;
+    let mut row_count = 0;
+
+    for (i, stripe) in stripes.iter().enumerate() {
+        if i > start && row_count + stripe.get_number_of_rows() > max_rows {
+            groups.push(start..i);
+            start = i;
+            row_count = 0;
+        }
+
+        row_count += stripe.get_number_of_rows();
+    }
+
+    if start < stripes.len() {
+        groups.push(start..stripes.len());
+    }
+
+    groups
+}
+
+/// Group consecutive stripes so that each group has at most `max_stripes` stripes.
+pub fn plan_by_stripes(stripe_count: usize, max_stripes: usize) -> Vec<Range<usize>> {
+    let max_stripes = max_stripes.max(1);
+
+    (0..stripe_count)
+        .step_by(max_stripes)
+        .map(|start| start..(start + max_stripes).min(stripe_count))
+        .collect()
+}
+
+/// Write a new ORC file containing the given range of stripes from `orc_file`.
+pub fn write_stripes<W: Write>(
+    orc_file: &mut OrcFile,
+    stripes: Range<usize>,
+    output: W,
+) -> Result<(), Error> {
+    write_selected_stripes(orc_file, &stripes.collect::<Vec<_>>(), output)
+}
+
+/// Write a new ORC file containing the given range of stripes from `orc_file`, padding the output
+/// so that stripes are aligned to blocks where possible.
+pub fn write_padded_stripes<W: Write>(
+    orc_file: &mut OrcFile,
+    stripes: Range<usize>,
+    output: W,
+    padding: &BlockPadding,
+) -> Result<(), Error> {
+    write_selected_stripes_with_footer(
+        orc_file,
+        &stripes.collect::<Vec<_>>(),
+        output,
+        Some(padding),
+        |_| {},
+    )
+}
+
+/// Write a new ORC file containing the given stripes from `orc_file`, in the given order.
+pub fn write_selected_stripes<W: Write>(
+    orc_file: &mut OrcFile,
+    stripes: &[usize],
+    output: W,
+) -> Result<(), Error> {
+    write_selected_stripes_with_footer(orc_file, stripes, output, None, |_| {})
+}
+
+/// Write a new ORC file containing the given stripes, updating the footer before it's written.
+///
+/// The update must not change anything that would require the stripe data to be re-encoded.
+pub(crate) fn write_selected_stripes_with_footer<W: Write, F: FnOnce(&mut orc_proto::Footer)>(
+    orc_file: &mut OrcFile,
+    stripes: &[usize],
+    output: W,
+    padding: Option<&BlockPadding>,
+    update_footer: F,
+) -> Result<(), Error> {
+    write_selected_stripes_with_replacements(
+        orc_file,
+        stripes,
+        output,
+        padding,
+        |_, _, _| Ok(None),
+        update_footer,
+    )
+}
+
+/// The new contents of a copied stripe, along with its new column statistics.
+pub(crate) struct ReplacementStripe {
+    bytes: Vec<u8>,
+    index_length: u64,
+    data_length: u64,
+    footer_length: u64,
+    column_statistics: Vec<(u32, orc_proto::ColumnStatistics)>,
+}
+
+/// New (uncompressed) streams and encoding for a column (using the column ids of the file).
+pub(crate) struct ColumnStreams {
+    pub(crate) column_id: u32,
+    pub(crate) encoding: orc_proto::ColumnEncoding,
+    pub(crate) streams: Vec<(orc_proto::stream::Kind, Vec<u8>)>,
+    pub(crate) statistics: orc_proto::ColumnStatistics,
+}
+
+/// Write a new ORC file containing the given stripes, where the stripes for which
+/// `replace_stripe` returns a replacement are written with its contents.
+pub(crate) fn write_selected_stripes_with_replacements<W, R, F>(
+    orc_file: &mut OrcFile,
+    stripes: &[usize],
+    mut output: W,
+    padding: Option<&BlockPadding>,
+    mut replace_stripe: R,
+    update_footer: F,
+) -> Result<(), Error>
+where
+    W: Write,
+    R: FnMut(
+        &mut OrcFile,
+        usize,
+        &orc_proto::StripeInformation,
+    ) -> Result<Option<ReplacementStripe>, Error>,
+    F: FnOnce(&mut orc_proto::Footer),
+{
+    let (postscript, mut footer, metadata) = orc_file.read_tail_messages()?;
+    let compression = orc_file.get_postscript().get_compression();
+
+    let selected_stripes = stripes
+        .iter()
+        .map(|index| footer.stripes.get(*index).cloned())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(Error::InvalidMetadata)?;
+
+    output.write_all(MAGIC)?;
+    let mut offset = MAGIC.len() as u64;
+    let mut row_count = 0;
+    let mut new_stripes = Vec::with_capacity(selected_stripes.len());
+    let mut new_statistics = vec![];
+
+    for (position, mut stripe) in selected_stripes.into_iter().enumerate() {
+        let replacement = replace_stripe(orc_file, stripes[position], &stripe)?;
+        let len = match &replacement {
+            Some(replacement) => replacement.bytes.len() as u64,
+            None => stripe.indexLength() + stripe.dataLength() + stripe.footerLength(),
+        };
+
+        if let Some(padding) = padding {
+            let padding_len = padding.get_padding(offset, len);
+            std::io::copy(&mut std::io::repeat(0).take(padding_len), &mut output)?;
+            offset += padding_len;
+        }
+
+        match replacement {
+            Some(replacement) => {
+                output.write_all(&replacement.bytes)?;
+                stripe.set_indexLength(replacement.index_length);
+                stripe.set_dataLength(replacement.data_length);
+                stripe.set_footerLength(replacement.footer_length);
+                new_statistics.extend(
+                    replacement
+                        .column_statistics
+                        .into_iter()
+                        .map(|(column_id, statistics)| (position, column_id, statistics)),
+                );
+            }
+            None => orc_file.copy_bytes(stripe.offset(), len, &mut output)?,
+        }
+
+        stripe.set_offset(offset);
+        offset += len;
+        row_count += stripe.numberOfRows();
+        new_stripes.push(stripe);
+    }
+
+    let metadata_bytes = match metadata {
+        Some(mut metadata)
+            if stripes
+                .iter()
+                .all(|index| *index < metadata.stripeStats.len()) =>
+        {
+            metadata.stripeStats = stripes
+                .iter()
+                .map(|index| metadata.stripeStats[*index].clone())
+                .collect();
+
+            for (position, column_id, statistics) in new_statistics {
+                if let Some(column_statistics) = metadata.stripeStats[position]
+                    .colStats
+                    .get_mut(column_id as usize)
+                {
+                    *column_statistics = statistics;
+                }
+            }
+
+            encode_chunks(&metadata.write_to_bytes()?, compression, MAX_CHUNK_LEN)
+        }
+        _ => vec![],
+    };
+    output.write_all(&metadata_bytes)?;
+
+    footer.set_headerLength(MAGIC.len() as u64);
+    footer.set_contentLength(offset);
+    footer.stripes = new_stripes;
+    footer.set_numberOfRows(row_count);
+    footer.statistics.clear();
+    update_footer(&mut footer);
+
+    let footer_bytes = encode_chunks(&footer.write_to_bytes()?, compression, MAX_CHUNK_LEN);
+    output.write_all(&footer_bytes)?;
+
+    let mut new_postscript = orc_proto::PostScript::new();
+    new_postscript.set_footerLength(footer_bytes.len() as u64);
+    new_postscript.compression = postscript.compression;
+    new_postscript.compressionBlockSize = postscript.compressionBlockSize;
+    new_postscript.version = postscript.version.clone();
+    new_postscript.set_metadataLength(metadata_bytes.len() as u64);
+    new_postscript.writerVersion = postscript.writerVersion;
+    new_postscript.set_magic("ORC".to_string());
+
+    let postscript_bytes = new_postscript.write_to_bytes()?;
+    let postscript_len =
+        u8::try_from(postscript_bytes.len()).map_err(|_| Error::InvalidMetadata)?;
+    output.write_all(&postscript_bytes)?;
+    output.write_all(&[postscript_len])?;
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Build a copy of a stripe where the streams of the given columns are replaced.
+///
+/// The columns' other streams (including their row indexes, whose positions would no longer be
+/// valid) are dropped, so the file's row index stride should be cleared.
+pub(crate) fn replace_column_streams(
+    orc_file: &mut OrcFile,
+    stripe: &orc_proto::StripeInformation,
+    columns: Vec<ColumnStreams>,
+) -> Result<ReplacementStripe, Error> {
+    let compression = orc_file.get_postscript().get_compression();
+    // Readers may reject chunks that are larger than the compression block size.
+    let max_chunk_len = orc_file
+        .get_postscript()
+        .get_compression_block_size()
+        .map_or(MAX_CHUNK_LEN, |block_size| {
+            (block_size as usize).clamp(1, MAX_CHUNK_LEN)
+        });
+
+    let footer_start = stripe.offset() + stripe.indexLength() + stripe.dataLength();
+    let mut stripe_footer: orc_proto::StripeFooter =
+        orc_file.read_message(SeekFrom::Start(footer_start), stripe.footerLength())?;
+
+    let mut index_bytes = vec![];
+    let mut data_bytes = vec![];
+    let mut offset = stripe.offset();
+    let mut streams = Vec::with_capacity(stripe_footer.streams.len());
+
+    for stream in std::mem::take(&mut stripe_footer.streams) {
+        let start = offset;
+        offset += stream.length();
+
+        if !columns
+            .iter()
+            .any(|column| column.column_id == stream.column())
+        {
+            let bytes = if start < stripe.offset() + stripe.indexLength() {
+                &mut index_bytes
+            } else {
+                &mut data_bytes
+            };
+            orc_file.copy_bytes(start, stream.length(), bytes)?;
+            streams.push(stream);
+        }
+    }
+
+    let mut column_statistics = Vec::with_capacity(columns.len());
+
+    for column in columns {
+        for (kind, bytes) in column.streams {
+            let encoded = encode_chunks(&bytes, compression, max_chunk_len);
+            let mut stream = orc_proto::Stream::new();
+            stream.set_kind(kind);
+            stream.set_column(column.column_id);
+            stream.set_length(encoded.len() as u64);
+            data_bytes.extend(encoded);
+            streams.push(stream);
+        }
+
+        *stripe_footer
+            .columns
+            .get_mut(column.column_id as usize)
+            .ok_or(Error::InvalidMetadata)? = column.encoding;
+        column_statistics.push((column.column_id, column.statistics));
+    }
+
+    stripe_footer.streams = streams;
+    let footer_bytes = encode_chunks(&stripe_footer.write_to_bytes()?, compression, max_chunk_len);
+
+    let index_length = index_bytes.len() as u64;
+    let data_length = data_bytes.len() as u64;
+    let footer_length = footer_bytes.len() as u64;
+    let mut bytes = index_bytes;
+    bytes.extend(data_bytes);
+    bytes.extend(footer_bytes);
+
+    Ok(ReplacementStripe {
+        bytes,
+        index_length,
+        data_length,
+        footer_length,
+        column_statistics,
+    })
+}
+
+/// Encode bytes for a file with the given compression, storing chunks in their original form.
+fn encode_chunks(bytes: &[u8], compression: CompressionKind, max_chunk_len: usize) -> Vec<u8> {
+    if compression == CompressionKind::None {
+        bytes.to_vec()
+    } else {
+        let mut result = Vec::with_capacity(bytes.len() + 3);
+
+        for chunk in bytes.chunks(max_chunk_len) {
+            let header = ((chunk.len() as u32) << 1) | 1;
+            result.extend_from_slice(&header.to_le_bytes()[..3]);
+            result.extend_from_slice(chunk);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_rows(orc_file: &mut OrcFile) -> Vec<Vec<String>> {
+        let columns = orc_file.get_field_column_indices();
+
+        orc_file
+            .map_rows(&columns, |values| {
+                Ok::<_, Error>(values.iter().map(|value| format!("{:?}", value)).collect())
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn copy_all_stripes() {
+        for path in [
+            "examples/ts-10k-zstd-2020-09-20.orc",
+            "examples/ts-1k-zlib-2020-09-20.orc",
+            "examples/ts-1k-none-2020-09-20.orc",
+        ] {
+            let mut orc_file = OrcFile::open(path).unwrap();
+            let stripe_count = orc_file.get_footer().get_stripes().len();
+            let output_path = std::env::temp_dir().join(format!(
+                "orcrs-split-test-{}-{}",
+                std::process::id(),
+                path.replace('/', "-")
+            ));
+
+            write_stripes(
+                &mut orc_file,
+                0..stripe_count,
+                std::fs::File::create(&output_path).unwrap(),
+            )
+            .unwrap();
+
+            let mut copy = OrcFile::open(&output_path).unwrap();
+            std::fs::remove_file(&output_path).unwrap();
+
+            assert_eq!(
+                copy.get_footer().get_number_of_rows(),
+                orc_file.get_footer().get_number_of_rows()
+            );
+            assert_eq!(read_rows(&mut copy), read_rows(&mut orc_file));
+        }
+    }
+
+    #[test]
+    fn padded_stripes() {
+        let mut orc_file = OrcFile::open("examples/ts-1k-none-2020-09-20.orc").unwrap();
+        let copies_path = std::env::temp_dir().join(format!(
+            "orcrs-split-test-{}-copies.orc",
+            std::process::id()
+        ));
+        let padded_path = std::env::temp_dir().join(format!(
+            "orcrs-split-test-{}-padded.orc",
+            std::process::id()
+        ));
+
+        write_selected_stripes(
+            &mut orc_file,
+            &[0, 0, 0],
+            std::fs::File::create(&copies_path).unwrap(),
+        )
+        .unwrap();
+        let mut copies = OrcFile::open(&copies_path).unwrap();
+
+        let stripe = copies.get_footer().get_stripes()[0];
+        let stripe_len =
+            stripe.get_index_length() + stripe.get_data_length() + stripe.get_footer_length();
+        // The second and third stripes don't fit in the rest of the first and second blocks.
+        let padding = BlockPadding::new(stripe_len + 100);
+
+        write_padded_stripes(
+            &mut copies,
+            0..3,
+            std::fs::File::create(&padded_path).unwrap(),
+            &padding,
+        )
+        .unwrap();
+        let mut padded = OrcFile::open(&padded_path).unwrap();
+        std::fs::remove_file(&copies_path).unwrap();
+        std::fs::remove_file(&padded_path).unwrap();
+
+        let offsets = padded
+            .get_footer()
+            .get_stripes()
+            .iter()
+            .map(|stripe| stripe.get_offset())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            offsets,
+            vec![
+                MAGIC.len() as u64,
+                padding.get_block_size(),
+                padding.get_block_size() * 2
+            ]
+        );
+        assert_eq!(read_rows(&mut padded), read_rows(&mut copies));
+    }
+
+    #[test]
+    fn padding() {
+        let padding = BlockPadding::new(1000).with_tolerance(0.1);
+
+        assert_eq!(padding.get_padding(0, 2000), 0);
+        assert_eq!(padding.get_padding(900, 100), 0);
+        assert_eq!(padding.get_padding(900, 101), 100);
+        assert_eq!(padding.get_padding(1899, 2000), 0);
+        assert_eq!(padding.get_padding(1000, 2000), 0);
+        assert_eq!(BlockPadding::new(0).get_padding(900, 101), 0);
+    }
+
+    #[test]
+    fn plan() {
+        let stripes = [10, 20, 5, 40, 1]
+            .iter()
+            .map(|row_count| {
+                let mut stripe = orc_proto::StripeInformation::new();
+                stripe.set_numberOfRows(*row_count);
+                StripeInformation::from_proto(&stripe)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(plan_by_rows(&stripes, 30), vec![0..2, 2..3, 3..4, 4..5]);
+        assert_eq!(plan_by_rows(&stripes, 1000), vec![0..5]);
+        assert_eq!(plan_by_stripes(5, 2), vec![0..2, 2..4, 4..5]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Statistics computed from decoded columns while rows are read.
+//!
+//! A [`StatsCollector`] can be attached to a scan (see
+//! [`crate::parser::MappedRows::with_stats_collector`]) to compute the same kinds of statistics
+//! that ORC stores, for files that don't have them or for checking the stored values.
+
+use crate::column::Column;
+use crate::meta::ValueRange;
+use crate::value::Value;
+use std::sync::{Arc, Mutex};
+
+/// Statistics computed from a column's decoded values, for files without stored statistics or for
+/// checking the stored statistics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComputedStatistics {
+    number_of_values: u64,
+    null_count: u64,
+    range: Option<ValueRange>,
+    // The numeric decimal range, since the range has formatted values.
+    decimal_range: Option<((i128, u8), (i128, u8))>,
+    byte_size: u64,
+}
+
+impl ComputedStatistics {
+    pub fn add_column(&mut self, column: &Column) {
+        for value in values(column) {
+            match value {
+                Value::Null => {
+                    self.null_count += 1;
+                    continue;
+                }
+                Value::Bool(_) => self.byte_size += 1,
+                Value::U64(_) | Value::I64(_) => {
+                    let value = value.to_i64_lossy().unwrap_or_default();
+                    self.byte_size += 8;
+                    self.range = match self.range.take() {
+                        Some(ValueRange::Integer(min, max)) => {
+                            Some(ValueRange::Integer(min.min(value), max.max(value)))
+                        }
+                        _ => Some(ValueRange::Integer(value, value)),
+                    };
+                }
+                Value::Bytes(value) => self.byte_size += value.len() as u64,
+                Value::Decimal { value, scale } => {
+                    let value = (value, scale);
+                    let less = |(a, a_scale): (i128, u8), (b, b_scale): (i128, u8)| {
+                        crate::decimal::compare(a, a_scale, b, b_scale).is_lt()
+                    };
+                    self.byte_size += 16;
+
+                    let (min, max) = self.decimal_range.unwrap_or((value, value));
+                    let range = (
+                        if less(value, min) { value } else { min },
+                        if less(max, value) { value } else { max },
+                    );
+
+                    if self.decimal_range != Some(range) {
+                        self.decimal_range = Some(range);
+                        let ((min, min_scale), (max, max_scale)) = range;
+                        self.range = Some(ValueRange::Decimal(
+                            crate::decimal::format_scaled(min, min_scale),
+                            crate::decimal::format_scaled(max, max_scale),
+                        ));
+                    }
+                }
+                Value::Timestamp(value) => {
+                    self.byte_size += 12;
+
+                    // Timestamp statistics are in milliseconds.
+                    if let Some(value) = value.to_millis() {
+                        self.range = match self.range.take() {
+                            Some(ValueRange::Timestamp(min, max)) => {
+                                Some(ValueRange::Timestamp(min.min(value), max.max(value)))
+                            }
+                            _ => Some(ValueRange::Timestamp(value, value)),
+                        };
+                    }
+                }
+                // ORC only counts list values.
+                Value::List(_) => {}
+                Value::Utf8(value) => {
+                    self.byte_size += value.len() as u64;
+                    match &mut self.range {
+                        Some(ValueRange::String(min, max)) => {
+                            if value < min.as_str() {
+                                *min = value.to_string();
+                            } else if value > max.as_str() {
+                                *max = value.to_string();
+                            }
+                        }
+                        _ => {
+                            self.range =
+                                Some(ValueRange::String(value.to_string(), value.to_string()))
+                        }
+                    }
+                }
+            }
+
+            self.number_of_values += 1;
+        }
+    }
+
+    /// The number of non-null values.
+    pub fn get_number_of_values(&self) -> u64 {
+        self.number_of_values
+    }
+
+    pub fn get_null_count(&self) -> u64 {
+        self.null_count
+    }
+
+    /// The minimum and maximum of an integer, decimal, or string column (`None` for boolean
+    /// columns, or if every value is null).
+    pub fn get_range(&self) -> Option<&ValueRange> {
+        self.range.as_ref()
+    }
+
+    /// The total size of the values in bytes (the length of each string or byte string, or eight
+    /// bytes for each integer, sixteen for each decimal, and one for each boolean).
+    pub fn get_byte_size(&self) -> u64 {
+        self.byte_size
+    }
+}
+
+/// Computes statistics for each selected column while rows are read.
+///
+/// A collector is attached to a scan with [`crate::parser::MappedRows::with_stats_collector`].
+/// Clones share the same statistics, so a clone can be kept to read them after the scan. Each
+/// stripe's columns are added when the stripe is loaded, so a scan that stops early still includes
+/// the whole of its last stripe.
+#[derive(Clone, Debug, Default)]
+pub struct StatsCollector {
+    statistics: Arc<Mutex<Vec<ComputedStatistics>>>,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The statistics for each selected column, in the order the columns were selected.
+    pub fn get_statistics(&self) -> Vec<ComputedStatistics> {
+        self.lock().clone()
+    }
+
+    pub(crate) fn add_columns(&self, columns: &[Column]) {
+        let mut statistics = self.lock();
+
+        if statistics.len() < columns.len() {
+            statistics.resize(columns.len(), ComputedStatistics::default());
+        }
+
+        for (statistics, column) in statistics.iter_mut().zip(columns) {
+            statistics.add_column(column);
+        }
+    }
+
+    // The statistics are always left in a valid state, so a panic in another thread doesn't matter.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ComputedStatistics>> {
+        self.statistics
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+fn values(column: &Column) -> impl Iterator<Item = Value<'_>> {
+    (0..).map_while(|row| column.get(row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::Utf8Policy;
+
+    #[test]
+    fn computed_statistics() {
+        let mut statistics = ComputedStatistics::default();
+        statistics.add_column(&Column::make_i64_column(vec![-5, 20, 4], &[1, 0, 0, 2]));
+
+        assert_eq!(statistics.get_number_of_values(), 3);
+        assert_eq!(statistics.get_null_count(), 3);
+        assert_eq!(statistics.get_range(), Some(&ValueRange::Integer(-5, 20)));
+        assert_eq!(statistics.get_byte_size(), 24);
+
+        let mut statistics = ComputedStatistics::default();
+        statistics.add_column(
+            &Column::make_utf8_direct_column(
+                Some(vec![0, 0, 1, 0]),
+                "fooabazz".as_bytes().to_vec(),
+                vec![3, 1, 4],
+                Utf8Policy::Strict,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(statistics.get_number_of_values(), 3);
+        assert_eq!(statistics.get_null_count(), 1);
+        assert_eq!(
+            statistics.get_range(),
+            Some(&ValueRange::String("a".to_string(), "foo".to_string()))
+        );
+        assert_eq!(statistics.get_byte_size(), 8);
+    }
+}
@@ -0,0 +1,277 @@
+//! Decoding and converting timestamp and date values.
+//!
+//! ORC stores timestamp seconds relative to 2015-01-01 00:00:00, with the nanoseconds in a separate
+//! stream in a compact encoding. Timestamp columns are decoded as [`Timestamp`] values, with
+//! nanosecond precision and a flag that distinguishes `TIMESTAMP` (local) from `TIMESTAMP_INSTANT`
+//! (UTC) columns. Dates are stored as days since the Unix epoch.
+//!
+//! Conversions to `chrono` types are enabled by the `chrono` feature.
+
+// Timestamp seconds are stored relative to 2015-01-01 00:00:00.
+const ORC_TIMESTAMP_EPOCH_SECONDS: i64 = 1_420_070_400;
+
+/// Convert a stored timestamp to seconds since the Unix epoch and nanoseconds (returning `None` on
+/// overflow or if there are more than a second's worth of nanoseconds).
+///
+/// The nanoseconds are stored with their number of trailing decimal zeros (less one) in the low
+/// three bits.
+pub fn decode_timestamp(seconds: i64, encoded_nanos: u64) -> Option<(i64, u32)> {
+    let zeros = (encoded_nanos & 7) as u32;
+    let mut nanos = encoded_nanos >> 3;
+
+    if zeros != 0 {
+        nanos = nanos.checked_mul(10u64.pow(zeros + 1))?;
+    }
+
+    let nanos = u32::try_from(nanos)
+        .ok()
+        .filter(|nanos| *nanos < 1_000_000_000)?;
+    let mut seconds = seconds.checked_add(ORC_TIMESTAMP_EPOCH_SECONDS)?;
+
+    // Writers truncate negative seconds towards zero (as the Java implementation does).
+    if seconds < 0 && nanos > 999_999 {
+        seconds -= 1;
+    }
+
+    Some((seconds, nanos))
+}
+
+/// A decoded timestamp value, with nanosecond precision.
+///
+/// Values from `TIMESTAMP` columns are local date-times (the seconds are counted from the Unix
+/// epoch as if the wall-clock time were UTC), and values from `TIMESTAMP_INSTANT` columns are UTC
+/// instants. Timestamps are ordered by time, with local values first if the times are equal.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Timestamp {
+    seconds: i64,
+    nanos: u32,
+    utc: bool,
+}
+
+impl Timestamp {
+    /// A timestamp from seconds since the Unix epoch and nanoseconds (returning `None` if there
+    /// are more than a second's worth of nanoseconds).
+    pub fn new(seconds: i64, nanos: u32, utc: bool) -> Option<Self> {
+        (nanos < 1_000_000_000).then_some(Self {
+            seconds,
+            nanos,
+            utc,
+        })
+    }
+
+    /// Convert a stored timestamp (see [`decode_timestamp`]).
+    pub(crate) fn decode(seconds: i64, encoded_nanos: u64, utc: bool) -> Option<Self> {
+        let (seconds, nanos) = decode_timestamp(seconds, encoded_nanos)?;
+
+        Self::new(seconds, nanos, utc)
+    }
+
+    pub fn get_seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    pub fn get_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Whether this is a UTC instant (from a `TIMESTAMP_INSTANT` column) rather than a local
+    /// date-time.
+    pub fn is_utc(&self) -> bool {
+        self.utc
+    }
+
+    /// Milliseconds since the Unix epoch, rounded down (returning `None` on overflow).
+    pub fn to_millis(&self) -> Option<i64> {
+        self.seconds
+            .checked_mul(1_000)?
+            .checked_add((self.nanos / 1_000_000) as i64)
+    }
+
+    /// The date and time (as the wall-clock time for local values).
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_date_time(&self) -> Option<chrono::NaiveDateTime> {
+        self.to_date_time().map(|date_time| date_time.naive_utc())
+    }
+
+    /// The instant (treating the wall-clock time of local values as UTC).
+    #[cfg(feature = "chrono")]
+    pub fn to_date_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp(self.seconds, self.nanos)
+    }
+}
+
+/// ISO 8601 form (such as `2020-09-20T12:00:00.123`), with a `Z` suffix for UTC values, and with
+/// three, six, or nine fractional digits if there are any nanoseconds.
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (year, month, day) = civil_from_days(self.seconds.div_euclid(86_400));
+        let seconds_of_day = self.seconds.rem_euclid(86_400);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds_of_day / 3_600,
+            seconds_of_day % 3_600 / 60,
+            seconds_of_day % 60
+        )?;
+
+        match self.nanos {
+            0 => {}
+            nanos if nanos % 1_000_000 == 0 => write!(f, ".{:03}", nanos / 1_000_000)?,
+            nanos if nanos % 1_000 == 0 => write!(f, ".{:06}", nanos / 1_000)?,
+            nanos => write!(f, ".{:09}", nanos)?,
+        }
+
+        if self.utc {
+            write!(f, "Z")?;
+        }
+
+        Ok(())
+    }
+}
+
+// The year, month, and day for a number of days since the Unix epoch (this is Howard Hinnant's
+// `civil_from_days` algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// The date and time for a decoded timestamp value in milliseconds since the Unix epoch (as the
+/// wall-clock time for `TIMESTAMP` columns).
+#[cfg(feature = "chrono")]
+pub fn to_naive_date_time(millis: i64) -> Option<chrono::NaiveDateTime> {
+    to_date_time(millis).map(|date_time| date_time.naive_utc())
+}
+
+/// The instant for a decoded timestamp value in milliseconds since the Unix epoch (as for
+/// `TIMESTAMP WITH LOCAL TIME ZONE` columns).
+#[cfg(feature = "chrono")]
+pub fn to_date_time(millis: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::from_timestamp_millis(millis)
+}
+
+/// The date for a number of days since the Unix epoch.
+#[cfg(feature = "chrono")]
+pub fn to_naive_date(days: i64) -> Option<chrono::NaiveDate> {
+    // 1970-01-01 is day 719,163 counting from 0001-01-01 as day 1.
+    days.checked_add(719_163)
+        .and_then(|days| i32::try_from(days).ok())
+        .and_then(chrono::NaiveDate::from_num_days_from_ce_opt)
+}
+
+/// Convert a stored timestamp to a date and time with nanosecond precision.
+#[cfg(feature = "chrono")]
+pub fn decode_naive_date_time(seconds: i64, encoded_nanos: u64) -> Option<chrono::NaiveDateTime> {
+    let (seconds, nanos) = decode_timestamp(seconds, encoded_nanos)?;
+
+    chrono::DateTime::from_timestamp(seconds, nanos).map(|date_time| date_time.naive_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_timestamps() {
+        // 2015-01-01 00:00:00.5, with eight trailing zeros.
+        assert_eq!(
+            decode_timestamp(0, (5 << 3) | 7),
+            Some((ORC_TIMESTAMP_EPOCH_SECONDS, 500_000_000))
+        );
+        assert_eq!(
+            Timestamp::decode(0, (5 << 3) | 7, false).and_then(|value| value.to_millis()),
+            Some(1_420_070_400_500)
+        );
+        assert_eq!(decode_timestamp(0, 123 << 3), Some((1_420_070_400, 123)));
+
+        // 1969-12-31 23:59:58.5 is written with the seconds truncated towards zero.
+        assert_eq!(
+            decode_timestamp(-ORC_TIMESTAMP_EPOCH_SECONDS - 1, (5 << 3) | 7),
+            Some((-2, 500_000_000))
+        );
+        assert_eq!(decode_timestamp(i64::MAX, 0), None);
+        assert_eq!(decode_timestamp(0, (10 << 3) | 7), None);
+    }
+
+    #[test]
+    fn timestamps() {
+        let local =
+            Timestamp::decode(-ORC_TIMESTAMP_EPOCH_SECONDS - 1, (5 << 3) | 7, false).unwrap();
+        let utc = Timestamp::new(-2, 500_000_000, true).unwrap();
+
+        assert_eq!(local.get_seconds(), -2);
+        assert_eq!(local.get_nanos(), 500_000_000);
+        assert!(!local.is_utc() && utc.is_utc());
+        assert_eq!(local.to_millis(), Some(-1_500));
+        assert_ne!(local, utc);
+        assert!(local < utc);
+        assert!(utc < Timestamp::new(-2, 500_000_001, false).unwrap());
+        assert_eq!(Timestamp::new(0, 1_000_000_000, false), None);
+        assert_eq!(Timestamp::new(i64::MAX, 0, true).unwrap().to_millis(), None);
+
+        assert_eq!(local.to_string(), "1969-12-31T23:59:58.500");
+        assert_eq!(utc.to_string(), "1969-12-31T23:59:58.500Z");
+        assert_eq!(
+            Timestamp::new(1_600_603_200, 0, true).unwrap().to_string(),
+            "2020-09-20T12:00:00Z"
+        );
+        assert_eq!(
+            Timestamp::new(951_782_400, 1_000, false)
+                .unwrap()
+                .to_string(),
+            "2000-02-29T00:00:00.000001"
+        );
+        assert_eq!(
+            Timestamp::decode(0, 123 << 3, false).unwrap().to_string(),
+            "2015-01-01T00:00:00.000000123"
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversions() {
+        let date_time = decode_naive_date_time(0, 123 << 3).unwrap();
+        assert_eq!(date_time.to_string(), "2015-01-01 00:00:00.000000123");
+        let timestamp = Timestamp::decode(0, 123 << 3, true).unwrap();
+        assert_eq!(timestamp.to_naive_date_time(), Some(date_time));
+        assert_eq!(
+            timestamp.to_date_time().unwrap().to_rfc3339(),
+            "2015-01-01T00:00:00.000000123+00:00"
+        );
+        assert_eq!(
+            to_naive_date_time(1_420_070_400_500).unwrap().to_string(),
+            "2015-01-01 00:00:00.500"
+        );
+        assert_eq!(
+            to_date_time(-500).unwrap().to_rfc3339(),
+            "1969-12-31T23:59:59.500+00:00"
+        );
+        assert_eq!(to_naive_date(0).unwrap().to_string(), "1970-01-01");
+        assert_eq!(to_naive_date(-1).unwrap().to_string(), "1969-12-31");
+        assert_eq!(to_naive_date(i64::MAX), None);
+
+        let column = crate::Column::make_i64_column(vec![1_420_070_400_500], &[0, 1]);
+        assert_eq!(
+            column.to_vec_opt::<chrono::DateTime<chrono::Utc>>(),
+            Some(vec![to_date_time(1_420_070_400_500), None])
+        );
+    }
+}
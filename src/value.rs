@@ -1,8 +1,42 @@
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+//! Decoded values.
+//!
+//! Integer columns are decoded to signed `I64` values (ORC integers are always signed). `U64` is used
+//! for values that are unsigned by construction, such as row numbers.
+//!
+//! Decimal columns are decoded to unscaled 128-bit integers with a scale, so that no precision is
+//! lost (see [`crate::decimal`]). Timestamp columns are decoded to [`Timestamp`] values with
+//! nanosecond precision (see [`crate::time`]). List values borrow their elements from the decoded child column
+//! (see [`ListValue`]).
+//!
+//! Values are ordered with nulls first, followed by booleans, integers, decimals, timestamps,
+//! strings, byte strings, and lists, with values of the same type compared in the usual way (lists element by
+//! element). Signed and unsigned integers are compared numerically, with a signed value first if
+//! they're equal, and decimals are compared numerically, with the smaller scale first if they're
+//! equal. This is a total order, so rows can be sorted directly.
+//!
+//! Values can also be hashed. All nulls are equal and have the same hash, so grouping or
+//! deduplicating by a value treats nulls as a single key. There are currently no floating point
+//! values, so there is no special handling for NaN.
+
+use crate::column::Column;
+use crate::time::Timestamp;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
 pub enum Value<'a> {
     Bool(bool),
     U64(u64),
+    I64(i64),
+    /// The value is `value / 10^scale`.
+    Decimal {
+        value: i128,
+        scale: u8,
+    },
+    Timestamp(Timestamp),
     Utf8(&'a str),
+    Bytes(&'a [u8]),
+    List(ListValue<'a>),
     Null,
 }
 
@@ -37,6 +71,58 @@ impl<'a> Value<'a> {
         }
     }
 
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_nullable_i64(&self) -> Option<Option<i64>> {
+        match self {
+            Self::I64(value) => Some(Some(*value)),
+            Self::Null => Some(None),
+            _ => None,
+        }
+    }
+
+    /// The unscaled value and scale of a decimal.
+    pub fn as_decimal(&self) -> Option<(i128, u8)> {
+        match self {
+            Self::Decimal { value, scale } => Some((*value, *scale)),
+            _ => None,
+        }
+    }
+
+    pub fn as_nullable_decimal(&self) -> Option<Option<(i128, u8)>> {
+        match self {
+            Self::Decimal { value, scale } => Some(Some((*value, *scale))),
+            Self::Null => Some(None),
+            _ => None,
+        }
+    }
+
+    /// Format a decimal with its scale (which is how text outputs represent decimal values).
+    pub fn to_decimal_string(&self) -> Option<String> {
+        self.as_decimal()
+            .map(|(value, scale)| crate::decimal::format_scaled(value, scale))
+    }
+
+    pub fn as_timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Self::Timestamp(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_nullable_timestamp(&self) -> Option<Option<Timestamp>> {
+        match self {
+            Self::Timestamp(value) => Some(Some(*value)),
+            Self::Null => Some(None),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         match self {
             Self::Utf8(value) => Some(value),
@@ -67,7 +153,615 @@ impl<'a> Value<'a> {
         }
     }
 
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_nullable_bytes(&self) -> Option<Option<&[u8]>> {
+        match self {
+            Self::Bytes(value) => Some(Some(value)),
+            Self::Null => Some(None),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<ListValue<'a>> {
+        match self {
+            Self::List(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_nullable_list(&self) -> Option<Option<ListValue<'a>>> {
+        match self {
+            Self::List(value) => Some(Some(*value)),
+            Self::Null => Some(None),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Convert a numeric value to a double, or `None` for nulls, timestamps, and strings.
+    ///
+    /// Integers may lose precision beyond 2^53 (and decimals beyond about 15 digits). Booleans are
+    /// 0 or 1.
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Self::U64(value) => Some(*value as f64),
+            Self::Decimal { value, scale } => Some(*value as f64 / 10f64.powi(*scale as i32)),
+            _ => self.to_i64_lossy().map(|value| value as f64),
+        }
+    }
+
+    /// Convert an integer value to a signed integer, or `None` for nulls, decimals, timestamps, and
+    /// strings.
+    ///
+    /// This is exact for all ORC integer columns. Unsigned values above `i64::MAX` wrap. Booleans
+    /// are 0 or 1.
+    pub fn to_i64_lossy(&self) -> Option<i64> {
+        match self {
+            Self::Bool(value) => Some(*value as i64),
+            Self::U64(value) => Some(*value as i64),
+            Self::I64(value) => Some(*value),
+            Self::Decimal { .. }
+            | Self::Timestamp(_)
+            | Self::Utf8(_)
+            | Self::Bytes(_)
+            | Self::List(_)
+            | Self::Null => None,
+        }
+    }
+
+    /// Convert a numeric value to an unsigned integer, or `None` for nulls, strings, and negative
+    /// values.
+    ///
+    /// Booleans are 0 or 1.
+    pub fn to_u64_checked(&self) -> Option<u64> {
+        match self {
+            Self::U64(value) => Some(*value),
+            _ => self
+                .to_i64_lossy()
+                .and_then(|value| u64::try_from(value).ok()),
+        }
+    }
+
+    pub fn to_owned_value(&self) -> OwnedValue {
+        match self {
+            Self::Bool(value) => OwnedValue::Bool(*value),
+            Self::U64(value) => OwnedValue::U64(*value),
+            Self::I64(value) => OwnedValue::I64(*value),
+            Self::Decimal { value, scale } => OwnedValue::Decimal {
+                value: *value,
+                scale: *scale,
+            },
+            Self::Timestamp(value) => OwnedValue::Timestamp(*value),
+            Self::Utf8(value) => OwnedValue::Utf8(value.to_string()),
+            Self::Bytes(value) => OwnedValue::Bytes(value.to_vec()),
+            Self::List(value) => {
+                OwnedValue::List(value.iter().map(|value| value.to_owned_value()).collect())
+            }
+            Self::Null => OwnedValue::Null,
+        }
+    }
+
+    // The position of this value's type in the cross-type ordering.
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::U64(_) | Self::I64(_) => 2,
+            Self::Decimal { .. } => 3,
+            Self::Timestamp(_) => 4,
+            Self::Utf8(_) => 5,
+            Self::Bytes(_) => 6,
+            Self::List(_) => 7,
+        }
+    }
+}
+
+impl Ord for Value<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::U64(a), Self::U64(b)) => a.cmp(b),
+            (Self::I64(a), Self::I64(b)) => a.cmp(b),
+            (Self::I64(a), Self::U64(b)) => (*a as i128).cmp(&(*b as i128)).then(Ordering::Less),
+            (Self::U64(a), Self::I64(b)) => (*a as i128).cmp(&(*b as i128)).then(Ordering::Greater),
+            (
+                Self::Decimal {
+                    value: a,
+                    scale: a_scale,
+                },
+                Self::Decimal {
+                    value: b,
+                    scale: b_scale,
+                },
+            ) => crate::decimal::compare(*a, *a_scale, *b, *b_scale).then(a_scale.cmp(b_scale)),
+            (Self::Timestamp(a), Self::Timestamp(b)) => a.cmp(b),
+            (Self::Utf8(a), Self::Utf8(b)) => a.cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+            (Self::List(a), Self::List(b)) => a.iter().cmp(b.iter()),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The elements of a list value.
+///
+/// These borrow from the decoded child column (or from the elements of an [`OwnedValue::List`]).
+#[derive(Clone, Copy)]
+pub struct ListValue<'a> {
+    elements: ListElements<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum ListElements<'a> {
+    Column {
+        values: &'a Column,
+        start: usize,
+        end: usize,
+    },
+    Owned(&'a [OwnedValue]),
+}
+
+impl<'a> ListValue<'a> {
+    /// The rows from `start` to `end` of the child column.
+    pub(crate) fn new(values: &'a Column, start: usize, end: usize) -> Self {
+        Self {
+            elements: ListElements::Column { values, start, end },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self.elements {
+            ListElements::Column { start, end, .. } => end - start,
+            ListElements::Owned(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<Value<'a>> {
+        match self.elements {
+            ListElements::Column { values, start, end } => {
+                if index < end - start {
+                    values.get(start + index)
+                } else {
+                    None
+                }
+            }
+            ListElements::Owned(values) => values.get(index).map(OwnedValue::as_value),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Value<'a>> {
+        let list = *self;
+        (0..self.len()).map_while(move |index| list.get(index))
+    }
+
+    /// Format the list as a JSON array (which is how text outputs represent list values), with
+    /// byte strings as hexadecimal strings.
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> String {
+        to_json(&Value::List(*self)).to_string()
+    }
+}
+
+#[cfg(feature = "json")]
+fn to_json(value: &Value<'_>) -> serde_json::Value {
+    match value {
+        Value::Bool(value) => (*value).into(),
+        Value::U64(value) => (*value).into(),
+        Value::I64(value) => (*value).into(),
+        Value::Decimal { value, scale } => crate::decimal::format_scaled(*value, *scale).into(),
+        Value::Timestamp(value) => value.to_string().into(),
+        Value::Utf8(value) => (*value).into(),
+        Value::Bytes(value) => to_hex(value).into(),
+        Value::List(value) => value.iter().map(|value| to_json(&value)).collect(),
+        Value::Null => serde_json::Value::Null,
+    }
+}
+
+impl std::fmt::Debug for ListValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for ListValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for ListValue<'_> {}
+
+impl Hash for ListValue<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for value in self.iter() {
+            value.hash(state);
+        }
+    }
+}
+
+/// Format a byte string as lowercase hexadecimal (which is how text outputs represent bytes values).
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// A value that doesn't borrow from the decoded column.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum OwnedValue {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    Decimal { value: i128, scale: u8 },
+    Timestamp(Timestamp),
+    Utf8(String),
+    Bytes(Vec<u8>),
+    List(Vec<OwnedValue>),
+    Null,
+}
+
+impl OwnedValue {
+    pub fn as_value(&self) -> Value<'_> {
+        match self {
+            Self::Bool(value) => Value::Bool(*value),
+            Self::U64(value) => Value::U64(*value),
+            Self::I64(value) => Value::I64(*value),
+            Self::Decimal { value, scale } => Value::Decimal {
+                value: *value,
+                scale: *scale,
+            },
+            Self::Timestamp(value) => Value::Timestamp(*value),
+            Self::Utf8(value) => Value::Utf8(value),
+            Self::Bytes(value) => Value::Bytes(value),
+            Self::List(values) => Value::List(ListValue {
+                elements: ListElements::Owned(values),
+            }),
+            Self::Null => Value::Null,
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, Self::Null)
     }
 }
+
+impl From<Value<'_>> for OwnedValue {
+    fn from(value: Value<'_>) -> Self {
+        value.to_owned_value()
+    }
+}
+
+impl Ord for OwnedValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_value().cmp(&other.as_value())
+    }
+}
+
+impl PartialOrd for OwnedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A plain Rust type that non-null values can be converted to (see
+/// [`crate::column::Column::to_vec_opt`]).
+pub trait FromValue: Sized {
+    /// Convert a value, or return `None` if it has the wrong type.
+    fn from_value(value: &Value<'_>) -> Option<Self>;
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+/// Signed integers are converted if they're not negative.
+impl FromValue for u64 {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::U64(value) => Some(*value),
+            Value::I64(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Unsigned integers are converted if they're not larger than `i64::MAX`.
+impl FromValue for i64 {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::I64(value) => Some(*value),
+            Value::U64(value) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Decimals are converted to their string representation.
+impl FromValue for String {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        value.as_string().or_else(|| value.to_decimal_string())
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        value.as_bytes().map(<[u8]>::to_vec)
+    }
+}
+
+/// Decimals are converted if they fit (with a scale of at most 28 and at most 96 bits), and
+/// integers are converted exactly.
+#[cfg(feature = "rust_decimal")]
+impl FromValue for rust_decimal::Decimal {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Decimal { value, scale } => {
+                Self::try_from_i128_with_scale(*value, *scale as u32).ok()
+            }
+            Value::I64(value) => Some((*value).into()),
+            Value::U64(value) => Some((*value).into()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Timestamp {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        value.as_timestamp()
+    }
+}
+
+/// Timestamps are converted if they're in range (with local values treated as UTC), and integers
+/// are treated as milliseconds since the Unix epoch.
+#[cfg(feature = "chrono")]
+impl FromValue for chrono::DateTime<chrono::Utc> {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Timestamp(value) => value.to_date_time(),
+            _ => value.as_i64().and_then(crate::time::to_date_time),
+        }
+    }
+}
+
+/// Timestamps are converted if they're in range (with UTC values as the UTC date and time), and
+/// integers are treated as milliseconds since the Unix epoch.
+#[cfg(feature = "chrono")]
+impl FromValue for chrono::NaiveDateTime {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Timestamp(value) => value.to_naive_date_time(),
+            _ => value.as_i64().and_then(crate::time::to_naive_date_time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn ordering() {
+        let mut values = vec![
+            Value::Utf8("b"),
+            Value::Bytes(b"a"),
+            Value::U64(10),
+            Value::Null,
+            Value::I64(2),
+            Value::Bool(true),
+            Value::Utf8("a"),
+            Value::I64(-5),
+            Value::U64(2),
+            Value::Bool(false),
+            Value::Decimal {
+                value: 150,
+                scale: 2,
+            },
+            Value::Decimal {
+                value: 15,
+                scale: 1,
+            },
+            Value::Decimal {
+                value: -2,
+                scale: 0,
+            },
+            Value::Null,
+        ];
+        values.sort();
+
+        assert_eq!(
+            values,
+            vec![
+                Value::Null,
+                Value::Null,
+                Value::Bool(false),
+                Value::Bool(true),
+                Value::I64(-5),
+                Value::I64(2),
+                Value::U64(2),
+                Value::U64(10),
+                Value::Decimal {
+                    value: -2,
+                    scale: 0
+                },
+                Value::Decimal {
+                    value: 15,
+                    scale: 1
+                },
+                Value::Decimal {
+                    value: 150,
+                    scale: 2,
+                },
+                Value::Utf8("a"),
+                Value::Utf8("b"),
+                Value::Bytes(b"a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn owned_ordering_matches() {
+        let values = [
+            Value::Null,
+            Value::Bool(true),
+            Value::U64(1),
+            Value::I64(1),
+            Value::I64(-1),
+            Value::U64(u64::MAX),
+            Value::Utf8(""),
+            Value::Bytes(b""),
+        ];
+
+        for a in values {
+            for b in values {
+                assert_eq!(
+                    a.to_owned_value().cmp(&b.to_owned_value()),
+                    a.cmp(&b),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let value = Value::Bytes(&[0x00, 0xab, 0x10]);
+
+        assert_eq!(value.as_bytes(), Some(&[0x00, 0xab, 0x10][..]));
+        assert_eq!(value.as_str(), None);
+        assert_eq!(Value::Null.as_nullable_bytes(), Some(None));
+        assert_eq!(to_hex(value.as_bytes().unwrap()), "00ab10");
+        assert_eq!(
+            value.to_owned_value(),
+            OwnedValue::Bytes(vec![0x00, 0xab, 0x10])
+        );
+    }
+
+    #[test]
+    fn lists() {
+        let column = Column::make_i64_column(vec![1, 2, 3], &[]);
+        let list = Value::List(ListValue::new(&column, 0, 2));
+        let owned = list.to_owned_value();
+
+        assert_eq!(
+            owned,
+            OwnedValue::List(vec![OwnedValue::I64(1), OwnedValue::I64(2)])
+        );
+        assert_eq!(owned.as_value(), list);
+        assert_eq!(
+            [list, owned.as_value()]
+                .iter()
+                .collect::<HashSet<_>>()
+                .len(),
+            1
+        );
+        assert!(Value::List(ListValue::new(&column, 0, 1)) < list);
+        assert!(list < Value::List(ListValue::new(&column, 1, 2)));
+        assert!(Value::Bytes(b"z") < list);
+        #[cfg(feature = "json")]
+        assert_eq!(ListValue::new(&column, 0, 3).to_json_string(), "[1,2,3]");
+    }
+
+    #[test]
+    fn numeric_coercion() {
+        let negative = Value::I64(-3);
+
+        assert_eq!(negative.as_i64(), Some(-3));
+        assert_eq!(negative.as_u64(), None);
+        assert_eq!(negative.to_i64_lossy(), Some(-3));
+        assert_eq!(negative.to_f64(), Some(-3.0));
+        assert_eq!(negative.to_u64_checked(), None);
+        assert_eq!(Value::I64(7).to_u64_checked(), Some(7));
+        assert_eq!(Value::U64(u64::MAX).to_u64_checked(), Some(u64::MAX));
+        assert_eq!(Value::U64(u64::MAX).to_f64(), Some(u64::MAX as f64));
+        assert_eq!(Value::Null.as_nullable_i64(), Some(None));
+        assert_eq!(i64::from_value(&Value::U64(7)), Some(7));
+        assert_eq!(u64::from_value(&negative), None);
+        assert_eq!(Value::U64(7).to_u64_checked(), Some(7));
+        assert_eq!(Value::U64(7).to_f64(), Some(7.0));
+        assert_eq!(Value::Bool(true).to_i64_lossy(), Some(1));
+        assert_eq!(Value::Bool(false).to_f64(), Some(0.0));
+        assert_eq!(Value::Utf8("1").to_i64_lossy(), None);
+        assert_eq!(Value::Null.to_f64(), None);
+
+        let decimal = Value::Decimal {
+            value: -1250,
+            scale: 3,
+        };
+        assert_eq!(decimal.as_decimal(), Some((-1250, 3)));
+        assert_eq!(decimal.to_decimal_string(), Some("-1.250".to_string()));
+        assert_eq!(decimal.to_f64(), Some(-1.25));
+        assert_eq!(decimal.to_i64_lossy(), None);
+        assert_eq!(String::from_value(&decimal), Some("-1.250".to_string()));
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn rust_decimal_conversion() {
+        let decimal = Value::Decimal {
+            value: -1250,
+            scale: 3,
+        };
+        assert_eq!(
+            rust_decimal::Decimal::from_value(&decimal),
+            Some(rust_decimal::Decimal::new(-1250, 3))
+        );
+        assert_eq!(
+            rust_decimal::Decimal::from_value(&Value::Decimal {
+                value: 1,
+                scale: 38
+            }),
+            None
+        );
+        assert_eq!(
+            rust_decimal::Decimal::from_value(&Value::I64(7)),
+            Some(rust_decimal::Decimal::new(7, 0))
+        );
+    }
+
+    #[test]
+    fn hashing() {
+        let values = [
+            Value::Null,
+            Value::Utf8("a"),
+            Value::Null,
+            Value::U64(1),
+            Value::Utf8("a"),
+            Value::Bool(true),
+            Value::U64(1),
+            Value::I64(1),
+        ];
+
+        let distinct = values.iter().collect::<HashSet<_>>();
+        let distinct_owned = values
+            .iter()
+            .map(Value::to_owned_value)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(distinct.len(), 5);
+        assert_eq!(distinct_owned.len(), 5);
+        assert!(distinct_owned.contains(&OwnedValue::Null));
+    }
+}
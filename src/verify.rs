@@ -0,0 +1,192 @@
+//! Cross-checks between decoded data and file metadata, used in strict mode.
+//!
+//! See [`crate::parser::OrcFile::set_strict`].
+
+use crate::column::Column;
+use crate::proto::orc_proto;
+use crate::value::Value;
+
+/// A disagreement between decoded data and the file metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Mismatch {
+    /// The number of rows in the stripes doesn't match the footer.
+    RowCount { expected: u64, actual: u64 },
+    /// The number of values decoded from a stream doesn't match the number expected.
+    ValueCount {
+        stream: &'static str,
+        expected: u64,
+        actual: u64,
+    },
+    /// The number of bytes in a stream doesn't match its declared length.
+    StreamLength { expected: u64, actual: u64 },
+    /// A dictionary-encoded row refers to an entry outside the dictionary.
+    DictionaryIndex {
+        row_index: usize,
+        index: u64,
+        dictionary_size: u32,
+    },
+    /// A value in the stripe statistics doesn't match the decoded values.
+    Statistics {
+        statistic: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Compare a decoded column against its stripe-level statistics.
+pub(crate) fn check_statistics(
+    column: &Column,
+    row_count: usize,
+    statistics: &orc_proto::ColumnStatistics,
+) -> Result<(), Mismatch> {
+    let mut value_count = 0;
+    let mut true_count = 0;
+    let mut int_range: Option<(i64, i64)> = None;
+    let mut string_range: Option<(&str, &str)> = None;
+    let mut bytes_sum = 0;
+
+    for row_index in 0..row_count {
+        match column.get(row_index) {
+            Some(Value::Null) | None => {}
+            Some(value) => {
+                value_count += 1;
+
+                match value {
+                    Value::Bool(value) => true_count += value as u64,
+                    Value::U64(_) | Value::I64(_) => {
+                        let value = value.to_i64_lossy().unwrap_or_default();
+                        int_range = Some(match int_range {
+                            Some((min, max)) => (min.min(value), max.max(value)),
+                            None => (value, value),
+                        });
+                    }
+                    Value::Utf8(value) => {
+                        string_range = Some(match string_range {
+                            Some((min, max)) => (min.min(value), max.max(value)),
+                            None => (value, value),
+                        });
+                    }
+                    Value::Bytes(value) => bytes_sum += value.len() as i64,
+                    Value::Decimal { .. } | Value::Timestamp(_) | Value::List(_) | Value::Null => {}
+                }
+            }
+        }
+    }
+
+    check(
+        "numberOfValues",
+        statistics.numberOfValues,
+        Some(value_count),
+    )?;
+    check(
+        "hasNull",
+        statistics.hasNull,
+        Some(value_count < row_count as u64),
+    )?;
+
+    if let Some(int_statistics) = statistics.intStatistics.as_ref() {
+        check(
+            "minimum",
+            int_statistics.minimum,
+            int_range.map(|(min, _)| min),
+        )?;
+        check(
+            "maximum",
+            int_statistics.maximum,
+            int_range.map(|(_, max)| max),
+        )?;
+    }
+
+    if let Some(string_statistics) = statistics.stringStatistics.as_ref() {
+        check(
+            "minimum",
+            string_statistics.minimum.as_deref(),
+            string_range.map(|(min, _)| min),
+        )?;
+        check(
+            "maximum",
+            string_statistics.maximum.as_deref(),
+            string_range.map(|(_, max)| max),
+        )?;
+    }
+
+    if let Some(binary_statistics) = statistics.binaryStatistics.as_ref() {
+        check("sum", binary_statistics.sum, Some(bytes_sum))?;
+    }
+
+    if let Some(bucket_statistics) = statistics.bucketStatistics.as_ref() {
+        check(
+            "count",
+            bucket_statistics.count.first().copied(),
+            Some(true_count),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Statistics are only checked if they are present (and for minimums and maximums, if there are
+// any non-null values).
+fn check<T: PartialEq + std::fmt::Debug>(
+    statistic: &'static str,
+    expected: Option<T>,
+    actual: Option<T>,
+) -> Result<(), Mismatch> {
+    match (expected, actual) {
+        (Some(expected), Some(actual)) if expected != actual => Err(Mismatch::Statistics {
+            statistic,
+            expected: format!("{:?}", expected),
+            actual: format!("{:?}", actual),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_int_statistics() {
+        let column = Column::make_i64_column(vec![3, -2, 7], &[0, 1, 0, 0]);
+
+        let mut statistics = orc_proto::ColumnStatistics::new();
+        statistics.set_numberOfValues(3);
+        statistics.set_hasNull(true);
+        let int_statistics = statistics.intStatistics.mut_or_insert_default();
+        int_statistics.set_minimum(-2);
+        int_statistics.set_maximum(7);
+
+        assert_eq!(check_statistics(&column, 4, &statistics), Ok(()));
+
+        statistics
+            .intStatistics
+            .mut_or_insert_default()
+            .set_maximum(8);
+
+        assert_eq!(
+            check_statistics(&column, 4, &statistics),
+            Err(Mismatch::Statistics {
+                statistic: "maximum",
+                expected: "8".to_string(),
+                actual: "7".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn check_value_count() {
+        let column = Column::make_i64_column(vec![1, 2], &[]);
+
+        let mut statistics = orc_proto::ColumnStatistics::new();
+        statistics.set_numberOfValues(3);
+
+        assert!(matches!(
+            check_statistics(&column, 2, &statistics),
+            Err(Mismatch::Statistics {
+                statistic: "numberOfValues",
+                ..
+            })
+        ));
+    }
+}